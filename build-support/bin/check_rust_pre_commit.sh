@@ -11,5 +11,11 @@ else
   ./cargo clippy --locked --all || exit 1
 fi
 
+# NB: The options crate's config parsing core is shared with a browser-based playground, so must
+# keep building for wasm32.
+echo "* Running \`./cargo check --target wasm32-unknown-unknown -p options\`"
+(cd src/rust/engine && rustup target add wasm32-unknown-unknown) || exit 1
+./cargo check --locked --target wasm32-unknown-unknown -p options || exit 1
+
 echo "* Checking formatting of Rust files"
 ./build-support/bin/check_rust_formatting.sh || exit 1
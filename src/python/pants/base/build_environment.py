@@ -5,7 +5,9 @@ from __future__ import annotations
 
 import logging
 import os
+import sys
 from pathlib import Path
+from typing import Mapping
 
 from pants.base.build_root import BuildRoot
 from pants.engine.internals import native_engine
@@ -37,6 +39,28 @@ def get_default_pants_config_file() -> str:
     return os.path.join(get_buildroot(), "pants.toml")
 
 
+def get_user_config_file_paths(env: Mapping[str, str]) -> list[str]:
+    """Return the machine-wide and per-user config files, in increasing order of precedence.
+
+    This mirrors `user_config_files` in the Rust options parser.
+    """
+    if sys.platform == "win32":
+        dirs = [env.get("PROGRAMDATA"), env.get("APPDATA")]
+    else:
+        xdg_config_home = env.get("XDG_CONFIG_HOME")
+        home = env.get("HOME")
+        if xdg_config_home and os.path.isabs(xdg_config_home):
+            user_dir = xdg_config_home
+        elif home and sys.platform == "darwin":
+            user_dir = os.path.join(home, "Library", "Application Support")
+        elif home:
+            user_dir = os.path.join(home, ".config")
+        else:
+            user_dir = None
+        dirs = ["/etc", user_dir]
+    return [os.path.join(d, "pants", "pants.toml") for d in dirs if d]
+
+
 def is_in_container() -> bool:
     """Return true if this process is likely running inside of a container."""
     # https://stackoverflow.com/a/49944991/38265 and https://github.com/containers/podman/issues/3586
@@ -31,6 +31,7 @@ from pants.init.engine_initializer import EngineInitializer, GraphScheduler, Gra
 from pants.init.logging import stdio_destination_use_color
 from pants.init.options_initializer import OptionsInitializer
 from pants.init.specs_calculator import calculate_specs
+from pants.option.config import ScopeAllowances
 from pants.option.global_options import DynamicRemoteOptions, DynamicUIRenderer, GlobalOptions
 from pants.option.options import Options
 from pants.option.options_bootstrapper import OptionsBootstrapper
@@ -110,7 +111,13 @@ class LocalPantsRunner:
 
         # Verify configs.
         if global_bootstrap_options.verify_config:
-            options.verify_configs(options_bootstrapper.config)
+            options.verify_configs(
+                options_bootstrapper.config,
+                ScopeAllowances(
+                    patterns=tuple(global_bootstrap_options.verify_config_allowed_scopes),
+                    unknown_scopes=global_bootstrap_options.verify_config_unknown_scopes,
+                ),
+            )
 
         # If we're running with the daemon, we'll be handed a warmed Scheduler, which we use
         # to initialize a session here.
@@ -612,6 +612,58 @@ OptionValue = Tuple[Optional[T], int]
 OptionListValue = Tuple[list[T], int]
 OptionDictValue = Tuple[dict[str, Any], int]
 
+class PyOptionRegistry:
+    def __init__(self) -> None: ...
+    # The option type is named as in help, e.g. `int` or `list[str]`.
+    def register(
+        self,
+        option_id: PyOptionId,
+        option_type: str,
+        default: Any,
+        help: str = "",
+        deprecated: Optional[str] = None,
+    ) -> None: ...
+
+class PyRawValue:
+    # Where the value was found: a flag, an env var name, or a config file path, line and section.
+    location: str
+    # The value as written, or None for a flag that was given without a value.
+    value: Optional[str]
+
+class PyEdit:
+    # One of `replace`, `add`, `remove`, `insert`, `insert_before` or `insert_after`.
+    action: str
+    # For `insert`, the index at which the items are inserted.
+    index: Optional[int]
+    value: Any
+
+class PyContribution:
+    # E.g. `env`, or `config file pants.toml`.
+    source: str
+    rank: int
+    raw_values: list[PyRawValue]
+    edits: list[PyEdit]
+
+class PyExplanation:
+    option: str
+    default: Optional[Any]
+    # In order of increasing rank.
+    contributions: list[PyContribution]
+    value: Any
+    rank: int
+    # A human-readable rendering of the derivation, as returned by `str()`.
+    trace: str
+
+class PyLintFinding:
+    # E.g. `redundant-default`.
+    rule: str
+    path: str
+    # The 1-based line of the finding, if it could be located.
+    line: Optional[int]
+    message: str
+    # Whether the finding may be fixed by removing the line which sets the offending value.
+    fixable: bool
+
 class PyOptionParser:
     def __init__(
         self,
@@ -624,6 +676,15 @@ class PyOptionParser:
     def get_int(self, option_id: PyOptionId, default: Optional[int]) -> OptionValue[int]: ...
     def get_float(self, option_id: PyOptionId, default: Optional[float]) -> OptionValue[float]: ...
     def get_string(self, option_id: PyOptionId, default: Optional[str]) -> OptionValue[str]: ...
+    # The default and value are RFC 3339 dates or datetimes, such as `2024-12-31T23:59:59Z`.
+    def get_date(self, option_id: PyOptionId, default: Optional[str]) -> OptionValue[str]: ...
+    # The value is given as e.g. `80%` or `0.8`, and is returned as a fraction between 0 and 1.
+    def get_percentage(
+        self, option_id: PyOptionId, default: Optional[float]
+    ) -> OptionValue[float]: ...
+    # The default is `auto`, a multiple of the CPU count such as `0.5x`, or a positive int, and the
+    # value is resolved against the number of CPUs.
+    def get_concurrency(self, option_id: PyOptionId, default: str) -> Tuple[int, int]: ...
     def get_bool_list(
         self, option_id: PyOptionId, default: list[bool]
     ) -> OptionListValue[bool]: ...
@@ -635,7 +696,23 @@ class PyOptionParser:
         self, option_id: PyOptionId, default: list[str]
     ) -> OptionListValue[str]: ...
     def get_dict(self, option_id: PyOptionId, default: dict[str, Any]) -> OptionDictValue: ...
+    def get_dict_list(
+        self, option_id: PyOptionId, default: list[dict[str, Any]]
+    ) -> OptionListValue[dict[str, Any]]: ...
+    def get_args(self) -> list[str]: ...
     def get_passthrough_args(self) -> Optional[list[str]]: ...
+    # The option type is named as in help, e.g. `int` or `list[str]`.
+    def explain(
+        self, option_id: PyOptionId, option_type: str, default: Any
+    ) -> PyExplanation: ...
+    def lint_config(self, registry: PyOptionRegistry) -> list[PyLintFinding]: ...
+    # The effective config as TOML, with comments noting the origin of each value if `annotate`.
+    def dump_config(self, registry: PyOptionRegistry, annotate: bool) -> str: ...
+    # The config files and fromfiles consumed so far, with the sha256 digests of their content, or
+    # None for optional fromfiles that did not exist.
+    def consumed_files(self) -> dict[str, Optional[str]]: ...
+    # One of `config`, `keyring`, `env` or `flag`, or None if the option is not explicitly set.
+    def explicit_source_kind(self, option_id: PyOptionId) -> Optional[str]: ...
 
 # ------------------------------------------------------------------------------
 # Testutil
@@ -7,6 +7,7 @@ import logging
 import os
 import re
 from dataclasses import dataclass
+from enum import Enum
 from types import SimpleNamespace
 from typing import Any, Dict, Iterable, List, Mapping, Protocol, Union, cast
 
@@ -44,6 +45,31 @@ class ConfigSource(Protocol):
 DEFAULT_SECTION = "DEFAULT"
 
 
+class UnknownScopePolicy(Enum):
+    """How config sections that don't correspond to any known scope are treated by verification."""
+
+    error = "error"
+    warning = "warning"
+
+
+@dataclass(frozen=True)
+class ScopeAllowances:
+    """Describes which config sections are acceptable beyond the known scopes.
+
+    This is needed for the scopes of third-party plugins, which may only be registered after the
+    config is verified. In a pattern, `*` matches any (possibly empty) run of characters.
+    """
+
+    patterns: tuple[str, ...] = ()
+    unknown_scopes: UnknownScopePolicy = UnknownScopePolicy.error
+
+    def is_allowed(self, section: str) -> bool:
+        return any(
+            re.fullmatch(".*".join(re.escape(part) for part in pattern.split("*")), section)
+            for pattern in self.patterns
+        )
+
+
 @dataclass(frozen=True, eq=False)
 class Config:
     """Encapsulates config file loading and access, including encapsulation of support for multiple
@@ -94,10 +120,19 @@ class Config:
         }
         return _ConfigValues(config_source, toml_values, seed_values)
 
-    def verify(self, section_to_valid_options: dict[str, set[str]]):
+    def verify(
+        self,
+        section_to_valid_options: dict[str, set[str]],
+        allowances: ScopeAllowances = ScopeAllowances(),
+    ):
         error_log = []
         for config_values in self.values:
-            error_log.extend(config_values.get_verification_errors(section_to_valid_options))
+            errors, warnings = config_values.get_verification_errors(
+                section_to_valid_options, allowances
+            )
+            error_log.extend(errors)
+            for warning in warnings:
+                logger.warning(warning)
         if error_log:
             for error in error_log:
                 logger.error(error)
@@ -265,22 +300,33 @@ class _ConfigValues:
 
         return stringify(option_value)
 
-    def get_verification_errors(self, section_to_valid_options: dict[str, set[str]]) -> list[str]:
+    def get_verification_errors(
+        self,
+        section_to_valid_options: dict[str, set[str]],
+        allowances: ScopeAllowances = ScopeAllowances(),
+    ) -> tuple[list[str], list[str]]:
+        """Returns the errors and the warnings found when verifying this config file."""
         error_log = []
+        warning_log = []
         for section, vals in self.section_to_values.items():
             if section == DEFAULT_SECTION:
                 continue
             try:
                 valid_options_in_section = section_to_valid_options[section]
             except KeyError:
-                error_log.append(f"Invalid section [{section}] in {self.path}")
+                if not allowances.is_allowed(section):
+                    msg = f"Invalid section [{section}] in {self.path}"
+                    if allowances.unknown_scopes == UnknownScopePolicy.warning:
+                        warning_log.append(msg)
+                    else:
+                        error_log.append(msg)
             else:
                 for option in sorted(set(vals.keys()) - valid_options_in_section):
                     if option not in valid_options_in_section:
                         error_log.append(
                             f"Invalid option '{option}' under [{section}] in {self.path}"
                         )
-        return error_log
+        return error_log, warning_log
 
 
 @dataclass(frozen=True)
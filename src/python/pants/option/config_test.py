@@ -10,7 +10,7 @@ from typing import Dict
 import pytest
 
 from pants.engine.fs import FileContent
-from pants.option.config import Config, TomlSerializer
+from pants.option.config import Config, ScopeAllowances, TomlSerializer, UnknownScopePolicy
 
 
 @dataclass(frozen=True)
@@ -286,6 +286,58 @@ def test_merged_config() -> None:
     _compare(config, _expected_combined_values)
 
 
+def test_verify() -> None:
+    config = Config.load(
+        [
+            FileContent(
+                "file.toml",
+                dedent(
+                    """
+                    [DEFAULT]
+                    anything = "goes"
+
+                    [GLOBAL]
+                    level = "debug"
+                    bad_key = 1
+
+                    [mypy-extra]
+                    foo = 1
+
+                    [acme-lint]
+                    bar = 2
+                    """
+                ).encode(),
+            )
+        ]
+    )
+    section_to_valid_options = {"GLOBAL": {"level"}}
+
+    def check(allowances: ScopeAllowances) -> tuple[list[str], list[str]]:
+        return config.values[0].get_verification_errors(section_to_valid_options, allowances)
+
+    assert check(ScopeAllowances()) == (
+        [
+            "Invalid option 'bad_key' under [GLOBAL] in file.toml",
+            "Invalid section [mypy-extra] in file.toml",
+            "Invalid section [acme-lint] in file.toml",
+        ],
+        [],
+    )
+    assert check(ScopeAllowances(patterns=("mypy-*",))) == (
+        [
+            "Invalid option 'bad_key' under [GLOBAL] in file.toml",
+            "Invalid section [acme-lint] in file.toml",
+        ],
+        [],
+    )
+    assert check(
+        ScopeAllowances(patterns=("*-extra",), unknown_scopes=UnknownScopePolicy.warning)
+    ) == (
+        ["Invalid option 'bad_key' under [GLOBAL] in file.toml"],
+        ["Invalid section [acme-lint] in file.toml"],
+    )
+
+
 def test_toml_serializer() -> None:
     original_values: Dict = {
         "GLOBAL": {
@@ -389,5 +389,34 @@ class DictValueComponent:
             raise ParseError(f"Invalid dict value: {value}")
         return cls(action, dict(val))
 
+    @classmethod
+    def from_key_value_pair(cls, value: str) -> DictValueComponent | None:
+        """Interpret a flag value such as `KEY=VALUE` as adding a single string entry to a dict.
+
+        The key ends at the first `=` that is not escaped as `\\=` (with a literal backslash in a
+        key escaped as `\\\\`), so the value may contain `=`. Returns None for a value that is not
+        such a pair, e.g. a dict literal or a fromfile.
+        """
+        if value.startswith(("{", "@", "+{")):
+            return None
+        key = []
+        chars = iter(enumerate(value))
+        for i, c in chars:
+            if c == "\\":
+                escaped = next(chars, None)
+                if escaped is None:
+                    key.append(c)
+                elif escaped[1] in ("=", "\\"):
+                    key.append(escaped[1])
+                else:
+                    key.extend((c, escaped[1]))
+            elif c == "=":
+                if not key:
+                    return None
+                return cls(cls.EXTEND, {"".join(key): value[i + 1 :]})
+            else:
+                key.append(c)
+        return None
+
     def __repr__(self) -> str:
         return f"{self.action} {self.val}"
@@ -2,7 +2,7 @@
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 from textwrap import dedent
-from typing import Dict, List, Union
+from typing import Dict, List, Optional, Union
 
 import pytest
 
@@ -97,6 +97,28 @@ class TestCustomTypes:
         assert_dict_error("1")
         assert_dict_error('"a"')
 
+    def test_dict_key_value_pair(self) -> None:
+        def assert_pair_parsed(s: str, *, expected: Optional[ParsedDict]) -> None:
+            component = DictValueComponent.from_key_value_pair(s)
+            if expected is None:
+                assert component is None
+            else:
+                assert component is not None
+                assert component.action == DictValueComponent.EXTEND
+                assert expected == component.val
+
+        assert_pair_parsed("a=b", expected={"a": "b"})
+        assert_pair_parsed("a=", expected={"a": ""})
+        assert_pair_parsed("OPTS=--level=debug", expected={"OPTS": "--level=debug"})
+        assert_pair_parsed(r"k\=ey=v", expected={"k=ey": "v"})
+        assert_pair_parsed(r"k\\=v", expected={"k\\": "v"})
+        assert_pair_parsed(r"k\ey=v", expected={r"k\ey": "v"})
+        assert_pair_parsed("=b", expected=None)
+        assert_pair_parsed("ab", expected=None)
+        assert_pair_parsed("{'a': 'b'}", expected=None)
+        assert_pair_parsed("+{'a': 'b'}", expected=None)
+        assert_pair_parsed("@a=b.json", expected=None)
+
     def test_list(self) -> None:
         self.assert_list_parsed("[]", expected=[])
         self.assert_list_parsed("[1, 2, 3]", expected=[1, 2, 3])
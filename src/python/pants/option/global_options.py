@@ -29,6 +29,7 @@ from pants.base.glob_match_error_behavior import GlobMatchErrorBehavior
 from pants.engine.env_vars import CompleteEnvironmentVars
 from pants.engine.fs import FileContent
 from pants.engine.internals.native_engine import PyExecutor
+from pants.option.config import UnknownScopePolicy
 from pants.option.custom_types import memory_size
 from pants.option.errors import OptionsError
 from pants.option.option_types import (
@@ -1031,6 +1032,187 @@ class BootstrapOptions:
         default=["/etc/pantsrc", "~/.pants.rc", ".pants.rc"],
         help="Override config with values from these files, using syntax matching that of `--pants-config-files`.",
     )
+    user_config = BoolOption(
+        advanced=True,
+        default=True,
+        # NB: See `--pants-config-files`.
+        fingerprint=False,
+        help=softwrap(
+            """
+            Layer the machine-wide `/etc/pants/pants.toml` and the per-user
+            `$XDG_CONFIG_HOME/pants/pants.toml` (defaulting to `~/.config/pants/pants.toml`)
+            beneath the repo's config files, for personal defaults such as `[GLOBAL].dynamic_ui`.
+
+            On macOS the per-user file defaults to
+            `~/Library/Application Support/pants/pants.toml`, and on Windows the files are
+            `%PROGRAMDATA%\\pants\\pants.toml` and `%APPDATA%\\pants\\pants.toml`.
+
+            As it determines which config files are read, this option may only be set by a flag
+            or environment variable.
+            """
+        ),
+    )
+    strict_toml = BoolOption(
+        advanced=True,
+        default=False,
+        # NB: See `--pants-config-files`.
+        fingerprint=False,
+        help=softwrap(
+            """
+            Reject list and dict values in config files that are written as strings, such as
+            `"+['a']"` or `'{"k": "v"}'`, in favor of native TOML arrays and tables, with `.add`
+            and `.remove` keys for list edits. References to `@fromfile`s are still allowed.
+
+            Only takes effect with the native options parser.
+            """
+        ),
+    )
+    age_identity = StrOption(
+        advanced=True,
+        default=None,
+        metavar="<path>",
+        # NB: See `--pants-config-files`.
+        fingerprint=False,
+        help=softwrap(
+            """
+            The path of an age identity file with which to decrypt encrypted config values,
+            written as `ENC[age:<base64 of the age ciphertext>]`. Requires the `age` binary.
+
+            Only takes effect with the native options parser.
+            """
+        ),
+    )
+    decryption_command = StrListOption(
+        advanced=True,
+        # NB: See `--pants-config-files`.
+        fingerprint=False,
+        help=softwrap(
+            """
+            A command with which to decrypt encrypted config values, written as `ENC[...]`, e.g.
+            values encrypted by a SOPS-compatible flow. The command is run with the encrypted value
+            on stdin, and must write the decrypted value to stdout. Takes precedence over
+            `[GLOBAL].age_identity`.
+
+            Decrypted values are treated as secrets, and are redacted when explaining options.
+
+            Only takes effect with the native options parser.
+            """
+        ),
+    )
+    decryption_timeout_secs = IntOption(
+        advanced=True,
+        default=30,
+        # NB: See `--pants-config-files`.
+        fingerprint=False,
+        help=softwrap(
+            """
+            How long `[GLOBAL].decryption_command`, or `age`, may take to decrypt a config value
+            before it is killed, and config parsing fails. Each distinct value is decrypted at most
+            once.
+
+            Only takes effect with the native options parser.
+            """
+        ),
+    )
+    deprecations_as_errors = BoolOption(
+        advanced=True,
+        default=False,
+        help=softwrap(
+            """
+            Treat the use of deprecated options as an error rather than a warning, e.g. so that CI
+            can ensure that no deprecated options remain set before an upgrade.
+
+            Options listed in `[GLOBAL].allowed_deprecations` remain warnings.
+
+            Only takes effect with the native options parser.
+            """
+        ),
+    )
+    allowed_deprecations = StrListOption(
+        advanced=True,
+        metavar="<flag>",
+        help=softwrap(
+            """
+            The flags of deprecated options, such as `--test-output`, whose use remains a warning
+            when `[GLOBAL].deprecations_as_errors` is set.
+            """
+        ),
+    )
+    shell_interpolation = BoolOption(
+        advanced=True,
+        default=False,
+        # NB: See `--pants-config-files`.
+        fingerprint=False,
+        help=softwrap(
+            """
+            Allow config values to run commands via `%(shell <command>)s` placeholders, which
+            are replaced by the stdout of the command, e.g. `%(shell git rev-parse --short HEAD)s`.
+            Commands are run from the build root, at most once each.
+
+            As it governs how config files are parsed, this can only be set by flags or the
+            environment, e.g. by exporting `PANTS_SHELL_INTERPOLATION=true` in `.pants.bootstrap`.
+
+            Only takes effect with the native options parser.
+            """
+        ),
+    )
+    shell_interpolation_timeout_secs = IntOption(
+        advanced=True,
+        default=10,
+        # NB: See `--pants-config-files`.
+        fingerprint=False,
+        help=softwrap(
+            """
+            How long the command of a `%(shell <command>)s` placeholder, or an executable
+            fromfile, may run before it is killed, and config parsing fails. Like
+            `[GLOBAL].shell_interpolation`, this can only be set by flags or the environment.
+            """
+        ),
+    )
+    executable_fromfiles = BoolOption(
+        advanced=True,
+        default=False,
+        # NB: See `--pants-config-files`.
+        fingerprint=False,
+        help=softwrap(
+            """
+            Allow option values to run executable files via `@!path/to/script`, which is replaced
+            by the stdout of the script, e.g. `@!build-support/resolves.json.sh`. Scripts are run
+            from the build root, at most once each.
+
+            As it lets config files run commands, this can only be set by flags or the
+            environment, e.g. by exporting `PANTS_EXECUTABLE_FROMFILES=true` in
+            `.pants.bootstrap`.
+
+            Only takes effect with the native options parser.
+            """
+        ),
+    )
+    fromfile_max_size = IntOption(
+        advanced=True,
+        default=512 * 1024 * 1024,
+        help=softwrap(
+            """
+            The maximum size, in bytes, of a file that an option value refers to as a fromfile,
+            such as `@path/to/file.txt`. Larger files are an error.
+
+            Only takes effect with the native options parser.
+            """
+        ),
+    )
+    fromfile_resolve_symlinks = BoolOption(
+        advanced=True,
+        default=False,
+        help=softwrap(
+            """
+            If true, a fromfile that is a symlink is tracked by the path of the file that it links
+            to, so that changes to that file are noticed, e.g. by pantsd. Errors still refer to
+            the path that the fromfile was given as.
+
+            Only takes effect with the native options parser.
+            """
+        ),
+    )
     pythonpath = StrListOption(
         advanced=True,
         help=softwrap(
@@ -1056,6 +1238,32 @@ class BootstrapOptions:
         advanced=True,
         help="Verify that all config file values correspond to known options.",
     )
+    verify_config_allowed_scopes = StrListOption(
+        advanced=True,
+        default=[],
+        help=softwrap(
+            """
+            Config sections that `--verify-config` should accept even though they don't
+            correspond to a known scope, such as the scopes of plugins that are registered later.
+
+            In each pattern, `*` matches any run of characters, e.g. `mypy-*`.
+            """
+        ),
+    )
+    verify_config_unknown_scopes = EnumOption(
+        default=UnknownScopePolicy.error,
+        advanced=True,
+        help=softwrap(
+            f"""
+            How `--verify-config` treats config sections that don't correspond to a known scope
+            and aren't matched by `[GLOBAL].verify_config_allowed_scopes`.
+
+            - `{UnknownScopePolicy.error.value}`: Unknown sections will cause Pants to exit.
+            - `{UnknownScopePolicy.warning.value}`: Unknown sections will be logged but Pants
+              will continue.
+            """
+        ),
+    )
     stats_record_option_scopes = StrListOption(
         advanced=True,
         default=["*"],
@@ -64,6 +64,25 @@ class NativeOptionParser:
             (dict, None): self._native_parser.get_dict,
         }
 
+    @staticmethod
+    def _option_id(scope, flags) -> native_engine.PyOptionId:
+        # '--foo.bar-baz' -> ['foo', 'bar', 'baz']
+        name_parts = flags[-1][2:].replace(".", "-").split("-")
+        switch = flags[0][1:] if len(flags) > 1 else None  # '-d' -> 'd'
+        return native_engine.PyOptionId(*name_parts, scope=scope or "GLOBAL", switch=switch)
+
+    def explain(self, *, scope, flags, default, type_name: str) -> native_engine.PyExplanation:
+        """Explains how the value of an option is derived, given its type as named in help."""
+        return self._native_parser.explain(self._option_id(scope, flags), type_name, default)
+
+    def get_args(self) -> list[str]:
+        """The args, with any response files (e.g. `@args.rsp`) expanded."""
+        return self._native_parser.get_args()
+
+    def consumed_files(self) -> dict[str, Optional[str]]:
+        """The config files and fromfiles consumed so far, with the digests of their content."""
+        return self._native_parser.consumed_files()
+
     def get(
         self, *, scope, flags, default, option_type, member_type=None, passthrough=False
     ) -> Tuple[Any, Rank]:
@@ -71,10 +90,7 @@ class NativeOptionParser:
             # TODO: When we switch to Python 3.11, use: return isinstance(typ, EnumType)
             return inspect.isclass(typ) and issubclass(typ, Enum)
 
-        # '--foo.bar-baz' -> ['foo', 'bar', 'baz']
-        name_parts = flags[-1][2:].replace(".", "-").split("-")
-        switch = flags[0][1:] if len(flags) > 1 else None  # '-d' -> 'd'
-        option_id = native_engine.PyOptionId(*name_parts, scope=scope or "GLOBAL", switch=switch)
+        option_id = self._option_id(scope, flags)
 
         rust_option_type = option_type
         rust_member_type = member_type
@@ -11,7 +11,7 @@ from typing import Any, Iterable, Mapping, Sequence
 from pants.base.build_environment import get_buildroot
 from pants.base.deprecated import warn_or_error
 from pants.option.arg_splitter import ArgSplitter
-from pants.option.config import Config
+from pants.option.config import Config, ScopeAllowances
 from pants.option.errors import ConfigValidationError
 from pants.option.native_options import NativeOptionParser
 from pants.option.option_util import is_list_option
@@ -135,8 +135,10 @@ class Options:
         # We need parsers for all the intermediate scopes, so inherited option values
         # can propagate through them.
         complete_known_scope_infos = cls.complete_scopes(known_scope_infos)
+        native_parser = NativeOptionParser(args, env, config.sources(), allow_pantsrc=True)
         splitter = ArgSplitter(complete_known_scope_infos, get_buildroot())
-        split_args = splitter.split_args(args)
+        # NB: The native parser expands any response files (e.g. `@args.rsp`) in the args.
+        split_args = splitter.split_args(native_parser.get_args())
 
         if split_args.passthru and len(split_args.goals) > 1:
             raise cls.AmbiguousPassthroughError(
@@ -163,8 +165,6 @@ class Options:
         parser_by_scope = {si.scope: Parser(env, config, si) for si in complete_known_scope_infos}
         known_scope_to_info = {s.scope: s for s in complete_known_scope_infos}
 
-        native_parser = NativeOptionParser(args, env, config.sources(), allow_pantsrc=True)
-
         return cls(
             builtin_goal=split_args.builtin_goal,
             goals=split_args.goals,
@@ -256,8 +256,14 @@ class Options:
     def scope_to_flags(self) -> dict[str, list[str]]:
         return self._scope_to_flags
 
-    def verify_configs(self, global_config: Config) -> None:
-        """Verify all loaded configs have correct scopes and options."""
+    def verify_configs(
+        self, global_config: Config, allowances: ScopeAllowances = ScopeAllowances()
+    ) -> None:
+        """Verify all loaded configs have correct scopes and options.
+
+        Sections of unknown scopes are checked against the `allowances`, so that the scopes of
+        plugins registered later need not fail verification.
+        """
 
         section_to_valid_options = {}
         for scope in self.known_scope_to_info:
@@ -265,7 +271,7 @@ class Options:
             section_to_valid_options[section] = set(
                 self.for_scope(scope, check_deprecations=False, log_parser_warnings=True)
             )
-        global_config.verify(section_to_valid_options)
+        global_config.verify(section_to_valid_options, allowances)
 
     def is_known_scope(self, scope: str) -> bool:
         """Whether the given scope is known by this instance.
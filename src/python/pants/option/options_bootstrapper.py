@@ -10,7 +10,12 @@ from dataclasses import dataclass
 from pathlib import Path
 from typing import TYPE_CHECKING, Iterable, Mapping, Sequence
 
-from pants.base.build_environment import get_buildroot, get_default_pants_config_file, pants_version
+from pants.base.build_environment import (
+    get_buildroot,
+    get_default_pants_config_file,
+    get_user_config_file_paths,
+    pants_version,
+)
 from pants.base.exceptions import BuildConfigurationError
 from pants.engine.unions import UnionMembership
 from pants.option.alias import CliAlias
@@ -86,7 +91,13 @@ class OptionsBootstrapper:
             if arg.startswith(flag):
                 path_list_values.append(ListValueComponent.create(arg[len(flag) :]))
 
-        return ListValueComponent.merge(path_list_values).val
+        config_file_paths = ListValueComponent.merge(path_list_values).val
+        # An uncommitted `pants.local.toml` next to `pants.toml` is layered above the other config
+        # files, mirroring the discovery in the Rust options parser.
+        local = os.path.join(os.path.dirname(default), "pants.local.toml")
+        if Path(local).is_file() and local not in config_file_paths:
+            config_file_paths.append(local)
+        return config_file_paths
 
     @staticmethod
     def parse_bootstrap_options(
@@ -147,9 +158,17 @@ class OptionsBootstrapper:
             )
             bootstrap_option_values = initial_bootstrap_options.for_global_scope()
 
-            # Now re-read the config, post-bootstrapping. Note the order: First whatever we bootstrapped
-            # from (typically pants.toml), then config override, then rcfiles.
+            # Now re-read the config, post-bootstrapping. Note the order: First the machine-wide and
+            # per-user config, then whatever we bootstrapped from (typically pants.toml), then config
+            # override, then rcfiles.
             full_config_sources = pre_bootstrap_config.sources()
+            if allow_pantsrc and bootstrap_option_values.user_config:
+                user_config_files = [
+                    filecontent_for(p)
+                    for p in get_user_config_file_paths(env)
+                    if os.path.isfile(p) and p not in config_file_paths
+                ]
+                full_config_sources[0:0] = user_config_files
             if allow_pantsrc and bootstrap_option_values.pantsrc:
                 rcfiles = [
                     os.path.expanduser(str(rcfile))
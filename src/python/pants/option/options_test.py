@@ -113,6 +113,12 @@ def test_bool_explicit_values() -> None:
     assert_val("False", False)
     assert_val("true", True)
     assert_val("True", True)
+    assert_val("yes", True)
+    assert_val("On", True)
+    assert_val("1", True)
+    assert_val("NO", False)
+    assert_val("off", False)
+    assert_val("0", False)
 
 
 def test_bool_defaults() -> None:
@@ -218,7 +224,8 @@ def no_exception():
         (
             dict(type=bool, default="not a bool"),
             pytest.raises(
-                BooleanConversionError, match=r'Got "not a bool"\. Expected "True" or "False"\.'
+                BooleanConversionError,
+                match=r'Got "not a bool"\. Expected "True", "False", "yes", "no", "on", "off", "1" or "0"',
             ),
         ),
         (
@@ -652,6 +659,11 @@ def test_env_var_of_type_int() -> None:
     options.register(GLOBAL_SCOPE, "--foo-bar", type=list, member_type=int)
     assert [123, 456] == options.for_global_scope().foo_bar
 
+    for val, expected in (("1_000", 1000), ("0x10", 16), ("0o755", 493), ("-0b101", -5)):
+        options = create_options_object(env={"PANTS_FOO_BAR": val})
+        options.register(GLOBAL_SCOPE, "--foo-bar", type=int)
+        assert expected == options.for_global_scope().foo_bar
+
 
 def test_arg_scoping() -> None:
     # Some basic smoke tests.
@@ -981,6 +993,13 @@ def test_dict_option() -> None:
         expected={"a": "b++", "c": "d"},
     )
 
+    # A flag may also add a single entry as a `KEY=VALUE` pair.
+    check(flags="--dicty=c=d", expected=all_args)
+    check(
+        flags="--dicty=c=d --dicty=OPTS=--level=debug --dicty='k\\=ey=v'",
+        expected={**all_args, "OPTS": "--level=debug", "k=ey": "v"},
+    )
+
 
 def test_defaults() -> None:
     # Hard-coded defaults.
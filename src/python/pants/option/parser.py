@@ -89,13 +89,27 @@ class Parser:
             return val
         if isinstance(val, str):
             s = val.lower()
-            if s == "true":
+            if s in ("true", "yes", "on", "1"):
                 return True
-            if s == "false":
+            if s in ("false", "no", "off", "0"):
                 return False
-            raise BooleanConversionError(f'Got "{val}". Expected "True" or "False".')
+            raise BooleanConversionError(
+                f'Got "{val}". Expected "True", "False", "yes", "no", "on", "off", "1" or "0" '
+                "(in any case)."
+            )
         raise BooleanConversionError(f"Got {val}. Expected True or False.")
 
+    @staticmethod
+    def ensure_int(val: int | str) -> int:
+        if isinstance(val, str):
+            # As in Python literals, ints may also be written in hex, octal or binary. We try
+            # plain decimal first, since a base of 0 rejects leading zeros, e.g. `010`.
+            try:
+                return int(val)
+            except ValueError:
+                return int(val, 0)
+        return int(val)
+
     @classmethod
     def _invert(cls, s: bool | str | None) -> bool | None:
         if s is None:
@@ -525,6 +539,8 @@ class Parser:
         if member_type == dict:
             return DictValueComponent.create(value).val
         try:
+            if member_type == int:
+                return Parser.ensure_int(value)
             return member_type(value)
         except ValueError as error:
             raise ParseError(str(error))
@@ -541,6 +557,8 @@ class Parser:
                 return ListValueComponent.create(val_str, member_type=member_type)
             if type_arg == dict:
                 return DictValueComponent.create(val_str)
+            if type_arg == int:
+                return cls.ensure_int(val_str)
             return type_arg(val_str)
         except (TypeError, ValueError) as e:
             if issubclass(type_arg, Enum):
@@ -653,6 +671,12 @@ class Parser:
 
         # Get value from cmd-line flags.
         flag_vals = list(flag_val_strs)
+        if is_dict_option(kwargs):
+            # A dict flag may also add a single entry as `KEY=VALUE`.
+            flag_vals = [
+                (DictValueComponent.from_key_value_pair(v) or v) if isinstance(v, str) else v
+                for v in flag_vals
+            ]
         if kwargs.get("passthrough") and passthru_arg_strs:
             # NB: Passthrough arguments are either of type `str` or `shell_str`
             # (see self._validate): the former never need interpretation, and the latter do not
@@ -65,7 +65,7 @@ async fn execute(start: SystemTime) -> Result<i32, String> {
     let mut keys_with_non_utf8_values = dropped.keys_with_non_utf8_values;
     keys_with_non_utf8_values.sort();
     for name in keys_with_non_utf8_values {
-        log::warn!("Environment variable with non-UTF-8 value ignored: {name}");
+        log::warn!("Environment variable with non-UTF-8 value read lossily: {name}");
     }
     let mut non_utf8_keys = dropped.non_utf8_keys;
     non_utf8_keys.sort();
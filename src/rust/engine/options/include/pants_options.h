@@ -0,0 +1,45 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+// The C ABI of the options crate, when built with the `cdylib` feature: see `src/ffi.rs`.
+//
+// Every request and response is a NUL-terminated JSON string. A response describing a failure
+// has the form `{"error": "..."}`. Every response, and every handle, must be freed with the
+// corresponding `pants_options_free_*` function.
+
+#ifndef PANTS_OPTIONS_H
+#define PANTS_OPTIONS_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct OptionsHandle OptionsHandle;
+
+// Request: {"args": [...], "env": {...}, "config_files": [...], "allow_pantsrc": false,
+//           "buildroot": "..."}, where every field is optional. If `config_files` is absent, the
+//           config files are discovered as by Pants.
+//
+// Returns null on failure, in which case `*error` (if `error` is not null) is set to a response.
+OptionsHandle *pants_options_parse(const char *request, char **error);
+
+// Request: {"scope": "...", "name": "...", "type": "...", "default": ...}, where `scope` is
+//          absent for global options, `name` is as in the flag of the option (e.g.
+//          `pants-workdir`), and `type` is as named in help (e.g. `int` or `list[str]`).
+// Response: {"value": ..., "source": {"type": "..."}, "rank": ...}
+char *pants_options_get(const OptionsHandle *handle, const char *request);
+
+// Request: {"sections": {"<section>": ["<key>", ...]}, "allow": ["<pattern>", ...],
+//           "warn_on_unknown_scopes": false}, where only `sections` is required.
+// Response: {"errors": [...], "warnings": [...]}
+char *pants_options_validate(const OptionsHandle *handle, const char *request);
+
+void pants_options_free_parser(OptionsHandle *handle);
+
+void pants_options_free_string(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // PANTS_OPTIONS_H
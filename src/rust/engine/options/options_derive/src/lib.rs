@@ -0,0 +1,221 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, Fields, Ident, LitChar, LitStr,
+    Type,
+};
+
+///
+/// Derives `options::Subsystem` for a struct with named fields, each of which is an option of a
+/// single scope.
+///
+/// The scope is given by `#[options(scope = "...")]` on the struct, and is the global scope if
+/// that is omitted. Each field may be annotated with `#[option(...)]`, which accepts:
+///
+/// * `name = "..."`: The name of the option, as in its flag, e.g. `"pants-workdir"`. Defaults to
+///   the name of the field, with underscores in place of dashes.
+/// * `short = '.'`: A single-character flag for the option.
+/// * `default = ...`: An expression that converts `Into` the type of the field. Defaults to the
+///   `Default` of the type.
+/// * `help = "..."`, `advanced`, `group = "..."` and `deprecated = "..."`: As for
+///   `options::OptionInfo`.
+///
+/// The type of each field must implement `options::OptionField`.
+///
+#[proc_macro_derive(Subsystem, attributes(options, option))]
+pub fn derive_subsystem(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+// A field of the struct, and the option that it holds.
+struct FieldOption {
+    ident: Ident,
+    ty: Type,
+    name: Vec<String>,
+    short: Option<LitChar>,
+    default: Option<Expr>,
+    help: Option<LitStr>,
+    advanced: bool,
+    group: Option<LitStr>,
+    deprecated: Option<LitStr>,
+}
+
+impl FieldOption {
+    fn parse(ident: Ident, ty: Type, attrs: &[Attribute]) -> Result<Self, Error> {
+        let field_name = ident.to_string();
+        let mut option = FieldOption {
+            name: name_components(field_name.trim_start_matches("r#"), '_'),
+            ident,
+            ty,
+            short: None,
+            default: None,
+            help: None,
+            advanced: false,
+            group: None,
+            deprecated: None,
+        };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("option")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    option.name = name_components(&name.value(), '-');
+                    if option.name.is_empty() {
+                        return Err(Error::new(name.span(), "An option name may not be empty."));
+                    }
+                } else if meta.path.is_ident("short") {
+                    option.short = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default") {
+                    option.default = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("help") {
+                    option.help = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("advanced") {
+                    option.advanced = true;
+                } else if meta.path.is_ident("group") {
+                    option.group = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("deprecated") {
+                    option.deprecated = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("Unrecognized `option` attribute."));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(option)
+    }
+
+    fn option_id(&self, scope: &TokenStream2) -> TokenStream2 {
+        let name = &self.name;
+        let short = match &self.short {
+            Some(short) => quote!(::std::option::Option::Some(#short)),
+            None => quote!(::std::option::Option::None),
+        };
+        quote! {
+            ::options::OptionId::new(#scope, [#(#name),*].iter(), #short)
+                .expect("The derived name of an option should be non-empty")
+        }
+    }
+
+    fn default(&self) -> TokenStream2 {
+        let ty = &self.ty;
+        match &self.default {
+            Some(default) => quote!(::std::convert::Into::<#ty>::into(#default)),
+            None => quote!(<#ty as ::std::default::Default>::default()),
+        }
+    }
+}
+
+fn name_components(name: &str, separator: char) -> Vec<String> {
+    name.split(separator)
+        .filter(|component| !component.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn scope(attrs: &[Attribute]) -> Result<TokenStream2, Error> {
+    let mut scope = quote!(::options::Scope::Global);
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("options")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("scope") {
+                let name: LitStr = meta.value()?.parse()?;
+                scope = quote!(::options::Scope::named(#name));
+                Ok(())
+            } else {
+                Err(meta.error("Unrecognized `options` attribute."))
+            }
+        })?;
+    }
+    Ok(scope)
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "A Subsystem must be a struct with named fields.",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "A Subsystem must be a struct with named fields.",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "A Subsystem may not be generic.",
+        ));
+    }
+    let scope = scope(&input.attrs)?;
+    let options = fields
+        .iter()
+        .map(|field| {
+            FieldOption::parse(
+                field.ident.clone().expect("Named fields have idents"),
+                field.ty.clone(),
+                &field.attrs,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let registrations = options.iter().map(|option| {
+        let ty = &option.ty;
+        let id = option.option_id(&scope);
+        let default = option.default();
+        let help = option.help.iter();
+        let advanced = option.advanced.then(|| quote!(.advanced()));
+        let group = option.group.iter();
+        let deprecated = option.deprecated.iter();
+        quote! {
+            registry.register(
+                <#ty as ::options::OptionField>::option_info(#id, &#default)
+                    #(.help(#help))*
+                    #advanced
+                    #(.group(#group))*
+                    #(.deprecated(#deprecated))*
+            )?;
+        }
+    });
+    let parses = options.iter().map(|option| {
+        let ident = &option.ident;
+        let ty = &option.ty;
+        let id = option.option_id(&scope);
+        let default = option.default();
+        quote! {
+            #ident: <#ty as ::options::OptionField>::read(parser, &#id, #default)?,
+        }
+    });
+
+    let name = &input.ident;
+    Ok(quote! {
+        impl ::options::Subsystem for #name {
+            fn register(
+                registry: &mut ::options::OptionRegistry,
+            ) -> ::std::result::Result<(), ::std::string::String> {
+                #(#registrations)*
+                ::std::result::Result::Ok(())
+            }
+
+            fn parse(
+                parser: &::options::OptionParser,
+            ) -> ::std::result::Result<Self, ::std::string::String> {
+                ::std::result::Result::Ok(Self {
+                    #(#parses)*
+                })
+            }
+        }
+    })
+}
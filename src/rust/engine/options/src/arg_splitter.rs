@@ -0,0 +1,388 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{HashMap, HashSet};
+use std::path::{PathBuf, MAIN_SEPARATOR};
+
+use indexmap::IndexMap;
+
+use crate::id::Scope;
+
+// The names of the builtin goals that are requested when there is no goal, or an unknown goal,
+// respectively, if they are known to the splitter.
+pub const NO_GOAL_NAME: &str = "__no_goal";
+pub const UNKNOWN_GOAL_NAME: &str = "__unknown_goal";
+
+// The `--level` global option is also recognized as `-l`, as in `-ldebug`.
+const LEVEL_SHORT_ARGS: &[&str] = &["-ltrace", "-ldebug", "-linfo", "-lwarn", "-lerror"];
+
+///
+/// Where an arg was found: its index in the args, and its byte range in the command line formed
+/// by joining the args with single spaces (as in e.g. `$COMP_LINE` for shell completion).
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ArgSpan {
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A goal, spec or passthrough arg, and where it was found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplitArg {
+    pub value: String,
+    pub span: ArgSpan,
+}
+
+///
+/// A flag, and the scope that it applies to. A flag that names its scope explicitly, such as
+/// `--test-output=all`, is descoped, i.e. it is `--output=all` in the `test` scope.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplitFlag {
+    pub scope: Scope,
+    pub flag: String,
+    pub span: ArgSpan,
+}
+
+/// The result of splitting args.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SplitArgs {
+    // The requested builtin goal, if any, which may have been requested implicitly, e.g. by
+    // requesting no goal at all.
+    pub builtin_goal: Option<String>,
+    // The explicitly requested goals, by their canonical names, at their first occurrence.
+    pub goals: Vec<SplitArg>,
+    pub unknown_goals: Vec<SplitArg>,
+    pub flags: Vec<SplitFlag>,
+    // The specs to run against, e.g. targets or files and directories.
+    pub specs: Vec<SplitArg>,
+    // The args after a `--` separator.
+    pub passthru: Vec<SplitArg>,
+}
+
+impl SplitArgs {
+    ///
+    /// The flags of each scope. Every requested goal (including any explicitly requested builtin
+    /// goal, and any unknown goal) and the global scope are present, even if they have no flags.
+    ///
+    pub fn scope_to_flags(&self) -> IndexMap<Scope, Vec<&str>> {
+        let mut scope_to_flags = IndexMap::new();
+        scope_to_flags.insert(Scope::Global, vec![]);
+        let goals = self
+            .goals
+            .iter()
+            .chain(&self.unknown_goals)
+            .map(|goal| goal.value.as_str())
+            .chain(
+                self.builtin_goal
+                    .as_deref()
+                    .filter(|goal| ![NO_GOAL_NAME, UNKNOWN_GOAL_NAME].contains(goal)),
+            );
+        for goal in goals {
+            scope_to_flags.entry(Scope::named(goal)).or_default();
+        }
+        for flag in &self.flags {
+            scope_to_flags
+                .entry(flag.scope.clone())
+                .or_default()
+                .push(flag.flag.as_str());
+        }
+        scope_to_flags
+    }
+}
+
+#[derive(Clone, Debug)]
+struct GoalInfo {
+    name: String,
+    builtin: bool,
+}
+
+///
+/// Splits a command line into goals, scoped flags, specs and passthrough args, given the goals
+/// and scopes that are known. Recognizes, e.g.:
+///
+/// ```text
+/// pants check --foo lint target1: dir f.ext
+/// pants --global-opt check target1: dir f.ext --check-flag
+/// pants --check-flag check target1: dir f.ext
+/// pants goal -- passthru foo
+/// ```
+///
+/// This is the splitter of the CLI front-end, and of shell completion, so that they agree on how
+/// a command line is read.
+///
+#[derive(Clone, Debug)]
+pub struct ArgSplitter {
+    build_root: PathBuf,
+    // Keyed by the names and aliases of the goals.
+    goals: HashMap<String, GoalInfo>,
+    // The names of all known scopes, including goals and their aliases.
+    scopes: HashSet<String>,
+    // The `<scope>-` prefixes of flags that name their scope explicitly, in reverse order, so that
+    // the longest matching prefix is found first.
+    scoping_prefixes: Vec<(String, Scope)>,
+}
+
+impl ArgSplitter {
+    /// Creates a splitter that detects specs relative to the given build root.
+    pub fn new(build_root: impl Into<PathBuf>) -> Self {
+        Self {
+            build_root: build_root.into(),
+            goals: HashMap::new(),
+            scopes: HashSet::new(),
+            scoping_prefixes: vec![],
+        }
+    }
+
+    /// Adds a known scope that is not a goal, e.g. that of a subsystem.
+    pub fn scope(mut self, name: &str) -> Self {
+        self.add_scope(name);
+        self
+    }
+
+    /// Adds a known goal, which may also be requested via any of the given aliases.
+    pub fn goal(self, name: &str, aliases: &[&str]) -> Self {
+        self.add_goal(name, aliases, false)
+    }
+
+    ///
+    /// Adds a known builtin goal, such as `help`, which may also be requested via any of the given
+    /// aliases, such as `-h`.
+    ///
+    pub fn builtin_goal(self, name: &str, aliases: &[&str]) -> Self {
+        self.add_goal(name, aliases, true)
+    }
+
+    fn add_goal(mut self, name: &str, aliases: &[&str], builtin: bool) -> Self {
+        self.add_scope(name);
+        let info = GoalInfo {
+            name: name.to_owned(),
+            builtin,
+        };
+        for goal in aliases.iter().copied().chain([name]) {
+            self.goals.insert(goal.to_owned(), info.clone());
+            self.scopes.insert(goal.to_owned());
+        }
+        self
+    }
+
+    fn add_scope(&mut self, name: &str) {
+        if self.scopes.insert(name.to_owned()) {
+            self.scoping_prefixes
+                .push((format!("{name}-"), Scope::named(name)));
+            self.scoping_prefixes.sort_by(|(a, _), (b, _)| b.cmp(a));
+        }
+    }
+
+    ///
+    /// Whether the arg looks like a spec, rather than a goal: i.e. whether it is an ignore spec
+    /// (`-dir::`), contains characters that only occur in specs, or names a file or directory in
+    /// the build root.
+    ///
+    pub fn likely_a_spec(&self, arg: &str) -> bool {
+        if arg.starts_with('-') && !arg.starts_with("--") && !self.is_single_dash_goal(arg) {
+            return true;
+        }
+        arg.contains([MAIN_SEPARATOR, '.', ':', '*', '#']) || self.build_root.join(arg).exists()
+    }
+
+    fn is_single_dash_goal(&self, arg: &str) -> bool {
+        !arg.starts_with("--") && arg.starts_with('-') && self.goals.contains_key(arg)
+    }
+
+    ///
+    /// Splits the given args, which must *not* include the argv[0] process name.
+    ///
+    pub fn split_args<I: IntoIterator<Item = String>>(&self, args: I) -> SplitArgs {
+        let mut offset = 0;
+        let args = args
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let span = ArgSpan {
+                    index,
+                    start: offset,
+                    end: offset + value.len(),
+                };
+                offset = span.end + 1;
+                SplitArg { value, span }
+            })
+            .collect::<Vec<_>>();
+        Split {
+            splitter: self,
+            args: &args,
+            next: 0,
+            result: SplitArgs::default(),
+            builtin_span: None,
+        }
+        .split()
+    }
+}
+
+// The state of splitting a single command line.
+struct Split<'a> {
+    splitter: &'a ArgSplitter,
+    args: &'a [SplitArg],
+    next: usize,
+    result: SplitArgs,
+    // Where the builtin goal was requested, if it was requested explicitly.
+    builtin_span: Option<ArgSpan>,
+}
+
+impl<'a> Split<'a> {
+    fn split(mut self) -> SplitArgs {
+        for flag in self.consume_flags() {
+            self.add_flag(flag, &Scope::Global);
+        }
+
+        while let Some(goal) = self.consume_scope() {
+            let flags = self.consume_flags();
+            let scope = self.add_goal(goal);
+            for flag in flags {
+                let scope = if is_level_short_arg(&flag.value) {
+                    Scope::Global
+                } else {
+                    scope.clone()
+                };
+                self.add_flag(flag, &scope);
+            }
+        }
+
+        while let Some(arg) = self.peek() {
+            if arg.value == "--" {
+                break;
+            }
+            self.next += 1;
+            if self.at_flag_value(arg) {
+                // Flags after the specs apply to the global scope.
+                self.add_flag(arg, &Scope::Global);
+            } else if self.splitter.likely_a_spec(&arg.value) {
+                self.result.specs.push(arg.clone());
+            } else {
+                self.add_goal(arg);
+            }
+        }
+
+        if self.result.builtin_goal.is_none() {
+            let implicit_goal = if !self.result.unknown_goals.is_empty() {
+                Some(UNKNOWN_GOAL_NAME)
+            } else if self.result.goals.is_empty() {
+                Some(NO_GOAL_NAME)
+            } else {
+                None
+            };
+            self.result.builtin_goal = implicit_goal
+                .filter(|goal| self.splitter.goals.contains_key(*goal))
+                .map(str::to_owned);
+        }
+
+        if self.peek().is_some_and(|arg| arg.value == "--") {
+            self.result.passthru = self.args[self.next + 1..].to_vec();
+        }
+        self.result
+    }
+
+    fn peek(&self) -> Option<&'a SplitArg> {
+        self.args.get(self.next)
+    }
+
+    // Whether the arg is a flag, rather than a scope, spec or `--` separator.
+    fn at_flag_value(&self, arg: &SplitArg) -> bool {
+        (arg.value.starts_with("--") || is_level_short_arg(&arg.value))
+            && arg.value != "--"
+            && !self.splitter.scopes.contains(&arg.value)
+    }
+
+    // Consumes flags until the first arg that is not a flag.
+    fn consume_flags(&mut self) -> Vec<&'a SplitArg> {
+        let mut flags = vec![];
+        while let Some(arg) = self.peek().filter(|arg| self.at_flag_value(arg)) {
+            flags.push(arg);
+            self.next += 1;
+        }
+        flags
+    }
+
+    fn consume_scope(&mut self) -> Option<&'a SplitArg> {
+        let arg = self
+            .peek()
+            .filter(|arg| self.splitter.scopes.contains(&arg.value))?;
+        self.next += 1;
+        Some(arg)
+    }
+
+    // Records a goal, returning the scope to which the flags that follow it apply.
+    fn add_goal(&mut self, arg: &SplitArg) -> Scope {
+        let Some(info) = self.splitter.goals.get(&arg.value) else {
+            self.result.unknown_goals.push(arg.clone());
+            return Scope::named(&arg.value);
+        };
+        let goal = SplitArg {
+            value: info.name.clone(),
+            span: arg.span,
+        };
+        // NB: A builtin goal requested via a single dash alias (e.g. `-h`) takes precedence over
+        // one requested by name, which is then treated as a regular goal.
+        if info.builtin && (self.result.builtin_goal.is_none() || arg.value.starts_with('-')) {
+            if let Some(previous) = self.result.builtin_goal.take() {
+                if let Some(span) = self.builtin_span {
+                    self.add_regular_goal(SplitArg {
+                        value: previous,
+                        span,
+                    });
+                }
+            }
+            self.result.builtin_goal = Some(info.name.clone());
+            self.builtin_span = Some(arg.span);
+        } else {
+            self.add_regular_goal(goal);
+        }
+        // Flags apply to the builtin goal, if any.
+        Scope::named(
+            self.result
+                .builtin_goal
+                .as_deref()
+                .unwrap_or(info.name.as_str()),
+        )
+    }
+
+    fn add_regular_goal(&mut self, goal: SplitArg) {
+        if !self
+            .result
+            .goals
+            .iter()
+            .any(|existing| existing.value == goal.value)
+        {
+            self.result.goals.push(goal);
+        }
+    }
+
+    fn add_flag(&mut self, arg: &SplitArg, default_scope: &Scope) {
+        let (scope, flag) = self.descope(&arg.value, default_scope);
+        self.result.flags.push(SplitFlag {
+            scope,
+            flag,
+            span: arg.span,
+        });
+    }
+
+    // If the flag names its scope explicitly, splits the scope off of it.
+    fn descope(&self, flag: &str, default_scope: &Scope) -> (Scope, String) {
+        for (scoping_prefix, scope) in &self.splitter.scoping_prefixes {
+            for flag_prefix in ["--", "--no-"] {
+                if let Some(rest) = flag
+                    .strip_prefix(flag_prefix)
+                    .and_then(|rest| rest.strip_prefix(scoping_prefix.as_str()))
+                {
+                    return (scope.clone(), format!("{flag_prefix}{rest}"));
+                }
+            }
+        }
+        (default_scope.clone(), flag.to_owned())
+    }
+}
+
+fn is_level_short_arg(arg: &str) -> bool {
+    LEVEL_SHORT_ARGS.contains(&arg)
+}
@@ -0,0 +1,332 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fs;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use tempfile::TempDir;
+
+use crate::{ArgSpan, ArgSplitter, Scope, SplitArg, SplitArgs, NO_GOAL_NAME, UNKNOWN_GOAL_NAME};
+
+fn splitter(build_root: &Path) -> ArgSplitter {
+    ArgSplitter::new(build_root)
+        .goal("check", &[])
+        .goal("test", &[])
+        .scope("jvm")
+        .scope("reporting")
+        .builtin_goal("bsp", &[])
+        .builtin_goal("help", &["-h", "--help"])
+        .builtin_goal("help-advanced", &["--help-advanced"])
+        .builtin_goal("help-all", &[])
+        .builtin_goal("version", &["-v", "-V", "--version"])
+        .builtin_goal(NO_GOAL_NAME, &[])
+        .builtin_goal(UNKNOWN_GOAL_NAME, &[])
+}
+
+fn split(command_line: &str) -> SplitArgs {
+    let build_root = TempDir::new().unwrap();
+    splitter(build_root.path()).split_args(command_line.split_whitespace().map(str::to_owned))
+}
+
+fn values(args: &[SplitArg]) -> Vec<&str> {
+    args.iter().map(|arg| arg.value.as_str()).collect()
+}
+
+// The scope of each flag, where the global scope is "".
+fn assert_split(
+    command_line: &str,
+    goals: &[&str],
+    scope_to_flags: &[(&str, &[&str])],
+    specs: &[&str],
+    passthru: &[&str],
+) {
+    let split_args = split(command_line);
+    assert_eq!(goals, values(&split_args.goals), "{command_line}");
+    let expected_scope_to_flags = scope_to_flags
+        .iter()
+        .map(|(scope, flags)| {
+            let scope = if scope.is_empty() {
+                Scope::Global
+            } else {
+                Scope::named(scope)
+            };
+            (scope, flags.to_vec())
+        })
+        .collect::<IndexMap<_, _>>();
+    assert_eq!(
+        expected_scope_to_flags,
+        split_args.scope_to_flags(),
+        "{command_line}"
+    );
+    assert_eq!(specs, values(&split_args.specs), "{command_line}");
+    assert_eq!(passthru, values(&split_args.passthru), "{command_line}");
+}
+
+#[test]
+fn test_is_spec() {
+    let build_root = TempDir::new().unwrap();
+    let splitter = splitter(build_root.path());
+    for spec in [
+        "a/b/c",
+        "a/b:c",
+        ":c",
+        "::",
+        "./a.txt",
+        ".",
+        "*",
+        "a/**/*",
+        "a/b.txt:../tgt",
+        "dir#gen",
+        "//:tgt#gen",
+        "cache.tmp.java",
+    ] {
+        assert!(splitter.likely_a_spec(spec), "{spec}");
+        assert!(splitter.likely_a_spec(&format!("-{spec}")), "-{spec}");
+    }
+    for goal in ["foo", "a_b_c"] {
+        assert!(!splitter.likely_a_spec(goal), "{goal}");
+        assert!(splitter.likely_a_spec(&format!("-{goal}")), "-{goal}");
+    }
+    assert!(splitter.likely_a_spec("-"));
+    assert!(!splitter.likely_a_spec("--"));
+    assert!(!splitter.likely_a_spec("-h"));
+
+    // Directories in the build root are specs.
+    fs::create_dir(build_root.path().join("foo")).unwrap();
+    assert!(splitter.likely_a_spec("foo"));
+}
+
+#[test]
+fn test_valid_arg_splits() {
+    assert_split(
+        "--check-long-flag --gg -ltrace check --cc test --ii src/foo src/bar:baz",
+        &["check", "test"],
+        &[
+            ("", &["--gg", "-ltrace"]),
+            ("check", &["--long-flag", "--cc"]),
+            ("test", &["--ii"]),
+        ],
+        &["src/foo", "src/bar:baz"],
+        &[],
+    );
+    assert_split(
+        "--fff=arg check --gg-gg=arg-arg test --iii --check-long-flag src/foo -ltrace --another",
+        &["check", "test"],
+        &[
+            ("", &["--fff=arg", "-ltrace", "--another"]),
+            ("check", &["--gg-gg=arg-arg", "--long-flag"]),
+            ("test", &["--iii"]),
+        ],
+        &["src/foo"],
+        &[],
+    );
+    assert_split(
+        "check test foo::",
+        &["check", "test"],
+        &[("", &[]), ("check", &[]), ("test", &[])],
+        &["foo::"],
+        &[],
+    );
+    assert_split(
+        "check test:test",
+        &["check"],
+        &[("", &[]), ("check", &[])],
+        &["test:test"],
+        &[],
+    );
+    for spec in [
+        "test:test",
+        "./test",
+        "//test",
+        ".",
+        "*",
+        "-",
+        "-a/b",
+        "check.java",
+    ] {
+        assert_split(
+            &format!("test {spec}"),
+            &["test"],
+            &[("", &[]), ("test", &[])],
+            &[spec],
+            &[],
+        );
+    }
+}
+
+#[test]
+fn test_descoping_qualified_flags() {
+    assert_split(
+        "check test --check-bar --no-test-baz foo/bar",
+        &["check", "test"],
+        &[("", &[]), ("check", &["--bar"]), ("test", &["--no-baz"])],
+        &["foo/bar"],
+        &[],
+    );
+    // Qualified flags don't count as explicit goals.
+    assert_split(
+        "check --test-bar foo/bar",
+        &["check"],
+        &[("", &[]), ("check", &[]), ("test", &["--bar"])],
+        &["foo/bar"],
+        &[],
+    );
+    assert_split(
+        "--jvm-options=-Dbar=baz test --reporting-template-dir=path foo:bar",
+        &["test"],
+        &[
+            ("", &[]),
+            ("jvm", &["--options=-Dbar=baz"]),
+            ("test", &[]),
+            ("reporting", &["--template-dir=path"]),
+        ],
+        &["foo:bar"],
+        &[],
+    );
+}
+
+#[test]
+fn test_passthru_args() {
+    assert_split(
+        "-lerror --fff=arg check --gg=arg test --iii src/foo -- passthru1 passthru2 -linfo",
+        &["check", "test"],
+        &[
+            ("", &["-lerror", "--fff=arg"]),
+            ("check", &["--gg=arg"]),
+            ("test", &["--iii"]),
+        ],
+        &["src/foo"],
+        &["passthru1", "passthru2", "-linfo"],
+    );
+}
+
+#[test]
+fn test_help_detection() {
+    for (command_line, builtin_goal) in [
+        ("", NO_GOAL_NAME),
+        ("help", "help"),
+        ("-h", "help"),
+        ("--help", "help"),
+        ("--help-advanced", "help-advanced"),
+        ("help-all", "help-all"),
+    ] {
+        assert_eq!(
+            Some(builtin_goal),
+            split(command_line).builtin_goal.as_deref(),
+            "{command_line}"
+        );
+    }
+
+    // A builtin goal requested via a dash alias takes precedence.
+    assert_split(
+        "--help --help-advanced --builtin-option --help-advanced-option",
+        &["help"],
+        &[
+            ("", &[]),
+            ("help", &[]),
+            ("help-advanced", &["--builtin-option", "--option"]),
+        ],
+        &[],
+        &[],
+    );
+    assert_eq!(
+        Some("help-advanced"),
+        split("--help --help-advanced").builtin_goal.as_deref()
+    );
+    let split_args = split("check help-all test --help");
+    assert_eq!(Some("help"), split_args.builtin_goal.as_deref());
+    assert_eq!(vec!["check", "test", "help-all"], values(&split_args.goals));
+
+    assert_split(
+        "check -h -linfo",
+        &["check"],
+        &[("", &["-linfo"]), ("check", &[]), ("help", &[])],
+        &[],
+        &[],
+    );
+    assert_split(
+        "help check -x",
+        &["check"],
+        &[("", &[]), ("help", &[]), ("check", &[])],
+        &["-x"],
+        &[],
+    );
+    assert_split(
+        "test src/foo/bar:baz --help",
+        &["test"],
+        &[("", &[]), ("test", &[]), ("help", &[])],
+        &["src/foo/bar:baz"],
+        &[],
+    );
+    assert_eq!(
+        Some("version"),
+        split("--version check --foo path/to:tgt")
+            .builtin_goal
+            .as_deref()
+    );
+}
+
+#[test]
+fn test_unknown_goal_detection() {
+    for (command_line, unknown_goals) in [
+        ("foo", vec!["foo"]),
+        ("check foo", vec!["foo"]),
+        ("foo bar baz:qux", vec!["foo", "bar"]),
+        ("foo check bar baz:qux", vec!["foo", "bar"]),
+        // Subsystem scopes are not goals.
+        ("jvm reporting", vec!["jvm", "reporting"]),
+    ] {
+        let split_args = split(command_line);
+        assert_eq!(
+            Some(UNKNOWN_GOAL_NAME),
+            split_args.builtin_goal.as_deref(),
+            "{command_line}"
+        );
+        assert_eq!(unknown_goals, values(&split_args.unknown_goals));
+    }
+    for command_line in ["foo/bar:baz", "f.ext", "-linfo", "--arg"] {
+        assert_eq!(
+            Some(NO_GOAL_NAME),
+            split(command_line).builtin_goal.as_deref(),
+            "{command_line}"
+        );
+    }
+}
+
+#[test]
+fn test_spans() {
+    let split_args = split("--test-output=all test src:: -- -k foo");
+    assert_eq!(
+        ArgSpan {
+            index: 0,
+            start: 0,
+            end: 17
+        },
+        split_args.flags[0].span
+    );
+    assert_eq!(
+        ArgSpan {
+            index: 1,
+            start: 18,
+            end: 22
+        },
+        split_args.goals[0].span
+    );
+    assert_eq!(
+        ArgSpan {
+            index: 2,
+            start: 23,
+            end: 28
+        },
+        split_args.specs[0].span
+    );
+    assert_eq!(
+        vec![(4, 32, 34), (5, 35, 38)],
+        split_args
+            .passthru
+            .iter()
+            .map(|arg| (arg.span.index, arg.span.start, arg.span.end))
+            .collect::<Vec<_>>()
+    );
+}
@@ -1,17 +1,22 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+use std::collections::{BTreeSet, HashMap};
 use std::env;
 
 use super::id::{is_valid_scope_name, NameTransform, OptionId, Scope};
-use super::{DictEdit, OptionsSource};
-use crate::fromfile::FromfileExpander;
+use super::{DictEdit, DictEditAction, OptionsSource, RawValue, Val};
+use crate::fromfile::{is_stdin, FromfileExpander};
+use crate::merge::{merge_list, ConflictStrategy};
 use crate::parse::{ParseError, Parseable};
-use crate::ListEdit;
+use crate::registry::OptionRegistry;
+use crate::spelling::Spellings;
+use crate::{ListEdit, ListEditAction};
 use core::iter::once;
+use indexmap::IndexMap;
 use itertools::{chain, Itertools};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Arg {
     context: Scope,
     flag: String,
@@ -83,24 +88,40 @@ impl Arg {
     }
 }
 
-#[derive(Debug)]
+///
+/// The args after `--`, which are passed through to the goal in whose scope they appear, e.g. to
+/// the `test` goal in `pants test src/python:: -- -k test_foo`.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PassthroughArgs {
+    pub scope: Scope,
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
 pub struct Args {
+    pub(crate) arg_strs: Vec<String>,
     args: Vec<Arg>,
     passthrough_args: Option<Vec<String>>,
+    // The scope in effect at the `--`, if any.
+    passthrough_scope: Scope,
 }
 
 impl Args {
     // Create an Args instance with the provided args, which must *not* include the
     // argv[0] process name.
     pub fn new<I: IntoIterator<Item = String>>(arg_strs: I) -> Self {
+        let arg_strs = arg_strs.into_iter().collect::<Vec<_>>();
         let mut args: Vec<Arg> = vec![];
         let mut passthrough_args: Option<Vec<String>> = None;
+        let mut passthrough_scope = Scope::Global;
         let mut scope = Scope::Global;
-        let mut args_iter = arg_strs.into_iter();
+        let mut args_iter = arg_strs.clone().into_iter();
         while let Some(arg_str) = args_iter.next() {
             if arg_str == "--" {
                 // We've hit the passthrough args delimiter (`--`).
                 passthrough_args = Some(args_iter.collect::<Vec<String>>());
+                passthrough_scope = scope;
                 break;
             } else if arg_str.starts_with("--") {
                 let mut components = arg_str.splitn(2, '=');
@@ -131,9 +152,40 @@ impl Args {
         }
 
         Self {
+            arg_strs,
             args,
             passthrough_args,
+            passthrough_scope,
+        }
+    }
+
+    ///
+    /// The args with each response file among them, e.g. `@args.rsp`, replaced by the args that
+    /// it contains, one per line, ignoring blank lines and comment lines that start with `#`. A
+    /// response file may itself contain response files, and `@@` escapes an arg that starts with
+    /// `@`. Args after `--` are passthrough args, which are not expanded here.
+    ///
+    /// This keeps very long command lines, e.g. of CI builds, within the argv limits of the OS.
+    ///
+    pub(crate) fn expand_response_files(
+        self,
+        fromfile_expander: &FromfileExpander,
+    ) -> Result<Self, String> {
+        if !self.arg_strs.iter().any(|arg| arg.starts_with('@')) {
+            return Ok(self);
+        }
+        let mut arg_strs = vec![];
+        let mut passthrough = false;
+        for arg in self.arg_strs {
+            expand_response_file(
+                fromfile_expander,
+                arg,
+                &mut vec![],
+                &mut passthrough,
+                &mut arg_strs,
+            )?;
         }
+        Ok(Self::new(arg_strs))
     }
 
     pub fn argv() -> Self {
@@ -143,6 +195,86 @@ impl Args {
     }
 }
 
+// Expands an arg into `expanded`, which is a response file unless it follows a `--`, given the
+// response files that it is nested in.
+fn expand_response_file(
+    fromfile_expander: &FromfileExpander,
+    arg: String,
+    nesting: &mut Vec<String>,
+    passthrough: &mut bool,
+    expanded: &mut Vec<String>,
+) -> Result<(), String> {
+    if *passthrough || !arg.starts_with('@') {
+        *passthrough |= arg == "--";
+        expanded.push(arg);
+        return Ok(());
+    }
+    if let Some(escaped) = arg.strip_prefix("@@") {
+        expanded.push(format!("@{escaped}"));
+        return Ok(());
+    }
+    if nesting.contains(&arg) {
+        return Err(format!(
+            "The response file {arg} contains itself, via {}.",
+            nesting.join(" -> ")
+        ));
+    }
+    let content = fromfile_expander
+        .expand(arg.clone())
+        .map_err(|e| e.render("the command line"))?;
+    nesting.push(arg);
+    for line in content.iter().flat_map(|content| content.lines()) {
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            expand_response_file(
+                fromfile_expander,
+                line.to_owned(),
+                nesting,
+                passthrough,
+                expanded,
+            )?;
+        }
+    }
+    nesting.pop();
+    Ok(())
+}
+
+///
+/// Parses a `KEY=VALUE` pair, as in `--env-vars=KEY=VALUE`, into an edit that adds the key to a
+/// dict option. The key ends at the first `=` that is not escaped as `\=` (with a literal backslash
+/// in a key escaped as `\\`), so the value may contain `=` unescaped, and is a string as written.
+///
+/// Returns None for a value that is not such a pair, e.g. a dict literal or a fromfile.
+///
+fn parse_key_value_pair(value: &str) -> Option<DictEdit> {
+    if value.starts_with(['{', '@']) || value.starts_with("+{") {
+        return None;
+    }
+    let mut key = String::new();
+    let mut chars = value.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped @ ('=' | '\\'))) => key.push(escaped),
+                Some((_, other)) => {
+                    key.push(c);
+                    key.push(other);
+                }
+                None => key.push(c),
+            },
+            '=' if key.is_empty() => return None,
+            '=' => {
+                return Some(DictEdit {
+                    action: DictEditAction::Add,
+                    items: IndexMap::from([(key, Val::String(value[i + 1..].into()))]),
+                })
+            }
+            _ => key.push(c),
+        }
+    }
+    None
+}
+
 pub(crate) struct ArgsReader {
     args: Args,
     #[allow(dead_code)]
@@ -153,15 +285,109 @@ impl ArgsReader {
     pub fn new(args: Args, fromfile_expander: FromfileExpander) -> Self {
         Self {
             args,
-            fromfile_expander,
+            fromfile_expander: fromfile_expander.allowing_stdin(),
         }
     }
 
+    /// The flags whose values are read from stdin, via `@-`.
+    pub(crate) fn stdin_flags(&self) -> Vec<&str> {
+        self.args
+            .args
+            .iter()
+            .filter(|arg| arg.value.as_deref().is_some_and(is_stdin))
+            .map(|arg| arg.flag.as_str())
+            .collect()
+    }
+
     #[allow(dead_code)]
     pub fn get_passthrough_args(&self) -> Option<&Vec<String>> {
         self.args.passthrough_args.as_ref()
     }
 
+    ///
+    /// A reader of the same args, with each long flag that abbreviates a flag of an option of the
+    /// registry expanded to that flag, e.g. `--concur` to `--concurrent`. The flags that may be
+    /// abbreviated after a scope on the command line include the unqualified flags of its options,
+    /// e.g. `--out` for `--output` after `test`.
+    ///
+    /// A flag that is itself a flag of some option, or that abbreviates none, is left as is, and it
+    /// is an error for a flag to abbreviate more than one.
+    ///
+    pub(crate) fn expand_abbreviations(&self, registry: &OptionRegistry) -> Result<Self, String> {
+        let mut flags_by_context: HashMap<Scope, BTreeSet<String>> = HashMap::new();
+        let mut args = self.args.clone();
+        for arg in args.args.iter_mut() {
+            if !arg.flag.starts_with("--") {
+                continue;
+            }
+            let flags = flags_by_context
+                .entry(arg.context.clone())
+                .or_insert_with_key(|context| {
+                    let mut flags = BTreeSet::new();
+                    for option in registry.options() {
+                        let spellings = Spellings::new(option);
+                        flags.extend(spellings.flags);
+                        if &option.id.scope == context {
+                            flags.extend(spellings.scoped_flags);
+                        }
+                    }
+                    flags
+                });
+            if flags.contains(&arg.flag) {
+                continue;
+            }
+            let candidates = flags
+                .iter()
+                .filter(|flag| flag.starts_with(&arg.flag))
+                .collect::<Vec<_>>();
+            match candidates[..] {
+                [] => {}
+                [flag] => arg.flag = flag.clone(),
+                _ => {
+                    return Err(format!(
+                        "The flag {} is ambiguous, as it abbreviates each of: {}",
+                        arg.flag,
+                        candidates.iter().join(", ")
+                    ))
+                }
+            }
+        }
+        Ok(Self {
+            args,
+            fromfile_expander: self.fromfile_expander.clone(),
+        })
+    }
+
+    ///
+    /// The passthrough args, with any fromfile among them (e.g. `-- @args.txt`) expanded in place
+    /// into the list of args that it contains.
+    ///
+    pub(crate) fn expand_passthrough_args(&self) -> Result<Option<PassthroughArgs>, String> {
+        let Some(passthrough_args) = &self.args.passthrough_args else {
+            return Ok(None);
+        };
+        let mut args = vec![];
+        for arg in passthrough_args {
+            if !arg.starts_with('@') {
+                args.push(arg.clone());
+                continue;
+            }
+            let edits = self
+                .fromfile_expander
+                .expand_to_list::<String>(arg.clone())
+                .map_err(|e| e.render("the passthrough args"))?;
+            args.extend(merge_list(
+                vec![],
+                edits.unwrap_or_default(),
+                ConflictStrategy::LastWins,
+            )?);
+        }
+        Ok(Some(PassthroughArgs {
+            scope: self.args.passthrough_scope.clone(),
+            args,
+        }))
+    }
+
     fn to_bool(&self, arg: &Arg) -> Result<Option<bool>, ParseError> {
         // An arg can represent a bool either by having an explicit value parseable as a bool,
         // or by having no value (in which case it represents true).
@@ -174,20 +400,37 @@ impl ArgsReader {
         }
     }
 
+    ///
+    /// Repeated flags accumulate, as in `--tag=a --tag=b`: a scalar value is always added to the
+    /// list, and so is the content of a fromfile after the first flag, rather than replacing the
+    /// list. Only an explicit list literal, as in `--tag=['a']`, replaces the value of a repeated
+    /// flag.
+    ///
     fn get_list<T: Parseable>(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<T>>>, String> {
         let mut edits = vec![];
-        for arg in &self.args.args {
-            if arg.matches(id) {
-                let value = arg.value.as_ref().ok_or_else(|| {
-                    format!("Expected list option {} to have a value.", self.display(id))
-                })?;
-                if let Some(es) = self
-                    .fromfile_expander
-                    .expand_to_list::<T>(value.to_string())
-                    .map_err(|e| e.render(&arg.flag))?
-                {
-                    edits.extend(es);
-                }
+        for (occurrence, arg) in self
+            .args
+            .args
+            .iter()
+            .filter(|arg| arg.matches(id))
+            .enumerate()
+        {
+            let value = arg.value.as_ref().ok_or_else(|| {
+                format!("Expected list option {} to have a value.", self.display(id))
+            })?;
+            if let Some(es) = self
+                .fromfile_expander
+                .expand_to_list::<T>(value.to_string())
+                .map_err(|e| e.render(&arg.flag))?
+            {
+                let accumulate = occurrence > 0 && value.starts_with('@');
+                edits.extend(es.into_iter().map(|edit| match edit.action {
+                    ListEditAction::Replace if accumulate => ListEdit {
+                        action: ListEditAction::Add,
+                        ..edit
+                    },
+                    _ => edit,
+                }));
             }
         }
         if edits.is_empty() {
@@ -226,6 +469,23 @@ impl OptionsSource for ArgsReader {
         Ok(None)
     }
 
+    fn get_bytes(&self, id: &OptionId) -> Result<Option<Vec<u8>>, String> {
+        for arg in self.args.args.iter().rev() {
+            if arg.matches(id) {
+                return self
+                    .fromfile_expander
+                    .expand_to_bytes(arg.value.clone().ok_or_else(|| {
+                        format!(
+                            "Expected bytes option {} to have a value.",
+                            self.display(id)
+                        )
+                    })?)
+                    .map_err(|e| e.render(&arg.flag));
+            };
+        }
+        Ok(None)
+    }
+
     fn get_bool(&self, id: &OptionId) -> Result<Option<bool>, String> {
         // We iterate in reverse so that the rightmost arg wins in case an option
         // is specified multiple times.
@@ -258,6 +518,13 @@ impl OptionsSource for ArgsReader {
         self.get_list::<String>(id)
     }
 
+    fn get_dict_list(
+        &self,
+        id: &OptionId,
+    ) -> Result<Option<Vec<ListEdit<IndexMap<String, Val>>>>, String> {
+        self.get_list::<IndexMap<String, Val>>(id)
+    }
+
     fn get_dict(&self, id: &OptionId) -> Result<Option<Vec<DictEdit>>, String> {
         let mut edits = vec![];
         for arg in self.args.args.iter() {
@@ -265,7 +532,9 @@ impl OptionsSource for ArgsReader {
                 let value = arg.value.clone().ok_or_else(|| {
                     format!("Expected dict option {} to have a value.", self.display(id))
                 })?;
-                if let Some(es) = self
+                if let Some(edit) = parse_key_value_pair(&value) {
+                    edits.push(edit);
+                } else if let Some(es) = self
                     .fromfile_expander
                     .expand_to_dict(value)
                     .map_err(|e| e.render(&arg.flag))?
@@ -280,4 +549,16 @@ impl OptionsSource for ArgsReader {
             Ok(Some(edits))
         }
     }
+
+    fn get_raw(&self, id: &OptionId) -> Vec<RawValue> {
+        self.args
+            .args
+            .iter()
+            .filter(|arg| arg.matches(id) || arg.matches_negation(id))
+            .map(|arg| RawValue {
+                location: arg.flag.clone(),
+                value: arg.value.clone(),
+            })
+            .collect()
+    }
 }
@@ -2,12 +2,17 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 use core::fmt::Debug;
-use maplit::hashmap;
+use indexmap::indexmap;
 
-use crate::args::{Args, ArgsReader};
+use std::sync::Arc;
+
+use crate::args::{Args, ArgsReader, PassthroughArgs};
 use crate::fromfile::test_util::write_fromfile;
 use crate::fromfile::FromfileExpander;
-use crate::{option_id, DictEdit, DictEditAction, Val};
+use crate::MemoryFileSystem;
+use crate::{
+    option_id, DictEdit, DictEditAction, OptionInfo, OptionRegistry, OptionType, Scope, Val,
+};
 use crate::{ListEdit, ListEditAction, OptionId, OptionsSource};
 
 fn mk_args<I>(args: I) -> ArgsReader
@@ -95,7 +100,7 @@ fn test_bool() {
     assert!(args.get_bool(&option_id!("dne")).unwrap().is_none());
     assert!(args.get_passthrough_args().is_none());
     assert_eq!(
-        "Problem parsing -c bool value:\n1:swallow\n  ^\nExpected 'true' or 'false' at line 1 column 1".to_owned(),
+        "Problem parsing -c bool value:\n1:swallow\n  ^\nExpected 'true', 'false', 'yes', 'no', 'on', 'off', '1' or '0' (in any case) at line 1 column 1".to_owned(),
         args.get_bool(&option_id!(-'c', "unladen", "capacity"))
             .unwrap_err()
     );
@@ -188,7 +193,7 @@ fn test_string_list() {
 Problem parsing --bad string list value:
 1:['mis', 'matched')
   -----------------^
-Expected \",\" or the end of a list indicated by ']' at line 1 column 18"
+Mismatched `)`, where the `[` at line 1 column 1 must be closed first at line 1 column 18"
         .to_owned();
 
     assert_eq!(
@@ -197,6 +202,130 @@ Expected \",\" or the end of a list indicated by ']' at line 1 column 18"
     );
 }
 
+#[test]
+fn test_repeated_list_flags() {
+    let (_tmpdir, fromfile_path) = write_fromfile("tags.json", r#"["j1", "j2"]"#);
+    let fromfile = format!("--tag=@{}", fromfile_path.display());
+    let args = mk_args([
+        fromfile.as_str(),
+        "--tag=a",
+        "--tag=+['b','c']",
+        "--tag=-['a']",
+        "--tag=d",
+        fromfile.as_str(),
+    ]);
+    let edit = |action, items: &[&str]| ListEdit {
+        action,
+        items: items.iter().map(|item| item.to_string()).collect(),
+    };
+    assert_eq!(
+        vec![
+            edit(ListEditAction::Replace, &["j1", "j2"]),
+            edit(ListEditAction::Add, &["a"]),
+            edit(ListEditAction::Add, &["b", "c"]),
+            edit(ListEditAction::Remove, &["a"]),
+            edit(ListEditAction::Add, &["d"]),
+            // Only the first fromfile replaces the list.
+            edit(ListEditAction::Add, &["j1", "j2"]),
+        ],
+        args.get_string_list(&option_id!("tag")).unwrap().unwrap()
+    );
+
+    // An explicit list literal still replaces the list, even when repeated.
+    let args = mk_args(["--tag=a", "--tag=['b']", "--tag=c"]);
+    assert_eq!(
+        vec![
+            edit(ListEditAction::Add, &["a"]),
+            edit(ListEditAction::Replace, &["b"]),
+            edit(ListEditAction::Add, &["c"]),
+        ],
+        args.get_string_list(&option_id!("tag")).unwrap().unwrap()
+    );
+
+    let args = mk_args(["--num=1", "--num=+[2]", "--num=3"]);
+    assert_eq!(
+        vec![
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec![1]
+            },
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec![2]
+            },
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec![3]
+            },
+        ],
+        args.get_int_list(&option_id!("num")).unwrap().unwrap()
+    );
+}
+
+#[test]
+fn test_bool_and_float_lists() {
+    let args = mk_args(vec![
+        "--bools=[true, False]",
+        "--bools=-[false]",
+        "--floats=+[1.5, 2]",
+    ]);
+    assert_eq!(
+        vec![
+            ListEdit {
+                action: ListEditAction::Replace,
+                items: vec![true, false]
+            },
+            ListEdit {
+                action: ListEditAction::Remove,
+                items: vec![false]
+            },
+        ],
+        args.get_bool_list(&option_id!("bools")).unwrap().unwrap()
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec![1.5, 2.0]
+        }],
+        args.get_float_list(&option_id!("floats")).unwrap().unwrap()
+    );
+    assert!(args.get_float_list(&option_id!("dne")).unwrap().is_none());
+}
+
+#[test]
+fn test_enum_list() {
+    let args = mk_args(vec![
+        "--colors=+['red']",
+        "--colors=-['blue']",
+        "--bad=['pink']",
+    ]);
+    let choices = ["red", "green", "blue"];
+    assert_eq!(
+        vec![
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec!["red".to_string()]
+            },
+            ListEdit {
+                action: ListEditAction::Remove,
+                items: vec!["blue".to_string()]
+            },
+        ],
+        args.get_enum_list(&option_id!("colors"), &choices)
+            .unwrap()
+            .unwrap()
+    );
+    assert_eq!(
+        "Invalid value `pink` for --bad: must be one of red, green, blue",
+        args.get_enum_list(&option_id!("bad"), &choices)
+            .unwrap_err()
+    );
+    assert!(args
+        .get_enum_list(&option_id!("dne"), &choices)
+        .unwrap()
+        .is_none());
+}
+
 #[test]
 fn test_scalar_fromfile() {
     fn do_test<T: PartialEq + Debug>(
@@ -289,16 +418,132 @@ fn test_list_fromfile() {
     );
 }
 
+#[test]
+fn test_bool_and_float_list_fromfile() {
+    let (_tmpdir, fromfile_path) = write_fromfile("fromfile.json", "[true, false]");
+    let args = mk_args(vec![format!("--foo=@{}", fromfile_path.display())]);
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![true, false]
+        }],
+        args.get_bool_list(&option_id!("foo")).unwrap().unwrap()
+    );
+
+    let (_tmpdir, fromfile_path) = write_fromfile("fromfile.txt", "-[1.5]");
+    let args = mk_args(vec![format!("--foo=@{}", fromfile_path.display())]);
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Remove,
+            items: vec![1.5]
+        }],
+        args.get_float_list(&option_id!("foo")).unwrap().unwrap()
+    );
+}
+
+#[test]
+fn test_dict_list() {
+    let args = mk_args(vec![
+        r#"--matrix=[{"py": "3.11"}, {"py": "3.12", "arch": "arm"}]"#.to_string(),
+        r#"--matrix=-[{"py": "3.11"}],+[{"py": "3.13"}]"#.to_string(),
+        r#"--matrix={"py": "3.14"}"#.to_string(),
+    ]);
+    assert_eq!(
+        vec![
+            ListEdit {
+                action: ListEditAction::Replace,
+                items: vec![
+                    indexmap! {"py".to_string() => Val::String("3.11".into())},
+                    indexmap! {
+                        "py".to_string() => Val::String("3.12".into()),
+                        "arch".to_string() => Val::String("arm".into()),
+                    },
+                ]
+            },
+            ListEdit {
+                action: ListEditAction::Remove,
+                items: vec![indexmap! {"py".to_string() => Val::String("3.11".into())}]
+            },
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec![indexmap! {"py".to_string() => Val::String("3.13".into())}]
+            },
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec![indexmap! {"py".to_string() => Val::String("3.14".into())}]
+            },
+        ],
+        args.get_dict_list(&option_id!("matrix")).unwrap().unwrap()
+    );
+
+    let (_tmpdir, fromfile_path) =
+        write_fromfile("fromfile.json", r#"[{"py": "3.11", "shards": 2}]"#);
+    let args = mk_args(vec![format!("--matrix=@{}", fromfile_path.display())]);
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![indexmap! {
+                "py".to_string() => Val::String("3.11".into()),
+                "shards".to_string() => Val::Int(2),
+            }]
+        }],
+        args.get_dict_list(&option_id!("matrix")).unwrap().unwrap()
+    );
+
+    let args = mk_args(vec!["--matrix=[1]".to_string()]);
+    assert!(args
+        .get_dict_list(&option_id!("matrix"))
+        .unwrap_err()
+        .starts_with("Problem parsing --matrix dict list value"));
+}
+
+#[test]
+fn test_key_value_dict_flags() {
+    let args = mk_args([
+        "--env-vars={'A': 'a'}",
+        "--env-vars=PATH=/bin:/usr/bin",
+        "--env-vars=OPTS=--x=1 --y=2",
+        r"--env-vars=KEY\=WITH\\EQUALS=v",
+        "--env-vars=EMPTY=",
+        "--env-vars=+{'B': 'b'}",
+    ]);
+    let add = |key: &str, value: &str| DictEdit {
+        action: DictEditAction::Add,
+        items: indexmap! {key.to_string() => Val::String(value.into())},
+    };
+    assert_eq!(
+        vec![
+            DictEdit {
+                action: DictEditAction::Replace,
+                items: indexmap! {"A".to_string() => Val::String("a".into())},
+            },
+            add("PATH", "/bin:/usr/bin"),
+            add("OPTS", "--x=1 --y=2"),
+            add(r"KEY=WITH\EQUALS", "v"),
+            add("EMPTY", ""),
+            add("B", "b"),
+        ],
+        args.get_dict(&option_id!("env", "vars")).unwrap().unwrap()
+    );
+
+    // A pair must have a key.
+    let args = mk_args(["--env-vars==v"]);
+    assert!(args
+        .get_dict(&option_id!("env", "vars"))
+        .unwrap_err()
+        .starts_with("Problem parsing --env-vars dict value"));
+}
+
 #[test]
 fn test_dict_fromfile() {
     fn do_test(content: &str, filename: &str) {
         let expected = vec![
             DictEdit {
                 action: DictEditAction::Replace,
-                items: hashmap! {
-                "FOO".to_string() => Val::Dict(hashmap! {
+                items: indexmap! {
+                "FOO".to_string() => Val::Dict(indexmap! {
                     "BAR".to_string() => Val::Float(3.14),
-                    "BAZ".to_string() => Val::Dict(hashmap! {
+                    "BAZ".to_string() => Val::Dict(indexmap! {
                         "QUX".to_string() => Val::Bool(true),
                         "QUUX".to_string() => Val::List(vec![ Val::Int(1), Val::Int(2)])
                     })
@@ -306,8 +551,8 @@ fn test_dict_fromfile() {
             },
             DictEdit {
                 action: DictEditAction::Add,
-                items: hashmap! {
-                    "KEY".to_string() => Val::String("VALUE".to_string()),
+                items: indexmap! {
+                    "KEY".to_string() => Val::String("VALUE".into()),
                 },
             },
         ];
@@ -345,7 +590,7 @@ fn test_dict_fromfile() {
     // Test adding, rather than replacing, from a raw text fromfile.
     let expected_add = vec![DictEdit {
         action: DictEditAction::Add,
-        items: hashmap! {"FOO".to_string() => Val::Int(42)},
+        items: indexmap! {"FOO".to_string() => Val::Int(42)},
     }];
 
     let (_tmpdir, fromfile_path) = write_fromfile("fromfile.txt", "+{'FOO':42}");
@@ -397,9 +642,204 @@ fn test_passthrough_args() {
     );
 }
 
+#[test]
+fn test_expand_passthrough_args() {
+    let (_tmpdir, txt) = write_fromfile("args.txt", "-k\ntest_foo\n");
+    let (_tmpdir, json) = write_fromfile("args.json", r#"["-v", "--lf"]"#);
+    let args = mk_args([
+        "test".to_owned(),
+        "--debug".to_owned(),
+        "--".to_owned(),
+        "-x".to_owned(),
+        format!("@{}", txt.display()),
+        format!("@{}", json.display()),
+        "@?/does/not/exist".to_owned(),
+    ]);
+    assert_eq!(
+        Some(PassthroughArgs {
+            scope: Scope::named("test"),
+            args: ["-x", "-k", "test_foo", "-v", "--lf"]
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+        }),
+        args.expand_passthrough_args().unwrap()
+    );
+
+    let args = mk_args(["--", "@/does/not/exist"]);
+    let err = args.expand_passthrough_args().unwrap_err();
+    assert!(
+        err.starts_with("Problem reading /does/not/exist for the passthrough args"),
+        "{err}"
+    );
+
+    let args = mk_args(["--foo=bar", "--", "a"]);
+    assert_eq!(
+        Scope::Global,
+        args.expand_passthrough_args().unwrap().unwrap().scope
+    );
+    assert_eq!(
+        None,
+        mk_args(["--foo=bar"]).expand_passthrough_args().unwrap()
+    );
+}
+
+#[test]
+fn test_response_files() {
+    let expander = FromfileExpander::relative_to_cwd();
+    expander.set_filesystem(Arc::new(
+        MemoryFileSystem::new()
+            .file(
+                "/rsp/args.rsp",
+                "# The goals.\ntest\n\n  --debug  \n@@literal\n@/rsp/nested.rsp\n",
+            )
+            .file("/rsp/nested.rsp", "src/python::\n--\n@passthrough\n")
+            .file("/rsp/cycle.rsp", "@/rsp/cycle.rsp\n"),
+    ));
+    let expand = |args: &[&str]| {
+        Args::new(args.iter().map(|arg| arg.to_string())).expand_response_files(&expander)
+    };
+
+    assert_eq!(
+        vec![
+            "-ldebug",
+            "test",
+            "--debug",
+            "@literal",
+            "src/python::",
+            "--",
+            "@passthrough",
+            "-x",
+        ],
+        expand(&["-ldebug", "@/rsp/args.rsp", "-x"])
+            .unwrap()
+            .arg_strs
+    );
+    // Args after `--` are not response files, nor are flag values.
+    assert_eq!(
+        vec!["--foo=@/rsp/args.rsp", "--", "@/rsp/args.rsp"],
+        expand(&["--foo=@/rsp/args.rsp", "--", "@/rsp/args.rsp"])
+            .unwrap()
+            .arg_strs
+    );
+    // An optional response file that doesn't exist is empty.
+    assert_eq!(
+        vec!["test"],
+        expand(&["test", "@?/rsp/missing.rsp"]).unwrap().arg_strs
+    );
+
+    let err = expand(&["@/rsp/missing.rsp"]).unwrap_err();
+    assert!(
+        err.starts_with("Problem reading /rsp/missing.rsp for the command line"),
+        "{err}"
+    );
+    assert_eq!(
+        "The response file @/rsp/cycle.rsp contains itself, via @/rsp/cycle.rsp.",
+        expand(&["@/rsp/cycle.rsp"]).unwrap_err()
+    );
+}
+
 #[test]
 fn test_empty_passthrough_args() {
     let args = mk_args(["-ldebug", "--foo=bar", "--"]);
 
     assert_eq!(Some(&vec![]), args.get_passthrough_args());
 }
+
+#[test]
+fn test_stdin() {
+    let expander = FromfileExpander::relative_to_cwd();
+    expander.set_stdin("[\"a\", \"b\"]");
+    let args = ArgsReader::new(
+        Args::new(
+            ["--names=@-", "--mapping=@-.json", "--other=@-x"]
+                .into_iter()
+                .map(str::to_owned),
+        ),
+        expander.clone(),
+    );
+    assert_eq!(
+        Some(vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec!["a".to_owned(), "b".to_owned()],
+        }]),
+        args.get_string_list(&option_id!("names")).unwrap()
+    );
+    // Stdin is read once, however many times the value is.
+    assert_eq!(
+        Some("[\"a\", \"b\"]".to_owned()),
+        args.get_string(&option_id!("names")).unwrap()
+    );
+    assert_eq!(vec!["--names", "--mapping"], args.stdin_flags());
+    // Only flags read stdin: otherwise `@-` refers to a file named `-`.
+    assert!(expander.expand("@-".to_owned()).is_err());
+}
+
+#[test]
+fn test_abbreviated_flags() {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!("concurrent"), OptionType::Bool(false)),
+        OptionInfo::new(option_id!("level"), OptionType::String("info".to_owned())),
+        OptionInfo::new(
+            option_id!("cache", "dir"),
+            OptionType::String("".to_owned()),
+        ),
+        OptionInfo::new(option_id!("cache", "size"), OptionType::Int(0)),
+        OptionInfo::new(
+            option_id!(["test"], "output"),
+            OptionType::String("".to_owned()),
+        ),
+        OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false)),
+    ] {
+        registry.register(option).unwrap();
+    }
+    let expand = |args: &[&str]| mk_args(args).expand_abbreviations(&registry);
+
+    let args = expand(&["--concur", "--lev=debug", "--test-out=all", "--unknown=x"]).unwrap();
+    assert_eq!(
+        Some(true),
+        args.get_bool(&option_id!("concurrent")).unwrap()
+    );
+    assert_eq!(
+        Some("debug".to_owned()),
+        args.get_string(&option_id!("level")).unwrap()
+    );
+    assert_eq!(
+        Some("all".to_owned()),
+        args.get_string(&option_id!(["test"], "output")).unwrap()
+    );
+    assert_eq!(
+        Some("x".to_owned()),
+        args.get_string(&option_id!("unknown")).unwrap()
+    );
+
+    // After a scope, the unqualified flags of its options may be abbreviated, as may negations.
+    let args = expand(&["test", "--out=none", "--no-deb"]).unwrap();
+    assert_eq!(
+        Some("none".to_owned()),
+        args.get_string(&option_id!(["test"], "output")).unwrap()
+    );
+    assert_eq!(
+        Some(false),
+        args.get_bool(&option_id!(["test"], "debug")).unwrap()
+    );
+
+    // Exact flags are never abbreviations, even if they are a prefix of another flag.
+    let args = expand(&["--level=warn"]).unwrap();
+    assert_eq!(
+        Some("warn".to_owned()),
+        args.get_string(&option_id!("level")).unwrap()
+    );
+
+    assert_eq!(
+        "The flag --cache is ambiguous, as it abbreviates each of: --cache-dir, --cache-size",
+        expand(&["--cache=x"]).err().unwrap()
+    );
+    // An unqualified flag is only abbreviated in the scope of its option.
+    assert!(expand(&["--out=all"])
+        .unwrap()
+        .get_string(&option_id!(["test"], "output"))
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,81 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+///
+/// Encodes bytes as padded base64, using the standard alphabet of RFC 4648.
+///
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |n, (i, b)| n | (u32::from(*b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// Serializes bytes as a base64 string, which is more legible than an array of ints.
+pub(crate) fn serialize<S: serde::Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode(bytes))
+}
+
+///
+/// Decodes base64, as e.g. PEM files and `base64` write it: whitespace (including line breaks) is
+/// ignored, and padding is optional. The URL-safe alphabet is accepted as well as the standard one.
+///
+pub(crate) fn decode(content: &str) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::with_capacity(content.len() / 4 * 3);
+    let mut n = 0_u32;
+    let mut digits = 0;
+    let mut padding = 0;
+    for (offset, c) in content.char_indices() {
+        let digit = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' | '-' => 62,
+            '/' | '_' => 63,
+            '=' => {
+                padding += 1;
+                continue;
+            }
+            c if c.is_ascii_whitespace() => continue,
+            c => return Err(format!("Invalid base64 character `{c}` at offset {offset}")),
+        };
+        if padding > 0 {
+            return Err(format!(
+                "Invalid base64: `{c}` at offset {offset} follows padding"
+            ));
+        }
+        n = n << 6 | digit;
+        digits += 1;
+        if digits == 4 {
+            decoded.extend_from_slice(&n.to_be_bytes()[1..]);
+            n = 0;
+            digits = 0;
+        }
+    }
+    match digits {
+        0 => (),
+        1 => return Err("Invalid base64: the input is truncated".to_owned()),
+        // The remaining digits encode 1 or 2 bytes, in their high bits.
+        _ => {
+            let bytes = (n << (6 * (4 - digits))).to_be_bytes();
+            decoded.extend_from_slice(&bytes[1..digits]);
+        }
+    }
+    Ok(decoded)
+}
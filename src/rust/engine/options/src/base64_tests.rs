@@ -0,0 +1,42 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::base64::{decode, encode};
+
+#[test]
+fn test_round_trip() {
+    for (bytes, encoded) in [
+        (&b""[..], ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg=="),
+        (b"\x00\xff\xfe", "AP/+"),
+    ] {
+        assert_eq!(encoded, encode(bytes));
+        assert_eq!(Ok(bytes.to_vec()), decode(encoded));
+    }
+}
+
+#[test]
+fn test_decode_leniently() {
+    assert_eq!(Ok(b"foob".to_vec()), decode("Zm9v\nYg\n"));
+    assert_eq!(Ok(b"foob".to_vec()), decode("Zm9vYg"));
+    assert_eq!(Ok(b"\x00\xff\xfe".to_vec()), decode("AP_-"));
+}
+
+#[test]
+fn test_decode_errors() {
+    assert_eq!(
+        Err("Invalid base64 character `*` at offset 2".to_owned()),
+        decode("Zm*v")
+    );
+    assert_eq!(
+        Err("Invalid base64: `Y` at offset 5 follows padding".to_owned()),
+        decode("Zm8=\nYg==")
+    );
+    assert_eq!(
+        Err("Invalid base64: the input is truncated".to_owned()),
+        decode("Zm9vY")
+    );
+}
@@ -6,7 +6,6 @@ use std::ops::Deref;
 use std::path::{Path, PathBuf};
 
 use log::debug;
-use std::os::unix::ffi::OsStrExt;
 
 #[derive(Clone, Debug)]
 pub struct BuildRoot(PathBuf);
@@ -67,11 +66,11 @@ impl BuildRoot {
     }
 
     pub fn convert_to_string(&self) -> Result<String, String> {
-        String::from_utf8(self.0.as_os_str().as_bytes().to_vec()).map_err(|e| {
+        // NB: Not via `OsStrExt`, which is unix-only, so that this compiles for e.g. wasm32.
+        self.0.to_str().map(str::to_owned).ok_or_else(|| {
             format!(
-                "Failed to decode build root path {}: {}",
-                self.0.display(),
-                e
+                "Failed to decode build root path {}: it is not valid UTF-8",
+                self.0.display()
             )
         })
     }
@@ -0,0 +1,108 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::{Args, Env};
+
+lazy_static! {
+    // A reference to an env var in a config file, e.g. in `%(env.HOME)s` or `%(if:env.CI:a:b)s`.
+    static ref ENV_REFERENCE_RE: Regex = Regex::new(r"\benv\.([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+}
+
+// The env vars that the parser itself consults, other than those of options: to compute seeds,
+// and to discover the per-user config files.
+const CONSULTED_ENV_VARS: &[&str] = &[
+    "CI",
+    "SOURCE_DATE_EPOCH",
+    "HOME",
+    "XDG_CONFIG_HOME",
+    "APPDATA",
+    "PROGRAMDATA",
+];
+
+///
+/// The env vars other than those of options that a parse reads: those that the given config files
+/// refer to, and those that the parser itself consults.
+///
+pub(crate) fn env_vars_read(configs: &[Arc<Config>]) -> BTreeSet<String> {
+    CONSULTED_ENV_VARS
+        .iter()
+        .map(|name| name.to_string())
+        .chain(
+            configs
+                .iter()
+                .flat_map(|config| ENV_REFERENCE_RE.captures_iter(config.content()))
+                .map(|captures| captures[1].to_owned()),
+        )
+        .collect()
+}
+
+///
+/// The args and env vars that a parse consumed, which may be persisted and later replayed via
+/// `OptionParser::from_capture`, e.g. to reproduce the options of a CI build locally. The config
+/// files are not captured, as they are expected to be those of the same commit.
+///
+/// The env vars captured are those of options (i.e. prefixed with `PANTS_`), those that the config
+/// files refer to, and those that the parser itself consults.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OptionsCapture {
+    pub(crate) args: Vec<String>,
+    pub(crate) env: BTreeMap<String, String>,
+}
+
+impl OptionsCapture {
+    pub(crate) fn new(
+        args: Vec<String>,
+        env: &HashMap<String, String>,
+        configs: &[Arc<Config>],
+    ) -> Self {
+        let env_vars_read = env_vars_read(configs);
+        let env = env
+            .iter()
+            .filter(|(name, _)| name.starts_with("PANTS_") || env_vars_read.contains(*name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        Self { args, env }
+    }
+
+    pub fn args(&self) -> Args {
+        Args::new(self.args.clone())
+    }
+
+    pub fn env(&self) -> Env {
+        Env::new(self.env.clone().into_iter().collect())
+    }
+
+    pub fn write_to(&self, path: &Path) -> Result<(), String> {
+        let mut content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize options capture: {e}"))?;
+        content.push('\n');
+        fs::write(path, content)
+            .map_err(|e| format!("Failed to write options capture to {}: {e}", path.display()))
+    }
+
+    pub fn read_from(path: &Path) -> Result<OptionsCapture, String> {
+        let content = fs::read(path).map_err(|e| {
+            format!(
+                "Failed to read options capture from {}: {e}",
+                path.display()
+            )
+        })?;
+        serde_json::from_slice(&content).map_err(|e| {
+            format!(
+                "Failed to deserialize options capture from {}: {e}",
+                path.display()
+            )
+        })
+    }
+}
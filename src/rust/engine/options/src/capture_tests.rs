@@ -0,0 +1,68 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use tempfile::TempDir;
+
+use crate::{option_id, Args, BuildRoot, Env, OptionParser, OptionsCapture};
+
+#[test]
+fn test_capture_and_replay() {
+    let buildroot = TempDir::new().unwrap();
+    fs::write(
+        buildroot.path().join("pants.toml"),
+        "[GLOBAL]\nname = '%(env.USER_NAME)s'\ncolors = '%(if:env.CI:false:true)s'\n",
+    )
+    .unwrap();
+    let env = HashMap::from([
+        ("PANTS_LEVEL".to_owned(), "debug".to_owned()),
+        ("USER_NAME".to_owned(), "jo".to_owned()),
+        ("CI".to_owned(), "true".to_owned()),
+        ("TERM".to_owned(), "xterm".to_owned()),
+    ]);
+    let args = vec!["--pants-workdir=/tmp/work".to_owned(), "test".to_owned()];
+    let parser = OptionParser::new(
+        Args::new(args.clone()),
+        Env::new(env),
+        None,
+        false,
+        false,
+        Some(BuildRoot::find_from(buildroot.path()).unwrap()),
+    )
+    .unwrap();
+
+    // Env vars that neither options nor config files consume are not captured.
+    let capture = parser.capture();
+    assert_eq!(args, capture.args);
+    assert_eq!(
+        BTreeMap::from([
+            ("CI".to_owned(), "true".to_owned()),
+            ("PANTS_LEVEL".to_owned(), "debug".to_owned()),
+            ("USER_NAME".to_owned(), "jo".to_owned()),
+        ]),
+        capture.env
+    );
+
+    let path = buildroot.path().join("capture.json");
+    capture.write_to(&path).unwrap();
+    let capture = OptionsCapture::read_from(&path).unwrap();
+
+    let replayed = OptionParser::from_capture(
+        &capture,
+        false,
+        false,
+        Some(BuildRoot::find_from(buildroot.path()).unwrap()),
+    )
+    .unwrap();
+    for (id, expected) in [
+        (option_id!("level"), "debug"),
+        (option_id!("name"), "jo"),
+        (option_id!("colors"), "false"),
+        (option_id!("pants", "workdir"), "/tmp/work"),
+    ] {
+        assert_eq!(expected, replayed.parse_string(&id, "").unwrap().value);
+    }
+    assert_eq!(capture, replayed.capture());
+}
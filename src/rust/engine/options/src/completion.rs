@@ -0,0 +1,293 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use itertools::Itertools;
+
+use crate::id::Scope;
+use crate::registry::{OptionInfo, OptionRegistry};
+use crate::spelling::Spellings;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!(
+                "Unknown shell `{s}`: expected one of bash, zsh or fish."
+            )),
+        }
+    }
+}
+
+///
+/// Generates a script which completes the flags, scopes and enum values of the options in the
+/// registry for the given shell. Source the script in the shell (or, for zsh, install it on the
+/// `fpath` as `_pants`) to enable completion of `pants` command lines.
+///
+/// Flags are offered in their fully qualified spelling (`--test-output`) anywhere, and also in
+/// their short spelling (`--output`) after the scope that they belong to.
+///
+pub fn completion_script(registry: &OptionRegistry, shell: Shell) -> String {
+    let completions = Completions::new(registry);
+    match shell {
+        Shell::Bash => completions.bash(),
+        Shell::Zsh => completions.zsh(),
+        Shell::Fish => completions.fish(),
+    }
+}
+
+const HEADER: &str = "# Completions for pants, generated from its registered options.";
+
+// Quotes a word for bash, zsh and fish, unless it consists only of characters that need no quoting.
+fn quote(word: &str) -> String {
+    if !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.,:/=+@%".contains(c))
+    {
+        word.to_owned()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+// An option with choices, and the patterns that match its flags when they are given a value.
+struct EnumOption<'a> {
+    option: &'a OptionInfo,
+    // Patterns for `$scope:$current_word`.
+    patterns: Vec<String>,
+}
+
+struct Completions<'a> {
+    scopes: Vec<&'a str>,
+    flags: Vec<String>,
+    scoped_flags: Vec<(&'a str, Vec<String>)>,
+    enum_options: Vec<EnumOption<'a>>,
+}
+
+impl<'a> Completions<'a> {
+    fn new(registry: &'a OptionRegistry) -> Self {
+        let scopes = registry
+            .scopes()
+            .into_iter()
+            .map(Scope::name)
+            .collect::<Vec<_>>();
+        let flags = registry
+            .options()
+            .flat_map(|option| Spellings::new(option).flags)
+            .collect();
+        let scoped_flags = registry
+            .scopes()
+            .into_iter()
+            .map(|scope| {
+                let flags = registry
+                    .options_in(scope)
+                    .flat_map(|option| Spellings::new(option).scoped_flags)
+                    .collect();
+                (scope.name(), flags)
+            })
+            .collect();
+        let enum_options = registry
+            .options()
+            .filter(|option| !option.choices.is_empty())
+            .map(|option| {
+                let mut patterns = vec![format!("*:{}=*", option.flag(false))];
+                if let Scope::Scope(scope) = &option.id.scope {
+                    patterns.push(format!("{scope}:{}=*", option.scoped_flag(false)));
+                }
+                EnumOption { option, patterns }
+            })
+            .collect();
+        Self {
+            scopes,
+            flags,
+            scoped_flags,
+            enum_options,
+        }
+    }
+
+    fn bash(&self) -> String {
+        let mut script = String::new();
+        writeln!(script, "{HEADER}").unwrap();
+        writeln!(script, "_pants_completions() {{").unwrap();
+        // NB: Bash splits words at `=` by default, so the current word is taken from the line.
+        writeln!(script, r#"    local line="${{COMP_LINE:0:COMP_POINT}}""#).unwrap();
+        writeln!(script, r#"    local cur="${{line##* }}""#).unwrap();
+        self.detect_scope(&mut script, r#""${COMP_WORDS[@]:1:COMP_CWORD-1}""#);
+        writeln!(script, r#"    case "$scope:$cur" in"#).unwrap();
+        for enum_option in &self.enum_options {
+            writeln!(
+                script,
+                r#"        {})
+            COMPREPLY=($(compgen -W "{}" -- "${{cur#*=}}"))
+            return ;;"#,
+                enum_option.patterns.join("|"),
+                enum_option.option.choices.join(" ")
+            )
+            .unwrap();
+        }
+        writeln!(script, "    esac").unwrap();
+        writeln!(script, r#"    local words="{}""#, self.words()).unwrap();
+        writeln!(script, r#"    case "$scope" in"#).unwrap();
+        for (scope, flags) in &self.scoped_flags {
+            writeln!(
+                script,
+                r#"        {scope}) words="$words {}" ;;"#,
+                flags.join(" ")
+            )
+            .unwrap();
+        }
+        writeln!(script, "    esac").unwrap();
+        writeln!(
+            script,
+            r#"    COMPREPLY=($(compgen -W "$words" -- "$cur"))"#
+        )
+        .unwrap();
+        writeln!(script, "}}").unwrap();
+        writeln!(script, "complete -o default -F _pants_completions pants").unwrap();
+        script
+    }
+
+    fn zsh(&self) -> String {
+        let mut script = String::new();
+        writeln!(script, "#compdef pants").unwrap();
+        writeln!(script, "{HEADER}").unwrap();
+        writeln!(script, "_pants() {{").unwrap();
+        writeln!(script, r#"    local cur="${{words[CURRENT]}}""#).unwrap();
+        self.detect_scope(&mut script, r#""${(@)words[2,CURRENT-1]}""#);
+        writeln!(script, r#"    case "$scope:$cur" in"#).unwrap();
+        for enum_option in &self.enum_options {
+            writeln!(
+                script,
+                r#"        ({})
+            compset -P '*='
+            compadd -- {}
+            return ;;"#,
+                enum_option.patterns.join("|"),
+                enum_option
+                    .option
+                    .choices
+                    .iter()
+                    .map(|c| quote(c))
+                    .join(" ")
+            )
+            .unwrap();
+        }
+        writeln!(script, "    esac").unwrap();
+        writeln!(script, "    local -a candidates=({})", self.words()).unwrap();
+        writeln!(script, r#"    case "$scope" in"#).unwrap();
+        for (scope, flags) in &self.scoped_flags {
+            writeln!(
+                script,
+                "        ({scope}) candidates+=({}) ;;",
+                flags.join(" ")
+            )
+            .unwrap();
+        }
+        writeln!(script, "    esac").unwrap();
+        writeln!(script, r#"    compadd -- "${{candidates[@]}}""#).unwrap();
+        writeln!(script, "    _files").unwrap();
+        writeln!(script, "}}").unwrap();
+        writeln!(script, "compdef _pants pants").unwrap();
+        script
+    }
+
+    fn fish(&self) -> String {
+        let mut script = String::new();
+        writeln!(script, "{HEADER}").unwrap();
+        if !self.scopes.is_empty() {
+            writeln!(
+                script,
+                "complete -c pants -a {}",
+                quote(&self.scopes.join(" "))
+            )
+            .unwrap();
+        }
+        for (scope, flags) in &self.scoped_flags {
+            let condition = quote(&format!("__fish_seen_subcommand_from {scope}"));
+            for flag in flags {
+                writeln!(
+                    script,
+                    "complete -c pants -n {condition} -l {}",
+                    flag.trim_start_matches('-')
+                )
+                .unwrap();
+            }
+        }
+        for flag in &self.flags {
+            match flag.strip_prefix("--") {
+                Some(long) => writeln!(script, "complete -c pants -l {long}").unwrap(),
+                None => writeln!(script, "complete -c pants -s {}", &flag[1..]).unwrap(),
+            }
+        }
+        for enum_option in &self.enum_options {
+            let choices = quote(
+                &enum_option
+                    .option
+                    .choices
+                    .iter()
+                    .map(|c| quote(c))
+                    .join(" "),
+            );
+            writeln!(
+                script,
+                "complete -c pants -l {} -x -a {choices}",
+                enum_option.option.flag(false).trim_start_matches('-')
+            )
+            .unwrap();
+            if let Scope::Scope(scope) = &enum_option.option.id.scope {
+                writeln!(
+                    script,
+                    "complete -c pants -n {} -l {} -x -a {choices}",
+                    quote(&format!("__fish_seen_subcommand_from {scope}")),
+                    enum_option
+                        .option
+                        .scoped_flag(false)
+                        .trim_start_matches('-')
+                )
+                .unwrap();
+            }
+        }
+        script
+    }
+
+    // The words that may be completed anywhere: fully qualified flags and scope names.
+    fn words(&self) -> String {
+        self.flags
+            .iter()
+            .map(String::as_str)
+            .chain(self.scopes.iter().copied())
+            .join(" ")
+    }
+
+    // Sets `$scope` to the last scope named before the current word, for bash and zsh.
+    fn detect_scope(&self, script: &mut String, preceding_words: &str) {
+        writeln!(script, r#"    local scope="" word"#).unwrap();
+        if self.scopes.is_empty() {
+            return;
+        }
+        writeln!(script, "    for word in {preceding_words}; do").unwrap();
+        writeln!(script, r#"        case "$word" in"#).unwrap();
+        writeln!(
+            script,
+            r#"            {}) scope="$word" ;;"#,
+            self.scopes.join("|")
+        )
+        .unwrap();
+        writeln!(script, "        esac").unwrap();
+        writeln!(script, "    done").unwrap();
+    }
+}
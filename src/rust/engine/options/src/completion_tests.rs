@@ -0,0 +1,79 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::{completion_script, option_id, OptionInfo, OptionRegistry, OptionType, Shell};
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(
+            option_id!(-'l', "level"),
+            OptionType::String("info".to_owned()),
+        )
+        .choices(&["debug", "info"]),
+        OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false)),
+        OptionInfo::new(
+            option_id!(["test"], "output"),
+            OptionType::String("failed".to_owned()),
+        )
+        .choices(&["all", "failed", "none"]),
+        OptionInfo::new(option_id!(["lint"], "only"), OptionType::StringList(vec![])),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+#[test]
+fn test_shell_from_str() {
+    assert_eq!(Ok(Shell::Zsh), "zsh".parse());
+    assert_eq!(
+        Err("Unknown shell `csh`: expected one of bash, zsh or fish.".to_owned()),
+        "csh".parse::<Shell>()
+    );
+}
+
+#[test]
+fn test_bash() {
+    let script = completion_script(&registry(), Shell::Bash);
+    assert!(script.contains(
+        r#"    local words="--level -l --test-debug --no-test-debug --test-output --lint-only test lint""#
+    ));
+    assert!(script.contains(r#"        test) words="$words --debug --no-debug --output" ;;"#));
+    assert!(script.contains(
+        r#"        *:--test-output=*|test:--output=*)
+            COMPREPLY=($(compgen -W "all failed none" -- "${cur#*=}"))"#
+    ));
+    assert!(script.contains(r#"            test|lint) scope="$word" ;;"#));
+    assert!(script.ends_with("complete -o default -F _pants_completions pants\n"));
+}
+
+#[test]
+fn test_zsh() {
+    let script = completion_script(&registry(), Shell::Zsh);
+    assert!(script.starts_with("#compdef pants\n"));
+    assert!(script.contains(
+        "    local -a candidates=(--level -l --test-debug --no-test-debug --test-output --lint-only test lint)"
+    ));
+    assert!(script.contains("        (lint) candidates+=(--only) ;;"));
+    assert!(script.contains(
+        "        (*:--level=*)
+            compset -P '*='
+            compadd -- debug info"
+    ));
+}
+
+#[test]
+fn test_fish() {
+    let script = completion_script(&registry(), Shell::Fish);
+    assert!(script.contains("complete -c pants -a 'test lint'\n"));
+    assert!(script.contains("complete -c pants -s l\n"));
+    assert!(script.contains("complete -c pants -l no-test-debug\n"));
+    assert!(
+        script.contains("complete -c pants -n '__fish_seen_subcommand_from test' -l no-debug\n")
+    );
+    assert!(script.contains("complete -c pants -l level -x -a 'debug info'\n"));
+    assert!(script.contains(
+        "complete -c pants -n '__fish_seen_subcommand_from test' -l output -x -a 'all failed none'\n"
+    ));
+}
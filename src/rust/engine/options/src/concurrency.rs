@@ -0,0 +1,72 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::thread::available_parallelism;
+
+///
+/// The value of a concurrency option, such as `[GLOBAL].process_execution_local_parallelism`:
+/// either `auto` (one per CPU), a multiple of the CPU count such as `0.5x` or `2x`, or a positive
+/// int. It is resolved to a count against the number of CPUs.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Concurrency {
+    Auto,
+    PerCpu(f64),
+    Absolute(usize),
+}
+
+impl Concurrency {
+    pub fn parse(value: &str) -> Result<Concurrency, String> {
+        let value = value.trim();
+        let concurrency = if value.eq_ignore_ascii_case("auto") {
+            Some(Concurrency::Auto)
+        } else if let Some(multiple) = value.strip_suffix(['x', 'X']) {
+            multiple
+                .parse::<f64>()
+                .ok()
+                .filter(|multiple| multiple.is_finite() && *multiple > 0.0)
+                .map(Concurrency::PerCpu)
+        } else {
+            value
+                .parse::<usize>()
+                .ok()
+                .filter(|count| *count > 0)
+                .map(Concurrency::Absolute)
+        };
+        concurrency.ok_or_else(|| {
+            "Expected `auto`, a multiple of the CPU count such as `0.5x`, or a positive int"
+                .to_owned()
+        })
+    }
+
+    ///
+    /// Resolves this concurrency against the given number of CPUs. A multiple of the CPU count is
+    /// rounded down, but is always at least 1.
+    ///
+    pub fn resolve(self, cpus: usize) -> usize {
+        match self {
+            Concurrency::Auto => cpus,
+            Concurrency::PerCpu(multiple) => ((cpus as f64 * multiple) as usize).max(1),
+            Concurrency::Absolute(count) => count,
+        }
+    }
+}
+
+impl fmt::Display for Concurrency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Concurrency::Auto => write!(f, "auto"),
+            Concurrency::PerCpu(multiple) => write!(f, "{multiple}x"),
+            Concurrency::Absolute(count) => write!(f, "{count}"),
+        }
+    }
+}
+
+///
+/// The number of CPUs available to this process, or 1 if it can't be detected.
+///
+pub fn cpu_count() -> usize {
+    available_parallelism().map_or(1, NonZeroUsize::get)
+}
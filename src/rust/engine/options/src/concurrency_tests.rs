@@ -0,0 +1,38 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::concurrency::{cpu_count, Concurrency};
+
+#[test]
+fn test_parse() {
+    assert_eq!(Ok(Concurrency::Auto), Concurrency::parse("auto"));
+    assert_eq!(Ok(Concurrency::Auto), Concurrency::parse(" AUTO "));
+    assert_eq!(Ok(Concurrency::PerCpu(0.5)), Concurrency::parse("0.5x"));
+    assert_eq!(Ok(Concurrency::PerCpu(2.0)), Concurrency::parse("2X"));
+    assert_eq!(Ok(Concurrency::Absolute(8)), Concurrency::parse("8"));
+
+    let expected = Err(
+        "Expected `auto`, a multiple of the CPU count such as `0.5x`, or a positive int".to_owned(),
+    );
+    for invalid in ["", "0", "-1", "x", "0x", "-0.5x", "infx", "1.5", "many"] {
+        assert_eq!(expected, Concurrency::parse(invalid), "{invalid}");
+    }
+}
+
+#[test]
+fn test_resolve() {
+    assert_eq!(16, Concurrency::Auto.resolve(16));
+    assert_eq!(8, Concurrency::PerCpu(0.5).resolve(16));
+    assert_eq!(1, Concurrency::PerCpu(0.5).resolve(1));
+    assert_eq!(3, Concurrency::PerCpu(0.75).resolve(5));
+    assert_eq!(32, Concurrency::PerCpu(2.0).resolve(16));
+    assert_eq!(4, Concurrency::Absolute(4).resolve(16));
+    assert!(cpu_count() >= 1);
+}
+
+#[test]
+fn test_display() {
+    for value in ["auto", "0.5x", "2x", "8"] {
+        assert_eq!(value, Concurrency::parse(value).unwrap().to_string());
+    }
+}
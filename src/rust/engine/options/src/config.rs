@@ -1,54 +1,48 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-use std::collections::{HashMap, HashSet};
-use std::fs;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use lazy_static::lazy_static;
+use indexmap::IndexMap;
+use itertools::Itertools;
 use regex::Regex;
 use toml::value::Table;
 use toml::Value;
 
-use super::{DictEdit, DictEditAction, ListEdit, ListEditAction, OptionsSource, Val};
-use crate::fromfile::FromfileExpander;
+use super::{
+    Datetime, DictEdit, DictEditAction, ListEdit, ListEditAction, OptionsSource, RawValue, Val,
+    UNSET,
+};
+use crate::concurrency::Concurrency;
+use crate::decrypt::{is_envelope, Decryptor};
+use crate::filesystem::{FileSystem, LocalFileSystem};
+use crate::fromfile::{decode_utf8, FromfileExpander};
 use crate::id::{NameTransform, OptionId};
-use crate::parse::Parseable;
+use crate::interpolation::{is_whole_placeholder, InterpolationMap, Interpolator};
+use crate::parse::{check_percentage, parse_datetime, parse_dict, parse_percentage, Parseable};
+use crate::seeds::LazySeeds;
+use crate::shell::ShellInterpolation;
+use crate::snapshot::digest;
+use crate::stats::{Counter, ParseStats, Phase};
+use crate::types::ToVal;
 
-type InterpolationMap = HashMap<String, String>;
+pub(crate) static DEFAULT_SECTION: &str = "DEFAULT";
+pub(crate) static GLOBAL_SECTION: &str = "GLOBAL";
 
-static DEFAULT_SECTION: &str = "DEFAULT";
+///
+/// A section that sets this key to `false` does not pick up values from the DEFAULT section.
+/// Sections of nested scopes inherit the setting of the nearest enclosing section that sets it.
+///
+pub(crate) static INHERIT_DEFAULT_KEY: &str = "inherit_default";
 
-lazy_static! {
-    static ref PLACEHOLDER_RE: Regex = Regex::new(r"%\(([a-zA-Z0-9_.]+)\)s").unwrap();
-}
-
-pub(crate) fn interpolate_string(
-    value: String,
-    replacements: &InterpolationMap,
-) -> Result<String, String> {
-    let caps_vec: Vec<_> = PLACEHOLDER_RE.captures_iter(&value).collect();
-    if caps_vec.is_empty() {
-        return Ok(value);
-    }
-
-    let mut new_value = String::with_capacity(value.len());
-    let mut last_match = 0;
-    for caps in caps_vec {
-        let m = caps.get(0).unwrap();
-        new_value.push_str(&value[last_match..m.start()]);
-        let placeholder_name = &caps[1];
-        let replacement = replacements.get(placeholder_name).ok_or(format!(
-            "Unknown value for placeholder `{}`",
-            placeholder_name
-        ))?;
-        new_value.push_str(replacement);
-        last_match = m.end();
-    }
-    new_value.push_str(&value[last_match..]);
-    // A replacement string may itself contain a placeholder, so we recurse.
-    interpolate_string(new_value, replacements)
-}
+///
+/// A section may list the names of options under this key, as in `final = ["remote_cache_read"]`,
+/// so that no source of higher precedence, such as a later config file, an env var or a flag, may
+/// set them. Useful for policies that are enforced by a shared config file.
+///
+pub(crate) static FINAL_KEY: &str = "final";
 
 struct InterpolationError {
     key: String,
@@ -58,19 +52,23 @@ struct InterpolationError {
 fn interpolate_value(
     key: &str,
     value: Value,
-    replacements: &InterpolationMap,
+    interpolator: &mut Interpolator,
 ) -> Result<Value, InterpolationError> {
     Ok(match value {
-        Value::String(s) => Value::String(interpolate_string(s, replacements).map_err(|msg| {
-            InterpolationError {
-                key: key.to_string(),
-                msg,
-            }
-        })?),
+        Value::String(s) => {
+            Value::String(
+                interpolator
+                    .interpolate(&s)
+                    .map_err(|msg| InterpolationError {
+                        key: key.to_string(),
+                        msg,
+                    })?,
+            )
+        }
         Value::Array(v) => {
             let new_v: Result<Vec<_>, _> = v
                 .into_iter()
-                .map(|x| interpolate_value(key, x, replacements))
+                .map(|x| interpolate_value(key, x, interpolator))
                 .collect();
             Value::Array(new_v?)
         }
@@ -82,7 +80,7 @@ fn interpolate_value(
                         // Use the section-level key even if this is a nested table value.
                         if key.is_empty() { &k } else { key },
                         v,
-                        replacements,
+                        interpolator,
                     ) {
                         Ok(new_v) => Ok((k, new_v)),
                         Err(s) => Err(s),
@@ -95,6 +93,26 @@ fn interpolate_value(
     })
 }
 
+// Records the keys of the section (and of its sub-tables, which may be the sections of nested
+// scopes) whose values consist of a single placeholder, by section name and key.
+fn find_whole_placeholders(
+    section_name: &str,
+    section: &Value,
+    found: &mut HashMap<(String, String), String>,
+) {
+    for (key, value) in section.as_table().into_iter().flatten() {
+        match value {
+            Value::String(s) if is_whole_placeholder(s) => {
+                found.insert((section_name.to_owned(), key.clone()), s.clone());
+            }
+            Value::Table(_) => {
+                find_whole_placeholders(&format!("{section_name}.{key}"), value, found)
+            }
+            _ => (),
+        }
+    }
+}
+
 struct ValueConversionError<'a> {
     expected_type: &'static str,
     given_value: &'a Value,
@@ -104,16 +122,18 @@ trait FromValue: Parseable {
     fn from_value(value: &Value) -> Result<Self, ValueConversionError>;
 
     fn from_config(config: &ConfigReader, id: &OptionId) -> Result<Option<Self>, String> {
-        if let Some(value) = config.get_value(id) {
-            if value.is_str() {
+        if let Some((section_name, value)) = config.get_value(id) {
+            if let Some(value) = value.as_str() {
                 match config
                     .fromfile_expander
-                    .expand(value.as_str().unwrap().to_owned())
+                    .expand(config.decrypt(id, value)?)
                     .map_err(|e| e.render(config.display(id)))?
                 {
-                    Some(expanded_value) => Ok(Some(
-                        Self::parse(&expanded_value).map_err(|e| e.render(config.display(id)))?,
-                    )),
+                    Some(expanded_value) => {
+                        Ok(Some(Self::parse(&expanded_value).map_err(|e| {
+                            config.coercion_error(section_name, id, e.render(config.display(id)))
+                        })?))
+                    }
                     _ => Ok(None),
                 }
             } else {
@@ -188,10 +208,27 @@ impl FromValue for i64 {
     }
 }
 
+impl FromValue for i128 {
+    fn from_value(value: &Value) -> Result<i128, ValueConversionError> {
+        // NB: TOML ints are 64 bit, so bigger ints must be given as strings.
+        if let Some(int) = value.as_integer() {
+            Ok(int.into())
+        } else {
+            Err(ValueConversionError {
+                expected_type: "int",
+                given_value: value,
+            })
+        }
+    }
+}
+
 impl FromValue for f64 {
     fn from_value(value: &Value) -> Result<f64, ValueConversionError> {
+        // As for floats parsed from strings, we coerce ints.
         if let Some(float) = value.as_float() {
             Ok(float)
+        } else if let Some(int) = value.as_integer() {
+            Ok(int as f64)
         } else {
             Err(ValueConversionError {
                 expected_type: "float",
@@ -201,13 +238,54 @@ impl FromValue for f64 {
     }
 }
 
-fn toml_value_to_val(value: &Value) -> Val {
+impl FromValue for IndexMap<String, Val> {
+    fn from_value(value: &Value) -> Result<IndexMap<String, Val>, ValueConversionError> {
+        if value.is_table() {
+            Ok(toml_table_to_dict(value))
+        } else {
+            Err(ValueConversionError {
+                expected_type: "table",
+                given_value: value,
+            })
+        }
+    }
+}
+
+// Renders a table key, which must be quoted unless it consists only of ASCII letters, digits,
+// underscores and dashes.
+pub(crate) fn toml_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_bare {
+        key.to_owned()
+    } else {
+        Value::String(key.to_owned()).to_string()
+    }
+}
+
+// Renders a value on a single line, as the `Display` impl of a `Value::Table` spans many lines.
+fn toml_value_to_inline_string(value: &Value) -> String {
+    match value {
+        Value::Array(a) => format!("[{}]", a.iter().map(toml_value_to_inline_string).join(", ")),
+        Value::Table(t) => format!(
+            "{{{}}}",
+            t.iter()
+                .map(|(k, v)| format!("{} = {}", toml_key(k), toml_value_to_inline_string(v)))
+                .join(", ")
+        ),
+        _ => value.to_string(),
+    }
+}
+
+pub(crate) fn toml_value_to_val(value: &Value) -> Val {
     match value {
-        Value::String(s) => Val::String(s.to_owned()),
+        Value::String(s) => Val::String(s.into()),
         Value::Integer(i) => Val::Int(*i),
         Value::Float(f) => Val::Float(*f),
         Value::Boolean(b) => Val::Bool(*b),
-        Value::Datetime(d) => Val::String(d.to_string()),
+        Value::Datetime(d) => Val::Date(d.clone()),
         Value::Array(a) => Val::List(a.iter().map(toml_value_to_val).collect()),
         Value::Table(t) => Val::Dict(
             t.iter()
@@ -218,7 +296,7 @@ fn toml_value_to_val(value: &Value) -> Val {
 }
 
 // Helper function. Only call if you know that the arg is a Value::Table.
-fn toml_table_to_dict(table: &Value) -> HashMap<String, Val> {
+fn toml_table_to_dict(table: &Value) -> IndexMap<String, Val> {
     if !table.is_table() {
         panic!("Expected a TOML table but received: {table}");
     }
@@ -229,6 +307,74 @@ fn toml_table_to_dict(table: &Value) -> HashMap<String, Val> {
     }
 }
 
+// The inverse of `toml_value_to_val`, for those values which TOML can represent.
+fn val_to_toml_value(val: &Val) -> Option<Value> {
+    Some(match val {
+        Val::Bool(b) => Value::Boolean(*b),
+        Val::Int(i) => Value::Integer(*i),
+        Val::Float(f) => Value::Float(*f),
+        Val::String(s) => Value::String(s.to_string()),
+        Val::List(l) => Value::Array(l.iter().map(val_to_toml_value).collect::<Option<_>>()?),
+        Val::Dict(d) => Value::Table(
+            d.iter()
+                .map(|(k, v)| Some((k.clone(), val_to_toml_value(v)?)))
+                .collect::<Option<_>>()?,
+        ),
+        Val::Date(d) => Value::Datetime(d.clone()),
+        Val::Bytes(_) | Val::Null => return None,
+    })
+}
+
+///
+/// Renders a resolved value as a TOML value on a single line, e.g. for the right hand side of an
+/// option in a config file, such that it is read back as an equal value. Fails for those values
+/// which TOML cannot represent: bytes, and `None` (including within lists and dicts).
+///
+pub fn val_to_toml(val: &Val) -> Result<String, String> {
+    val_to_toml_value(val)
+        .map(|value| toml_value_to_inline_string(&value))
+        .ok_or_else(|| format!("The value {val} cannot be represented in TOML."))
+}
+
+///
+/// Renders the given list edits of `option_name` as native TOML, if they can be written that way:
+/// i.e., as a single edit, or as an addition alongside a removal, other than an insertion.
+///
+fn list_edits_to_toml<T: ToVal>(option_name: &str, edits: &[ListEdit<T>]) -> Option<String> {
+    let expressible = match edits {
+        [_] => true,
+        [a, b] => {
+            a.action != b.action
+                && a.action != ListEditAction::Replace
+                && b.action != ListEditAction::Replace
+        }
+        _ => false,
+    };
+    if !expressible {
+        return None;
+    }
+    edits
+        .iter()
+        .map(|edit| {
+            let key = match edit.action {
+                ListEditAction::Replace => option_name.to_owned(),
+                ListEditAction::Add => format!("{option_name}.add"),
+                ListEditAction::Remove => format!("{option_name}.remove"),
+                // Insertions have no native TOML syntax.
+                _ => return None,
+            };
+            let value = val_to_toml_value(&edit.items.to_val())?;
+            Some(format!("{key} = {}", toml_value_to_inline_string(&value)))
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|lines| lines.join("\n"))
+}
+
+// In strict mode, strings are still accepted for list and dict values that refer to a fromfile.
+fn is_fromfile(value: &str) -> bool {
+    value.trim_start().starts_with('@')
+}
+
 #[derive(Clone, Debug)]
 pub struct ConfigSource {
     pub path: PathBuf,
@@ -237,13 +383,35 @@ pub struct ConfigSource {
 
 impl ConfigSource {
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ConfigSource, String> {
-        let content = fs::read_to_string(&path).map_err(|e| {
+        Self::from_filesystem(&LocalFileSystem, path)
+    }
+
+    /// Reads a config file from the given filesystem.
+    pub fn from_filesystem<P: AsRef<Path>>(
+        fs: &dyn FileSystem,
+        path: P,
+    ) -> Result<ConfigSource, String> {
+        let content = fs.read(path.as_ref()).map_err(|e| {
             format!(
                 "Failed to read config file {}: {}",
                 path.as_ref().display(),
                 e
             )
         })?;
+        Self::from_bytes(path, content)
+    }
+
+    ///
+    /// Creates a config source from the raw content of a config file, which must be UTF-8. A
+    /// leading byte order mark is dropped.
+    ///
+    pub fn from_bytes<P: AsRef<Path>>(path: P, content: Vec<u8>) -> Result<ConfigSource, String> {
+        let content = decode_utf8(content, 0).map_err(|e| {
+            format!(
+                "Failed to read config file {}: {e}",
+                path.as_ref().display()
+            )
+        })?;
         Ok(ConfigSource {
             path: path.as_ref().to_path_buf(),
             content,
@@ -251,16 +419,120 @@ impl ConfigSource {
     }
 }
 
-#[derive(Clone)]
+/// How config sections that don't correspond to any known scope are treated by validation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnknownScopePolicy {
+    Error,
+    Warn,
+}
+
+/// Describes which config sections are acceptable beyond the known scopes. This is needed for
+/// the scopes of third-party plugins, which may only be registered after the config is validated.
+#[derive(Clone, Debug)]
+pub struct ScopeAllowances {
+    patterns: Vec<Regex>,
+    unknown_scopes: UnknownScopePolicy,
+}
+
+impl ScopeAllowances {
+    pub fn new() -> Self {
+        Self {
+            patterns: vec![],
+            unknown_scopes: UnknownScopePolicy::Error,
+        }
+    }
+
+    /// Allow any section whose name matches the given pattern, in which `*` matches any
+    /// (possibly empty) run of characters. E.g., `mypy-*` or `*.plugin`.
+    pub fn allow_pattern(mut self, pattern: &str) -> Self {
+        let regex = pattern
+            .split('*')
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(".*");
+        self.patterns
+            .push(Regex::new(&format!("^{regex}$")).unwrap());
+        self
+    }
+
+    pub fn unknown_scopes(mut self, policy: UnknownScopePolicy) -> Self {
+        self.unknown_scopes = policy;
+        self
+    }
+
+    fn is_allowed(&self, section_name: &str) -> bool {
+        self.patterns.iter().any(|re| re.is_match(section_name))
+    }
+}
+
+impl Default for ScopeAllowances {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct ConfigValidation {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+// NB: A parsed Config can be large, so it is not Clone: share it via an Arc instead.
 pub(crate) struct Config {
+    path: PathBuf,
+    digest: String,
+    // The raw content, used only to locate values when explaining them.
+    content: String,
     value: Value,
+    // The values that consisted of a single placeholder before interpolation, by section and key.
+    whole_placeholders: HashMap<(String, String), String>,
 }
 
 impl Config {
     pub(crate) fn parse(
         config_source: &ConfigSource,
         seed_values: &InterpolationMap,
+        lazy_seeds: Option<&LazySeeds>,
+        shell: Option<&ShellInterpolation>,
+        stats: &ParseStats,
     ) -> Result<Config, String> {
+        Self::parse_filtered(config_source, seed_values, lazy_seeds, None, shell, stats)
+    }
+
+    ///
+    /// Parses only the given global option names out of the `[GLOBAL]` and `[DEFAULT]` sections of
+    /// the config, skipping the interpolation of every other value. Values of the remaining
+    /// sections are still available as interpolation sources, but nothing else is retained.
+    ///
+    pub(crate) fn parse_bootstrap(
+        config_source: &ConfigSource,
+        seed_values: &InterpolationMap,
+        lazy_seeds: Option<&LazySeeds>,
+        option_names: &[&str],
+        shell: Option<&ShellInterpolation>,
+        stats: &ParseStats,
+    ) -> Result<Config, String> {
+        Self::parse_filtered(
+            config_source,
+            seed_values,
+            lazy_seeds,
+            Some(option_names),
+            shell,
+            stats,
+        )
+    }
+
+    fn parse_filtered(
+        config_source: &ConfigSource,
+        seed_values: &InterpolationMap,
+        lazy_seeds: Option<&LazySeeds>,
+        option_names: Option<&[&str]>,
+        shell: Option<&ShellInterpolation>,
+        stats: &ParseStats,
+    ) -> Result<Config, String> {
+        let _span = stats.span(Phase::ConfigParse);
+        stats.increment(Counter::ConfigFilesRead, 1);
+        stats.increment(Counter::ConfigBytesRead, config_source.content.len() as u64);
         let config = config_source.content.parse::<Value>().map_err(|e| {
             format!(
                 "Failed to parse config file {}: {}",
@@ -276,8 +548,16 @@ impl Config {
             if let Some(section) = section {
                 if let Some(table) = section.as_table() {
                     for (key, value) in table.iter() {
-                        if let Value::String(s) = value {
-                            imap.insert(key.clone(), s.clone());
+                        match value {
+                            Value::String(s) => {
+                                imap.insert(key.clone(), s.clone());
+                            }
+                            // Arrays of strings are available as list literals, e.g. for the
+                            // `join` filter, as in `%(items|join:,)s`.
+                            Value::Array(items) if items.iter().all(Value::is_str) => {
+                                imap.insert(key.clone(), toml_value_to_inline_string(value));
+                            }
+                            _ => (),
                         }
                     }
                 }
@@ -288,9 +568,19 @@ impl Config {
         let default_imap =
             add_section_to_interpolation_map(seed_values.clone(), config.get(DEFAULT_SECTION))?;
 
+        let mut whole_placeholders = HashMap::new();
+        for (section_name, section) in config.as_table().into_iter().flatten() {
+            find_whole_placeholders(section_name, section, &mut whole_placeholders);
+        }
+
         let new_sections: Result<Vec<(String, Value)>, String> = match config {
             Value::Table(t) => t
                 .into_iter()
+                .filter(|(section_name, _)| {
+                    option_names.is_none()
+                        || section_name == DEFAULT_SECTION
+                        || section_name == GLOBAL_SECTION
+                })
                 .map(|(section_name, section)| {
                     if !section.is_table() {
                         return Err(format!(
@@ -307,16 +597,35 @@ impl Config {
                     } else {
                         add_section_to_interpolation_map(default_imap.clone(), Some(&section))?
                     };
-                    let new_section = interpolate_value("", section.clone(), &section_imap)
-                        .map_err(|e| {
-                            format!(
-                                "{} in config file {}, section {}, key {}",
-                                e.msg,
-                                config_source.path.display(),
-                                section_name,
-                                e.key
-                            )
-                        })?;
+                    let section = match (option_names, section) {
+                        (Some(names), Value::Table(t)) => Value::Table(
+                            t.into_iter()
+                                .filter(|(key, _)| {
+                                    names.contains(&key.as_str())
+                                        || key == INHERIT_DEFAULT_KEY
+                                        || key == FINAL_KEY
+                                })
+                                .collect(),
+                        ),
+                        (_, section) => section,
+                    };
+                    let _interpolation_span = stats.span(Phase::Interpolation);
+                    let new_section = interpolate_value(
+                        "",
+                        section,
+                        &mut Interpolator::new(&section_imap)
+                            .lazy_seeds(lazy_seeds)
+                            .shell(shell),
+                    )
+                    .map_err(|e| {
+                        format!(
+                            "{} in config file {}, section {}, key {}",
+                            e.msg,
+                            config_source.path.display(),
+                            section_name,
+                            e.key
+                        )
+                    })?;
                     Ok((section_name, new_section))
                 })
                 .collect(),
@@ -331,108 +640,391 @@ impl Config {
 
         let new_table = Table::from_iter(new_sections?);
         Ok(Self {
+            path: config_source.path.clone(),
+            digest: digest(config_source.content.as_bytes()),
+            content: config_source.content.clone(),
             value: Value::Table(new_table),
+            whole_placeholders,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// The sections of the config, with their (interpolated) tables, in the order written.
+    pub(crate) fn sections(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.value
+            .as_table()
+            .into_iter()
+            .flat_map(|table| table.iter().map(|(name, section)| (name.as_str(), section)))
+    }
+
+    ///
+    /// Returns the table for the given section, if any. The section of a nested scope may be
+    /// written either as a sub-table of its parent, `[test.pytest]`, or quoted, `["test.pytest"]`.
+    ///
+    pub(crate) fn section(&self, section_name: &str) -> Option<&Value> {
+        self.value.get(section_name).or_else(|| {
+            let mut parts = section_name.split('.');
+            let outermost = self.value.get(parts.next()?)?;
+            parts.try_fold(outermost, |table, part| table.get(part))
         })
     }
+
+    pub(crate) fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    ///
+    /// Returns the placeholder that `key` in `section` consisted of before interpolation, if its
+    /// value was a single placeholder. Such a value may be coerced to the type of its option, even
+    /// in strict mode.
+    ///
+    pub(crate) fn whole_placeholder(&self, section: &str, key: &str) -> Option<&str> {
+        self.whole_placeholders
+            .get(&(section.to_owned(), key.to_owned()))
+            .map(String::as_str)
+    }
+
+    ///
+    /// Returns the 1-based line number at which `key` is set in `section`, if it can be found.
+    ///
+    /// This is a best-effort textual search rather than a parse: it recognizes `key = ...` and
+    /// `key.add = ...` under a `[section]` header, as well as a `[section.key]` sub-table header.
+    ///
+    pub(crate) fn line_of(&self, section: &str, key: &str) -> Option<usize> {
+        let mut current_section = None;
+        for (i, line) in self.content.lines().enumerate() {
+            let line = line.trim();
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let header = header.trim().trim_matches('"');
+                if header
+                    .strip_prefix(section)
+                    .and_then(|h| h.strip_prefix('.'))
+                    == Some(key)
+                {
+                    return Some(i + 1);
+                }
+                current_section = Some(header);
+            } else if current_section == Some(section) {
+                if let Some(rest) = line.strip_prefix(key) {
+                    if rest.trim_start().starts_with(['=', '.']) {
+                        return Some(i + 1);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub(crate) fn validate(
+        &self,
+        section_to_valid_keys: &HashMap<String, HashSet<String>>,
+        allowances: &ScopeAllowances,
+        validation: &mut ConfigValidation,
+    ) {
+        let Some(table) = self.value.as_table() else {
+            return;
+        };
+        let mut sections = table
+            .iter()
+            .map(|(section_name, section)| (section_name.to_owned(), section))
+            .collect::<VecDeque<_>>();
+        while let Some((section_name, section)) = sections.pop_front() {
+            if section_name == DEFAULT_SECTION {
+                continue;
+            }
+            if let Some(valid_keys) = section_to_valid_keys.get(&section_name) {
+                if let Some(section_table) = section.as_table() {
+                    for (key, value) in section_table {
+                        if key == INHERIT_DEFAULT_KEY {
+                            if !value.is_bool() {
+                                validation.errors.push(format!(
+                                    "Expected '{key}' under [{section_name}] in {} to be a boolean but given {value}",
+                                    self.path.display()
+                                ));
+                            }
+                            continue;
+                        }
+                        if key == FINAL_KEY {
+                            let names = value.as_array().and_then(|names| {
+                                names.iter().map(Value::as_str).collect::<Option<Vec<_>>>()
+                            });
+                            match names {
+                                Some(names) => {
+                                    for name in names {
+                                        if !valid_keys.contains(name) {
+                                            validation.errors.push(format!(
+                                                "Invalid option '{name}' listed as {key} \
+                                                under [{section_name}] in {}",
+                                                self.path.display()
+                                            ));
+                                        }
+                                    }
+                                }
+                                None => validation.errors.push(format!(
+                                    "Expected '{key}' under [{section_name}] in {} to be a list \
+                                    of option names but given {value}",
+                                    self.path.display()
+                                )),
+                            }
+                            continue;
+                        }
+                        // The sub-table of a nested scope is validated as a section of its own,
+                        // as is any other sub-table that is not the value of an option, so that
+                        // it is reported as an unknown scope rather than an unknown option.
+                        let nested_section_name = format!("{section_name}.{key}");
+                        if value.is_table()
+                            && (section_to_valid_keys.contains_key(&nested_section_name)
+                                || !valid_keys.contains(key))
+                        {
+                            sections.push_back((nested_section_name, value));
+                        } else if !valid_keys.contains(key) {
+                            validation.errors.push(format!(
+                                "Invalid option '{key}' under [{section_name}] in {}",
+                                self.path.display()
+                            ));
+                        }
+                    }
+                }
+            } else if !allowances.is_allowed(&section_name) {
+                let msg = format!(
+                    "Invalid section [{section_name}] in {}",
+                    self.path.display()
+                );
+                match allowances.unknown_scopes {
+                    UnknownScopePolicy::Error => validation.errors.push(msg),
+                    UnknownScopePolicy::Warn => validation.warnings.push(msg),
+                }
+            }
+        }
+    }
 }
 
+#[derive(Clone)]
 pub(crate) struct ConfigReader {
-    config: Config,
+    config: Arc<Config>,
     fromfile_expander: FromfileExpander,
+    strict: bool,
+    decryptor: Option<Arc<dyn Decryptor>>,
+    // The names of the scopes nested in another, e.g. `test.pytest`, whose sub-tables are sections
+    // of their own rather than the values of options of the enclosing section.
+    nested_scopes: Arc<HashSet<String>>,
 }
 
 impl ConfigReader {
-    pub fn new(config: Config, fromfile_expander: FromfileExpander) -> Self {
+    pub fn new(config: Arc<Config>, fromfile_expander: FromfileExpander) -> Self {
         Self {
             config,
             fromfile_expander,
+            strict: false,
+            decryptor: None,
+            nested_scopes: Arc::default(),
         }
     }
 
+    ///
+    /// Encrypted values, written as envelopes such as `ENC[age:...]`, are decrypted with the given
+    /// decryptor when they are read. Without one, reading an encrypted value is an error.
+    ///
+    pub(crate) fn decryptor(mut self, decryptor: Option<Arc<dyn Decryptor>>) -> Self {
+        self.decryptor = decryptor;
+        self
+    }
+
+    // Decrypts the value of the option `id` if it is an encrypted envelope.
+    fn decrypt(&self, id: &OptionId, value: &str) -> Result<String, String> {
+        if !is_envelope(value) {
+            return Ok(value.to_owned());
+        }
+        let path = self.config.path.display();
+        let Some(decryptor) = &self.decryptor else {
+            return Err(format!(
+                "The value of {id} in {path} is encrypted, but neither `age_identity` nor \
+                `decryption_command` is configured to decrypt it."
+            ));
+        };
+        decryptor
+            .decrypt(value)
+            .map_err(|e| format!("Failed to decrypt the value of {id} in {path}: {e}"))
+    }
+
+    ///
+    /// In strict mode, list and dict values must be written as native TOML arrays and tables,
+    /// with `.add` and `.remove` keys for edits, rather than as strings in the syntax used by
+    /// flags and env vars, such as `"+[1, 2]"` or `'{"a": 1}'`. Fromfile references are still
+    /// accepted.
+    ///
+    pub(crate) fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    ///
+    /// The scopes nested in another, e.g. `test.pytest`. The sub-table `[test.pytest]` also
+    /// appears as the key `pytest` of the `[test]` section, but is not read as the value of an
+    /// option `[test] pytest`.
+    ///
+    pub(crate) fn nested_scopes(mut self, nested_scopes: Arc<HashSet<String>>) -> Self {
+        self.nested_scopes = nested_scopes;
+        self
+    }
+
     fn option_name(id: &OptionId) -> String {
         id.name("_", NameTransform::None)
     }
 
+    ///
+    /// The sections consulted for the option `id`, in increasing order of priority: the DEFAULT
+    /// section, then the sections of the scopes that `id`'s scope is nested in, from the outermost
+    /// in, and finally the section of `id`'s scope itself.
+    ///
+    /// The DEFAULT section is omitted if the scope opts out of it via `inherit_default = false`.
+    ///
+    fn sections<'a>(&self, id: &'a OptionId) -> Vec<&'a str> {
+        let lineage = id.scope.lineage();
+        let inherit_default = lineage
+            .iter()
+            .rev()
+            .find_map(|section_name| {
+                self.get_from_section(section_name, INHERIT_DEFAULT_KEY)?
+                    .as_bool()
+            })
+            .unwrap_or(true);
+        inherit_default
+            .then_some(DEFAULT_SECTION)
+            .into_iter()
+            .chain(lineage)
+            .collect()
+    }
+
     fn get_from_section(&self, section_name: &str, option_name: &str) -> Option<&Value> {
-        self.config
-            .value
-            .get(section_name)
-            .and_then(|table| table.get(option_name))
+        let value = self.config.section(section_name)?.get(option_name)?;
+        if value.is_table()
+            && self
+                .nested_scopes
+                .contains(&format!("{section_name}.{option_name}"))
+        {
+            return None;
+        }
+        Some(value)
     }
 
-    fn get_value(&self, id: &OptionId) -> Option<&Value> {
+    // Returns the value of the option `id`, along with the name of the section it is set in.
+    fn get_value<'a>(&'a self, id: &'a OptionId) -> Option<(&'a str, &'a Value)> {
         let option_name = Self::option_name(id);
-        self.get_from_section(id.scope.name(), &option_name)
-            .or(self.get_from_section(DEFAULT_SECTION, &option_name))
+        self.sections(id)
+            .into_iter()
+            .rev()
+            .find_map(|section_name| {
+                Some((
+                    section_name,
+                    self.get_from_section(section_name, &option_name)?,
+                ))
+            })
     }
 
-    fn get_list<T: FromValue + Parseable>(
+    // Adds the origin of a value that was interpolated from a single placeholder to an error about
+    // coercing it to the type of its option.
+    fn coercion_error(&self, section_name: &str, id: &OptionId, err: String) -> String {
+        let option_name = Self::option_name(id);
+        match self.config.whole_placeholder(section_name, &option_name) {
+            Some(placeholder) => format!(
+                "{err}\nThe value was interpolated from `{placeholder}` in config file {}, \
+                section {section_name}, key {option_name}.",
+                self.config.path.display()
+            ),
+            None => err,
+        }
+    }
+
+    fn get_list<T: FromValue + Parseable + ToVal>(
         &self,
         id: &OptionId,
     ) -> Result<Option<Vec<ListEdit<T>>>, String> {
-        let from_scoped_section_opt = self.get_list_from_section(id.scope.name(), id)?;
-
-        Ok(
-            if let Some(from_default_section) = self.get_list_from_section(DEFAULT_SECTION, id)? {
-                Some(itertools::concat([
-                    from_default_section,
-                    from_scoped_section_opt.unwrap_or(vec![]),
-                ]))
-            } else {
-                from_scoped_section_opt
-            },
-        )
+        let mut edits: Option<Vec<ListEdit<T>>> = None;
+        for section_name in self.sections(id) {
+            if let Some(section_edits) = self.get_list_from_section(section_name, id)? {
+                edits.get_or_insert_with(Vec::new).extend(section_edits);
+            }
+        }
+        Ok(edits)
     }
 
-    fn get_list_from_section<T: FromValue + Parseable>(
+    fn get_list_from_section<T: FromValue + Parseable + ToVal>(
         &self,
         section_name: &str,
         id: &OptionId,
     ) -> Result<Option<Vec<ListEdit<T>>>, String> {
         let mut list_edits = vec![];
-        if let Some(table) = self.config.value.get(section_name) {
-            let option_name = &Self::option_name(id);
-            if let Some(value) = table.get(option_name) {
-                match value {
-                    Value::Table(sub_table) => {
-                        if sub_table.is_empty()
-                            || !sub_table.keys().collect::<HashSet<_>>().is_subset(
-                                &["add".to_owned(), "remove".to_owned()]
-                                    .iter()
-                                    .collect::<HashSet<_>>(),
-                            )
-                        {
-                            return Err(format!(
+        let option_name = &Self::option_name(id);
+        if let Some(value) = self.get_from_section(section_name, option_name) {
+            match value {
+                Value::Table(sub_table) => {
+                    if sub_table.is_empty()
+                        || !sub_table.keys().collect::<HashSet<_>>().is_subset(
+                            &["add".to_owned(), "remove".to_owned()]
+                                .iter()
+                                .collect::<HashSet<_>>(),
+                        )
+                    {
+                        return Err(format!(
                                 "Expected {option_name} to contain an 'add' element, a 'remove' element or both but found: {sub_table:?}"
                             ));
-                        }
-                        if let Some(add) = sub_table.get("add") {
-                            list_edits.push(ListEdit {
-                                action: ListEditAction::Add,
-                                items: T::extract_list(&format!("{option_name}.add"), add)?,
-                            });
-                        }
-                        if let Some(remove) = sub_table.get("remove") {
-                            list_edits.push(ListEdit {
-                                action: ListEditAction::Remove,
-                                items: T::extract_list(&format!("{option_name}.remove"), remove)?,
-                            });
-                        }
                     }
-                    Value::String(v) => {
-                        if let Some(es) = self
-                            .fromfile_expander
-                            .expand_to_list::<T>(v.to_string())
-                            .map_err(|e| e.render(self.display(id)))?
-                        {
-                            list_edits.extend(es);
-                        }
+                    if let Some(add) = sub_table.get("add") {
+                        list_edits.push(ListEdit {
+                            action: ListEditAction::Add,
+                            items: T::extract_list(&format!("{option_name}.add"), add)?,
+                        });
+                    }
+                    if let Some(remove) = sub_table.get("remove") {
+                        list_edits.push(ListEdit {
+                            action: ListEditAction::Remove,
+                            items: T::extract_list(&format!("{option_name}.remove"), remove)?,
+                        });
+                    }
+                }
+                Value::String(v)
+                    if self.strict
+                        && !is_fromfile(v)
+                        && !is_envelope(v)
+                        && self
+                            .config
+                            .whole_placeholder(section_name, option_name)
+                            .is_none() =>
+                {
+                    let fix = T::parse_list(v)
+                        .ok()
+                        .and_then(|edits| list_edits_to_toml(option_name, &edits))
+                        .unwrap_or_else(|| {
+                            format!("{option_name}.add = [...] and/or {option_name}.remove = [...]")
+                        });
+                    return Err(format!(
+                            "Expected {option_name} to be a TOML array or a table of 'add' and 'remove' \
+                            arrays, but given the string {v:?}, which strict_toml disallows. Instead use:\n{fix}"
+                        ));
+                }
+                Value::String(v) => {
+                    if let Some(es) = self
+                        .fromfile_expander
+                        .expand_to_list::<T>(self.decrypt(id, v)?)
+                        .map_err(|e| {
+                            self.coercion_error(section_name, id, e.render(self.display(id)))
+                        })?
+                    {
+                        list_edits.extend(es);
                     }
-                    value => list_edits.push(ListEdit {
-                        action: ListEditAction::Replace,
-                        items: T::extract_list(option_name, value)?,
-                    }),
                 }
+                value => list_edits.push(ListEdit {
+                    action: ListEditAction::Replace,
+                    items: T::extract_list(option_name, value)?,
+                }),
             }
         }
 
@@ -448,35 +1040,60 @@ impl ConfigReader {
         section_name: &str,
         id: &OptionId,
     ) -> Result<Option<Vec<DictEdit>>, String> {
-        if let Some(table) = self.config.value.get(section_name) {
-            let option_name = Self::option_name(id);
-            if let Some(value) = table.get(&option_name) {
-                match value {
-                    Value::Table(sub_table) => {
-                        if let Some(add) = sub_table.get("add") {
-                            if sub_table.len() == 1 && add.is_table() {
-                                return Ok(Some(vec![DictEdit {
-                                    action: DictEditAction::Add,
-                                    items: toml_table_to_dict(add),
-                                }]));
-                            }
+        let option_name = Self::option_name(id);
+        if let Some(value) = self.get_from_section(section_name, &option_name) {
+            match value {
+                Value::Table(sub_table) => {
+                    if let Some(add) = sub_table.get("add") {
+                        if sub_table.len() == 1 && add.is_table() {
+                            return Ok(Some(vec![DictEdit {
+                                action: DictEditAction::Add,
+                                items: toml_table_to_dict(add),
+                            }]));
                         }
-                        return Ok(Some(vec![DictEdit {
-                            action: DictEditAction::Replace,
-                            items: toml_table_to_dict(value),
-                        }]));
                     }
-                    Value::String(v) => {
-                        return self
-                            .fromfile_expander
-                            .expand_to_dict(v.to_owned())
-                            .map_err(|e| e.render(self.display(id)));
-                    }
-                    _ => {
-                        return Err(format!(
+                    return Ok(Some(vec![DictEdit {
+                        action: DictEditAction::Replace,
+                        items: toml_table_to_dict(value),
+                    }]));
+                }
+                Value::String(v)
+                    if self.strict
+                        && !is_fromfile(v)
+                        && !is_envelope(v)
+                        && self
+                            .config
+                            .whole_placeholder(section_name, &option_name)
+                            .is_none() =>
+                {
+                    let fix = parse_dict(v)
+                        .ok()
+                        .and_then(|edit| {
+                            let key = match edit.action {
+                                DictEditAction::Replace => option_name.clone(),
+                                DictEditAction::Add => format!("{option_name}.add"),
+                            };
+                            let value = val_to_toml_value(&Val::Dict(edit.items))?;
+                            Some(format!("{key} = {}", toml_value_to_inline_string(&value)))
+                        })
+                        .unwrap_or_else(|| format!("{option_name} = {{...}}"));
+                    return Err(format!(
+                        "Expected {option_name} to be a TOML table, but given the string {v:?}, \
+                            which strict_toml disallows. Instead use:\n{fix}"
+                    ));
+                }
+                Value::String(v) => {
+                    return self
+                        .fromfile_expander
+                        .expand_to_dict(self.decrypt(id, v)?)
+                        .map_err(|e| {
+                            self.coercion_error(section_name, id, e.render(self.display(id)))
+                        });
+                }
+                _ => {
+                    return Err(format!(
                             "Expected {option_name} to be a toml table or Python dict, but given {value}."
                         ));
-                    }
                 }
             }
         }
@@ -493,6 +1110,22 @@ impl OptionsSource for ConfigReader {
         String::from_config(self, id)
     }
 
+    fn get_bytes(&self, id: &OptionId) -> Result<Option<Vec<u8>>, String> {
+        match self.get_value(id) {
+            Some((_, value)) => match value.as_str() {
+                Some(value) => self
+                    .fromfile_expander
+                    .expand_to_bytes(self.decrypt(id, value)?)
+                    .map_err(|e| e.render(self.display(id))),
+                None => Err(format!(
+                    "Expected {} to be a string but given {value}",
+                    self.display(id)
+                )),
+            },
+            None => Ok(None),
+        }
+    }
+
     fn get_bool(&self, id: &OptionId) -> Result<Option<bool>, String> {
         bool::from_config(self, id)
     }
@@ -501,10 +1134,79 @@ impl OptionsSource for ConfigReader {
         i64::from_config(self, id)
     }
 
+    fn get_big_int(&self, id: &OptionId) -> Result<Option<i128>, String> {
+        i128::from_config(self, id)
+    }
+
     fn get_float(&self, id: &OptionId) -> Result<Option<f64>, String> {
         f64::from_config(self, id)
     }
 
+    fn get_date(&self, id: &OptionId) -> Result<Option<Datetime>, String> {
+        match self.get_value(id) {
+            Some((_, Value::Datetime(datetime))) if datetime.date.is_some() => {
+                Ok(Some(datetime.clone()))
+            }
+            Some((_, Value::Datetime(time))) => Err(format!(
+                "Invalid date `{time}` for {}: {}",
+                self.display(id),
+                parse_datetime(&time.to_string()).unwrap_err()
+            )),
+            _ => self
+                .get_string(id)?
+                .map(|value| {
+                    parse_datetime(&value).map_err(|e| {
+                        format!("Invalid date `{value}` for {}: {e}", self.display(id))
+                    })
+                })
+                .transpose(),
+        }
+    }
+
+    fn get_percentage(&self, id: &OptionId) -> Result<Option<f64>, String> {
+        // A fraction may be given as a TOML float or int, rather than as a string.
+        if let Some((_, value)) = self.get_value(id) {
+            if let Some(fraction) = value.as_float().or(value.as_integer().map(|i| i as f64)) {
+                return check_percentage(fraction).map(Some).map_err(|e| {
+                    format!("Invalid percentage `{value}` for {}: {e}", self.display(id))
+                });
+            }
+        }
+        self.get_string(id)?
+            .map(|value| {
+                parse_percentage(&value).map_err(|e| {
+                    format!("Invalid percentage `{value}` for {}: {e}", self.display(id))
+                })
+            })
+            .transpose()
+    }
+
+    fn get_concurrency(&self, id: &OptionId) -> Result<Option<Concurrency>, String> {
+        // A count may be given as a TOML int, rather than as a string.
+        if let Some((_, value)) = self.get_value(id) {
+            if let Some(count) = value.as_integer() {
+                return Concurrency::parse(&count.to_string())
+                    .map(Some)
+                    .map_err(|e| {
+                        format!(
+                            "Invalid concurrency `{count}` for {}: {e}",
+                            self.display(id)
+                        )
+                    });
+            }
+        }
+        self.get_string(id)?
+            .map(|value| {
+                Concurrency::parse(&value).map_err(|e| {
+                    format!(
+                        "Invalid concurrency `{value}` for {}: {e}",
+                        self.display(id)
+                    )
+                })
+            })
+            .transpose()
+    }
+
     fn get_bool_list(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<bool>>>, String> {
         self.get_list::<bool>(id)
     }
@@ -521,18 +1223,64 @@ impl OptionsSource for ConfigReader {
         self.get_list::<String>(id)
     }
 
+    fn get_dict_list(
+        &self,
+        id: &OptionId,
+    ) -> Result<Option<Vec<ListEdit<IndexMap<String, Val>>>>, String> {
+        self.get_list::<IndexMap<String, Val>>(id)
+    }
+
     fn get_dict(&self, id: &OptionId) -> Result<Option<Vec<DictEdit>>, String> {
-        let from_scoped_section_opt = self.get_dict_from_section(id.scope.name(), id)?;
-
-        Ok(
-            if let Some(from_default_section) = self.get_dict_from_section(DEFAULT_SECTION, id)? {
-                Some(itertools::concat([
-                    from_default_section,
-                    from_scoped_section_opt.unwrap_or(vec![]),
-                ]))
-            } else {
-                from_scoped_section_opt
-            },
-        )
+        let mut edits: Option<Vec<DictEdit>> = None;
+        for section_name in self.sections(id) {
+            if let Some(section_edits) = self.get_dict_from_section(section_name, id)? {
+                edits.get_or_insert_with(Vec::new).extend(section_edits);
+            }
+        }
+        Ok(edits)
+    }
+
+    fn is_secret(&self, id: &OptionId) -> bool {
+        let option_name = Self::option_name(id);
+        self.sections(id).into_iter().any(|section_name| {
+            matches!(
+                self.get_from_section(section_name, &option_name),
+                Some(Value::String(v)) if is_envelope(v)
+            )
+        })
+    }
+
+    fn is_unset(&self, id: &OptionId) -> bool {
+        // NB: The raw value of a config source is rendered as TOML, so we check the value itself.
+        matches!(self.get_value(id), Some((_, Value::String(value))) if value == UNSET)
+    }
+
+    fn is_final(&self, id: &OptionId) -> bool {
+        // NB: The DEFAULT section may not make options final, as it applies to every scope.
+        let option_name = Self::option_name(id);
+        id.scope.lineage().into_iter().any(|section_name| {
+            self.get_from_section(section_name, FINAL_KEY)
+                .and_then(Value::as_array)
+                .is_some_and(|names| names.iter().any(|name| name.as_str() == Some(&option_name)))
+        })
+    }
+
+    fn get_raw(&self, id: &OptionId) -> Vec<RawValue> {
+        let option_name = Self::option_name(id);
+        self.sections(id)
+            .into_iter()
+            .filter_map(|section_name| {
+                let value = self.get_from_section(section_name, &option_name)?;
+                let path = self.config.path.display();
+                let location = match self.config.line_of(section_name, &option_name) {
+                    Some(line) => format!("{path}:{line} [{section_name}]"),
+                    None => format!("{path} [{section_name}]"),
+                };
+                Some(RawValue {
+                    location,
+                    value: Some(toml_value_to_inline_string(value)),
+                })
+            })
+            .collect()
     }
 }
@@ -1,24 +1,31 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-use maplit::hashmap;
+use indexmap::{indexmap, IndexMap};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::fs::File;
 use std::io::Write;
+use std::sync::Arc;
 
-use crate::config::{interpolate_string, ConfigSource};
+use crate::config::{
+    toml_value_to_val, val_to_toml, ConfigSource, ConfigValidation, ScopeAllowances,
+    UnknownScopePolicy,
+};
+use crate::interpolation::interpolate_string;
 use crate::{
-    option_id, DictEdit, DictEditAction, ListEdit, ListEditAction, OptionId, OptionsSource, Val,
+    option_id, DictEdit, DictEditAction, ListEdit, ListEditAction, OptionId, OptionsSource, Scope,
+    Val,
 };
 
 use crate::config::{Config, ConfigReader};
 use crate::fromfile::test_util::write_fromfile;
 use crate::fromfile::FromfileExpander;
+use crate::stats::ParseStats;
 use tempfile::TempDir;
 
-fn maybe_config(file_content: &str) -> Result<ConfigReader, String> {
+fn parse_config(file_content: &str) -> Result<Config, String> {
     let dir = TempDir::new().unwrap();
     let path = dir.path().join("pants.toml");
     File::create(&path)
@@ -31,8 +38,15 @@ fn maybe_config(file_content: &str) -> Result<ConfigReader, String> {
             ("seed1".to_string(), "seed1val".to_string()),
             ("seed2".to_string(), "seed2val".to_string()),
         ]),
+        None,
+        None,
+        &ParseStats::default(),
     )
-    .map(|config| ConfigReader::new(config, FromfileExpander::relative_to_cwd()))
+}
+
+fn maybe_config(file_content: &str) -> Result<ConfigReader, String> {
+    parse_config(file_content)
+        .map(|config| ConfigReader::new(Arc::new(config), FromfileExpander::relative_to_cwd()))
 }
 
 fn config(file_content: &str) -> ConfigReader {
@@ -56,6 +70,22 @@ fn test_display() {
     );
 }
 
+#[test]
+fn test_config_source_encoding() {
+    // A byte order mark is dropped, and CRLF line endings are handled.
+    let config = config("\u{feff}[GLOBAL]\r\nname = 'pants'\r\n");
+    assert_eq!(
+        Some("pants".to_owned()),
+        config.get_string(&option_id!("name")).unwrap()
+    );
+
+    assert_eq!(
+        "Failed to read config file pants.toml: The file is not valid UTF-8: it has an invalid \
+        byte sequence at byte offset 17",
+        ConfigSource::from_bytes("pants.toml", b"[GLOBAL]\nname = '\xff'\n".to_vec()).unwrap_err()
+    );
+}
+
 #[test]
 fn test_interpolate_string() {
     fn interp(
@@ -101,6 +131,72 @@ fn test_interpolate_string() {
     );
 }
 
+#[test]
+fn test_interpolate_config_with_filters() {
+    let conf = config(
+        "[DEFAULT]\n\
+     name = 'pants'\n\
+     platforms = ['linux', 'macos']\n\
+     [foo]\n\
+     field = '%(name|upper)s on %(platforms|join:, )s'\n",
+    );
+    assert_eq!(
+        "PANTS on linux, macos",
+        conf.get_string(&option_id!(["foo"], "field"))
+            .unwrap()
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_interpolate_typed_values() {
+    let content = "[DEFAULT]\n\
+     concurrency = '8'\n\
+     verbose = 'true'\n\
+     names = ['a', 'b']\n\
+     [foo]\n\
+     jobs = '%(concurrency)s'\n\
+     debug = '%(verbose)s'\n\
+     tags = '%(names)s'\n\
+     other_tags = \"['%(verbose)s']\"\n\
+     bad = '%(verbose)s'\n";
+    let conf = ConfigReader::new(
+        Arc::new(parse_config(content).unwrap()),
+        FromfileExpander::relative_to_cwd(),
+    )
+    .strict(true);
+    assert_eq!(Some(8), conf.get_int(&option_id!(["foo"], "jobs")).unwrap());
+    assert_eq!(
+        Some(true),
+        conf.get_bool(&option_id!(["foo"], "debug")).unwrap()
+    );
+    // A value that is a single placeholder may be a list even in strict mode, unlike a string
+    // that merely contains a placeholder.
+    assert_eq!(
+        Some(vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec!["a".to_owned(), "b".to_owned()],
+        }]),
+        conf.get_string_list(&option_id!(["foo"], "tags")).unwrap()
+    );
+    assert!(conf
+        .get_string_list(&option_id!(["foo"], "other_tags"))
+        .unwrap_err()
+        .contains("which strict_toml disallows"));
+
+    let err = conf.get_int(&option_id!(["foo"], "bad")).unwrap_err();
+    assert!(err.starts_with("Problem parsing [foo] bad int value:\n1:true\n"));
+    assert!(
+        Regex::new(
+            "\nThe value was interpolated from `%\\(verbose\\)s` in config file .*pants.toml, \
+            section foo, key bad.$"
+        )
+        .unwrap()
+        .is_match(&err),
+        "{err}"
+    );
+}
+
 #[test]
 fn test_interpolate_config() {
     let conf = config(
@@ -148,9 +244,9 @@ fn test_interpolate_config() {
     assert_eq!(
         vec![DictEdit {
             action: DictEditAction::Replace,
-            items: HashMap::from([
-                ("fruit".to_string(), Val::String("strawberry".to_string())),
-                ("spice".to_string(), Val::String("black pepper".to_string()))
+            items: IndexMap::from([
+                ("fruit".to_string(), Val::String("strawberry".into())),
+                ("spice".to_string(), Val::String("black pepper".into()))
             ])
         }],
         conf.get_dict(&option_id!(["groceries"], "inline_table"))
@@ -267,7 +363,7 @@ fn test_default_section_dict() {
 
     let mut expected = vec![DictEdit {
         action: DictEditAction::Replace,
-        items: hashmap! { "x".to_string() => Val::Int(2) },
+        items: indexmap! { "x".to_string() => Val::Int(2) },
     }];
 
     assert_eq!(
@@ -285,11 +381,11 @@ fn test_default_section_dict() {
     expected = vec![
         DictEdit {
             action: DictEditAction::Replace,
-            items: hashmap! { "x".to_string() => Val::Int(2) },
+            items: indexmap! { "x".to_string() => Val::Int(2) },
         },
         DictEdit {
             action: DictEditAction::Add,
-            items: hashmap! { "a".to_string() => Val::Int(3) },
+            items: indexmap! { "a".to_string() => Val::Int(3) },
         },
     ];
 
@@ -353,15 +449,119 @@ fn test_list_fromfile() {
     );
 }
 
+#[test]
+fn test_bool_float_and_enum_lists() {
+    let (_tmpdir, fromfile_path) = write_fromfile("fromfile.yaml", "- 1.5\n- 2\n");
+    let conf = config(
+        format!(
+            "[GLOBAL]\n\
+             bools = [true, false]\n\
+             floats = '@{}'\n\
+             colors.add = ['red']\n\
+             colors.remove = ['blue']\n\
+             bad = ['pink']\n",
+            fromfile_path.display()
+        )
+        .as_str(),
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![true, false]
+        }],
+        conf.get_bool_list(&option_id!("bools")).unwrap().unwrap()
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![1.5, 2.0]
+        }],
+        conf.get_float_list(&option_id!("floats")).unwrap().unwrap()
+    );
+
+    let choices = ["red", "green", "blue"];
+    assert_eq!(
+        vec![
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec!["red".to_string()]
+            },
+            ListEdit {
+                action: ListEditAction::Remove,
+                items: vec!["blue".to_string()]
+            },
+        ],
+        conf.get_enum_list(&option_id!("colors"), &choices)
+            .unwrap()
+            .unwrap()
+    );
+    assert_eq!(
+        "Invalid value `pink` for [GLOBAL] bad: must be one of red, green, blue",
+        conf.get_enum_list(&option_id!("bad"), &choices)
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_dict_list() {
+    let conf = config(
+        "[test]\n\
+         [[test.matrix]]\n\
+         py = '3.11'\n\
+         [[test.matrix]]\n\
+         py = '3.12'\n\
+         arch = 'arm'\n\
+         [other]\n\
+         matrix.add = [{py = '3.13'}]\n\
+         matrix.remove = [{py = '3.11'}]\n\
+         bad = [1]\n",
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![
+                indexmap! {"py".to_string() => Val::String("3.11".into())},
+                indexmap! {
+                    "py".to_string() => Val::String("3.12".into()),
+                    "arch".to_string() => Val::String("arm".into()),
+                },
+            ]
+        }],
+        conf.get_dict_list(&option_id!(["test"], "matrix"))
+            .unwrap()
+            .unwrap()
+    );
+    assert_eq!(
+        vec![
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec![indexmap! {"py".to_string() => Val::String("3.13".into())}]
+            },
+            ListEdit {
+                action: ListEditAction::Remove,
+                items: vec![indexmap! {"py".to_string() => Val::String("3.11".into())}]
+            },
+        ],
+        conf.get_dict_list(&option_id!(["other"], "matrix"))
+            .unwrap()
+            .unwrap()
+    );
+    assert_eq!(
+        "Expected bad to be an array of tables but given [1] containing non-table item 1",
+        conf.get_dict_list(&option_id!(["other"], "bad"))
+            .unwrap_err()
+    );
+}
+
 #[test]
 fn test_dict_fromfile() {
     fn do_test(content: &str, filename: &str) {
         let expected = vec![DictEdit {
             action: DictEditAction::Replace,
-            items: hashmap! {
-            "FOO".to_string() => Val::Dict(hashmap! {
+            items: indexmap! {
+            "FOO".to_string() => Val::Dict(indexmap! {
                 "BAR".to_string() => Val::Float(3.14),
-                "BAZ".to_string() => Val::Dict(hashmap! {
+                "BAZ".to_string() => Val::Dict(indexmap! {
                     "QUX".to_string() => Val::Bool(true),
                     "QUUX".to_string() => Val::List(vec![ Val::Int(1), Val::Int(2)])
                 })
@@ -410,3 +610,417 @@ fn test_nonexistent_optional_fromfile() {
     let conf = config("[GLOBAL]\nfoo = '@?/does/not/exist'\n");
     assert!(conf.get_string(&option_id!("foo")).unwrap().is_none());
 }
+
+#[test]
+fn test_validate() {
+    let conf = parse_config(
+        "[DEFAULT]\n\
+     anything = 'goes'\n\
+     [GLOBAL]\n\
+     level = 'debug'\n\
+     bad_key = 1\n\
+     [mypy-extra]\n\
+     foo = 1\n\
+     [acme-lint]\n\
+     bar = 2\n",
+    )
+    .unwrap();
+    let section_to_valid_keys =
+        HashMap::from([("GLOBAL".to_string(), HashSet::from(["level".to_string()]))]);
+
+    let check = |allowances: ScopeAllowances| {
+        let mut validation = ConfigValidation::default();
+        conf.validate(&section_to_valid_keys, &allowances, &mut validation);
+        let strip = |msgs: Vec<String>| {
+            msgs.into_iter()
+                .map(|msg| msg.split(" in ").next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        (strip(validation.errors), strip(validation.warnings))
+    };
+
+    assert_eq!(
+        (
+            vec![
+                "Invalid option 'bad_key' under [GLOBAL]".to_string(),
+                // Tables are validated in the order in which they were written.
+                "Invalid section [mypy-extra]".to_string(),
+                "Invalid section [acme-lint]".to_string(),
+            ],
+            vec![]
+        ),
+        check(ScopeAllowances::new())
+    );
+
+    assert_eq!(
+        (
+            vec![
+                "Invalid option 'bad_key' under [GLOBAL]".to_string(),
+                "Invalid section [acme-lint]".to_string(),
+            ],
+            vec![]
+        ),
+        check(ScopeAllowances::new().allow_pattern("mypy-*"))
+    );
+
+    assert_eq!(
+        (
+            vec!["Invalid option 'bad_key' under [GLOBAL]".to_string()],
+            vec!["Invalid section [acme-lint]".to_string()]
+        ),
+        check(
+            ScopeAllowances::new()
+                .allow_pattern("*-extra")
+                .unknown_scopes(UnknownScopePolicy::Warn)
+        )
+    );
+}
+
+#[test]
+fn test_nested_scopes() {
+    let conf = config(
+        "[DEFAULT]\n\
+         level = 'info'\n\
+         names = ['default']\n\
+         [test]\n\
+         level = 'debug'\n\
+         names.add = ['test']\n\
+         timeout = 5\n\
+         [test.pytest]\n\
+         names.add = ['pytest']\n\
+         timeout = 10\n\
+         [\"test.mypy\"]\n\
+         level = 'warn'\n",
+    );
+    let pytest =
+        |name: &str| OptionId::new(Scope::named("test.pytest"), [name].iter(), None).unwrap();
+
+    // Nested scopes inherit from the scopes they are nested in before falling back to DEFAULT.
+    assert_eq!(
+        Some("debug".to_string()),
+        conf.get_string(&pytest("level")).unwrap()
+    );
+    assert_eq!(Some(10), conf.get_int(&pytest("timeout")).unwrap());
+    assert_eq!(
+        Some("warn".to_string()),
+        conf.get_string(&option_id!(["test.mypy"], "level"))
+            .unwrap()
+    );
+    assert_eq!(
+        Some(5),
+        conf.get_int(&option_id!(["test.mypy"], "timeout")).unwrap()
+    );
+    assert_eq!(
+        vec![
+            ListEdit {
+                action: ListEditAction::Replace,
+                items: vec!["default".to_string()]
+            },
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec!["test".to_string()]
+            },
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec!["pytest".to_string()]
+            },
+        ],
+        conf.get_string_list(&pytest("names")).unwrap().unwrap()
+    );
+
+    // The lookup chain is surfaced in the raw values.
+    assert_eq!(
+        vec!["[DEFAULT]", "[test]", "[test.pytest]"],
+        conf.get_raw(&pytest("names"))
+            .iter()
+            .map(|raw| raw.location.rsplit(' ').next().unwrap())
+            .collect::<Vec<_>>()
+    );
+
+    let section_to_valid_keys = HashMap::from([
+        (
+            "test".to_string(),
+            HashSet::from([
+                "level".to_string(),
+                "names".to_string(),
+                "timeout".to_string(),
+            ]),
+        ),
+        (
+            "test.pytest".to_string(),
+            HashSet::from(["names".to_string(), "timeout".to_string()]),
+        ),
+        (
+            "test.mypy".to_string(),
+            HashSet::from(["level".to_string()]),
+        ),
+    ]);
+    let mut validation = ConfigValidation::default();
+    parse_config("[test]\nlevel = 'debug'\n[test.pytest]\ntimeout = 10\nbad = 1\n")
+        .unwrap()
+        .validate(
+            &section_to_valid_keys,
+            &ScopeAllowances::new(),
+            &mut validation,
+        );
+    assert_eq!(
+        vec!["Invalid option 'bad' under [test.pytest]".to_string()],
+        validation
+            .errors
+            .iter()
+            .map(|msg| msg.split(" in ").next().unwrap().to_string())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_nested_scope_is_not_an_option() {
+    let content = "[test]\n\
+         pytest = { a = 1 }\n\
+         [test.mypy]\n\
+         timeout = 10\n";
+    let mypy = option_id!(["test"], "mypy");
+
+    // Without knowing the nested scopes, the sub-table reads as the value of the option.
+    assert!(config(content).get_dict(&mypy).unwrap().is_some());
+    let conf = config(content).nested_scopes(Arc::new(HashSet::from(["test.mypy".to_string()])));
+    assert_eq!(None, conf.get_dict(&mypy).unwrap());
+    assert!(conf.get_raw(&mypy).is_empty());
+    assert_eq!(
+        Some(10),
+        conf.get_int(&option_id!(["test.mypy"], "timeout")).unwrap()
+    );
+    // An inline table is still the value of an option whose name is not a nested scope.
+    assert!(conf
+        .get_dict(&option_id!(["test"], "pytest"))
+        .unwrap()
+        .is_some());
+
+    // The sub-table of an unknown nested scope is reported as such, subject to the allowances.
+    let section_to_valid_keys =
+        HashMap::from([("test".to_string(), HashSet::from(["pytest".to_string()]))]);
+    let validate = |allowances: &ScopeAllowances| {
+        let mut validation = ConfigValidation::default();
+        parse_config(content).unwrap().validate(
+            &section_to_valid_keys,
+            allowances,
+            &mut validation,
+        );
+        (validation.errors, validation.warnings)
+    };
+    let (errors, warnings) = validate(&ScopeAllowances::new());
+    assert!(warnings.is_empty());
+    assert_eq!(1, errors.len());
+    assert!(errors[0].starts_with("Invalid section [test.mypy] in "));
+    let (errors, _) = validate(&ScopeAllowances::new().allow_pattern("test.*"));
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn test_validate_final() {
+    let section_to_valid_keys = HashMap::from([(
+        "test".to_string(),
+        HashSet::from(["level".to_string(), "names".to_string()]),
+    )]);
+    let validate = |content: &str| {
+        let mut validation = ConfigValidation::default();
+        parse_config(content).unwrap().validate(
+            &section_to_valid_keys,
+            &ScopeAllowances::new(),
+            &mut validation,
+        );
+        validation
+            .errors
+            .iter()
+            .map(|msg| msg.split(" in ").next().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert!(validate("[test]\nfinal = ['level', 'names']\nlevel = 'debug'\n").is_empty());
+    assert_eq!(
+        vec!["Invalid option 'bad' listed as final under [test]".to_string()],
+        validate("[test]\nfinal = ['level', 'bad']\n")
+    );
+    assert_eq!(
+        vec!["Expected 'final' under [test]".to_string()],
+        validate("[test]\nfinal = 'level'\n")
+    );
+}
+
+#[test]
+fn test_inherit_default_opt_out() {
+    let conf = config(
+        "[DEFAULT]\n\
+         level = 'info'\n\
+         names = ['default']\n\
+         [test]\n\
+         inherit_default = false\n\
+         [test.pytest]\n\
+         names.add = ['pytest']\n\
+         [test.mypy]\n\
+         inherit_default = true\n\
+         [lint]\n",
+    );
+    let nested = |scope: &str| OptionId::new(Scope::named(scope), ["level"].iter(), None).unwrap();
+
+    assert_eq!(
+        Some("info".to_string()),
+        conf.get_string(&option_id!(["lint"], "level")).unwrap()
+    );
+    assert_eq!(
+        None,
+        conf.get_string(&option_id!(["test"], "level")).unwrap()
+    );
+    // Nested scopes follow the nearest enclosing section that sets the marker.
+    assert_eq!(None, conf.get_string(&nested("test.pytest")).unwrap());
+    assert_eq!(
+        Some("info".to_string()),
+        conf.get_string(&nested("test.mypy")).unwrap()
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec!["pytest".to_string()]
+        }],
+        conf.get_string_list(
+            &OptionId::new(Scope::named("test.pytest"), ["names"].iter(), None).unwrap()
+        )
+        .unwrap()
+        .unwrap()
+    );
+
+    // The marker is not an option, but must be a boolean.
+    let section_to_valid_keys = HashMap::from([
+        ("test".to_string(), HashSet::from(["level".to_string()])),
+        ("lint".to_string(), HashSet::from(["level".to_string()])),
+    ]);
+    let mut validation = ConfigValidation::default();
+    parse_config("[test]\ninherit_default = false\n[lint]\ninherit_default = 'no'\n")
+        .unwrap()
+        .validate(
+            &section_to_valid_keys,
+            &ScopeAllowances::new(),
+            &mut validation,
+        );
+    assert_eq!(
+        vec!["Expected 'inherit_default' under [lint]".to_string()],
+        validation
+            .errors
+            .iter()
+            .map(|msg| msg.split(" in ").next().unwrap().to_string())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_strict() {
+    let conf = |content: &str| config(content).strict(true);
+
+    // Native TOML values, and references to fromfiles, are accepted.
+    let native = conf(
+        "[GLOBAL]\n\
+         names = ['a', 'b']\n\
+         extra.add = [1]\n\
+         extra.remove = [2]\n\
+         env = {A = '1'}\n\
+         from = '@?missing.json'\n",
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec!["a".to_string(), "b".to_string()]
+        }],
+        native
+            .get_string_list(&option_id!("names"))
+            .unwrap()
+            .unwrap()
+    );
+    assert_eq!(
+        2,
+        native
+            .get_int_list(&option_id!("extra"))
+            .unwrap()
+            .unwrap()
+            .len()
+    );
+    assert_eq!(
+        1,
+        native.get_dict(&option_id!("env")).unwrap().unwrap().len()
+    );
+    assert_eq!(None, native.get_string_list(&option_id!("from")).unwrap());
+
+    // Stringly-typed values are rejected, with the equivalent native TOML as a fix.
+    let stringly = conf(
+        "[GLOBAL]\n\
+         add = '+[33]'\n\
+         edits = '+[1],-[2]'\n\
+         many = '+[1],+[2]'\n\
+         env = '+{ \"x\": 2 }'\n",
+    );
+    let err = stringly.get_int_list(&option_id!("add")).unwrap_err();
+    assert!(err.contains("strict_toml"));
+    assert!(err.ends_with("Instead use:\nadd.add = [33]"), "{err}");
+    assert!(stringly
+        .get_int_list(&option_id!("edits"))
+        .unwrap_err()
+        .ends_with("Instead use:\nedits.add = [1]\nedits.remove = [2]"));
+    assert!(stringly
+        .get_int_list(&option_id!("many"))
+        .unwrap_err()
+        .ends_with("Instead use:\nmany.add = [...] and/or many.remove = [...]"));
+    assert!(stringly
+        .get_dict(&option_id!("env"))
+        .unwrap_err()
+        .ends_with("Instead use:\nenv.add = {x = 2}"));
+
+    // Only list and dict values are affected.
+    assert_eq!(
+        Some("+[33]".to_string()),
+        stringly.get_string(&option_id!("add")).unwrap()
+    );
+}
+
+#[test]
+fn test_shared_across_threads() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let conf = config("[GLOBAL]\nfoo = 'bar'\n");
+    assert_send_sync(&conf);
+    let readers = (0..4).map(|_| conf.clone()).collect::<Vec<_>>();
+    std::thread::scope(|scope| {
+        for reader in &readers {
+            scope.spawn(move || {
+                assert_eq!(
+                    Some("bar".to_string()),
+                    reader.get_string(&option_id!("foo")).unwrap()
+                );
+            });
+        }
+    });
+}
+
+#[test]
+fn test_val_to_toml() {
+    let val = Val::Dict(indexmap! {
+        "str".to_string() => Val::String("a \"b\"\n\\c".into()),
+        "a.b c".to_string() => Val::Int(-1),
+        "float".to_string() => Val::Float(2.0),
+        "date".to_string() => Val::Date("2024-12-31".parse().unwrap()),
+        "list".to_string() => Val::List(vec![
+            Val::Bool(true),
+            Val::List(vec![]),
+            Val::Dict(indexmap! {"x".to_string() => Val::Float(0.5)}),
+        ]),
+    });
+    let toml = val_to_toml(&val).unwrap();
+    assert_eq!(
+        r#"{str = "a \"b\"\n\\c", "a.b c" = -1, float = 2.0, date = 2024-12-31, list = [true, [], {x = 0.5}]}"#,
+        toml
+    );
+    let table = format!("key = {toml}").parse::<toml::Value>().unwrap();
+    assert_eq!(val, toml_value_to_val(&table["key"]));
+
+    assert_eq!(
+        "The value [1, None] cannot be represented in TOML.",
+        val_to_toml(&Val::List(vec![Val::Int(1), Val::Null])).unwrap_err()
+    );
+}
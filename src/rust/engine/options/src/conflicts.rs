@@ -0,0 +1,182 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use toml::Value;
+
+use crate::config::{toml_value_to_val, Config, DEFAULT_SECTION, INHERIT_DEFAULT_KEY};
+use crate::decrypt::is_envelope;
+use crate::id::NameTransform;
+use crate::parse::{parse_dict, Parseable};
+use crate::registry::OptionRegistry;
+use crate::types::FromVal;
+use crate::{OptionId, OptionType, Val};
+
+/// Where a config value is defined, and what kind of TOML value it is.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigDefinition {
+    pub section: String,
+    pub path: PathBuf,
+    // The 1-based line of the definition, if it could be located.
+    pub line: Option<usize>,
+    pub kind: &'static str,
+    // Whether the value can be read as the registered type of the option.
+    pub valid: bool,
+}
+
+impl fmt::Display for ConfigDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} in {}", self.kind, self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// An option which is set in both the DEFAULT section and the section of its scope to different
+/// kinds of value, at least one of which is not valid for its registered type. Since the section
+/// of the scope takes precedence, such a conflict would otherwise only be discovered when the
+/// option is read, if at all.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TypeConflict {
+    pub id: OptionId,
+    pub type_name: &'static str,
+    pub default: ConfigDefinition,
+    pub scoped: ConfigDefinition,
+}
+
+impl fmt::Display for TypeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let key = self.id.name("_", NameTransform::None);
+        let describe = |definition: &ConfigDefinition| {
+            let validity = if definition.valid {
+                String::new()
+            } else {
+                format!(", which is not a valid {}", self.type_name)
+            };
+            format!("[{}] {key} is {definition}{validity}", definition.section)
+        };
+        write!(
+            f,
+            "Conflicting types for {}, which is registered as {}: {}, but {}.",
+            self.id,
+            self.type_name,
+            describe(&self.default),
+            describe(&self.scoped)
+        )
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "a string",
+        Value::Integer(_) => "an int",
+        Value::Float(_) => "a float",
+        Value::Boolean(_) => "a bool",
+        Value::Datetime(_) => "a datetime",
+        Value::Array(_) => "a list",
+        Value::Table(_) => "a table",
+    }
+}
+
+fn is_valid_list<T: FromVal + Parseable>(value: &Value) -> bool {
+    match value {
+        Value::String(s) => T::parse_list(s).is_ok(),
+        // A table may hold the `add` and `remove` edits of the list.
+        Value::Table(table) => table.iter().all(|(key, value)| {
+            (key == "add" || key == "remove")
+                && Vec::<T>::from_val(toml_value_to_val(value)).is_ok()
+        }),
+        value => Vec::<T>::from_val(toml_value_to_val(value)).is_ok(),
+    }
+}
+
+fn is_valid_scalar<T: FromVal + Parseable>(value: &Value) -> bool {
+    match value {
+        Value::String(s) => T::parse(s).is_ok(),
+        value => T::from_val(toml_value_to_val(value)).is_ok(),
+    }
+}
+
+// Whether the value can be read as the given type. Values which are only resolved when they are
+// read, such as fromfiles and encrypted values, are assumed to be valid.
+fn is_valid(value: &Value, option_type: &OptionType) -> bool {
+    if let Value::String(s) = value {
+        if s.starts_with('@') || is_envelope(s) {
+            return true;
+        }
+    }
+    match option_type {
+        OptionType::Bool(_) => is_valid_scalar::<bool>(value),
+        OptionType::Int(_) => is_valid_scalar::<i64>(value),
+        OptionType::Float(_) => is_valid_scalar::<f64>(value),
+        OptionType::String(_) => is_valid_scalar::<String>(value),
+        OptionType::BoolList(_) => is_valid_list::<bool>(value),
+        OptionType::IntList(_) => is_valid_list::<i64>(value),
+        OptionType::FloatList(_) => is_valid_list::<f64>(value),
+        OptionType::StringList(_) => is_valid_list::<String>(value),
+        OptionType::DictList(_) => is_valid_list::<IndexMap<String, Val>>(value),
+        OptionType::Dict(_) => match value {
+            Value::String(s) => parse_dict(s).is_ok(),
+            value => value.is_table(),
+        },
+    }
+}
+
+pub(crate) fn check(configs: &[Arc<Config>], registry: &OptionRegistry) -> Vec<TypeConflict> {
+    let mut conflicts = vec![];
+    for option in registry.options() {
+        let section = option.id.scope.name();
+        if section == DEFAULT_SECTION {
+            continue;
+        }
+        let key = option.id.name("_", NameTransform::None);
+        let definitions = |section: &str, is_default: bool| {
+            configs
+                .iter()
+                .filter(|config| {
+                    !is_default
+                        || config
+                            .section(option.id.scope.name())
+                            .and_then(|table| table.get(INHERIT_DEFAULT_KEY))
+                            .and_then(Value::as_bool)
+                            .unwrap_or(true)
+                })
+                .filter_map(|config| {
+                    let value = config.section(section)?.get(&key)?;
+                    Some(ConfigDefinition {
+                        section: section.to_owned(),
+                        path: config.path().to_path_buf(),
+                        line: config.line_of(section, &key),
+                        kind: kind(value),
+                        valid: is_valid(value, &option.option_type),
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        let defaults = definitions(DEFAULT_SECTION, true);
+        if defaults.is_empty() {
+            continue;
+        }
+        for scoped in definitions(section, false) {
+            for default in &defaults {
+                if default.kind != scoped.kind && !(default.valid && scoped.valid) {
+                    conflicts.push(TypeConflict {
+                        id: option.id.clone(),
+                        type_name: option.option_type.type_name(),
+                        default: default.clone(),
+                        scoped: scoped.clone(),
+                    });
+                }
+            }
+        }
+    }
+    conflicts
+}
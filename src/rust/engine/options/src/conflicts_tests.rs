@@ -0,0 +1,108 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use tempfile::TempDir;
+
+use crate::{
+    option_id, Args, BuildRoot, ConfigDefinition, ConfigSource, Env, OptionInfo, OptionParser,
+    OptionRegistry, OptionType, TypeConflict,
+};
+
+#[test]
+fn test_check_config_types() {
+    let buildroot = TempDir::new().unwrap();
+    fs::write(buildroot.path().join("BUILDROOT"), "").unwrap();
+    let config = |path: &str, content: &str| ConfigSource {
+        path: PathBuf::from(path),
+        content: content.to_owned(),
+    };
+    let parser = OptionParser::new(
+        Args::new(vec![]),
+        Env::new(HashMap::new()),
+        Some(vec![
+            config(
+                "pants.toml",
+                "[DEFAULT]\nfoo = [1]\nbar = 'x'\nbaz = 1\nqux = [1]\n\n\
+                [scope]\nfoo = 'x'\nbar = 2\nbaz = '+[2]'\n\n\
+                [other]\ninherit_default = false\nfoo = 'x'\n",
+            ),
+            config("pants.ci.toml", "[scope]\nqux = \"+[2]\"\n"),
+        ]),
+        false,
+        false,
+        Some(BuildRoot::find_from(buildroot.path()).unwrap()),
+    )
+    .unwrap();
+
+    let mut registry = OptionRegistry::new();
+    for option in [
+        // A list in DEFAULT, but a string that isn't a list of ints in the scope.
+        OptionInfo::new(option_id!(["scope"], "foo"), OptionType::IntList(vec![])),
+        // A string in DEFAULT, but an int in the scope.
+        OptionInfo::new(
+            option_id!(["scope"], "bar"),
+            OptionType::String("".to_owned()),
+        ),
+        // An int in DEFAULT, for a list whose scoped string value is valid.
+        OptionInfo::new(option_id!(["scope"], "baz"), OptionType::IntList(vec![])),
+        // Different kinds, but both valid lists.
+        OptionInfo::new(option_id!(["scope"], "qux"), OptionType::IntList(vec![])),
+        // The scope doesn't inherit from DEFAULT.
+        OptionInfo::new(option_id!(["other"], "foo"), OptionType::IntList(vec![])),
+    ] {
+        registry.register(option).unwrap();
+    }
+
+    let conflicts = parser.check_config_types(&registry);
+    let definition = |section: &str, line, kind, valid| ConfigDefinition {
+        section: section.to_owned(),
+        path: PathBuf::from("pants.toml"),
+        line: Some(line),
+        kind,
+        valid,
+    };
+    assert_eq!(
+        vec![
+            TypeConflict {
+                id: option_id!(["scope"], "foo"),
+                type_name: "list[int]",
+                default: definition("DEFAULT", 2, "a list", true),
+                scoped: definition("scope", 8, "a string", false),
+            },
+            TypeConflict {
+                id: option_id!(["scope"], "bar"),
+                type_name: "str",
+                default: definition("DEFAULT", 3, "a string", true),
+                scoped: definition("scope", 9, "an int", false),
+            },
+            TypeConflict {
+                id: option_id!(["scope"], "baz"),
+                type_name: "list[int]",
+                default: definition("DEFAULT", 4, "an int", false),
+                scoped: definition("scope", 10, "a string", true),
+            },
+        ],
+        conflicts
+    );
+    assert_eq!(
+        vec![
+            "Conflicting types for [scope] foo, which is registered as list[int]: [DEFAULT] foo \
+            is a list in pants.toml:2, but [scope] foo is a string in pants.toml:8, which is not \
+            a valid list[int].",
+            "Conflicting types for [scope] bar, which is registered as str: [DEFAULT] bar is a \
+            string in pants.toml:3, but [scope] bar is an int in pants.toml:9, which is not a \
+            valid str.",
+            "Conflicting types for [scope] baz, which is registered as list[int]: [DEFAULT] baz \
+            is an int in pants.toml:4, which is not a valid list[int], but [scope] baz is a \
+            string in pants.toml:10.",
+        ],
+        conflicts
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,165 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use itertools::Itertools;
+
+use super::{OptionId, OptionParser, OptionType, Source, Val};
+use crate::explain::describe_source;
+
+#[derive(Clone, Debug)]
+struct Implication {
+    option: OptionId,
+    option_type: OptionType,
+    when: Val,
+    implied: OptionId,
+    implied_type: OptionType,
+    value: Val,
+}
+
+#[derive(Clone, Debug)]
+enum Constraint {
+    /// At most one of the options may be explicitly set.
+    MutuallyExclusive(Vec<OptionId>),
+    /// If `option` is explicitly set, then `required` must be too.
+    Requires {
+        option: OptionId,
+        required: OptionId,
+    },
+    /// If `option` resolves to `when`, then `implied` must resolve to `value`.
+    Implies(Box<Implication>),
+}
+
+/// A violated constraint, naming the options involved and the sources of their values.
+#[derive(Debug, PartialEq)]
+pub struct ConstraintViolation {
+    pub message: String,
+    pub options: Vec<(OptionId, Source)>,
+}
+
+///
+/// A registry of constraints between options, which are checked against a fully configured
+/// `OptionParser` via `OptionParser::check_constraints`.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    constraints: Vec<Constraint>,
+}
+
+impl Constraints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mutually_exclusive(mut self, options: Vec<OptionId>) -> Self {
+        self.constraints
+            .push(Constraint::MutuallyExclusive(options));
+        self
+    }
+
+    pub fn requires(mut self, option: OptionId, required: OptionId) -> Self {
+        self.constraints
+            .push(Constraint::Requires { option, required });
+        self
+    }
+
+    /// If `option` (of the given type and default) resolves to `when`, then `implied` must resolve
+    /// to `value`.
+    pub fn implies(
+        mut self,
+        option: OptionId,
+        option_type: OptionType,
+        when: Val,
+        implied: OptionId,
+        implied_type: OptionType,
+        value: Val,
+    ) -> Self {
+        self.constraints
+            .push(Constraint::Implies(Box::new(Implication {
+                option,
+                option_type,
+                when,
+                implied,
+                implied_type,
+                value,
+            })));
+        self
+    }
+
+    pub(crate) fn check(&self, parser: &OptionParser) -> Result<Vec<ConstraintViolation>, String> {
+        let mut violations = vec![];
+        for constraint in &self.constraints {
+            match constraint {
+                Constraint::MutuallyExclusive(options) => {
+                    let set = options
+                        .iter()
+                        .filter_map(|id| Some((id.clone(), parser.explicit_source(id)?)))
+                        .collect::<Vec<_>>();
+                    if set.len() > 1 {
+                        violations.push(ConstraintViolation {
+                            message: format!(
+                                "Only one of {} may be set, but {} were all set",
+                                options.iter().join(", "),
+                                set.iter()
+                                    .map(|(id, source)| {
+                                        format!("{id} (from {})", describe_source(source))
+                                    })
+                                    .join(", ")
+                            ),
+                            options: set,
+                        });
+                    }
+                }
+                Constraint::Requires { option, required } => {
+                    if let Some(source) = parser.explicit_source(option) {
+                        if parser.explicit_source(required).is_none() {
+                            violations.push(ConstraintViolation {
+                                message: format!(
+                                    "{option} (from {}) requires {required} to be set",
+                                    describe_source(&source)
+                                ),
+                                options: vec![
+                                    (option.clone(), source),
+                                    (required.clone(), Source::Default),
+                                ],
+                            });
+                        }
+                    }
+                }
+                Constraint::Implies(implication) => {
+                    let Implication {
+                        option,
+                        option_type,
+                        when,
+                        implied,
+                        implied_type,
+                        value,
+                    } = implication.as_ref();
+                    let (option_value, option_source) =
+                        parser.resolve(option, option_type.clone())?;
+                    if option_value != *when {
+                        continue;
+                    }
+                    let (implied_value, implied_source) =
+                        parser.resolve(implied, implied_type.clone())?;
+                    if implied_value != *value {
+                        violations.push(ConstraintViolation {
+                            message: format!(
+                                "{option} = {} (from {}) requires {implied} = {}, but it is {} (from {})",
+                                when,
+                                describe_source(&option_source),
+                                value,
+                                implied_value,
+                                describe_source(&implied_source),
+                            ),
+                            options: vec![
+                                (option.clone(), option_source),
+                                (implied.clone(), implied_source),
+                            ],
+                        });
+                    }
+                }
+            }
+        }
+        Ok(violations)
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::tests::parser;
+use crate::{option_id, ConstraintViolation, Constraints, OptionType, Source, Val};
+
+#[test]
+fn test_mutually_exclusive() {
+    let constraints = Constraints::new().mutually_exclusive(vec![
+        option_id!("loop"),
+        option_id!("once"),
+        option_id!("forever"),
+    ]);
+    assert!(parser(&["--loop"], &[], "")
+        .check_constraints(&constraints)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        vec![ConstraintViolation {
+            message: "Only one of [GLOBAL] loop, [GLOBAL] once, [GLOBAL] forever may be set, \
+                      but [GLOBAL] once (from env), [GLOBAL] forever (from flags) were all set"
+                .to_owned(),
+            options: vec![
+                (option_id!("once"), Source::Env),
+                (option_id!("forever"), Source::Flag)
+            ],
+        }],
+        parser(&["--no-forever"], &[("PANTS_ONCE", "true")], "")
+            .check_constraints(&constraints)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_requires() {
+    let constraints = Constraints::new().requires(option_id!("cert"), option_id!("key"));
+    assert!(parser(&[], &[], "")
+        .check_constraints(&constraints)
+        .unwrap()
+        .is_empty());
+    assert!(parser(&["--cert=a", "--key=b"], &[], "")
+        .check_constraints(&constraints)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        vec![ConstraintViolation {
+            message: "[GLOBAL] cert (from flags) requires [GLOBAL] key to be set".to_owned(),
+            options: vec![
+                (option_id!("cert"), Source::Flag),
+                (option_id!("key"), Source::Default)
+            ],
+        }],
+        parser(&["--cert=a"], &[], "")
+            .check_constraints(&constraints)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_implies() {
+    let constraints = Constraints::new().implies(
+        option_id!("remote"),
+        OptionType::Bool(false),
+        Val::Bool(true),
+        option_id!("cache"),
+        OptionType::Bool(true),
+        Val::Bool(true),
+    );
+    assert!(parser(&["--no-cache"], &[], "")
+        .check_constraints(&constraints)
+        .unwrap()
+        .is_empty());
+    assert!(parser(&["--remote"], &[], "")
+        .check_constraints(&constraints)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        vec![ConstraintViolation {
+            message: "[GLOBAL] remote = true (from flags) requires [GLOBAL] cache = true, \
+                      but it is false (from env)"
+                .to_owned(),
+            options: vec![
+                (option_id!("remote"), Source::Flag),
+                (option_id!("cache"), Source::Env)
+            ],
+        }],
+        parser(&["--remote"], &[("PANTS_CACHE", "false")], "")
+            .check_constraints(&constraints)
+            .unwrap()
+    );
+}
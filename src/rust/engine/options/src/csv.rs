@@ -0,0 +1,76 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+///
+/// A record of a CSV file, along with the 1-based line number that it starts on.
+///
+pub(crate) type Record = (usize, Vec<String>);
+
+///
+/// Parses CSV content as described by RFC 4180: fields are separated by commas, and may be quoted
+/// with `"` in order to contain commas, newlines or (doubled) quotes. Records are separated by
+/// `\n` or `\r\n`, and blank lines are ignored.
+///
+pub(crate) fn parse_csv(content: &str) -> Result<Vec<Record>, String> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = content.chars().peekable();
+    // Whether the current field was quoted, and so is complete.
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() && !quoted => {
+                let quote_line = line;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => {
+                            return Err(format!(
+                                "Unterminated quoted field starting on line {quote_line}"
+                            ))
+                        }
+                    }
+                }
+                quoted = true;
+            }
+            ',' => {
+                record.push(std::mem::take(&mut field));
+                quoted = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' => {
+                if !record.is_empty() || !field.is_empty() || quoted {
+                    record.push(std::mem::take(&mut field));
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                quoted = false;
+                line += 1;
+                record_line = line;
+            }
+            c if quoted => {
+                return Err(format!(
+                    "Unexpected character `{c}` after a quoted field on line {line}"
+                ))
+            }
+            c => field.push(c),
+        }
+    }
+    if !record.is_empty() || !field.is_empty() || quoted {
+        record.push(field);
+        records.push((record_line, record));
+    }
+    Ok(records)
+}
@@ -0,0 +1,45 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::csv::parse_csv;
+
+fn records(items: &[(usize, &[&str])]) -> Vec<(usize, Vec<String>)> {
+    items
+        .iter()
+        .map(|(line, fields)| (*line, fields.iter().map(|f| f.to_string()).collect()))
+        .collect()
+}
+
+#[test]
+fn test_parse_csv() {
+    assert_eq!(Ok(vec![]), parse_csv(""));
+    assert_eq!(
+        Ok(records(&[
+            (1, &["path", "owner"]),
+            (2, &["src/a", "alice"]),
+            (4, &["src/b", ""]),
+        ])),
+        parse_csv("path,owner\r\nsrc/a,alice\n\nsrc/b,")
+    );
+    assert_eq!(
+        Ok(records(&[(1, &["a, b", "say \"hi\"", ""]), (3, &["c"]),])),
+        parse_csv("\"a, b\",\"say \"\"hi\"\"\",\"\"\n\n\"c\"\n")
+    );
+    // Quoted fields may span lines.
+    assert_eq!(
+        Ok(records(&[(1, &["multi\nline", "x"]), (3, &["y", "z"])])),
+        parse_csv("\"multi\nline\",x\ny,z\n")
+    );
+}
+
+#[test]
+fn test_parse_csv_errors() {
+    assert_eq!(
+        Err("Unterminated quoted field starting on line 2".to_owned()),
+        parse_csv("a\n\"b\nc")
+    );
+    assert_eq!(
+        Err("Unexpected character `x` after a quoted field on line 1".to_owned()),
+        parse_csv("\"a\"x,b")
+    );
+}
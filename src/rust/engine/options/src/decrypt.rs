@@ -0,0 +1,173 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::shell::run_with_input;
+
+// The prefix of the envelope of a value encrypted to an age recipient: the base64 of the binary
+// age file follows, as in `ENC[age:YWdlLWVuY3J5cHRpb24ub3Jn...]`.
+const AGE_PREFIX: &str = "age:";
+
+///
+/// Whether the config value is an encrypted envelope, e.g. `ENC[age:...]`, or an
+/// `ENC[AES256_GCM,data:...]` value as written by SOPS.
+///
+pub(crate) fn is_envelope(value: &str) -> bool {
+    envelope_payload(value).is_some()
+}
+
+fn envelope_payload(value: &str) -> Option<&str> {
+    value.trim().strip_prefix("ENC[")?.strip_suffix(']')
+}
+
+///
+/// Decrypts the encrypted envelopes of config values. Values are decrypted when the options
+/// that they are set for are read, so that only the secrets that are used need to be decryptable.
+///
+pub trait Decryptor: Send + Sync {
+    /// Decrypts the given envelope, e.g. `ENC[age:...]`, returning the plaintext.
+    fn decrypt(&self, envelope: &str) -> Result<String, String>;
+}
+
+// How long a decryption may take, by default, before its command is killed.
+pub(crate) const DEFAULT_DECRYPTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Runs the command with the input on stdin, killing it if it does not complete within the timeout,
+// and returns its stdout, without trailing newlines.
+fn run(command: Command, input: Vec<u8>, timeout: Duration) -> Result<String, String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output =
+        run_with_input(command, Some(input), timeout).map_err(|e| format!("{program} {e}"))?;
+    let output =
+        String::from_utf8(output).map_err(|_| "The decrypted value is not UTF-8.".to_owned())?;
+    Ok(output.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+///
+/// Memoizes the decryptions of another `Decryptor` by envelope, so that each distinct value is
+/// decrypted at most once per parse, however many times the options that it is set for are read.
+///
+pub(crate) struct MemoizedDecryptor {
+    decryptor: Arc<dyn Decryptor>,
+    results: Mutex<HashMap<String, Result<String, String>>>,
+}
+
+impl MemoizedDecryptor {
+    pub(crate) fn new(decryptor: Arc<dyn Decryptor>) -> Self {
+        Self {
+            decryptor,
+            results: Mutex::default(),
+        }
+    }
+}
+
+impl Decryptor for MemoizedDecryptor {
+    fn decrypt(&self, envelope: &str) -> Result<String, String> {
+        if let Some(result) = self.results.lock().unwrap().get(envelope) {
+            return result.clone();
+        }
+        let result = self.decryptor.decrypt(envelope);
+        self.results
+            .lock()
+            .unwrap()
+            .insert(envelope.to_owned(), result.clone());
+        result
+    }
+}
+
+///
+/// Decrypts `ENC[age:...]` envelopes with an age identity file, via the `age` CLI.
+///
+pub struct AgeDecryptor {
+    identity: PathBuf,
+    timeout: Duration,
+}
+
+impl AgeDecryptor {
+    pub fn new(identity: PathBuf) -> Self {
+        Self {
+            identity,
+            timeout: DEFAULT_DECRYPTION_TIMEOUT,
+        }
+    }
+
+    /// Sets how long `age` may take to decrypt a value before it is killed.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // Wraps the base64 payload of an envelope in the armor that `age --decrypt` accepts.
+    fn armor(payload: &str) -> String {
+        let base64 = payload
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<Vec<_>>();
+        let mut armored = "-----BEGIN AGE ENCRYPTED FILE-----\n".to_owned();
+        for line in base64.chunks(64) {
+            armored.extend(line);
+            armored.push('\n');
+        }
+        armored.push_str("-----END AGE ENCRYPTED FILE-----\n");
+        armored
+    }
+}
+
+impl Decryptor for AgeDecryptor {
+    fn decrypt(&self, envelope: &str) -> Result<String, String> {
+        let payload = envelope_payload(envelope)
+            .and_then(|payload| payload.strip_prefix(AGE_PREFIX))
+            .ok_or_else(|| {
+                "Only `ENC[age:...]` values can be decrypted with `age_identity`: configure a \
+                `decryption_command` to decrypt other values."
+                    .to_owned()
+            })?;
+        let mut command = Command::new("age");
+        command
+            .arg("--decrypt")
+            .arg("--identity")
+            .arg(&self.identity);
+        run(command, Self::armor(payload).into_bytes(), self.timeout)
+    }
+}
+
+///
+/// Decrypts envelopes by running a command with the envelope on stdin, and taking the plaintext
+/// from its stdout. This supports SOPS-compatible flows, such as a script which decrypts
+/// `ENC[AES256_GCM,...]` values with a data key held in a KMS.
+///
+pub struct CommandDecryptor {
+    argv: Vec<String>,
+    timeout: Duration,
+}
+
+impl CommandDecryptor {
+    pub fn new(argv: Vec<String>) -> Result<Self, String> {
+        if argv.is_empty() {
+            return Err("The decryption command must not be empty.".to_owned());
+        }
+        Ok(Self {
+            argv,
+            timeout: DEFAULT_DECRYPTION_TIMEOUT,
+        })
+    }
+
+    /// Sets how long the command may take to decrypt a value before it is killed.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Decryptor for CommandDecryptor {
+    fn decrypt(&self, envelope: &str) -> Result<String, String> {
+        let mut command = Command::new(&self.argv[0]);
+        command.args(&self.argv[1..]);
+        run(command, envelope.trim().as_bytes().to_vec(), self.timeout)
+    }
+}
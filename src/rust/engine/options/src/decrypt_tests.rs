@@ -0,0 +1,179 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tempfile::TempDir;
+
+use crate::decrypt::is_envelope;
+use crate::tests::parser;
+use crate::{
+    option_id, AgeDecryptor, CommandDecryptor, Decryptor, OptionType, Source, Val, Validator,
+};
+
+// A "decryption" command which applies rot13 to the payload of `ENC[rot13:...]` envelopes.
+const ROT13: &str = r#"decryption_command = ["sh", "-c", "sed -e 's/^ENC\\[rot13://' -e 's/]$//' | tr a-z n-za-m"]"#;
+
+#[test]
+fn test_is_envelope() {
+    assert!(is_envelope("ENC[age:YWdl]"));
+    assert!(is_envelope(
+        "ENC[AES256_GCM,data:abc,iv:def,tag:ghi,type:str]"
+    ));
+    assert!(is_envelope(" ENC[x] "));
+    assert!(!is_envelope("ENC[x"));
+    assert!(!is_envelope("enc[x]"));
+    assert!(!is_envelope("secret"));
+}
+
+#[test]
+fn test_command_decryptor() {
+    let decryptor = CommandDecryptor::new(vec![
+        "sh".to_owned(),
+        "-c".to_owned(),
+        "tr a-z A-Z".to_owned(),
+    ])
+    .unwrap();
+    assert_eq!("ENC[SECRET]", decryptor.decrypt("ENC[secret]\n").unwrap());
+    // A command may write its output before it has read all of its input.
+    let large = format!("ENC[{}]", "x".repeat(1 << 20));
+    assert_eq!(large.to_uppercase(), decryptor.decrypt(&large).unwrap());
+
+    let failing = CommandDecryptor::new(vec![
+        "sh".to_owned(),
+        "-c".to_owned(),
+        "echo nope >&2; exit 1".to_owned(),
+    ])
+    .unwrap();
+    assert_eq!(
+        "sh failed with exit status: 1: nope",
+        failing.decrypt("ENC[secret]").unwrap_err()
+    );
+
+    let hanging = CommandDecryptor::new(vec!["sleep".to_owned(), "10".to_owned()])
+        .unwrap()
+        .timeout(Duration::from_millis(100));
+    assert_eq!(
+        "sleep timed out after 100ms.",
+        hanging.decrypt("ENC[secret]").unwrap_err()
+    );
+
+    assert!(CommandDecryptor::new(vec![]).is_err());
+}
+
+#[test]
+fn test_age_decryptor_rejects_other_envelopes() {
+    let err = AgeDecryptor::new(PathBuf::from("key.txt"))
+        .decrypt("ENC[AES256_GCM,data:abc]")
+        .unwrap_err();
+    assert!(err.starts_with("Only `ENC[age:...]` values"), "{err}");
+}
+
+#[test]
+fn test_decrypt_config_values() {
+    let mut option_parser = parser(
+        &[],
+        &[],
+        &format!(
+            "[GLOBAL]\nstrict_toml = true\n{ROT13}\n\n\
+        [auth]\ntoken = 'ENC[rot13:frperg]'\nscopes = 'ENC[rot13:[\"n\", \"o\"]]'\nuser = 'me'\n"
+        ),
+    );
+    let token = option_parser
+        .parse_string(&option_id!(["auth"], "token"), "")
+        .unwrap();
+    assert_eq!("secret", token.value);
+    // Encrypted values are allowed in strict mode, even for lists.
+    assert_eq!(
+        vec!["a".to_owned(), "b".to_owned()],
+        option_parser
+            .parse_string_list(&option_id!(["auth"], "scopes"), vec![])
+            .unwrap()
+            .value
+    );
+
+    // Decrypted values are secret, so are redacted in explanations.
+    let explanation = option_parser
+        .explain(
+            &option_id!(["auth"], "token"),
+            OptionType::String("".to_owned()),
+        )
+        .unwrap();
+    assert_eq!(Val::String("<redacted>".into()), explanation.value);
+    assert!(matches!(explanation.source, Source::Config { .. }));
+    assert!(!explanation.to_string().contains("secret"));
+    let explanation = option_parser
+        .explain(
+            &option_id!(["auth"], "user"),
+            OptionType::String("".to_owned()),
+        )
+        .unwrap();
+    assert_eq!(Val::String("me".into()), explanation.value);
+
+    // Nor are they revealed by validation errors.
+    option_parser.register_validator(
+        option_id!(["auth"], "token"),
+        Validator::matches("^[A-Z]+$").unwrap(),
+    );
+    assert_eq!(
+        "Invalid value for [auth] token (from config file pants.toml): <redacted> does not match the pattern \
+        `^[A-Z]+$`",
+        option_parser
+            .parse_string(&option_id!(["auth"], "token"), "")
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_decryption_errors() {
+    let option_parser = parser(&[], &[], "[auth]\ntoken = 'ENC[rot13:frperg]'\n");
+    assert_eq!(
+        "The value of [auth] token in pants.toml is encrypted, but neither `age_identity` nor \
+        `decryption_command` is configured to decrypt it.",
+        option_parser
+            .parse_string(&option_id!(["auth"], "token"), "")
+            .unwrap_err()
+    );
+
+    let option_parser = parser(
+        &[],
+        &[],
+        "[GLOBAL]\ndecryption_command = ['sh', '-c', 'echo bad key >&2; exit 3']\n\
+        [auth]\ntoken = 'ENC[rot13:frperg]'\n",
+    );
+    assert_eq!(
+        "Failed to decrypt the value of [auth] token in pants.toml: sh failed with exit status: 3: \
+        bad key",
+        option_parser
+            .parse_string(&option_id!(["auth"], "token"), "")
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_decryptions_are_memoized() {
+    let tmpdir = TempDir::new().unwrap();
+    let runs = tmpdir.path().join("runs.txt");
+    let option_parser = parser(
+        &[],
+        &[],
+        &format!(
+            "[GLOBAL]\ndecryption_command = ['sh', '-c', 'echo run >> {}; tr a-z A-Z']\n\
+            [auth]\ntoken = 'ENC[secret]'\nother_token = 'ENC[secret]'\n",
+            runs.display()
+        ),
+    );
+    for id in [
+        option_id!(["auth"], "token"),
+        option_id!(["auth"], "token"),
+        option_id!(["auth"], "other", "token"),
+    ] {
+        assert_eq!(
+            "ENC[SECRET]",
+            option_parser.parse_string(&id, "").unwrap().value
+        );
+    }
+    assert_eq!("run\n", fs::read_to_string(&runs).unwrap());
+}
@@ -0,0 +1,75 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use super::{OptionId, OptionParser, OptionType, Val};
+
+///
+/// Computes the default value of an option, typically from the values of other options, which
+/// are read via the given `DefaultResolver`. Register via `OptionParser::register_computed_default`.
+///
+pub type ComputedDefault = Arc<dyn Fn(&DefaultResolver) -> Result<Val, String> + Send + Sync>;
+
+/// A handle via which a computed default reads the values of other options.
+pub struct DefaultResolver<'a> {
+    parser: &'a OptionParser,
+}
+
+impl DefaultResolver<'_> {
+    ///
+    /// Resolves the value of the option identified by `id`, of the given type and default. If that
+    /// option has a computed default of its own, it is computed as well.
+    ///
+    pub fn resolve(&self, id: &OptionId, option_type: OptionType) -> Result<Val, String> {
+        self.parser.resolve(id, option_type).map(|(value, _)| value)
+    }
+}
+
+thread_local! {
+    // The options whose computed defaults are currently being computed on this thread, in order.
+    // Computed defaults call back into the parser synchronously, so this detects reference cycles
+    // without needing to thread a stack through every getter.
+    static IN_PROGRESS: RefCell<Vec<OptionId>> = const { RefCell::new(vec![]) };
+}
+
+// Marks an option's computed default as in progress until dropped, so that it is no longer in
+// progress even if computing it fails or panics.
+struct InProgress;
+
+impl InProgress {
+    fn enter(id: &OptionId) -> Result<Self, String> {
+        IN_PROGRESS.with(|in_progress| {
+            let mut in_progress = in_progress.borrow_mut();
+            if let Some(pos) = in_progress.iter().position(|other| other == id) {
+                let cycle = in_progress[pos..]
+                    .iter()
+                    .chain(std::iter::once(id))
+                    .join(" -> ");
+                return Err(format!(
+                    "The computed default of {id} depends on itself: {cycle}"
+                ));
+            }
+            in_progress.push(id.clone());
+            Ok(InProgress)
+        })
+    }
+}
+
+impl Drop for InProgress {
+    fn drop(&mut self) {
+        IN_PROGRESS.with(|in_progress| in_progress.borrow_mut().pop());
+    }
+}
+
+pub(crate) fn compute(
+    parser: &OptionParser,
+    id: &OptionId,
+    computed_default: &ComputedDefault,
+) -> Result<Val, String> {
+    let _in_progress = InProgress::enter(id)?;
+    computed_default(&DefaultResolver { parser })
+}
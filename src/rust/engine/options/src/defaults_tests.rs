@@ -0,0 +1,122 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::panic::{self, AssertUnwindSafe};
+
+use indexmap::IndexMap;
+
+use crate::tests;
+use crate::{option_id, OptionParser, OptionType, Source, Val, Validator};
+
+fn parser(args: &[&str], env: &[(&str, &str)]) -> OptionParser {
+    let mut parser = tests::parser(args, env, "");
+    parser.register_computed_default(option_id!("jobs"), |resolver| {
+        match resolver.resolve(&option_id!("cores"), OptionType::Int(2))? {
+            Val::Int(cores) => Ok(Val::Int(cores * 2)),
+            _ => unreachable!(),
+        }
+    });
+    parser.register_computed_default(option_id!("tags"), |resolver| {
+        Ok(Val::List(vec![resolver.resolve(
+            &option_id!("name"),
+            OptionType::String("default".to_owned()),
+        )?]))
+    });
+    parser
+}
+
+#[test]
+fn test_computed_default() {
+    let jobs = option_id!("jobs");
+    let check = |args: &[&str], env: &[(&str, &str)], expected: i64, source: Source| {
+        let value = parser(args, env).parse_int(&jobs, 1).unwrap();
+        assert_eq!(expected, value.value);
+        assert_eq!(source, value.source);
+    };
+    check(&[], &[], 4, Source::Default);
+    check(&["--cores=3"], &[], 6, Source::Default);
+    check(&[], &[("PANTS_CORES", "5")], 10, Source::Default);
+    check(&["--cores=3", "--jobs=1"], &[], 1, Source::Flag);
+
+    // List edits apply on top of the computed default.
+    assert_eq!(
+        vec!["x".to_owned(), "y".to_owned()],
+        parser(&["--name=x", "--tags=+['y']"], &[])
+            .parse_string_list(&option_id!("tags"), vec![])
+            .unwrap()
+            .value
+    );
+}
+
+#[test]
+fn test_computed_default_errors() {
+    let mut parser = parser(&[], &[]);
+    parser.register_computed_default(option_id!("a"), |resolver| {
+        resolver.resolve(&option_id!("b"), OptionType::Int(0))
+    });
+    parser.register_computed_default(option_id!("b"), |resolver| {
+        resolver.resolve(&option_id!("a"), OptionType::Int(0))
+    });
+    assert_eq!(
+        "The computed default of [GLOBAL] a depends on itself: \
+         [GLOBAL] a -> [GLOBAL] b -> [GLOBAL] a",
+        parser.parse_int(&option_id!("a"), 0).unwrap_err()
+    );
+    // A failed computation doesn't poison later ones.
+    assert_eq!(4, parser.parse_int(&option_id!("jobs"), 0).unwrap().value);
+
+    assert_eq!(
+        "Invalid computed default for [GLOBAL] jobs: Expected a string but got 4",
+        parser.parse_string(&option_id!("jobs"), "").unwrap_err()
+    );
+}
+
+#[test]
+fn test_computed_default_is_lazy() {
+    let mut parser = parser(&["--a=5", "--b=['x']", "--c={'k': 'v'}"], &[]);
+    for id in [option_id!("a"), option_id!("b"), option_id!("c")] {
+        parser.register_computed_default(id, |_| Err("Evaluated!".to_owned()));
+    }
+    // Computed defaults are not evaluated when a source sets the option, or replaces its value.
+    assert_eq!(5, parser.parse_int(&option_id!("a"), 0).unwrap().value);
+    assert_eq!(
+        vec!["x".to_owned()],
+        parser
+            .parse_string_list(&option_id!("b"), vec![])
+            .unwrap()
+            .value
+    );
+    assert_eq!(
+        1,
+        parser
+            .parse_dict(&option_id!("c"), IndexMap::new())
+            .unwrap()
+            .value
+            .len()
+    );
+    // Nor is a value's type inferred from them, nor validated for a type that it does not have.
+    parser.register_validator(
+        option_id!("a"),
+        Validator::IntRange {
+            min: Some(10),
+            max: None,
+        },
+    );
+    assert_eq!(
+        "Invalid value for --a (from flags): 5 is less than the minimum of 10",
+        parser.get_value(&option_id!("a")).unwrap_err()
+    );
+    assert!(parser.get_value(&option_id!("b")).is_ok());
+}
+
+#[test]
+fn test_computed_default_panics() {
+    let mut parser = parser(&[], &[]);
+    parser.register_computed_default(option_id!("a"), |_| panic!("Oops"));
+    assert!(
+        panic::catch_unwind(AssertUnwindSafe(|| parser.parse_int(&option_id!("a"), 0))).is_err()
+    );
+    // A computation that panicked is no longer in progress, so is not mistaken for a cycle.
+    parser.register_computed_default(option_id!("a"), |_| Ok(Val::Int(1)));
+    assert_eq!(1, parser.parse_int(&option_id!("a"), 0).unwrap().value);
+}
@@ -0,0 +1,94 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::explain::describe_source;
+use crate::registry::OptionRegistry;
+use crate::{OptionId, OptionParser, Source};
+
+///
+/// How the use of deprecated options is treated: as warnings, or as errors so that CI can ensure
+/// that no deprecated options remain set before an upgrade. Options in the allowlist remain
+/// warnings either way.
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeprecationPolicy {
+    pub as_errors: bool,
+    // The flags of the deprecated options which may still be used, e.g. `--test-output`.
+    pub allowed: HashSet<String>,
+}
+
+impl DeprecationPolicy {
+    pub fn as_errors(mut self, as_errors: bool) -> Self {
+        self.as_errors = as_errors;
+        self
+    }
+
+    pub fn allow(mut self, flag: &str) -> Self {
+        self.allowed.insert(flag.to_owned());
+        self
+    }
+}
+
+/// A deprecated option which is set by a source other than its default.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeprecatedUse {
+    pub id: OptionId,
+    pub source: Source,
+    pub message: String,
+}
+
+impl fmt::Display for DeprecatedUse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is deprecated, but is set by {}: {}",
+            self.id,
+            describe_source(&self.source),
+            self.message
+        )
+    }
+}
+
+pub(crate) fn check(
+    parser: &OptionParser,
+    registry: &OptionRegistry,
+    policy: &DeprecationPolicy,
+) -> Result<Vec<DeprecatedUse>, String> {
+    let mut warnings = vec![];
+    let mut errors = vec![];
+    for option in registry.options() {
+        let Some(message) = &option.deprecated else {
+            continue;
+        };
+        let Some(source) = parser.explicit_source(&option.id) else {
+            continue;
+        };
+        let deprecated_use = DeprecatedUse {
+            id: option.id.clone(),
+            source,
+            message: message.clone(),
+        };
+        if policy.as_errors && !policy.allowed.contains(&option.flag(false)) {
+            errors.push(deprecated_use);
+        } else {
+            warnings.push(deprecated_use);
+        }
+    }
+    if errors.is_empty() {
+        Ok(warnings)
+    } else {
+        Err(format!(
+            "Deprecated options are set, which is an error as `[GLOBAL].deprecations_as_errors` \
+            is enabled:\n{}\nStop setting them, or add their flags to \
+            `[GLOBAL].allowed_deprecations` to allow them for now.",
+            errors
+                .iter()
+                .map(|error| format!("  {error}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))
+    }
+}
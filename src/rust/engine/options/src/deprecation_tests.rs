@@ -0,0 +1,113 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::tests::parser;
+use crate::{
+    option_id, DeprecatedUse, DeprecationPolicy, OptionInfo, OptionRegistry, OptionType, Source,
+};
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(
+            option_id!(["test"], "output"),
+            OptionType::String("".to_owned()),
+        )
+        .deprecated("Use `[test].report` instead."),
+        OptionInfo::new(option_id!("loop"), OptionType::Bool(false))
+            .deprecated("Use `--watch` instead."),
+        OptionInfo::new(option_id!("level"), OptionType::String("info".to_owned()))
+            .deprecated("Use `--log-level` instead."),
+        OptionInfo::new(option_id!(["test"], "report"), OptionType::Bool(false)),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+#[test]
+fn test_warnings() {
+    let parser = parser(
+        &["--loop", "--test-report"],
+        &[],
+        "[test]\noutput = 'all'\n",
+    );
+    assert_eq!(
+        vec![
+            DeprecatedUse {
+                id: option_id!(["test"], "output"),
+                source: Source::Config {
+                    ordinal: 0,
+                    path: "pants.toml".to_owned()
+                },
+                message: "Use `[test].report` instead.".to_owned(),
+            },
+            DeprecatedUse {
+                id: option_id!("loop"),
+                source: Source::Flag,
+                message: "Use `--watch` instead.".to_owned(),
+            },
+        ],
+        parser.check_deprecations(&registry()).unwrap()
+    );
+    assert_eq!(
+        "[GLOBAL] loop is deprecated, but is set by flags: Use `--watch` instead.",
+        parser.check_deprecations(&registry()).unwrap()[1].to_string()
+    );
+}
+
+#[test]
+fn test_as_errors() {
+    let config = "[GLOBAL]\ndeprecations_as_errors = true\n[test]\noutput = 'all'\n";
+    let err = parser(&["--loop"], &[], config)
+        .check_deprecations(&registry())
+        .unwrap_err();
+    assert_eq!(
+        "Deprecated options are set, which is an error as `[GLOBAL].deprecations_as_errors` is \
+        enabled:\n  \
+        [test] output is deprecated, but is set by config file pants.toml: Use `[test].report` \
+        instead.\n  \
+        [GLOBAL] loop is deprecated, but is set by flags: Use `--watch` instead.\n\
+        Stop setting them, or add their flags to `[GLOBAL].allowed_deprecations` to allow them \
+        for now.",
+        err
+    );
+
+    // Unset deprecated options are not an error.
+    assert_eq!(
+        Ok(vec![]),
+        parser(&[], &[], "[GLOBAL]\ndeprecations_as_errors = true\n")
+            .check_deprecations(&registry())
+    );
+}
+
+#[test]
+fn test_allowlist() {
+    let parser = parser(
+        &["--loop"],
+        &[
+            ("PANTS_DEPRECATIONS_AS_ERRORS", "true"),
+            ("PANTS_ALLOWED_DEPRECATIONS", "['--loop', '--test-output']"),
+        ],
+        "[test]\noutput = 'all'\n",
+    );
+    assert_eq!(
+        vec![option_id!(["test"], "output"), option_id!("loop")],
+        parser
+            .check_deprecations(&registry())
+            .unwrap()
+            .into_iter()
+            .map(|deprecated_use| deprecated_use.id)
+            .collect::<Vec<_>>()
+    );
+
+    let mut parser = parser;
+    parser.set_deprecation_policy(
+        DeprecationPolicy::default()
+            .as_errors(true)
+            .allow("--test-output"),
+    );
+    let err = parser.check_deprecations(&registry()).unwrap_err();
+    assert!(err.contains("[GLOBAL] loop is deprecated"), "{err}");
+    assert!(!err.contains("[test] output"), "{err}");
+}
@@ -0,0 +1,73 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt::Write;
+
+use crate::config::{toml_key, val_to_toml};
+use crate::explain::{describe_source, REDACTED};
+use crate::id::{NameTransform, Scope};
+use crate::registry::{OptionInfo, OptionRegistry};
+use crate::{OptionParser, Source};
+
+// Describes where the resolved value of an option came from, e.g. `from pants.ci.toml:42`, `from
+// PANTS_LEVEL`, `from --level` or `default`.
+fn origin(parser: &OptionParser, option: &OptionInfo, source: &Source) -> String {
+    if *source == Source::Default {
+        return "default".to_owned();
+    }
+    // NB: The raw values of a source are in increasing order of precedence.
+    let location = parser
+        .sources
+        .get(source)
+        .and_then(|s| s.get_raw(&option.id).pop())
+        .map(|raw| raw.location);
+    match location {
+        // The section of a config location is only of note if the value is inherited from another.
+        Some(location) => {
+            let own_section = format!(" [{}]", option.id.scope.name());
+            format!(
+                "from {}",
+                location.strip_suffix(&own_section).unwrap_or(&location)
+            )
+        }
+        None => format!("from {}", describe_source(source)),
+    }
+}
+
+pub(crate) fn dump(
+    parser: &OptionParser,
+    registry: &OptionRegistry,
+    annotate: bool,
+) -> Result<String, String> {
+    let mut dump = String::new();
+    let scopes = [&Scope::Global].into_iter().chain(registry.scopes());
+    for scope in scopes {
+        let groups = registry.groups_in(scope);
+        if groups.is_empty() {
+            continue;
+        }
+        if !dump.is_empty() {
+            dump.push('\n');
+        }
+        writeln!(dump, "[{}]", toml_key(scope.name())).unwrap();
+        for option in groups.into_iter().flat_map(|(_, options)| options) {
+            let id = &option.id;
+            let (value, source) = parser.resolve(id, option.option_type.clone())?;
+            if annotate {
+                writeln!(dump, "# {}", origin(parser, option, &source)).unwrap();
+            }
+            let key = toml_key(&id.name("_", NameTransform::None));
+            // NB: Secret values, and values that TOML cannot represent (e.g. `None`), are commented
+            // out, so that the dump may still be read as config.
+            if parser.sources.values().any(|source| source.is_secret(id)) {
+                writeln!(dump, "# {key} = {REDACTED}").unwrap();
+                continue;
+            }
+            match val_to_toml(&value) {
+                Ok(toml) => writeln!(dump, "{key} = {toml}").unwrap(),
+                Err(_) => writeln!(dump, "# {key} = {value}").unwrap(),
+            }
+        }
+    }
+    Ok(dump)
+}
@@ -0,0 +1,105 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::sync::Arc;
+
+use indexmap::indexmap;
+
+use crate::tests;
+use crate::{
+    option_id, CredentialStore, KeyringEntry, OptionInfo, OptionParser, OptionRegistry, OptionType,
+    Val,
+};
+
+struct Store;
+
+impl CredentialStore for Store {
+    fn get(&self, _entry: &KeyringEntry) -> Result<Option<String>, String> {
+        Ok(Some("hunter2".to_owned()))
+    }
+}
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!("level"), OptionType::String("info".to_owned())),
+        OptionInfo::new(
+            option_id!("pants", "workdir"),
+            OptionType::String(".pants.d".to_owned()),
+        ),
+        OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false)).group("debugging"),
+        OptionInfo::new(option_id!(["test"], "timeout"), OptionType::Int(60)),
+        OptionInfo::new(
+            option_id!(["test"], "extra", "env"),
+            OptionType::Dict(indexmap! {"A".to_owned() => Val::Null}),
+        ),
+        OptionInfo::new(
+            option_id!(["test"], "token"),
+            OptionType::String("".to_owned()),
+        )
+        .keyring("pants", "token"),
+        OptionInfo::new(
+            option_id!(["python-infer"], "unowned"),
+            OptionType::String("".to_owned()),
+        ),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+fn parser() -> OptionParser {
+    let mut parser = tests::parser(
+        &["--test-debug"],
+        &[("PANTS_LEVEL", "debug")],
+        "[DEFAULT]\nunowned = \"warning\"\n\n[test]\ntimeout = 120\n",
+    );
+    parser.register_keyring(&registry(), Arc::new(Store));
+    parser
+}
+
+#[test]
+fn test_dump_config() {
+    let parser = parser();
+    let registry = registry();
+    // Grouped options follow the ungrouped options of their scope.
+    assert_eq!(
+        r#"[GLOBAL]
+# from PANTS_LEVEL
+level = "debug"
+# default
+pants_workdir = ".pants.d"
+
+[test]
+# from pants.toml:5
+timeout = 120
+# default
+# extra_env = {"A": None}
+# from keyring entry pants/token
+# token = <redacted>
+# from --test-debug
+debug = true
+
+[python-infer]
+# from pants.toml:2 [DEFAULT]
+unowned = "warning"
+"#,
+        parser.dump_config(&registry, true).unwrap()
+    );
+    assert_eq!(
+        r#"[GLOBAL]
+level = "debug"
+pants_workdir = ".pants.d"
+
+[test]
+timeout = 120
+# extra_env = {"A": None}
+# token = <redacted>
+debug = true
+
+[python-infer]
+unowned = "warning"
+"#,
+        parser.dump_config(&registry, false).unwrap()
+    );
+}
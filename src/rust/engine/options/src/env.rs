@@ -4,9 +4,13 @@
 use std::collections::HashMap;
 use std::env;
 use std::ffi::OsString;
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+use log::warn;
 
 use super::id::{NameTransform, OptionId, Scope};
-use super::{DictEdit, OptionsSource};
+use super::{DictEdit, OptionsSource, RawValue, Val};
 use crate::fromfile::FromfileExpander;
 use crate::parse::Parseable;
 use crate::ListEdit;
@@ -14,8 +18,14 @@ use crate::ListEdit;
 #[derive(Debug)]
 pub struct Env {
     pub(crate) env: HashMap<String, String>,
+    // The raw values of the env vars whose values are not UTF-8, and so are held lossily in `env`.
+    pub(crate) non_utf8_values: HashMap<String, OsString>,
 }
 
+///
+/// The env vars that could not be captured exactly: those with non-UTF-8 names are dropped, and
+/// those with non-UTF-8 values are captured lossily, with invalid sequences replaced by U+FFFD.
+///
 #[derive(Debug)]
 pub struct DroppedEnvVars {
     pub non_utf8_keys: Vec<OsString>,
@@ -24,7 +34,10 @@ pub struct DroppedEnvVars {
 
 impl Env {
     pub fn new(env: HashMap<String, String>) -> Self {
-        Self { env }
+        Self {
+            env,
+            non_utf8_values: HashMap::new(),
+        }
     }
 
     pub fn capture_lossy() -> (Self, DroppedEnvVars) {
@@ -36,6 +49,7 @@ impl Env {
         I: Iterator<Item = (OsString, OsString)>,
     {
         let mut env: HashMap<String, String> = HashMap::with_capacity(env_os.size_hint().0);
+        let mut non_utf8_values = HashMap::new();
         let mut dropped = DroppedEnvVars {
             non_utf8_keys: Vec::new(),
             keys_with_non_utf8_values: Vec::new(),
@@ -45,11 +59,32 @@ impl Env {
                 (Ok(key), Ok(val)) => {
                     env.insert(key, val);
                 }
-                (Ok(key), Err(_)) => dropped.keys_with_non_utf8_values.push(key),
+                (Ok(key), Err(os_val)) => {
+                    env.insert(key.clone(), os_val.to_string_lossy().into_owned());
+                    non_utf8_values.insert(key.clone(), os_val);
+                    dropped.keys_with_non_utf8_values.push(key);
+                }
                 (Err(os_key), _) => dropped.non_utf8_keys.push(os_key),
             }
         }
-        (Self::new(env), dropped)
+        (
+            Self {
+                env,
+                non_utf8_values,
+            },
+            dropped,
+        )
+    }
+
+    ///
+    /// The raw value of the given env var, which need not be UTF-8. This is appropriate for values
+    /// which are paths, as those need not be UTF-8 either.
+    ///
+    pub fn get_os(&self, key: &str) -> Option<OsString> {
+        self.non_utf8_values
+            .get(key)
+            .cloned()
+            .or_else(|| self.env.get(key).map(OsString::from))
     }
 }
 
@@ -67,7 +102,21 @@ impl EnvReader {
         }
     }
 
-    fn env_var_names(id: &OptionId) -> Vec<String> {
+    ///
+    /// The preferred name of the env var for the option `id`, as shown in help: the shorter of
+    /// the names of a global option, e.g. `PANTS_LEVEL` rather than `PANTS_GLOBAL_LEVEL`.
+    ///
+    pub(crate) fn env_var_name(id: &OptionId) -> String {
+        let mut names = Self::env_var_names(id);
+        if id.scope == Scope::Global {
+            names.swap_remove(1)
+        } else {
+            names.swap_remove(0)
+        }
+    }
+
+    // Every env var that sets the option `id`, in order of precedence.
+    pub(crate) fn env_var_names(id: &OptionId) -> Vec<String> {
         let name = id.name("_", NameTransform::ToUpper);
         let mut names = vec![format!(
             "PANTS_{}_{}",
@@ -86,16 +135,29 @@ impl EnvReader {
         names
     }
 
-    fn get_list<T: Parseable>(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<T>>>, String> {
-        for env_var_name in &Self::env_var_names(id) {
-            if let Some(value) = self.env.env.get(env_var_name) {
-                return self
-                    .fromfile_expander
-                    .expand_to_list::<T>(value.to_owned())
-                    .map_err(|e| e.render(self.display(id)));
-            }
+    // The value of the first env var for the option `id` that is set, warning if it is not UTF-8.
+    fn get_value(&self, id: &OptionId) -> Option<String> {
+        let (env_var_name, value) = Self::env_var_names(id)
+            .into_iter()
+            .find_map(|name| Some((name.clone(), self.env.env.get(&name)?)))?;
+        if self.env.non_utf8_values.contains_key(&env_var_name) {
+            warn!(
+                "The value of the environment variable {env_var_name} is not valid UTF-8, so its \
+                invalid sequences were replaced with U+FFFD."
+            );
         }
-        Ok(None)
+        Some(value.to_owned())
+    }
+
+    fn get_list<T: Parseable>(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<T>>>, String> {
+        self.get_value(id)
+            .map(|value| {
+                self.fromfile_expander
+                    .expand_to_list::<T>(value)
+                    .map_err(|e| e.render(self.display(id)))
+            })
+            .transpose()
+            .map(Option::flatten)
     }
 }
 
@@ -114,15 +176,37 @@ impl OptionsSource for EnvReader {
     }
 
     fn get_string(&self, id: &OptionId) -> Result<Option<String>, String> {
-        for env_var_name in &Self::env_var_names(id) {
-            if let Some(value) = self.env.env.get(env_var_name) {
-                return self
-                    .fromfile_expander
-                    .expand(value.to_owned())
-                    .map_err(|e| e.render(self.display(id)));
-            }
+        self.get_value(id)
+            .map(|value| {
+                self.fromfile_expander
+                    .expand(value)
+                    .map_err(|e| e.render(self.display(id)))
+            })
+            .transpose()
+            .map(Option::flatten)
+    }
+
+    fn get_bytes(&self, id: &OptionId) -> Result<Option<Vec<u8>>, String> {
+        self.get_value(id)
+            .map(|value| {
+                self.fromfile_expander
+                    .expand_to_bytes(value)
+                    .map_err(|e| e.render(self.display(id)))
+            })
+            .transpose()
+            .map(Option::flatten)
+    }
+
+    fn get_path(&self, id: &OptionId) -> Result<Option<PathBuf>, String> {
+        // NB: A value that is not UTF-8 is taken as is, rather than as a fromfile.
+        let raw = Self::env_var_names(id)
+            .into_iter()
+            .find(|name| self.env.env.contains_key(name))
+            .and_then(|name| self.env.non_utf8_values.get(&name));
+        match raw {
+            Some(raw) => Ok(Some(PathBuf::from(raw))),
+            None => Ok(self.get_string(id)?.map(PathBuf::from)),
         }
-        Ok(None)
     }
 
     fn get_bool(&self, id: &OptionId) -> Result<Option<bool>, String> {
@@ -151,15 +235,36 @@ impl OptionsSource for EnvReader {
         self.get_list::<String>(id)
     }
 
+    fn get_dict_list(
+        &self,
+        id: &OptionId,
+    ) -> Result<Option<Vec<ListEdit<IndexMap<String, Val>>>>, String> {
+        self.get_list::<IndexMap<String, Val>>(id)
+    }
+
     fn get_dict(&self, id: &OptionId) -> Result<Option<Vec<DictEdit>>, String> {
-        for env_var_name in &Self::env_var_names(id) {
-            if let Some(value) = self.env.env.get(env_var_name) {
-                return self
-                    .fromfile_expander
-                    .expand_to_dict(value.to_owned())
-                    .map_err(|e| e.render(self.display(id)));
-            }
-        }
-        Ok(None)
+        self.get_value(id)
+            .map(|value| {
+                self.fromfile_expander
+                    .expand_to_dict(value)
+                    .map_err(|e| e.render(self.display(id)))
+            })
+            .transpose()
+            .map(Option::flatten)
+    }
+
+    fn get_raw(&self, id: &OptionId) -> Vec<RawValue> {
+        // Only the first env var that is set is consulted, so only it contributes.
+        Self::env_var_names(id)
+            .into_iter()
+            .find_map(|env_var_name| {
+                let value = self.env.env.get(&env_var_name)?.clone();
+                Some(RawValue {
+                    location: env_var_name,
+                    value: Some(value),
+                })
+            })
+            .into_iter()
+            .collect()
     }
 }
@@ -6,7 +6,7 @@ use crate::fromfile::test_util::write_fromfile;
 use crate::fromfile::FromfileExpander;
 use crate::{option_id, DictEdit, DictEditAction};
 use crate::{ListEdit, ListEditAction, OptionId, OptionsSource, Val};
-use maplit::hashmap;
+use indexmap::indexmap;
 use std::collections::HashMap;
 use std::ffi::OsString;
 use std::fmt::Debug;
@@ -40,10 +40,14 @@ fn test_capture_lossy() {
         ),
     ];
     let (env, dropped) = Env::do_capture_lossy(fake_vars.into_iter());
-    let captured_vars: Vec<(String, String)> = (&env).into();
+    let mut captured_vars: Vec<(String, String)> = (&env).into();
+    captured_vars.sort();
     assert_eq!(
         captured_vars,
-        vec![(String::from("GOOD_KEY1"), String::from("GOOD_VALUE"))]
+        vec![
+            (String::from("GOOD_KEY1"), String::from("GOOD_VALUE")),
+            (String::from("GOOD_KEY2"), String::from("BAD_\u{fffd}VALUE")),
+        ]
     );
     assert_eq!(
         dropped.non_utf8_keys,
@@ -53,6 +57,44 @@ fn test_capture_lossy() {
         dropped.keys_with_non_utf8_values,
         vec![String::from("GOOD_KEY2")]
     );
+    assert_eq!(
+        Some(OsString::from_vec(b"BAD_\xa5VALUE".to_vec())),
+        env.get_os("GOOD_KEY2")
+    );
+    assert_eq!(Some(OsString::from("GOOD_VALUE")), env.get_os("GOOD_KEY1"));
+    assert_eq!(None, env.get_os("MISSING"));
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn test_non_utf8_path() {
+    use std::os::unix::ffi::OsStringExt;
+    use std::path::PathBuf;
+
+    let fake_vars: Vec<(OsString, OsString)> = vec![
+        (
+            "PANTS_FOO".into(),
+            OsString::from_vec(b"/tmp/\xa5.txt".to_vec()),
+        ),
+        ("PANTS_BAR".into(), "/tmp/bar.txt".into()),
+    ];
+    let env = EnvReader::new(
+        Env::do_capture_lossy(fake_vars.into_iter()).0,
+        FromfileExpander::relative_to_cwd(),
+    );
+    assert_eq!(
+        Some(PathBuf::from(OsString::from_vec(b"/tmp/\xa5.txt".to_vec()))),
+        env.get_path(&option_id!("foo")).unwrap()
+    );
+    assert_eq!(
+        Some(PathBuf::from("/tmp/bar.txt")),
+        env.get_path(&option_id!("bar")).unwrap()
+    );
+    // As a string, the value is lossy.
+    assert_eq!(
+        Some("/tmp/\u{fffd}.txt".to_owned()),
+        env.get_string(&option_id!("foo")).unwrap()
+    );
 }
 
 #[test]
@@ -117,7 +159,7 @@ fn test_bool() {
 
     assert!(env.get_bool(&option_id!("dne")).unwrap().is_none());
     assert_eq!(
-        "Problem parsing PANTS_EGGS bool value:\n1:swallow\n  ^\nExpected 'true' or 'false' \
+        "Problem parsing PANTS_EGGS bool value:\n1:swallow\n  ^\nExpected 'true', 'false', 'yes', 'no', 'on', 'off', '1' or '0' (in any case) \
         at line 1 column 1"
             .to_owned(),
         env.get_bool(&option_id!("pants", "eggs")).unwrap_err()
@@ -197,7 +239,7 @@ fn test_string_list() {
 Problem parsing PANTS_BAD string list value:
 1:('mis', 'matched']
   -----------------^
-Expected \",\" or the end of a tuple indicated by ')' at line 1 column 18"
+Mismatched `]`, where the `(` at line 1 column 1 must be closed first at line 1 column 18"
         .to_owned();
 
     assert_eq!(
@@ -206,6 +248,43 @@ Expected \",\" or the end of a tuple indicated by ')' at line 1 column 18"
     );
 }
 
+#[test]
+fn test_bool_float_and_enum_lists() {
+    let env = env([
+        ("PANTS_BOOLS", "+[true]"),
+        ("PANTS_FLOATS", "[1.5, -2]"),
+        ("PANTS_COLORS", "red"),
+    ]);
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec![true]
+        }],
+        env.get_bool_list(&option_id!("bools")).unwrap().unwrap()
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![1.5, -2.0]
+        }],
+        env.get_float_list(&option_id!("floats")).unwrap().unwrap()
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec!["red".to_string()]
+        }],
+        env.get_enum_list(&option_id!("colors"), &["red", "green"])
+            .unwrap()
+            .unwrap()
+    );
+    assert_eq!(
+        "Invalid value `red` for PANTS_COLORS: must be one of green",
+        env.get_enum_list(&option_id!("colors"), &["green"])
+            .unwrap_err()
+    );
+}
+
 #[test]
 fn test_scalar_fromfile() {
     fn do_test<T: PartialEq + Debug>(
@@ -271,10 +350,10 @@ fn test_dict_fromfile() {
     fn do_test(content: &str, filename: &str) {
         let expected = vec![DictEdit {
             action: DictEditAction::Replace,
-            items: hashmap! {
-            "FOO".to_string() => Val::Dict(hashmap! {
+            items: indexmap! {
+            "FOO".to_string() => Val::Dict(indexmap! {
                 "BAR".to_string() => Val::Float(3.14),
-                "BAZ".to_string() => Val::Dict(hashmap! {
+                "BAZ".to_string() => Val::Dict(indexmap! {
                     "QUX".to_string() => Val::Bool(true),
                     "QUUX".to_string() => Val::List(vec![ Val::Int(1), Val::Int(2)])
                 })
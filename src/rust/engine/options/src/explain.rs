@@ -0,0 +1,344 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+
+use super::{
+    DictEdit, DictEditAction, DictOptionValue, ListEdit, ListEditAction, ListOptionValue, OptionId,
+    OptionValue, Source, Sources, Val,
+};
+use crate::types::ToVal;
+
+// Shown in place of secret values, e.g. those read from the keyring or decrypted from config.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// A raw, unparsed value for an option, as found in a single source.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct RawValue {
+    /// Where the value was found: a flag, an env var name, or a config file path, line and section.
+    pub location: String,
+    /// The value as written, or None for a flag that was given without a value.
+    pub value: Option<String>,
+}
+
+/// A single value or edit parsed from a source. Scalar values are always replacements.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Edit {
+    pub action: ListEditAction,
+    pub value: Val,
+}
+
+/// The contribution of a single source to the value of an option.
+#[derive(Debug, Serialize)]
+pub struct Contribution {
+    pub source: Source,
+    pub raw_values: Vec<RawValue>,
+    pub edits: Vec<Edit>,
+}
+
+///
+/// A trace of how the value of a single option was derived: its default, the contribution of each
+/// source in order of increasing priority, and the final value.
+///
+/// The `Display` impl renders a human-readable trace, while `to_json` renders a document for
+/// consumption by tools.
+///
+#[derive(Debug, Serialize)]
+pub struct Explanation {
+    pub option: String,
+    pub default: Option<Val>,
+    pub contributions: Vec<Contribution>,
+    pub value: Val,
+    pub source: Source,
+}
+
+// Renders a string as a double-quoted literal, escaped such that the option value parser reads it
+// back as the same string.
+fn quote(f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '"' => f.write_str("\\\"")?,
+            '\x07' => f.write_str("\\a")?,
+            '\x08' => f.write_str("\\b")?,
+            '\x0c' => f.write_str("\\f")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\x0b' => f.write_str("\\v")?,
+            c if c.is_ascii_control() => write!(f, "\\x{:02x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+// Renders a float such that it is read back as a float rather than an int: i.e. always with a
+// decimal point, and with a signed exponent, if any.
+fn write_float(f: &mut Formatter<'_>, x: f64) -> fmt::Result {
+    let repr = format!("{x:?}");
+    match repr.split_once('e') {
+        Some((mantissa, exponent)) => {
+            let point = if mantissa.contains('.') { "" } else { ".0" };
+            let sign = if exponent.starts_with('-') { "" } else { "+" };
+            write!(f, "{mantissa}{point}e{sign}{exponent}")
+        }
+        None => f.write_str(&repr),
+    }
+}
+
+///
+/// Values are rendered in the syntax of option values (e.g. `{"a": [1, 2.0, "x"], "b": None}`),
+/// so that the rendering of any value other than bytes, dates or non-finite floats parses back to
+/// an equal value. Secret values should be replaced with `REDACTED` before they are rendered.
+///
+impl Display for Val {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Val::Bool(b) => write!(f, "{b}"),
+            Val::Int(i) => write!(f, "{i}"),
+            Val::Float(x) => write_float(f, *x),
+            Val::String(s) => quote(f, s.as_str()),
+            Val::List(l) => {
+                f.write_str("[")?;
+                for (i, item) in l.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str("]")
+            }
+            // Dicts preserve the order in which their keys were written, so this is deterministic.
+            Val::Dict(d) => {
+                f.write_str("{")?;
+                for (i, (k, v)) in d.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    quote(f, k)?;
+                    write!(f, ": {v}")?;
+                }
+                f.write_str("}")
+            }
+            Val::Bytes(b) => write!(f, "<{} bytes>", b.len()),
+            Val::Date(d) => write!(f, "{d}"),
+            Val::Null => f.write_str("None"),
+        }
+    }
+}
+
+// Renders a list edit, e.g. `+["a"]`, `+0["a"]` or `+<"b"["a"]`. For insertions relative to an
+// anchor, the anchor is the first of the items.
+fn write_list_edit(f: &mut Formatter<'_>, action: ListEditAction, items: &Val) -> fmt::Result {
+    match (action, items) {
+        (ListEditAction::InsertBefore | ListEditAction::InsertAfter, Val::List(items))
+            if !items.is_empty() =>
+        {
+            let marker = if action == ListEditAction::InsertBefore {
+                "<"
+            } else {
+                ">"
+            };
+            write!(f, "+{marker}{}{}", items[0], Val::List(items[1..].to_vec()))
+        }
+        (ListEditAction::Insert(index), items) => write!(f, "+{index}{items}"),
+        (
+            ListEditAction::Add | ListEditAction::InsertBefore | ListEditAction::InsertAfter,
+            items,
+        ) => {
+            write!(f, "+{items}")
+        }
+        (ListEditAction::Remove, items) => write!(f, "-{items}"),
+        (ListEditAction::Replace, items) => write!(f, "{items}"),
+    }
+}
+
+impl<T: ToVal> Display for ListEdit<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_list_edit(f, self.action, &self.items.to_val())
+    }
+}
+
+impl Display for DictEdit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let prefix = match self.action {
+            DictEditAction::Replace => "",
+            DictEditAction::Add => "+",
+        };
+        write!(f, "{prefix}{}", self.items.to_val())
+    }
+}
+
+impl Edit {
+    fn replace(value: Val) -> Self {
+        Self {
+            action: ListEditAction::Replace,
+            value,
+        }
+    }
+}
+
+impl<T: ToVal> From<&ListEdit<T>> for Edit {
+    fn from(edit: &ListEdit<T>) -> Self {
+        Self {
+            action: edit.action,
+            value: edit.items.to_val(),
+        }
+    }
+}
+
+impl From<&DictEdit> for Edit {
+    fn from(edit: &DictEdit) -> Self {
+        Self {
+            action: match edit.action {
+                DictEditAction::Replace => ListEditAction::Replace,
+                DictEditAction::Add => ListEditAction::Add,
+            },
+            value: edit.items.to_val(),
+        }
+    }
+}
+
+impl Display for Edit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_list_edit(f, self.action, &self.value)
+    }
+}
+
+/// A description of a source, for use in messages: e.g. `env`, or `config file pants.toml`.
+pub fn describe_source(source: &Source) -> String {
+    match source {
+        Source::Default => "default".to_owned(),
+        Source::Config { path, .. } => format!("config file {path}"),
+        Source::Keyring => "keyring".to_owned(),
+        Source::Env => "env".to_owned(),
+        Source::Flag => "flags".to_owned(),
+        Source::Plugin { name, .. } => name.clone(),
+    }
+}
+
+impl Explanation {
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize explanation of {}: {e}", self.option))
+    }
+
+    fn new(
+        id: &OptionId,
+        sources: &Sources,
+        derivation: Vec<(Source, Vec<Edit>)>,
+        value: Val,
+        source: Source,
+    ) -> Self {
+        let is_secret = |source: &Source| sources.get(source).is_some_and(|s| s.is_secret(id));
+        let mut default = None;
+        let mut contributions = vec![];
+        for (source, mut edits) in derivation {
+            if source == Source::Default {
+                default = edits.into_iter().next().map(|edit| edit.value);
+                continue;
+            }
+            let raw_values = sources
+                .get(&source)
+                .map(|s| s.get_raw(id))
+                .unwrap_or_default();
+            if is_secret(&source) {
+                for edit in &mut edits {
+                    edit.value = Val::String(REDACTED.into());
+                }
+            }
+            contributions.push(Contribution {
+                source,
+                raw_values,
+                edits,
+            });
+        }
+        // NB: A value that is merged from a secret and other edits is redacted as a whole.
+        let value = if contributions.iter().any(|c| is_secret(&c.source)) || is_secret(&source) {
+            Val::String(REDACTED.into())
+        } else {
+            value
+        };
+        Self {
+            option: id.to_string(),
+            default,
+            contributions,
+            value,
+            source,
+        }
+    }
+}
+
+pub(crate) fn explain_scalar<T: ToVal>(
+    id: &OptionId,
+    sources: &Sources,
+    value: OptionValue<T>,
+) -> Explanation {
+    let derivation = value
+        .derivation
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(source, v)| (source, vec![Edit::replace(v.to_val())]))
+        .collect();
+    Explanation::new(id, sources, derivation, value.value.to_val(), value.source)
+}
+
+pub(crate) fn explain_list<T: ToVal>(
+    id: &OptionId,
+    sources: &Sources,
+    value: ListOptionValue<T>,
+) -> Explanation {
+    let derivation = value
+        .derivation
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(source, edits)| (source, edits.iter().map(Edit::from).collect()))
+        .collect();
+    Explanation::new(id, sources, derivation, value.value.to_val(), value.source)
+}
+
+pub(crate) fn explain_dict(
+    id: &OptionId,
+    sources: &Sources,
+    value: DictOptionValue,
+) -> Explanation {
+    let derivation = value
+        .derivation
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(source, edits)| (source, edits.iter().map(Edit::from).collect()))
+        .collect();
+    Explanation::new(id, sources, derivation, value.value.to_val(), value.source)
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.option)?;
+        match &self.default {
+            Some(default) => writeln!(f, "  default: {}", default)?,
+            None => writeln!(f, "  default: <none>")?,
+        }
+        for contribution in &self.contributions {
+            writeln!(f, "  {}:", describe_source(&contribution.source))?;
+            for raw in &contribution.raw_values {
+                match &raw.value {
+                    Some(value) => writeln!(f, "    {}: {value}", raw.location)?,
+                    None => writeln!(f, "    {}", raw.location)?,
+                }
+            }
+            for edit in &contribution.edits {
+                writeln!(f, "    => {edit}")?;
+            }
+        }
+        write!(
+            f,
+            "  value: {} (from {})",
+            self.value,
+            describe_source(&self.source)
+        )
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use indexmap::IndexMap;
+
+use crate::parse::{parse_dict, Parseable};
+use crate::tests::parser;
+use crate::{
+    option_id, DictEdit, DictEditAction, ListEdit, ListEditAction, OptionType, RawValue, Source,
+    Val,
+};
+
+#[test]
+fn test_explain_scalar() {
+    let option_parser = parser(
+        &["--level=warn"],
+        &[("PANTS_LEVEL", "error")],
+        "[GLOBAL]\n# The level.\nlevel = 'debug'\n",
+    );
+    let explanation = option_parser
+        .explain(&option_id!("level"), OptionType::String("info".to_owned()))
+        .unwrap();
+    assert_eq!(Some(Val::String("info".into())), explanation.default);
+    assert_eq!(Val::String("warn".into()), explanation.value);
+    assert_eq!(Source::Flag, explanation.source);
+    assert_eq!(3, explanation.contributions.len());
+    assert_eq!(
+        vec![RawValue {
+            location: "pants.toml:3 [GLOBAL]".to_owned(),
+            value: Some("\"debug\"".to_owned()),
+        }],
+        explanation.contributions[0].raw_values
+    );
+
+    assert_eq!(
+        "[GLOBAL] level
+  default: \"info\"
+  config file pants.toml:
+    pants.toml:3 [GLOBAL]: \"debug\"
+    => \"debug\"
+  env:
+    PANTS_LEVEL: error
+    => \"error\"
+  flags:
+    --level: warn
+    => \"warn\"
+  value: \"warn\" (from flags)",
+        explanation.to_string()
+    );
+}
+
+#[test]
+fn test_explain_list_and_dict() {
+    let option_parser = parser(
+        &["--ints=+[3]", "--ints=-[1]"],
+        &[],
+        "[DEFAULT]\nints = [0]\n\n[GLOBAL.map]\nb = 2\n",
+    );
+    let explanation = option_parser
+        .explain(&option_id!("ints"), OptionType::IntList(vec![1]))
+        .unwrap();
+    assert_eq!(
+        "[GLOBAL] ints
+  default: [1]
+  config file pants.toml:
+    pants.toml:2 [DEFAULT]: [0]
+    => [0]
+  flags:
+    --ints: +[3]
+    --ints: -[1]
+    => +[3]
+    => -[1]
+  value: [0, 3] (from flags)",
+        explanation.to_string()
+    );
+
+    let explanation = option_parser
+        .explain(
+            &option_id!("map"),
+            OptionType::Dict(IndexMap::from([("a".to_owned(), Val::Int(1))])),
+        )
+        .unwrap();
+    assert_eq!(
+        "[GLOBAL] map
+  default: {\"a\": 1}
+  config file pants.toml:
+    pants.toml:4 [GLOBAL]: {b = 2}
+    => {\"b\": 2}
+  value: {\"b\": 2} (from config file pants.toml)",
+        explanation.to_string()
+    );
+}
+
+#[test]
+fn test_explain_negated_flag() {
+    let option_parser = parser(&["--no-pantsd"], &[], "");
+    let explanation = option_parser
+        .explain(&option_id!("pantsd"), OptionType::Bool(true))
+        .unwrap();
+    assert_eq!(
+        vec![RawValue {
+            location: "--no-pantsd".to_owned(),
+            value: None,
+        }],
+        explanation.contributions[0].raw_values
+    );
+    assert_eq!(Val::Bool(false), explanation.value);
+}
+
+#[test]
+fn test_explain_json() {
+    let option_parser = parser(
+        &["--ints=-[1]"],
+        &[("PANTS_INTS", "+[2]")],
+        "[GLOBAL]\nints = [3]\n",
+    );
+    let explanation = option_parser
+        .explain(&option_id!("ints"), OptionType::IntList(vec![1]))
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&explanation.to_json().unwrap()).unwrap();
+    assert_eq!(
+        serde_json::json!({
+            "option": "[GLOBAL] ints",
+            "default": [1],
+            "contributions": [
+                {
+                    "source": {"type": "config", "ordinal": 0, "path": "pants.toml"},
+                    "raw_values": [{"location": "pants.toml:2 [GLOBAL]", "value": "[3]"}],
+                    "edits": [{"action": "replace", "value": [3]}],
+                },
+                {
+                    "source": {"type": "env"},
+                    "raw_values": [{"location": "PANTS_INTS", "value": "+[2]"}],
+                    "edits": [{"action": "add", "value": [2]}],
+                },
+                {
+                    "source": {"type": "flag"},
+                    "raw_values": [{"location": "--ints", "value": "-[1]"}],
+                    "edits": [{"action": "remove", "value": [1]}],
+                },
+            ],
+            "value": [3, 2],
+            "source": {"type": "flag"},
+        }),
+        json
+    );
+}
+
+#[test]
+fn test_display_round_trips() {
+    let dict = IndexMap::from([
+        (
+            "str".to_owned(),
+            Val::String("a \"b\" 'c'\\\n\t\x01é".into()),
+        ),
+        ("int".to_owned(), Val::Int(-3)),
+        (
+            "floats".to_owned(),
+            Val::List(vec![Val::Float(1.5), Val::Float(2.0), Val::Float(1e20)]),
+        ),
+        ("bool".to_owned(), Val::Bool(true)),
+        ("none".to_owned(), Val::Null),
+        (
+            "nested".to_owned(),
+            Val::Dict(IndexMap::from([("k\"".to_owned(), Val::List(vec![]))])),
+        ),
+    ]);
+    let rendered = Val::Dict(dict.clone()).to_string();
+    assert_eq!(
+        r#"{"str": "a \"b\" 'c'\\\n\t\x01é", "int": -3, "floats": [1.5, 2.0, 1.0e+20], "bool": true, "none": None, "nested": {"k\"": []}}"#,
+        rendered
+    );
+    assert_eq!(dict, IndexMap::<String, Val>::parse(&rendered).unwrap());
+
+    let edit = DictEdit {
+        action: DictEditAction::Add,
+        items: dict,
+    };
+    assert_eq!(edit, parse_dict(&edit.to_string()).unwrap());
+
+    for edit in [
+        ListEdit {
+            action: ListEditAction::Replace,
+            items: vec!["a,b".to_owned(), "\"".to_owned()],
+        },
+        ListEdit {
+            action: ListEditAction::Add,
+            items: vec!["c".to_owned()],
+        },
+        ListEdit {
+            action: ListEditAction::Remove,
+            items: vec![],
+        },
+        ListEdit {
+            action: ListEditAction::Insert(2),
+            items: vec!["d".to_owned()],
+        },
+        ListEdit {
+            action: ListEditAction::InsertBefore,
+            items: vec!["a,b".to_owned(), "e".to_owned()],
+        },
+    ] {
+        assert_eq!(
+            vec![edit.clone()],
+            String::parse_list(&edit.to_string()).unwrap()
+        );
+    }
+}
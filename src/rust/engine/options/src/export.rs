@@ -0,0 +1,75 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt;
+
+use crate::env::EnvReader;
+use crate::registry::OptionRegistry;
+use crate::{OptionParser, Source, Val};
+
+///
+/// An assignment of an env var that sets an option to its resolved value.
+///
+/// The `Display` impl renders `NAME=value`, with the value quoted for POSIX shells if need be, as
+/// for a wrapper script. The unquoted `value` is suitable for e.g. a container's env.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnvAssignment {
+    pub name: String,
+    pub value: String,
+}
+
+impl fmt::Display for EnvAssignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let is_safe = !self.value.is_empty()
+            && self
+                .value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.,:/=+@%".contains(c));
+        if is_safe {
+            write!(f, "{}={}", self.name, self.value)
+        } else {
+            write!(f, "{}='{}'", self.name, self.value.replace('\'', r"'\''"))
+        }
+    }
+}
+
+// Encodes a resolved value as the value of an env var, from which it is parsed back unchanged.
+fn encode(value: &Val) -> String {
+    match value {
+        // NB: A leading `@` would otherwise mark the value as a fromfile.
+        Val::String(s) if s.starts_with('@') => format!("@{s}"),
+        Val::String(s) => s.to_string(),
+        // Lists and dicts are rendered as literals, which replace rather than edit the default.
+        value => value.to_string(),
+    }
+}
+
+pub(crate) fn export(
+    parser: &OptionParser,
+    registry: &OptionRegistry,
+) -> Result<Vec<EnvAssignment>, String> {
+    let mut assignments = vec![];
+    for option in registry.options() {
+        let id = &option.id;
+        // NB: Secret values are not exported, so that a generated script may be committed.
+        if parser.sources.values().any(|source| source.is_secret(id)) {
+            continue;
+        }
+        let (value, source) = parser.resolve(id, option.option_type.clone())?;
+        if source == Source::Default {
+            continue;
+        }
+        if matches!(value, Val::Bytes(_)) {
+            return Err(format!(
+                "The value of {id} is bytes, which cannot be exported as an env var."
+            ));
+        }
+        assignments.push(EnvAssignment {
+            name: EnvReader::env_var_name(id),
+            value: encode(&value),
+        });
+    }
+    assignments.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(assignments)
+}
@@ -0,0 +1,115 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use indexmap::IndexMap;
+
+use crate::tests;
+use crate::{option_id, EnvAssignment, OptionInfo, OptionParser, OptionRegistry, OptionType};
+
+fn parser(env: &[(String, String)], config: &str) -> OptionParser {
+    let env = env
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect::<Vec<_>>();
+    tests::parser(&[], &env, config)
+}
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!("level"), OptionType::String("info".to_owned())),
+        OptionInfo::new(
+            option_id!("pants", "workdir"),
+            OptionType::String("".to_owned()),
+        ),
+        OptionInfo::new(option_id!(["test"], "timeout"), OptionType::Int(60)),
+        OptionInfo::new(option_id!(["test"], "ratio"), OptionType::Float(0.5)),
+        OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false)),
+        OptionInfo::new(
+            option_id!(["test"], "args"),
+            OptionType::StringList(vec!["-x".to_owned()]),
+        ),
+        OptionInfo::new(
+            option_id!(["test"], "extra", "env"),
+            OptionType::Dict(IndexMap::default()),
+        ),
+        OptionInfo::new(
+            option_id!(["python-infer"], "unowned"),
+            OptionType::String("".to_owned()),
+        ),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+#[test]
+fn test_export_env() {
+    let config = r#"
+[GLOBAL]
+level = "debug"
+pants_workdir = "@@work"
+
+[test]
+ratio = 2.0
+debug = true
+args.add = ["it's", "-v"]
+extra_env = { A = "1", B = ["x"] }
+
+[python-infer]
+unowned = "a b"
+"#;
+    let assignments = parser(&[], config).export_env(&registry()).unwrap();
+    let assignment = |name: &str, value: &str| EnvAssignment {
+        name: name.to_owned(),
+        value: value.to_owned(),
+    };
+    // Options set to their defaults are not exported.
+    assert_eq!(
+        vec![
+            assignment("PANTS_LEVEL", "debug"),
+            assignment("PANTS_PANTS_WORKDIR", "@@work"),
+            assignment("PANTS_PYTHON_INFER_UNOWNED", "a b"),
+            assignment("PANTS_TEST_ARGS", r#"["-x", "it's", "-v"]"#),
+            assignment("PANTS_TEST_DEBUG", "true"),
+            assignment("PANTS_TEST_EXTRA_ENV", r#"{"A": "1", "B": ["x"]}"#),
+            assignment("PANTS_TEST_RATIO", "2.0"),
+        ],
+        assignments
+    );
+    assert_eq!(
+        vec![
+            "PANTS_LEVEL=debug",
+            "PANTS_PANTS_WORKDIR=@@work",
+            "PANTS_PYTHON_INFER_UNOWNED='a b'",
+            r#"PANTS_TEST_ARGS='["-x", "it'\''s", "-v"]'"#,
+            "PANTS_TEST_DEBUG=true",
+            r#"PANTS_TEST_EXTRA_ENV='{"A": "1", "B": ["x"]}'"#,
+            "PANTS_TEST_RATIO=2.0",
+        ],
+        assignments
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    );
+
+    // The exported env vars set the options to the same values, without the config.
+    let env = assignments
+        .into_iter()
+        .map(|assignment| (assignment.name, assignment.value))
+        .collect::<Vec<_>>();
+    let exported = parser(&env, "");
+    let configured = parser(&[], config);
+    for option in registry().options() {
+        assert_eq!(
+            configured
+                .resolve(&option.id, option.option_type.clone())
+                .unwrap()
+                .0,
+            exported
+                .resolve(&option.id, option.option_type.clone())
+                .unwrap()
+                .0
+        );
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+// A C ABI for option parsing, for tooling which is not written in Python or Rust (such as a CI
+// orchestrator), but which must resolve options exactly as Pants does. Requests and responses are
+// JSON strings: see `include/pants_options.h` for the C declarations, and for the shape of each
+// request and response.
+//
+// This module is only compiled with the `cdylib` feature, which is meant to be built as e.g.:
+//
+//   cargo rustc -p options --release --features=cdylib --crate-type=cdylib
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{c_char, CStr, CString};
+use std::path::PathBuf;
+use std::ptr;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    Args, BuildRoot, ConfigSource, Env, OptionId, OptionParser, OptionType, Scope, ScopeAllowances,
+    UnknownScopePolicy, Val,
+};
+
+/// An opaque handle to a parser, which must be freed with `pants_options_free_parser`.
+pub struct OptionsHandle(OptionParser);
+
+#[derive(Deserialize)]
+struct ParseRequest {
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    // If absent, the config files are discovered as Pants would discover them.
+    config_files: Option<Vec<PathBuf>>,
+    #[serde(default)]
+    allow_pantsrc: bool,
+    // The directory from which to search for the build root, if not the current directory.
+    buildroot: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct GetRequest {
+    // The scope of the option, or the global scope if absent.
+    scope: Option<String>,
+    // The name of the option, as in its flag: e.g. `pants-workdir`.
+    name: String,
+    // The type of the option, as named in help: e.g. `int` or `list[str]`.
+    #[serde(rename = "type")]
+    option_type: String,
+    default: Val,
+}
+
+#[derive(Deserialize)]
+struct ValidateRequest {
+    // The valid keys of each known section.
+    sections: HashMap<String, HashSet<String>>,
+    // Patterns (in which `*` matches anything) for sections which are allowed, but not known.
+    #[serde(default)]
+    allow: Vec<String>,
+    // Whether unknown sections are warnings rather than errors.
+    #[serde(default)]
+    warn_on_unknown_scopes: bool,
+}
+
+fn read_request<T: DeserializeOwned>(request: *const c_char) -> Result<T, String> {
+    if request.is_null() {
+        return Err("The request is null.".to_owned());
+    }
+    // SAFETY: The caller guarantees that a non-null request is a valid NUL-terminated string.
+    let request = unsafe { CStr::from_ptr(request) }
+        .to_str()
+        .map_err(|e| format!("The request is not valid UTF-8: {e}"))?;
+    serde_json::from_str(request).map_err(|e| format!("Invalid request: {e}"))
+}
+
+fn to_c_string(json: serde_json::Value) -> *mut c_char {
+    // NB: Serialized JSON escapes any NUL characters, so this cannot fail.
+    CString::new(json.to_string())
+        .expect("Serialized JSON should not contain NUL characters")
+        .into_raw()
+}
+
+fn respond(result: Result<serde_json::Value, String>) -> *mut c_char {
+    to_c_string(match result {
+        Ok(response) => response,
+        Err(error) => json!({ "error": error }),
+    })
+}
+
+fn parse(request: ParseRequest) -> Result<OptionParser, String> {
+    let config_sources = request
+        .config_files
+        .map(|paths| {
+            paths
+                .iter()
+                .map(ConfigSource::from_file)
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+    let buildroot = request
+        .buildroot
+        .map(|start| BuildRoot::find_from(&start))
+        .transpose()?;
+    OptionParser::new(
+        Args::new(request.args),
+        Env::new(request.env),
+        config_sources,
+        request.allow_pantsrc,
+        false,
+        buildroot,
+    )
+}
+
+fn get(parser: &OptionParser, request: GetRequest) -> Result<serde_json::Value, String> {
+    let scope = match request.scope {
+        Some(scope) => Scope::named(&scope),
+        None => Scope::Global,
+    };
+    let id = OptionId::new(scope, request.name.split('-'), None)?;
+    let option_type = OptionType::from_type_name(&request.option_type, request.default)?;
+    let (value, source) = parser.resolve(&id, option_type)?;
+    Ok(json!({
+        "value": value,
+        "source": source,
+        "rank": source.rank() as usize,
+    }))
+}
+
+fn validate(parser: &OptionParser, request: ValidateRequest) -> serde_json::Value {
+    let allowances = request.allow.iter().fold(
+        ScopeAllowances::new().unknown_scopes(if request.warn_on_unknown_scopes {
+            UnknownScopePolicy::Warn
+        } else {
+            UnknownScopePolicy::Error
+        }),
+        |allowances, pattern| allowances.allow_pattern(pattern),
+    );
+    let validation = parser.validate_config(&request.sections, &allowances);
+    json!({
+        "errors": validation.errors,
+        "warnings": validation.warnings,
+    })
+}
+
+///
+/// Parses options from the sources described by the given JSON request, returning a handle to
+/// the parser, or null on failure, in which case `*error` (if `error` is not null) is set to a
+/// JSON response describing the error, to be freed with `pants_options_free_string`.
+///
+/// # Safety
+///
+/// `request` must be null or a valid NUL-terminated string, and `error` must be null or valid
+/// for writes.
+///
+#[no_mangle]
+pub unsafe extern "C" fn pants_options_parse(
+    request: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut OptionsHandle {
+    match read_request(request).and_then(parse) {
+        Ok(parser) => Box::into_raw(Box::new(OptionsHandle(parser))),
+        Err(e) => {
+            if !error.is_null() {
+                // SAFETY: The caller guarantees that a non-null `error` is valid for writes.
+                unsafe { *error = respond(Err(e)) };
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+///
+/// Resolves the value of a single option, returning a JSON response to be freed with
+/// `pants_options_free_string`.
+///
+/// # Safety
+///
+/// `handle` must be a live handle returned by `pants_options_parse`, and `request` must be null
+/// or a valid NUL-terminated string.
+///
+#[no_mangle]
+pub unsafe extern "C" fn pants_options_get(
+    handle: *const OptionsHandle,
+    request: *const c_char,
+) -> *mut c_char {
+    // SAFETY: The caller guarantees that the handle is live.
+    let parser = unsafe { &(*handle).0 };
+    respond(read_request(request).and_then(|request| get(parser, request)))
+}
+
+///
+/// Validates the sections and keys of the config files of the parser, returning a JSON response
+/// to be freed with `pants_options_free_string`.
+///
+/// # Safety
+///
+/// As for `pants_options_get`.
+///
+#[no_mangle]
+pub unsafe extern "C" fn pants_options_validate(
+    handle: *const OptionsHandle,
+    request: *const c_char,
+) -> *mut c_char {
+    // SAFETY: The caller guarantees that the handle is live.
+    let parser = unsafe { &(*handle).0 };
+    respond(read_request(request).map(|request| validate(parser, request)))
+}
+
+///
+/// # Safety
+///
+/// `handle` must be null, or a handle returned by `pants_options_parse` which has not already
+/// been freed.
+///
+#[no_mangle]
+pub unsafe extern "C" fn pants_options_free_parser(handle: *mut OptionsHandle) {
+    if !handle.is_null() {
+        // SAFETY: The caller guarantees that the handle was allocated by `pants_options_parse`.
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+///
+/// # Safety
+///
+/// `s` must be null, or a string returned by this API which has not already been freed.
+///
+#[no_mangle]
+pub unsafe extern "C" fn pants_options_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: The caller guarantees that the string was allocated by `to_c_string`.
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
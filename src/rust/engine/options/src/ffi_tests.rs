@@ -0,0 +1,131 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::ffi::{c_char, CStr, CString};
+use std::fs;
+use std::ptr;
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use crate::ffi::{
+    pants_options_free_parser, pants_options_free_string, pants_options_get, pants_options_parse,
+    pants_options_validate, OptionsHandle,
+};
+
+fn request(json: Value) -> CString {
+    CString::new(json.to_string()).unwrap()
+}
+
+fn response(s: *mut c_char) -> Value {
+    assert!(!s.is_null());
+    let value = serde_json::from_str(unsafe { CStr::from_ptr(s) }.to_str().unwrap()).unwrap();
+    unsafe { pants_options_free_string(s) };
+    value
+}
+
+fn parse(json: Value) -> Result<*mut OptionsHandle, Value> {
+    let mut error = ptr::null_mut();
+    let handle = unsafe { pants_options_parse(request(json).as_ptr(), &mut error) };
+    if handle.is_null() {
+        Err(response(error))
+    } else {
+        Ok(handle)
+    }
+}
+
+#[test]
+fn test_parse_and_get() {
+    let buildroot = TempDir::new().unwrap();
+    fs::write(
+        buildroot.path().join("pants.toml"),
+        "[GLOBAL]\nlevel = 'warn'\n\n[test]\ntimeout = 30\n",
+    )
+    .unwrap();
+    let handle = parse(json!({
+        "args": ["--test-timeout=60", "test"],
+        "env": {"PANTS_TEST_EXTRA_ARGS": "+['-v']"},
+        "config_files": [buildroot.path().join("pants.toml")],
+        "buildroot": buildroot.path(),
+    }))
+    .unwrap();
+
+    let get = |json: Value| response(unsafe { pants_options_get(handle, request(json).as_ptr()) });
+    assert_eq!(
+        json!({"value": "warn", "source": {"type": "config", "ordinal": 0, "path": "pants.toml"}, "rank": 3}),
+        get(json!({"name": "level", "type": "str", "default": "info"}))
+    );
+    assert_eq!(
+        json!({"value": 60, "source": {"type": "flag"}, "rank": 5}),
+        get(json!({"scope": "test", "name": "timeout", "type": "int", "default": 10}))
+    );
+    assert_eq!(
+        json!({"value": ["-x", "-v"], "source": {"type": "env"}, "rank": 4}),
+        get(json!({"scope": "test", "name": "extra-args", "type": "list[str]", "default": ["-x"]}))
+    );
+    assert_eq!(
+        json!({"value": false, "source": {"type": "default"}, "rank": 1}),
+        get(json!({"name": "pantsd", "type": "bool", "default": false}))
+    );
+    assert_eq!(
+        json!({"error": "Invalid default for an option of type int: Expected an int but got \"10\""}),
+        get(json!({"name": "timeout", "type": "int", "default": "10"}))
+    );
+    assert_eq!(
+        json!({"error": "Invalid request: missing field `default` at line 1 column 29"}),
+        get(json!({"name": "level", "type": "str"}))
+    );
+
+    unsafe { pants_options_free_parser(handle) };
+}
+
+#[test]
+fn test_parse_error() {
+    let buildroot = TempDir::new().unwrap();
+    let config = buildroot.path().join("pants.toml");
+    fs::write(&config, "[GLOBAL\n").unwrap();
+    let error =
+        parse(json!({"config_files": [config], "buildroot": buildroot.path()})).unwrap_err();
+    assert!(error["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Failed to parse config file"));
+
+    let mut error = ptr::null_mut();
+    let handle = unsafe { pants_options_parse(request(json!("args")).as_ptr(), &mut error) };
+    assert!(handle.is_null());
+    assert_eq!(
+        json!({"error": "Invalid request: invalid type: string \"args\", expected struct ParseRequest at line 1 column 6"}),
+        response(error)
+    );
+}
+
+#[test]
+fn test_validate() {
+    let buildroot = TempDir::new().unwrap();
+    let config = buildroot.path().join("pants.toml");
+    fs::write(
+        &config,
+        "[GLOBAL]\nlevel = 'warn'\nlevle = 'warn'\n\n[mypy-plugin]\nfoo = 1\n\n[unknown]\nbar = 2\n",
+    )
+    .unwrap();
+    let handle = parse(json!({"config_files": [config], "buildroot": buildroot.path()})).unwrap();
+
+    let validation = response(unsafe {
+        pants_options_validate(
+            handle,
+            request(json!({"sections": {"GLOBAL": ["level"]}, "allow": ["mypy-*"]})).as_ptr(),
+        )
+    });
+    assert_eq!(
+        json!({
+            "errors": [
+                format!("Invalid option 'levle' under [GLOBAL] in {}", config.display()),
+                format!("Invalid section [unknown] in {}", config.display()),
+            ],
+            "warnings": [],
+        }),
+        validation
+    );
+    unsafe { pants_options_free_parser(handle) };
+}
@@ -0,0 +1,130 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The metadata of a file that is needed to read it as a fromfile.
+#[derive(Clone, Debug)]
+pub struct FileMetadata {
+    pub len: u64,
+    pub is_dir: bool,
+    // The modification time of the file, if known, which allows its content to be cached.
+    pub modified: Option<SystemTime>,
+}
+
+///
+/// The files that config files and fromfiles are read from. By default this is the local
+/// filesystem, but an embedder without one, such as a browser-based playground compiled to wasm32,
+/// may provide the files in memory instead via `OptionParser::new_with_filesystem`.
+///
+pub trait FileSystem: Debug + Send + Sync {
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata>;
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Whether the path exists and is not a directory.
+    fn is_file(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|metadata| !metadata.is_dir)
+    }
+
+    /// Opens a file to be read incrementally, e.g. a line at a time.
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(Cursor::new(self.read(path)?)))
+    }
+
+    /// The target of a symlink, which is only used to explain errors.
+    fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// The path with any symlinks resolved.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LocalFileSystem;
+
+impl FileSystem for LocalFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let metadata = fs::metadata(path)?;
+        Ok(FileMetadata {
+            len: metadata.len(),
+            is_dir: metadata.is_dir(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+}
+
+///
+/// A filesystem of files held in memory, keyed by their full path (i.e. including the build root
+/// for relative fromfiles). It has no directories, other than those implied by the paths of its
+/// files.
+///
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    files: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(mut self, path: impl Into<PathBuf>, content: impl Into<Vec<u8>>) -> Self {
+        self.files.insert(path.into(), content.into());
+        self
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        if let Some(content) = self.files.get(path) {
+            return Ok(FileMetadata {
+                len: content.len() as u64,
+                is_dir: false,
+                modified: None,
+            });
+        }
+        if self.files.keys().any(|file| file.starts_with(path)) {
+            return Ok(FileMetadata {
+                len: 0,
+                is_dir: true,
+                modified: None,
+            });
+        }
+        Err(io::ErrorKind::NotFound.into())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.metadata(path)? {
+            FileMetadata { is_dir: true, .. } => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{} is a directory", path.display()),
+            )),
+            _ => Ok(self.files[path].clone()),
+        }
+    }
+}
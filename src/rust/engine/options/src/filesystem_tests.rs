@@ -0,0 +1,127 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::tests;
+use crate::{
+    option_id, Args, BuildRoot, ConfigSource, Env, FileSystem, MemoryFileSystem, OptionParser,
+};
+
+fn parser(config: &str, fs: MemoryFileSystem) -> OptionParser {
+    let config = ConfigSource::from_bytes("/repo/pants.toml", config.as_bytes().to_vec()).unwrap();
+    let mut parser = tests::parser_in(BuildRoot::for_path("/repo".into()), &[], &[], vec![config]);
+    parser.set_filesystem(Arc::new(fs));
+    parser
+}
+
+#[test]
+fn test_memory_filesystem_metadata() {
+    let fs = MemoryFileSystem::new().file("/repo/src/args.txt", "-v");
+    let file = fs.metadata(Path::new("/repo/src/args.txt")).unwrap();
+    assert_eq!((2, false), (file.len, file.is_dir));
+    assert!(file.modified.is_none());
+    assert!(fs.metadata(Path::new("/repo/src")).unwrap().is_dir);
+    assert_eq!(
+        std::io::ErrorKind::NotFound,
+        fs.metadata(Path::new("/repo/other")).unwrap_err().kind()
+    );
+    assert_eq!(
+        "/repo is a directory",
+        fs.read(Path::new("/repo")).unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn test_fromfiles_read_from_filesystem() {
+    let parser = parser(
+        "[GLOBAL]\nname = '@name.txt'\nitems = '@lists/items.json'\nmissing = '@?missing.txt'\n",
+        MemoryFileSystem::new()
+            .file("/repo/name.txt", "in memory")
+            .file("/repo/lists/items.json", r#"["a", "b"]"#),
+    );
+    assert_eq!(
+        "in memory",
+        parser
+            .parse_string(&option_id!("name"), "default")
+            .unwrap()
+            .value
+    );
+    assert_eq!(
+        vec!["a".to_owned(), "b".to_owned()],
+        parser
+            .parse_string_list(&option_id!("items"), vec![])
+            .unwrap()
+            .value
+    );
+    assert_eq!(
+        "default",
+        parser
+            .parse_string(&option_id!("missing"), "default")
+            .unwrap()
+            .value
+    );
+}
+
+#[test]
+fn test_fromfile_errors_from_filesystem() {
+    let parser = parser(
+        "[GLOBAL]\nname = '@missing.txt'\ndir = '@lists'\n",
+        MemoryFileSystem::new().file("/repo/lists/items.json", "[]"),
+    );
+    let err = parser
+        .parse_string(&option_id!("name"), "default")
+        .unwrap_err();
+    assert!(err.contains("/repo/missing.txt"), "{err}");
+    let err = parser
+        .parse_string(&option_id!("dir"), "default")
+        .unwrap_err();
+    assert!(err.contains("/repo/lists"), "{err}");
+}
+
+#[test]
+fn test_config_files_discovered_in_filesystem() {
+    let parser = OptionParser::new_with_filesystem(
+        Args::new(vec![]),
+        Env::new(HashMap::new()),
+        None,
+        false,
+        false,
+        Some(BuildRoot::for_path("/repo".into())),
+        Arc::new(
+            MemoryFileSystem::new()
+                .file(
+                    "/repo/pants.toml",
+                    "[GLOBAL]\nname = 'repo'\nlevel = 'info'\n",
+                )
+                .file("/repo/pants.local.toml", "[GLOBAL]\nlevel = 'debug'\n")
+                .file("/repo/src/pants.toml", "[scope]\nname = 'nested'\n"),
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        "repo",
+        parser
+            .parse_string(&option_id!("name"), "default")
+            .unwrap()
+            .value
+    );
+    assert_eq!(
+        "debug",
+        parser
+            .parse_string(&option_id!("level"), "warn")
+            .unwrap()
+            .value
+    );
+    assert_eq!(
+        "nested",
+        parser
+            .for_directory(Path::new("src"))
+            .unwrap()
+            .parse_string(&option_id!(["scope"], "name"), "default")
+            .unwrap()
+            .value
+    );
+}
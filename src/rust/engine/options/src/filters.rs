@@ -0,0 +1,127 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use lazy_static::lazy_static;
+
+use crate::parse::Parseable;
+use crate::{ListEdit, ListEditAction};
+
+///
+/// A transform of the value of a placeholder, as in `%(name|upper)s`. It is passed the value and
+/// the argument of the filter, if any, e.g. the `,` of `%(items|join:,)s`.
+///
+pub(crate) type Filter = Box<dyn Fn(&str, Option<&str>) -> Result<String, String> + Send + Sync>;
+
+lazy_static! {
+    pub(crate) static ref BUILTIN_FILTERS: Filters = Filters::builtin();
+}
+
+fn no_arg(
+    name: &'static str,
+    f: impl Fn(&str) -> String + Send + Sync + 'static,
+) -> (&'static str, Filter) {
+    (
+        name,
+        Box::new(move |value, arg| match arg {
+            None => Ok(f(value)),
+            Some(_) => Err(format!("The `{name}` filter takes no argument")),
+        }),
+    )
+}
+
+fn required_arg<'a>(name: &str, arg: Option<&'a str>) -> Result<&'a str, String> {
+    arg.ok_or_else(|| format!("The `{name}` filter requires an argument, as in `{name}:<arg>`"))
+}
+
+// Parses the value as a list of strings, e.g. `['a', 'b']` as written for a string array in TOML.
+fn parse_string_list(value: &str) -> Result<Vec<String>, String> {
+    match String::parse_list(value).as_deref() {
+        Ok(
+            [ListEdit {
+                action: ListEditAction::Replace,
+                items,
+            }],
+        ) => Ok(items.clone()),
+        _ => Err(format!(
+            "The `join` filter expects a list, such as `['a', 'b']`, but got `{value}`"
+        )),
+    }
+}
+
+///
+/// The filters that may be applied to the values of placeholders, by name.
+///
+pub(crate) struct Filters {
+    filters: HashMap<&'static str, Filter>,
+}
+
+impl Filters {
+    pub(crate) fn builtin() -> Self {
+        let mut filters = Self {
+            filters: HashMap::new(),
+        };
+        for (name, filter) in [
+            no_arg("upper", str::to_uppercase),
+            no_arg("lower", str::to_lowercase),
+            no_arg("trim", |value| value.trim().to_owned()),
+            no_arg("dirname", |value| {
+                Path::new(value)
+                    .parent()
+                    .map(|parent| parent.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            }),
+            no_arg("basename", |value| {
+                Path::new(value)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            }),
+        ] {
+            filters.register(name, filter);
+        }
+        filters.register(
+            "join",
+            Box::new(|value, arg| {
+                let separator = required_arg("join", arg)?;
+                Ok(parse_string_list(value)?.join(separator))
+            }),
+        );
+        filters.register(
+            "replace",
+            Box::new(|value, arg| {
+                let (from, to) = required_arg("replace", arg)?.split_once(':').ok_or(
+                    "The `replace` filter requires two arguments, as in `replace:<from>:<to>`",
+                )?;
+                Ok(value.replace(from, to))
+            }),
+        );
+        filters
+    }
+
+    pub(crate) fn register(&mut self, name: &'static str, filter: Filter) {
+        self.filters.insert(name, filter);
+    }
+
+    ///
+    /// Applies a chain of filters, as written after the name of a placeholder, e.g. the
+    /// `trim|join:,` of `%(items|trim|join:,)s`.
+    ///
+    pub(crate) fn apply(&self, value: &str, chain: &str) -> Result<String, String> {
+        let mut value = value.to_owned();
+        for filter in chain.split('|') {
+            let (name, arg) = match filter.split_once(':') {
+                Some((name, arg)) => (name, Some(arg)),
+                None => (filter, None),
+            };
+            let f = self
+                .filters
+                .get(name)
+                .ok_or_else(|| format!("Unknown filter `{name}`"))?;
+            value = f(&value, arg)?;
+        }
+        Ok(value)
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+
+use crate::filters::{Filters, BUILTIN_FILTERS};
+use crate::interpolation::{InterpolationMap, Interpolator};
+
+fn apply(value: &str, chain: &str) -> Result<String, String> {
+    BUILTIN_FILTERS.apply(value, chain)
+}
+
+#[test]
+fn test_builtin_filters() {
+    assert_eq!(Ok("ABC".to_owned()), apply("abc", "upper"));
+    assert_eq!(Ok("abc".to_owned()), apply("AbC", "lower"));
+    assert_eq!(Ok("a b".to_owned()), apply("  a b\n", "trim"));
+    assert_eq!(
+        Ok("src/python".to_owned()),
+        apply("src/python/BUILD", "dirname")
+    );
+    assert_eq!(Ok("".to_owned()), apply("BUILD", "dirname"));
+    assert_eq!(
+        Ok("BUILD".to_owned()),
+        apply("src/python/BUILD", "basename")
+    );
+    assert_eq!(Ok("a,b".to_owned()), apply("['a', \"b\"]", "join:,"));
+    assert_eq!(Ok("a b".to_owned()), apply("['a', 'b']", "join: "));
+    assert_eq!(Ok("1_2_3".to_owned()), apply("1.2.3", "replace:.:_"));
+    assert_eq!(
+        Ok("A_B".to_owned()),
+        apply(" a-b ", "trim|upper|replace:-:_")
+    );
+}
+
+#[test]
+fn test_filter_errors() {
+    assert_eq!(
+        Err("Unknown filter `shout`".to_owned()),
+        apply("a", "upper|shout")
+    );
+    assert_eq!(
+        Err("The `upper` filter takes no argument".to_owned()),
+        apply("a", "upper:x")
+    );
+    assert_eq!(
+        Err("The `join` filter requires an argument, as in `join:<arg>`".to_owned()),
+        apply("['a']", "join")
+    );
+    assert_eq!(
+        Err("The `join` filter expects a list, such as `['a', 'b']`, but got `a`".to_owned()),
+        apply("a", "join:,")
+    );
+    assert_eq!(
+        Err("The `replace` filter requires two arguments, as in `replace:<from>:<to>`".to_owned()),
+        apply("a", "replace:a")
+    );
+}
+
+#[test]
+fn test_interpolation() {
+    let replacements: InterpolationMap = [
+        ("name", "pants"),
+        ("loud", "%(name|upper)s!"),
+        ("items", "['x', 'y']"),
+        ("path", "dist/%(name)s/out.txt"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_owned(), v.to_owned()))
+    .collect::<HashMap<_, _>>();
+    let mut interpolator = Interpolator::new(&replacements);
+    // Filters apply to each use of a placeholder, but not to its own value.
+    assert_eq!(
+        "PANTS! pants x+y dist/pants",
+        interpolator
+            .interpolate("%(loud)s %(name)s %(items|join:+)s %(path|dirname)s")
+            .unwrap()
+    );
+    assert_eq!(
+        "PANTS!! DIST/PANTS/OUT.TXT",
+        interpolator
+            .interpolate("%(loud|replace:!:!!)s %(path|upper)s")
+            .unwrap()
+    );
+    assert_eq!(
+        "Failed to apply the filters of placeholder `name|join:,`: The `join` filter expects a \
+        list, such as `['a', 'b']`, but got `pants`",
+        interpolator.interpolate("%(name|join:,)s").unwrap_err()
+    );
+
+    // The registry of filters is extensible.
+    let mut filters = Filters::builtin();
+    filters.register(
+        "reverse",
+        Box::new(|value, _| Ok(value.chars().rev().collect())),
+    );
+    assert_eq!(
+        Ok("STNAP".to_owned()),
+        filters.apply("pants", "reverse|upper")
+    );
+}
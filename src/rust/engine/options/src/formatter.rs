@@ -0,0 +1,345 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt::Write;
+
+use toml::Value;
+
+// The lexical state carried from one line of a TOML document to the next.
+#[derive(Clone, Copy, Default)]
+struct ScanState {
+    // The nesting depth of arrays and inline tables.
+    depth: usize,
+    in_multiline_basic: bool,
+    in_multiline_literal: bool,
+}
+
+impl ScanState {
+    fn is_complete(&self) -> bool {
+        self.depth == 0 && !self.in_multiline_basic && !self.in_multiline_literal
+    }
+
+    ///
+    /// Advances the state over the given line, returning the byte offset of the comment on the
+    /// line, if any.
+    ///
+    fn scan(&mut self, line: &str) -> Option<usize> {
+        let bytes = line.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let rest = &line[i..];
+            if self.in_multiline_basic {
+                if rest.starts_with('\\') {
+                    i += 2;
+                    continue;
+                }
+                if rest.starts_with("\"\"\"") {
+                    self.in_multiline_basic = false;
+                    i += 3;
+                    continue;
+                }
+            } else if self.in_multiline_literal {
+                if rest.starts_with("'''") {
+                    self.in_multiline_literal = false;
+                    i += 3;
+                    continue;
+                }
+            } else if rest.starts_with("\"\"\"") {
+                self.in_multiline_basic = true;
+                i += 3;
+                continue;
+            } else if rest.starts_with("'''") {
+                self.in_multiline_literal = true;
+                i += 3;
+                continue;
+            } else {
+                match bytes[i] {
+                    b'"' => {
+                        i += 1;
+                        while i < bytes.len() && bytes[i] != b'"' {
+                            i += if bytes[i] == b'\\' { 2 } else { 1 };
+                        }
+                    }
+                    b'\'' => {
+                        i += 1;
+                        while i < bytes.len() && bytes[i] != b'\'' {
+                            i += 1;
+                        }
+                    }
+                    b'[' | b'{' => self.depth += 1,
+                    b']' | b'}' => self.depth = self.depth.saturating_sub(1),
+                    b'#' => return Some(i),
+                    _ => (),
+                }
+            }
+            i += 1;
+        }
+        None
+    }
+}
+
+// Finds the `=` of a `key = value` line, outside of any quoted key.
+fn find_equals(code: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in code.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '=') => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+struct Entry {
+    comments: Vec<String>,
+    key: String,
+    // The value, and for values that span multiple lines, its subsequent lines verbatim.
+    value: String,
+    continuation: Vec<String>,
+    comment: Option<String>,
+}
+
+impl Entry {
+    // Sorts an option's `.add` and `.remove` edits right after any plain value for it.
+    fn sort_key(&self) -> (&str, usize, &str) {
+        let (base, suffix) = if self.key.starts_with(['"', '\'']) {
+            (self.key.as_str(), "")
+        } else {
+            self.key
+                .split_once('.')
+                .map(|(base, suffix)| (base.trim(), suffix.trim()))
+                .unwrap_or((self.key.as_str(), ""))
+        };
+        let rank = match suffix {
+            "" => 0,
+            "add" => 1,
+            "remove" => 2,
+            _ => 3,
+        };
+        (base, rank, suffix)
+    }
+}
+
+struct Section {
+    comments: Vec<String>,
+    // None for the entries before the first header, which are kept first.
+    header: Option<String>,
+    header_comment: Option<String>,
+    entries: Vec<Entry>,
+    trailing_comments: Vec<String>,
+}
+
+impl Section {
+    fn new(comments: Vec<String>, header: Option<String>, header_comment: Option<String>) -> Self {
+        Self {
+            comments,
+            header,
+            header_comment,
+            entries: vec![],
+            trailing_comments: vec![],
+        }
+    }
+
+    // Sorts DEFAULT, then GLOBAL, then other sections by name, with nested sections right after
+    // the sections that they are nested in.
+    fn sort_key(&self) -> (usize, Vec<String>) {
+        let Some(header) = &self.header else {
+            return (0, vec![]);
+        };
+        let name = header.trim_matches(['[', ']']).trim();
+        let components = if name.starts_with('"') {
+            vec![name.trim_matches('"').to_owned()]
+        } else {
+            name.split('.')
+                .map(|component| component.trim().trim_matches('"').to_owned())
+                .collect()
+        };
+        let rank = match components.first().map(String::as_str) {
+            Some("DEFAULT") => 1,
+            Some("GLOBAL") => 2,
+            _ => 3,
+        };
+        (rank, components)
+    }
+}
+
+///
+/// Quotes a string value canonically: strings in the edit syntax of list and dict values, e.g.
+/// `'+["a"]'`, and strings containing double quotes or backslashes, are written as literal strings
+/// where possible, so that they need no escaping. Other strings are written as basic strings.
+///
+fn quote_string(s: &str) -> String {
+    let trimmed = s.trim_start();
+    let is_edit = ["+[", "-[", "[", "{", "+{"]
+        .iter()
+        .any(|prefix| trimmed.starts_with(prefix));
+    let can_be_literal = !s.contains(['\'', '\n', '\r']) && !s.chars().any(char::is_control);
+    if can_be_literal && (is_edit || s.contains(['"', '\\'])) {
+        return format!("'{s}'");
+    }
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => write!(quoted, "\\u{:04X}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn normalize_value(value: &str) -> String {
+    match format!("v = {value}").parse::<Value>() {
+        Ok(Value::Table(t)) => match t.get("v") {
+            Some(Value::String(s)) => quote_string(s),
+            _ => value.to_owned(),
+        },
+        _ => value.to_owned(),
+    }
+}
+
+fn parse_sections(content: &str) -> Vec<Section> {
+    let mut sections = vec![Section::new(vec![], None, None)];
+    let mut comments = vec![];
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            comments.push(trimmed.to_owned());
+            continue;
+        }
+        let mut state = ScanState::default();
+        let comment_start = state.scan(line);
+        let code = comment_start.map_or(line, |i| &line[..i]).trim();
+        let comment = comment_start.map(|i| line[i..].trim().to_owned());
+        if code.starts_with('[') {
+            let header = if code.starts_with("[[") {
+                format!("[[{}]]", code.trim_matches(['[', ']']).trim())
+            } else {
+                format!("[{}]", code.trim_matches(['[', ']']).trim())
+            };
+            // Comments before the first header belong to the file rather than to the section.
+            let section_comments = if sections.len() == 1 && sections[0].entries.is_empty() {
+                sections[0].comments.append(&mut comments);
+                vec![]
+            } else {
+                std::mem::take(&mut comments)
+            };
+            sections.push(Section::new(section_comments, Some(header), comment));
+            continue;
+        }
+        let (key, value) = match find_equals(code) {
+            Some(i) => (code[..i].trim(), code[i + 1..].trim()),
+            None => (code, ""),
+        };
+        let mut continuation = vec![];
+        while !state.is_complete() {
+            let Some(next) = lines.next() else {
+                break;
+            };
+            state.scan(next);
+            continuation.push(next.trim_end().to_owned());
+        }
+        let (value, comment) = if continuation.is_empty() {
+            (normalize_value(value), comment)
+        } else {
+            // The comment is part of the first line of a multi-line value, so is kept verbatim.
+            let first_line = line.trim();
+            let value_start = first_line.find('=').map_or(0, |i| i + 1);
+            (first_line[value_start..].trim().to_owned(), None)
+        };
+        sections.last_mut().unwrap().entries.push(Entry {
+            comments: std::mem::take(&mut comments),
+            key: key.to_owned(),
+            value,
+            continuation,
+            comment,
+        });
+    }
+    sections.last_mut().unwrap().trailing_comments = comments;
+    sections
+}
+
+fn render(sections: &mut [Section]) -> String {
+    let mut output = String::new();
+    for section in sections.iter_mut() {
+        if section.header.is_none() && section.comments.is_empty() && section.entries.is_empty() {
+            continue;
+        }
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        for comment in &section.comments {
+            writeln!(output, "{comment}").unwrap();
+        }
+        if let Some(header) = &section.header {
+            match &section.header_comment {
+                Some(comment) => writeln!(output, "{header}  {comment}").unwrap(),
+                None => writeln!(output, "{header}").unwrap(),
+            }
+        }
+        section
+            .entries
+            .sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        let width = section
+            .entries
+            .iter()
+            .map(|entry| entry.key.chars().count())
+            .max()
+            .unwrap_or(0);
+        for entry in &section.entries {
+            for comment in &entry.comments {
+                writeln!(output, "{comment}").unwrap();
+            }
+            let line = format!("{:width$} = {}", entry.key, entry.value);
+            match &entry.comment {
+                Some(comment) => writeln!(output, "{line}  {comment}").unwrap(),
+                None => writeln!(output, "{}", line.trim_end()).unwrap(),
+            }
+            for continuation in &entry.continuation {
+                writeln!(output, "{continuation}").unwrap();
+            }
+        }
+        for comment in &section.trailing_comments {
+            writeln!(output, "{comment}").unwrap();
+        }
+    }
+    output
+}
+
+///
+/// Formats the content of a config file canonically, preserving its comments:
+///
+/// - Sections are ordered DEFAULT, then GLOBAL, then by name, with one blank line between them.
+/// - Keys are ordered by name within each section, with `.add` and `.remove` edits after any
+///   plain value of the same option, and their `=` aligned.
+/// - String values are quoted consistently (see `quote_string`).
+///
+/// Values that span multiple lines are preserved verbatim. Fails if the content is not valid
+/// TOML, or if (as a safeguard) the formatted content would not have the same meaning.
+///
+pub fn format_config(content: &str) -> Result<String, String> {
+    let original = content
+        .parse::<Value>()
+        .map_err(|e| format!("Failed to parse config for formatting: {e}"))?;
+    let mut sections = parse_sections(content);
+    // NB: The sort is stable, so the elements of arrays of tables retain their order.
+    sections.sort_by_key(Section::sort_key);
+    let formatted = render(&mut sections);
+    match formatted.parse::<Value>() {
+        Ok(value) if value == original => Ok(formatted),
+        _ => Err(
+            "Formatting would change the meaning of the config, so it was left as is.".to_owned(),
+        ),
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::format_config;
+
+#[test]
+fn test_format() {
+    let content = r#"# The repo's config.
+
+[test]
+timeout = 10
+extra_env_vars.remove = ["B"]  # Not needed.
+extra_env_vars.add = ["A"]
+# Keep tests fast.
+debug=false
+
+[GLOBAL]
+pants_version = '2.22.0'
+backend_packages = """+["pants.backend.python"]"""
+[DEFAULT]
+  name = "x\"y"
+[test.pytest]
+args = [
+  "-vv",  # Verbose.
+  "--no-header",
+]
+# Trailing comment.
+"#;
+    let expected = r#"# The repo's config.
+
+[DEFAULT]
+name = 'x"y'
+
+[GLOBAL]
+backend_packages = '+["pants.backend.python"]'
+pants_version    = "2.22.0"
+
+[test]
+# Keep tests fast.
+debug                 = false
+extra_env_vars.add    = ["A"]
+extra_env_vars.remove = ["B"]  # Not needed.
+timeout               = 10
+
+[test.pytest]
+args = [
+  "-vv",  # Verbose.
+  "--no-header",
+]
+# Trailing comment.
+"#;
+    let formatted = format_config(content).unwrap();
+    assert_eq!(expected, formatted);
+    // Formatting is idempotent.
+    assert_eq!(expected, format_config(&formatted).unwrap());
+}
+
+#[test]
+fn test_format_quoting() {
+    assert_eq!(
+        "[GLOBAL]\n\
+         a = \"it's\"\n\
+         b = '{\"k\": 1}'\n\
+         c = \"line\\nbreak\"\n\
+         d = \"plain\"\n\
+         e = 'C:\\dir'\n",
+        format_config(
+            "[GLOBAL]\n\
+             d = 'plain'\n\
+             c = \"line\\nbreak\"\n\
+             b = \"{\\\"k\\\": 1}\"\n\
+             a = \"it's\"\n\
+             e = \"C:\\\\dir\"\n"
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_format_invalid() {
+    assert!(format_config("[GLOBAL]\nname = \n")
+        .unwrap_err()
+        .starts_with("Failed to parse config for formatting: "));
+}
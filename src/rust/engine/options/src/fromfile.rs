@@ -1,49 +1,483 @@
 // Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-use super::{BuildRoot, DictEdit, DictEditAction, ListEdit, ListEditAction};
+use super::{BuildRoot, DictEdit, DictEditAction, ListEdit, ListEditAction, Val};
 
-use crate::parse::{mk_parse_err, parse_dict, ParseError, Parseable};
+use crate::base64;
+use crate::csv::parse_csv;
+use crate::filesystem::{FileMetadata, FileSystem, LocalFileSystem};
+
+use crate::parse::{
+    attribute_parse_err, mk_parse_err, parse_dict, unexpanded_fromfile_err, ParseError, Parseable,
+};
+use crate::remote::{is_remote, split_pin, RemoteError, RemoteFetcher};
+use crate::shell::run_with_timeout;
+use crate::snapshot::digest;
+use crate::stats::{Counter, ParseStats, Phase};
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
 use log::warn;
-use serde::de::Deserialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{BufRead, Read};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
 use std::{fs, io};
 
+/// The default maximum size of a fromfile, in bytes.
+pub(crate) const DEFAULT_MAX_FROMFILE_SIZE: u64 = 512 * 1024 * 1024;
+
+// The files read by a FromfileExpander, mapped to the digest of their content, or to None for
+// an optional (@?) fromfile that didn't exist.
+pub(crate) type ConsumedFiles = BTreeMap<PathBuf, Option<String>>;
+
+// The remote fromfiles fetched by a FromfileExpander, by their references as written, including
+// any pin (e.g. `sha256=<hex>+https://...`), mapped to the digest of their content, or to None for
+// an optional (@?) remote fromfile that was unavailable.
+pub(crate) type RemoteInputs = BTreeMap<String, Option<String>>;
+
 // If the corresponding unexpanded value points to a @fromfile, then the
 // first component is the path to that file, and the second is the value from the file,
 // or None if the file doesn't exist and the @?fromfile syntax was used.
 //
 // Otherwise, the first component is None and the second is the original value.
-type ExpandedValue = (Option<PathBuf>, Option<String>);
+type ExpandedValue = (Option<PathBuf>, Option<Content>);
+
+// The content of an expanded value. The content of a local fromfile is shared with the
+// process-wide cache, so that it is not copied for every value that refers to the fromfile.
+enum Content {
+    Owned(String),
+    Shared(Arc<str>),
+}
+
+impl Content {
+    fn into_string(self) -> String {
+        match self {
+            Content::Owned(content) => content,
+            Content::Shared(content) => content.to_string(),
+        }
+    }
+}
+
+impl std::ops::Deref for Content {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Content::Owned(content) => content,
+            Content::Shared(content) => content,
+        }
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq)]
 enum FromfileType {
     Json,
     Yaml,
+    // Newline-delimited items, for list options, unless written in list syntax.
+    Text,
+    // A header row and records, for list options.
+    Csv,
+    // Raw bytes, for bytes options.
+    Binary,
     Unknown,
 }
 
 impl FromfileType {
     fn detect(path: &Path) -> FromfileType {
         if let Some(ext) = path.extension() {
-            if ext == "json" {
+            if ext == "json" || ext == "json5" {
                 return FromfileType::Json;
             } else if ext == "yml" || ext == "yaml" {
                 return FromfileType::Yaml;
+            } else if ext == "txt" {
+                return FromfileType::Text;
+            } else if ext == "csv" {
+                return FromfileType::Csv;
+            } else if ext == "bin" {
+                return FromfileType::Binary;
             };
         }
         FromfileType::Unknown
     }
 }
 
-fn try_deserialize<'a, DE: Deserialize<'a>>(
-    value: &'a str,
+struct CachedFile {
+    modified: SystemTime,
+    len: u64,
+    content: Arc<str>,
+}
+
+lazy_static! {
+    // NB: This is shared by every parse in the process, e.g. by each run in pantsd.
+    static ref FROMFILE_CACHE: Mutex<HashMap<PathBuf, CachedFile>> = Mutex::default();
+}
+
+///
+/// Decodes the content of a file as UTF-8, dropping any leading byte order mark. Invalid content
+/// is reported along with the byte offset of its first invalid sequence, counted from `offset`.
+///
+pub(crate) fn decode_utf8(bytes: Vec<u8>, offset: u64) -> Result<String, String> {
+    let content = String::from_utf8(bytes).map_err(|e| {
+        format!(
+            "The file is not valid UTF-8: it has an invalid byte sequence at byte offset {}",
+            offset + e.utf8_error().valid_up_to() as u64
+        )
+    })?;
+    Ok(match content.strip_prefix('\u{feff}') {
+        Some(content) if offset == 0 => content.to_owned(),
+        _ => content,
+    })
+}
+
+///
+/// Reads a fromfile, or returns its content from the process-wide cache if its modification time
+/// and length are unchanged since it was cached under `key`. The second component is true for a
+/// cache hit.
+///
+/// A file that is rewritten with content of the same length within the granularity of its
+/// modification time will not be noticed: callers that watch files for changes may call
+/// `invalidate_fromfile_cache` to be certain. A file whose modification time is not known is
+/// never cached.
+///
+fn read_cached(
+    fs: &dyn FileSystem,
+    path: &Path,
+    key: &Path,
+    metadata: &FileMetadata,
+) -> io::Result<(Arc<str>, bool)> {
+    let read = || {
+        decode_utf8(fs.read(path)?, 0)
+            .map(Arc::from)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    };
+    let Some(modified) = metadata.modified else {
+        return Ok((read()?, false));
+    };
+    if let Some(cached) = FROMFILE_CACHE.lock().unwrap().get(key) {
+        if cached.modified == modified && cached.len == metadata.len {
+            return Ok((cached.content.clone(), true));
+        }
+    }
+    let content = read()?;
+    FROMFILE_CACHE.lock().unwrap().insert(
+        key.to_path_buf(),
+        CachedFile {
+            modified,
+            len: metadata.len,
+            content: Arc::clone(&content),
+        },
+    );
+    Ok((content, false))
+}
+
+///
+/// Evicts the given fromfiles from the process-wide cache of their content, or every fromfile if
+/// `paths` is None, so that they are read again when next used. A symlink is evicted along with
+/// the file that it resolves to.
+///
+pub fn invalidate_fromfile_cache(paths: Option<&[PathBuf]>) {
+    let mut cache = FROMFILE_CACHE.lock().unwrap();
+    match paths {
+        Some(paths) => {
+            for path in paths {
+                cache.remove(path);
+                if let Ok(resolved) = fs::canonicalize(path) {
+                    cache.remove(&resolved);
+                }
+            }
+        }
+        None => cache.clear(),
+    }
+}
+
+///
+/// Replaces the generic message of an error reading a fromfile with one that explains its cause,
+/// where it is a common one. The kind of the error is kept, so that e.g. an optional (@?) fromfile
+/// that is a dangling symlink is still treated as not existing.
+///
+fn explain_io_error(err: io::Error, path: &Path, fs: &dyn FileSystem) -> io::Error {
+    let message = match err.kind() {
+        io::ErrorKind::PermissionDenied => {
+            "Permission denied: the file is not readable by the current user".to_owned()
+        }
+        io::ErrorKind::NotFound => match fs.read_link(path) {
+            Ok(target) => format!(
+                "The file is a dangling symlink to {}, which does not exist",
+                target.display()
+            ),
+            Err(_) => return err,
+        },
+        _ => return err,
+    };
+    io::Error::new(err.kind(), message)
+}
+
+// Whether the content of a list fromfile is written in the syntax of a list value, such as
+// `['a', 'b']` or `+['a']`, rather than as newline-delimited items.
+fn is_list_syntax(line: &str) -> bool {
+    let line = line.trim_start();
+    let line = line.strip_prefix(['+', '-']).unwrap_or(line);
+    line.starts_with(['[', '('])
+}
+
+///
+/// Whether the value reads stdin, as in `@-`, or with a hint of its format, as in `@-.json`.
+///
+pub(crate) fn is_stdin(value: &str) -> bool {
+    value
+        .strip_prefix("@-")
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+///
+/// Whether the path is an absolute Windows path: i.e. it starts with a drive letter, as in
+/// `C:\path\to\file` or `C:/path/to/file`, or it is a UNC path, as in `\\server\share\file`.
+///
+pub(crate) fn is_windows_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with(r"\\")
+        || (bytes.len() > 2
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'\\' | b'/'))
+}
+
+///
+/// The path of a fromfile, which may be written as a Windows path, relative to the build root.
+/// Backslashes separate the components of the path on every platform, so that the same config
+/// can be shared, and an absolute Windows path is not joined to the build root.
+///
+pub(crate) fn fromfile_path(build_root: &Path, path: &str) -> PathBuf {
+    if cfg!(windows) {
+        return build_root.join(path);
+    }
+    let native = PathBuf::from(path.replace('\\', "/"));
+    if is_windows_absolute(path) {
+        native
+    } else {
+        build_root.join(native)
+    }
+}
+
+// The fromfiles that a value concatenates, as in `@a.txt+@b.txt`, if it refers to several.
+fn concatenated_fromfiles(value: &str) -> Option<Vec<String>> {
+    let suffix = value.strip_prefix('@')?;
+    if suffix.starts_with('@') {
+        return None;
+    }
+    let fromfiles = suffix
+        .split("+@")
+        .map(|fromfile| format!("@{fromfile}"))
+        .collect::<Vec<_>>();
+    (fromfiles.len() > 1).then_some(fromfiles)
+}
+
+// Runs the script from the given directory, returning its stdout.
+fn run_script(script: &Path, cwd: &Path, timeout: Duration) -> Result<String, String> {
+    let mut command = Command::new(script);
+    if !cwd.as_os_str().is_empty() {
+        command.current_dir(cwd);
+    }
+    let stdout =
+        run_with_timeout(command, timeout).map_err(|e| format!("The executable fromfile {e}"))?;
+    decode_utf8(stdout, 0)
+        .map_err(|e| e.replacen("The file is", "The output of the executable fromfile is", 1))
+}
+
+///
+/// Parses the items of a list from CSV content. For a list of dicts, the header row holds the keys
+/// of each dict, and each record holds their (string) values. For a list of any other type, the
+/// content must have a single column, and each record holds an item.
+///
+fn csv_items<T: Parseable>(content: &str, path: &Path) -> Result<Vec<T>, ParseError> {
+    let mut records = parse_csv(content)
+        .map_err(|e| mk_parse_err(e, path))?
+        .into_iter();
+    let Some((_, header)) = records.next() else {
+        return Ok(vec![]);
+    };
+    let is_dict = T::OPTION_TYPE == <IndexMap<String, Val> as Parseable>::OPTION_TYPE;
+    if !is_dict && header.len() != 1 {
+        return Err(mk_parse_err(
+            format!(
+                "A CSV fromfile for a {} list must have a single column, but has {}: {}",
+                T::OPTION_TYPE,
+                header.len(),
+                header.join(", ")
+            ),
+            path,
+        ));
+    }
+    records
+        .map(|(line, record)| {
+            if record.len() != header.len() {
+                return Err(mk_parse_err(
+                    format!(
+                        "The record on line {line} has {} fields, but the header has {}",
+                        record.len(),
+                        header.len()
+                    ),
+                    path,
+                ));
+            }
+            if is_dict {
+                let dict = header
+                    .iter()
+                    .cloned()
+                    .zip(record.into_iter().map(serde_json::Value::String))
+                    .collect::<serde_json::Map<_, _>>();
+                serde_json::from_value(serde_json::Value::Object(dict))
+                    .map_err(|e| mk_parse_err(e, path))
+            } else {
+                T::parse(&record[0]).map_err(|e| {
+                    mk_parse_err(format!("line {line}: {}", e.render("the item")), path)
+                })
+            }
+        })
+        .collect()
+}
+
+///
+/// Relaxes JSON content, as is commonly hand-edited, into strict JSON: `//` and `/* */` comments
+/// are blanked out, as are trailing commas in arrays and objects. Line and column numbers are
+/// preserved, so that errors in the relaxed content refer to its own positions.
+///
+pub(crate) fn relax_json(content: &str) -> String {
+    let mut relaxed = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    // The quote of the string that we are in, if any.
+    let mut quote = None;
+    // The position in `relaxed` of a comma which may turn out to be trailing.
+    let mut comma = None;
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            relaxed.push(c);
+            if c == '\\' {
+                relaxed.extend(chars.next());
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                relaxed.push(' ');
+                while let Some(c) = chars.next_if(|c| *c != '\n') {
+                    relaxed.push(if c == '\r' { c } else { ' ' });
+                }
+            }
+            ('/', Some('*')) => {
+                relaxed.push(' ');
+                let mut prev = None;
+                for c in chars.by_ref() {
+                    relaxed.push(if c == '\n' || c == '\r' { c } else { ' ' });
+                    if prev == Some('*') && c == '/' {
+                        break;
+                    }
+                    prev = Some(c);
+                }
+            }
+            (c, _) if c.is_whitespace() => relaxed.push(c),
+            (c, _) => {
+                if let (Some(pos), ']' | '}') = (comma, c) {
+                    relaxed.replace_range(pos..pos + 1, " ");
+                }
+                comma = (c == ',').then_some(relaxed.len());
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                relaxed.push(c);
+            }
+        }
+    }
+    relaxed
+}
+
+///
+/// Applies YAML merge keys (https://yaml.org/type/merge.html): the mappings that a `<<` key refers
+/// to are merged into the mapping containing it, with its own keys taking precedence, as do the
+/// keys of earlier mappings in a sequence of them. Merged mappings may themselves use merge keys.
+///
+fn merge_yaml_keys(value: &mut serde_yaml::Value) -> Result<(), String> {
+    use serde_yaml::Value;
+    match value {
+        Value::Mapping(mapping) => {
+            for value in mapping.values_mut() {
+                merge_yaml_keys(value)?;
+            }
+            let sources = match mapping.shift_remove("<<") {
+                None => vec![],
+                Some(Value::Mapping(source)) => vec![source],
+                Some(Value::Sequence(sources)) => sources
+                    .into_iter()
+                    .map(|source| match source {
+                        Value::Mapping(source) => Ok(source),
+                        _ => Err(()),
+                    })
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| {
+                        "Each value merged with a `<<` key must be a mapping.".to_owned()
+                    })?,
+                Some(_) => {
+                    return Err(
+                        "The value of a `<<` key must be a mapping, or a sequence of \
+                                mappings."
+                            .to_owned(),
+                    )
+                }
+            };
+            for source in sources {
+                for (key, value) in source {
+                    mapping.entry(key).or_insert(value);
+                }
+            }
+        }
+        Value::Sequence(values) => {
+            for value in values {
+                merge_yaml_keys(value)?;
+            }
+        }
+        Value::Tagged(tagged) => merge_yaml_keys(&mut tagged.value)?,
+        _ => (),
+    }
+    Ok(())
+}
+
+fn deserialize_yaml<DE: DeserializeOwned>(value: &str) -> Result<DE, String> {
+    let render = |e: serde_yaml::Error| {
+        // NB: An alias within its own anchor is only detected as unbounded nesting.
+        if e.to_string().starts_with("recursion limit exceeded") {
+            format!("{e}: an alias may not refer to an anchor that contains it")
+        } else {
+            e.to_string()
+        }
+    };
+    // Deserializing via a `serde_yaml::Value` loses the positions of type errors, so we only do so
+    // when there may be merge keys to apply.
+    if !value.contains("<<") {
+        return serde_yaml::from_str(value).map_err(render);
+    }
+    let mut yaml = serde_yaml::from_str(value).map_err(render)?;
+    merge_yaml_keys(&mut yaml)?;
+    serde_yaml::from_value(yaml).map_err(render)
+}
+
+fn try_deserialize<DE: DeserializeOwned>(
+    value: &str,
     path_opt: Option<PathBuf>,
 ) -> Result<Option<DE>, ParseError> {
     if let Some(path) = path_opt {
         match FromfileType::detect(&path) {
-            FromfileType::Json => serde_json::from_str(value).map_err(|e| mk_parse_err(e, &path)),
-            FromfileType::Yaml => serde_yaml::from_str(value).map_err(|e| mk_parse_err(e, &path)),
+            // NB: Strict JSON is parsed as is, so that relaxing it is only paid for when needed.
+            FromfileType::Json => serde_json::from_str(value)
+                .or_else(|_| serde_json::from_str(&relax_json(value)))
+                .map_err(|e| mk_parse_err(e, &path)),
+            FromfileType::Yaml => deserialize_yaml(value).map_err(|e| mk_parse_err(e, &path)),
             _ => Ok(None),
         }
     } else {
@@ -51,9 +485,32 @@ fn try_deserialize<'a, DE: Deserialize<'a>>(
     }
 }
 
+// NB: Clones share the record of consumed files, remote inputs and volatile inputs, the fetcher of
+// remote fromfiles, the timeout of executable fromfiles, the output of
+// executable fromfiles, the content of stdin, the maximum size of fromfiles, and the statistics of
+// the parse.
 #[derive(Clone, Debug)]
 pub struct FromfileExpander {
     build_root: BuildRoot,
+    consumed: Arc<Mutex<ConsumedFiles>>,
+    remote: Arc<RwLock<Option<RemoteFetcher>>>,
+    remote_inputs: Arc<Mutex<RemoteInputs>>,
+    // The inputs whose content cannot be checked later, e.g. the output of executable fromfiles.
+    volatile_inputs: Arc<Mutex<BTreeSet<String>>>,
+    fs: Arc<RwLock<Arc<dyn FileSystem>>>,
+    // How long an executable fromfile may run, if they are enabled, and the output of each one
+    // that has been run.
+    script_timeout: Arc<Mutex<Option<Duration>>>,
+    executed: Arc<Mutex<HashMap<PathBuf, String>>>,
+    max_size: Arc<AtomicU64>,
+    // Whether fromfiles are recorded, and cached, by the paths that their symlinks resolve to.
+    resolve_symlinks: Arc<AtomicBool>,
+    // Whether fromfiles are expanded, rather than taken literally, as by a bootstrap parse.
+    expand: bool,
+    // Whether `@-` reads stdin, and its content once it has been read.
+    stdin: bool,
+    stdin_content: Arc<Mutex<Option<String>>>,
+    stats: ParseStats,
 }
 
 impl FromfileExpander {
@@ -61,62 +518,614 @@ impl FromfileExpander {
     pub fn relative_to(build_root: BuildRoot) -> Self {
         Self {
             build_root: build_root,
+            consumed: Arc::default(),
+            remote: Arc::default(),
+            remote_inputs: Arc::default(),
+            volatile_inputs: Arc::default(),
+            fs: Arc::new(RwLock::new(Arc::new(LocalFileSystem))),
+            script_timeout: Arc::default(),
+            executed: Arc::default(),
+            max_size: Arc::new(AtomicU64::new(DEFAULT_MAX_FROMFILE_SIZE)),
+            resolve_symlinks: Arc::default(),
+            expand: true,
+            stdin: false,
+            stdin_content: Arc::default(),
+            stats: ParseStats::default(),
         }
     }
 
+    ///
+    /// Enables `@https://...` fromfiles, fetched via the given fetcher. Remote fromfiles are
+    /// an error until then.
+    ///
+    pub(crate) fn set_remote(&self, remote: RemoteFetcher) {
+        *self.remote.write().unwrap() = Some(remote.stats(self.stats.clone()));
+    }
+
+    pub(crate) fn remote(&self) -> Option<RemoteFetcher> {
+        self.remote.read().unwrap().clone()
+    }
+
+    /// Reads local fromfiles from the given filesystem, rather than the local filesystem.
+    pub(crate) fn set_filesystem(&self, fs: Arc<dyn FileSystem>) {
+        *self.fs.write().unwrap() = fs;
+    }
+
+    pub(crate) fn filesystem(&self) -> Arc<dyn FileSystem> {
+        self.fs.read().unwrap().clone()
+    }
+
+    ///
+    /// Returns a clone of this expander for which `@-` reads stdin, rather than a file named `-`.
+    /// This is only appropriate for flags, as stdin may only be read once.
+    ///
+    pub(crate) fn allowing_stdin(&self) -> Self {
+        Self {
+            stdin: true,
+            ..self.clone()
+        }
+    }
+
+    ///
+    /// Returns a clone of this expander which doesn't read any file, script or URL that a value
+    /// refers to. A bootstrap parse reads only a few options, which it needn't pay to expand. As
+    /// taking such a value literally would give the option a wrong value, it is an error instead.
+    ///
+    pub(crate) fn literal(&self) -> Self {
+        Self {
+            expand: false,
+            ..self.clone()
+        }
+    }
+
+    // The fromfiles that a value concatenates, if it is to be expanded.
+    fn concatenated(&self, value: &str) -> Option<Vec<String>> {
+        concatenated_fromfiles(value).filter(|_| self.expand)
+    }
+
+    // Provides the content of stdin, rather than reading it. Useful in tests.
+    #[cfg(test)]
+    pub(crate) fn set_stdin(&self, content: &str) {
+        *self.stdin_content.lock().unwrap() = Some(content.to_owned());
+    }
+
+    ///
+    /// Enables executable fromfiles, e.g. `@!script.sh`, each of which is killed if it runs for
+    /// longer than the given timeout. They are an error until then.
+    ///
+    pub(crate) fn set_script_timeout(&self, timeout: Duration) {
+        *self.script_timeout.lock().unwrap() = Some(timeout);
+    }
+
+    /// Local fromfiles larger than the given number of bytes are an error.
+    pub(crate) fn set_max_size(&self, max_size: u64) {
+        self.max_size.store(max_size, Ordering::Relaxed);
+    }
+
+    ///
+    /// Whether a fromfile that is a symlink is recorded in the consumed files, and cached, by the
+    /// path that it resolves to, so that a change to the file that it links to is noticed. Errors
+    /// still report the path that the fromfile was given as.
+    ///
+    pub(crate) fn set_resolve_symlinks(&self, resolve_symlinks: bool) {
+        self.resolve_symlinks
+            .store(resolve_symlinks, Ordering::Relaxed);
+    }
+
+    // The path that a fromfile is recorded and cached by.
+    fn resolved(&self, path: &Path) -> PathBuf {
+        if self.resolve_symlinks.load(Ordering::Relaxed) {
+            self.filesystem()
+                .canonicalize(path)
+                .unwrap_or_else(|_| path.to_path_buf())
+        } else {
+            path.to_path_buf()
+        }
+    }
+
+    /// The directory that relative fromfile paths are relative to.
+    pub(crate) fn build_root(&self) -> &Path {
+        &self.build_root
+    }
+
+    /// The statistics of the parse that this expander reads fromfiles for.
+    pub(crate) fn stats(&self) -> &ParseStats {
+        &self.stats
+    }
+
     // Creates a FromfileExpander that treats relpaths as relative to the CWD.
     // Useful in tests.
     #[cfg(test)]
     pub(crate) fn relative_to_cwd() -> Self {
-        Self {
-            build_root: BuildRoot::for_path(PathBuf::from("")),
+        Self::relative_to(BuildRoot::for_path(PathBuf::from("")))
+    }
+
+    pub(crate) fn consumed_files(&self) -> ConsumedFiles {
+        self.consumed.lock().unwrap().clone()
+    }
+
+    pub(crate) fn remote_inputs(&self) -> RemoteInputs {
+        self.remote_inputs.lock().unwrap().clone()
+    }
+
+    pub(crate) fn volatile_inputs(&self) -> BTreeSet<String> {
+        self.volatile_inputs.lock().unwrap().clone()
+    }
+
+    // Returns the metadata of a local fromfile, which is an error if the file is too large.
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let fs = self.filesystem();
+        let metadata = fs
+            .metadata(path)
+            .map_err(|e| explain_io_error(e, path, fs.as_ref()))?;
+        if metadata.is_dir {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "The path is a directory, not a file",
+            ));
+        }
+        let max_size = self.max_size.load(Ordering::Relaxed);
+        if metadata.len > max_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "The file is {} bytes, which exceeds the maximum of {max_size} bytes set by \
+                    `[GLOBAL].fromfile_max_size`",
+                    metadata.len
+                ),
+            ));
         }
+        Ok(metadata)
+    }
+
+    pub(crate) fn read(&self, path: &Path) -> io::Result<Arc<str>> {
+        let fs = self.filesystem();
+        let (content, hit) = read_cached(
+            fs.as_ref(),
+            path,
+            &self.resolved(path),
+            &self.metadata(path)?,
+        )
+        .map_err(|e| explain_io_error(e, path, fs.as_ref()))?;
+        if hit {
+            self.stats.increment(Counter::FromfileCacheHits, 1);
+        }
+        Ok(content)
+    }
+
+    fn record(&self, path: &Path, content: Option<&str>) {
+        self.record_digest(
+            path,
+            content.map(|c| (c.len() as u64, digest(c.as_bytes()))),
+        );
+    }
+
+    // Records a fromfile by its length and digest, or as not existing.
+    fn record_digest(&self, path: &Path, read: Option<(u64, String)>) {
+        if let Some((len, _)) = &read {
+            self.stats.increment(Counter::FromfilesRead, 1);
+            self.stats.increment(Counter::FromfileBytesRead, *len);
+        }
+        self.consumed
+            .lock()
+            .unwrap()
+            .insert(self.resolved(path), read.map(|(_, digest)| digest));
+    }
+
+    // Records a remote fromfile, by its reference as written, by the digest of its content, or as
+    // unavailable.
+    fn record_remote(&self, target: &str, content: Option<&str>) {
+        if let Some(content) = content {
+            self.stats.increment(Counter::FromfilesRead, 1);
+            self.stats
+                .increment(Counter::FromfileBytesRead, content.len() as u64);
+        }
+        self.remote_inputs.lock().unwrap().insert(
+            target.to_owned(),
+            content.map(|content| digest(content.as_bytes())),
+        );
     }
 
     fn maybe_expand(&self, value: String) -> Result<ExpandedValue, ParseError> {
+        if !self.expand {
+            return match value.strip_prefix('@') {
+                Some(suffix) if suffix.starts_with('@') => {
+                    Ok((None, Some(Content::Owned(suffix.to_owned()))))
+                }
+                Some(_) => Err(unexpanded_fromfile_err(&value)),
+                None => Ok((None, Some(Content::Owned(value)))),
+            };
+        }
         if let Some(suffix) = value.strip_prefix('@') {
             if suffix.starts_with('@') {
                 // @@ escapes the initial @.
-                Ok((None, Some(suffix.to_owned())))
+                Ok((None, Some(Content::Owned(suffix.to_owned()))))
             } else {
+                let _span = self.stats.span(Phase::FromfileRead);
                 match suffix.strip_prefix('?') {
+                    Some(subsuffix) if is_remote(subsuffix) => self.fetch(subsuffix, true),
                     Some(subsuffix) => {
                         // @? means the path is allowed to not exist.
-                        let path = self.build_root.join(subsuffix);
-                        match fs::read_to_string(&path) {
-                            Ok(content) => Ok((Some(path), Some(content))),
+                        let path = fromfile_path(&self.build_root, subsuffix);
+                        match self.read(&path) {
+                            Ok(content) => {
+                                self.record(&path, Some(&content));
+                                Ok((Some(path), Some(Content::Shared(content))))
+                            }
                             Err(err) if err.kind() == io::ErrorKind::NotFound => {
                                 warn!("Optional file config '{}' does not exist.", path.display());
+                                self.record(&path, None);
                                 Ok((Some(path), None))
                             }
                             Err(err) => Err(mk_parse_err(err, &path)),
                         }
                     }
+                    None if suffix.starts_with('!') => self.execute(&suffix[1..]),
+                    _ if self.stdin && is_stdin(&value) => self.read_stdin(&suffix[1..]),
+                    _ if is_remote(suffix) => self.fetch(suffix, false),
                     _ => {
-                        let path = self.build_root.join(suffix);
-                        let content =
-                            fs::read_to_string(&path).map_err(|e| mk_parse_err(e, &path))?;
-                        Ok((Some(path), Some(content)))
+                        let path = fromfile_path(&self.build_root, suffix);
+                        let content = self.read(&path).map_err(|e| mk_parse_err(e, &path))?;
+                        self.record(&path, Some(&content));
+                        Ok((Some(path), Some(Content::Shared(content))))
                     }
                 }
             }
         } else {
-            Ok((None, Some(value)))
+            Ok((None, Some(Content::Owned(value))))
+        }
+    }
+
+    ///
+    /// Fetches a remote fromfile, e.g. `@https://example.com/args.json`. The URL stands in for the
+    /// path of the fromfile to detect its format. It is recorded among the remote inputs, rather
+    /// than the consumed files, as it is not on the filesystem.
+    ///
+    /// The content may be pinned to its sha256 digest, as in `@sha256=<hex>+https://...`, so that
+    /// it cannot change silently. Content which does not match its pin is an error, even for an
+    /// optional (@?) fromfile.
+    ///
+    fn fetch(&self, target: &str, optional: bool) -> Result<ExpandedValue, ParseError> {
+        let (sha256, url) = split_pin(target);
+        let path = PathBuf::from(url);
+        // NB: The fetcher is cloned, so that its lock is not held while fetching.
+        let fetched = match self.remote() {
+            Some(remote) => remote.fetch(url, sha256),
+            None => Err(RemoteError::Unsupported {
+                url: url.to_owned(),
+            }),
+        };
+        match fetched {
+            Ok(content) => {
+                self.record_remote(target, Some(&content));
+                Ok((Some(path), Some(Content::Owned(content))))
+            }
+            // NB: Content that is yet to be prefetched is not known to be unavailable.
+            Err(
+                err @ (RemoteError::DigestMismatch { .. }
+                | RemoteError::InvalidPin { .. }
+                | RemoteError::NotPrefetched { .. }),
+            ) => Err(mk_parse_err(err, &path)),
+            Err(err) if optional => {
+                warn!("Optional remote config '{url}' is unavailable: {err}");
+                self.record_remote(target, None);
+                Ok((Some(path), None))
+            }
+            Err(err) => Err(mk_parse_err(err, &path)),
+        }
+    }
+
+    ///
+    /// Reads stdin, at most once, e.g. for `@-` or `@-.json`. The hint, such as `.json`, stands in
+    /// for the extension of a path to detect the format of the content.
+    ///
+    fn read_stdin(&self, hint: &str) -> Result<ExpandedValue, ParseError> {
+        // NB: A path that doesn't exist is recorded, so that a snapshot is never fresh.
+        let path = PathBuf::from(format!("<stdin>{hint}"));
+        let mut stdin_content = self.stdin_content.lock().unwrap();
+        if stdin_content.is_none() {
+            let mut content = vec![];
+            io::stdin()
+                .read_to_end(&mut content)
+                .map_err(|e| mk_parse_err(e, &path))?;
+            *stdin_content = Some(decode_utf8(content, 0).map_err(|e| mk_parse_err(e, &path))?);
+        }
+        let content = stdin_content.clone().unwrap();
+        self.record(&path, Some(&content));
+        Ok((Some(path), Some(Content::Owned(content))))
+    }
+
+    ///
+    /// Runs an executable fromfile, e.g. `@!resolves.json.sh`, from the build root, and takes the
+    /// value from its stdout. The format of the output may be hinted by an extension preceding the
+    /// script's own, such as the `.json` of `resolves.json.sh`. Each script is run at most once.
+    ///
+    /// Executable fromfiles must be enabled via `set_script_timeout`.
+    ///
+    fn execute(&self, script: &str) -> Result<ExpandedValue, ParseError> {
+        let path = fromfile_path(&self.build_root, script);
+        let Some(timeout) = *self.script_timeout.lock().unwrap() else {
+            return Err(mk_parse_err(
+                "Executable fromfiles are disabled. They can be enabled by \
+                `[GLOBAL].executable_fromfiles`, which may only be set by flags or the env.",
+                &path,
+            ));
+        };
+        let memoized = self.executed.lock().unwrap().get(&path).cloned();
+        let output = match memoized {
+            Some(output) => output,
+            None => {
+                let output = run_script(&path, &self.build_root, timeout)
+                    .map_err(|e| mk_parse_err(e, &path))?;
+                self.executed
+                    .lock()
+                    .unwrap()
+                    .insert(path.clone(), output.clone());
+                output
+            }
+        };
+        // NB: The output of a script may change when the script does not, so it is recorded as a
+        // volatile input rather than a consumed file.
+        self.stats.increment(Counter::FromfilesRead, 1);
+        self.stats
+            .increment(Counter::FromfileBytesRead, output.len() as u64);
+        self.volatile_inputs
+            .lock()
+            .unwrap()
+            .insert(path.display().to_string());
+        let format_path = match path.file_stem().map(Path::new) {
+            Some(stem) if stem.extension().is_some() => path.with_file_name(stem),
+            _ => path,
+        };
+        Ok((Some(format_path), Some(Content::Owned(output))))
+    }
+
+    ///
+    /// The paths of the files that the given value refers to which are required to exist: i.e.
+    /// `@path` or `@!script`, but not `@?path` or `@@escaped`, including each of several
+    /// concatenated fromfiles.
+    ///
+    pub(crate) fn required_paths(&self, value: &str) -> Vec<PathBuf> {
+        match concatenated_fromfiles(value) {
+            Some(fromfiles) => fromfiles
+                .iter()
+                .filter_map(|fromfile| self.required_path(fromfile))
+                .collect(),
+            None => self.required_path(value).into_iter().collect(),
+        }
+    }
+
+    // The path of the single file that the given value refers to, if it is required to exist.
+    pub(crate) fn required_path(&self, value: &str) -> Option<PathBuf> {
+        let suffix = value.strip_prefix('@')?;
+        let suffix = suffix.strip_prefix('!').unwrap_or(suffix);
+        if suffix.starts_with(['@', '?']) || is_remote(suffix) {
+            None
+        } else {
+            Some(fromfile_path(&self.build_root, suffix))
+        }
+    }
+
+    ///
+    /// Expands each of several concatenated fromfiles in order, attributing any error to the
+    /// fromfile that caused it. The items of every fromfile after the first are passed through
+    /// `then`, so that e.g. a later list replaces none of the items of an earlier one. Optional
+    /// fromfiles that don't exist are skipped.
+    ///
+    fn expand_each<E>(
+        &self,
+        fromfiles: Vec<String>,
+        expand: impl Fn(String) -> Result<Option<Vec<E>>, ParseError>,
+        then: impl Fn(E) -> E,
+    ) -> Result<Option<Vec<E>>, ParseError> {
+        let mut expanded: Option<Vec<E>> = None;
+        for fromfile in fromfiles {
+            let Some(items) =
+                expand(fromfile.clone()).map_err(|e| attribute_parse_err(e, &fromfile))?
+            else {
+                continue;
+            };
+            match &mut expanded {
+                Some(expanded) => expanded.extend(items.into_iter().map(&then)),
+                None => expanded = Some(items),
+            }
         }
+        Ok(expanded)
     }
 
+    ///
+    /// Expands a value. A value that concatenates several fromfiles, as in `@a.txt+@b.txt`,
+    /// expands to their concatenated content.
+    ///
     pub(crate) fn expand(&self, value: String) -> Result<Option<String>, ParseError> {
+        if let Some(fromfiles) = self.concatenated(&value) {
+            let contents =
+                self.expand_each(fromfiles, |f| Ok(self.expand(f)?.map(|c| vec![c])), |c| c)?;
+            return Ok(contents.map(|contents| contents.concat()));
+        }
         let (_, expanded_value) = self.maybe_expand(value)?;
-        Ok(expanded_value)
+        Ok(expanded_value.map(Content::into_string))
+    }
+
+    // The path of the local fromfile of the given type that the value refers to, if any, and
+    // whether it is optional.
+    fn local_fromfile(&self, value: &str, file_type: FromfileType) -> Option<(PathBuf, bool)> {
+        let suffix = value.strip_prefix('@').filter(|_| self.expand)?;
+        let (suffix, optional) = match suffix.strip_prefix('?') {
+            Some(subsuffix) => (subsuffix, true),
+            None => (suffix, false),
+        };
+        if suffix.starts_with(['@', '!']) || is_remote(suffix) || (self.stdin && is_stdin(value)) {
+            return None;
+        }
+        let path = fromfile_path(&self.build_root, suffix);
+        (FromfileType::detect(&path) == file_type).then_some((path, optional))
+    }
+
+    ///
+    /// Expands a value to bytes, for options that carry e.g. certificates or key material, which
+    /// need not be UTF-8. A local `.bin` fromfile is read as is, and the content of a `@base64:`
+    /// fromfile, as in `@base64:certs/ca.b64`, is decoded. Other values are expanded as usual, and
+    /// encoded as UTF-8.
+    ///
+    pub(crate) fn expand_to_bytes(&self, value: String) -> Result<Option<Vec<u8>>, ParseError> {
+        if let Some(fromfile) = value.strip_prefix("@base64:").filter(|_| self.expand) {
+            let (path_opt, content) = self.maybe_expand(format!("@{fromfile}"))?;
+            let path = path_opt.unwrap_or_else(|| PathBuf::from(fromfile));
+            return content
+                .map(|content| base64::decode(&content).map_err(|e| mk_parse_err(e, &path)))
+                .transpose();
+        }
+        let Some((path, optional)) = self.local_fromfile(&value, FromfileType::Binary) else {
+            return Ok(self.expand(value)?.map(String::into_bytes));
+        };
+        let _span = self.stats.span(Phase::FromfileRead);
+        match self
+            .metadata(&path)
+            .and_then(|_| self.filesystem().read(&path))
+        {
+            Ok(content) => {
+                self.record_digest(&path, Some((content.len() as u64, digest(&content))));
+                Ok(Some(content))
+            }
+            Err(err) if optional && err.kind() == io::ErrorKind::NotFound => {
+                warn!("Optional file config '{}' does not exist.", path.display());
+                self.record(&path, None);
+                Ok(None)
+            }
+            Err(err) => Err(mk_parse_err(err, &path)),
+        }
+    }
+
+    ///
+    /// Reads a `.txt` list fromfile a line at a time, without buffering it, and parses each line
+    /// as an item. Its items are added to the value, as for a single item. Content that is written
+    /// in list syntax, such as `['a', 'b']`, is instead parsed as a whole.
+    ///
+    /// Items are trimmed, and blank lines and lines starting with `#` are ignored, so that
+    /// hand-maintained files may be documented inline. A line ending with `\` continues onto the
+    /// next line.
+    ///
+    fn stream_list<T: Parseable>(
+        &self,
+        path: &Path,
+        optional: bool,
+    ) -> Result<Option<Vec<ListEdit<T>>>, ParseError> {
+        let _span = self.stats.span(Phase::FromfileRead);
+        let fs = self.filesystem();
+        let file = match self.metadata(path).and_then(|_| {
+            fs.open(path)
+                .map_err(|e| explain_io_error(e, path, fs.as_ref()))
+        }) {
+            Ok(file) => file,
+            Err(err) if optional && err.kind() == io::ErrorKind::NotFound => {
+                warn!("Optional file config '{}' does not exist.", path.display());
+                self.record(path, None);
+                return Ok(None);
+            }
+            Err(err) => return Err(mk_parse_err(err, path)),
+        };
+        let parse_item = |item: &str, line_number: usize| {
+            T::parse(item.trim()).map_err(|e| {
+                mk_parse_err(
+                    format!("line {line_number}: {}", e.render("the item")),
+                    path,
+                )
+            })
+        };
+        let mut reader = io::BufReader::new(file);
+        let mut hasher = Sha256::new();
+        let mut len = 0;
+        let mut items = vec![];
+        let mut line_bytes = vec![];
+        // An item that is continued onto following lines, and the line that it started on.
+        let mut item = String::new();
+        let mut item_line_number = 0;
+        for line_number in 1.. {
+            line_bytes.clear();
+            if reader
+                .read_until(b'\n', &mut line_bytes)
+                .map_err(|e| mk_parse_err(e, path))?
+                == 0
+            {
+                break;
+            }
+            hasher.update(&line_bytes);
+            let line_len = line_bytes.len() as u64;
+            let line = decode_utf8(std::mem::take(&mut line_bytes), len)
+                .map_err(|e| mk_parse_err(e, path))?;
+            len += line_len;
+            if item.is_empty() {
+                let trimmed = line.trim();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    continue;
+                }
+                if items.is_empty() && is_list_syntax(trimmed) {
+                    let mut rest = vec![];
+                    reader
+                        .read_to_end(&mut rest)
+                        .map_err(|e| mk_parse_err(e, path))?;
+                    hasher.update(&rest);
+                    let rest = decode_utf8(rest, len).map_err(|e| mk_parse_err(e, path))?;
+                    len += rest.len() as u64;
+                    self.record_digest(path, Some((len, hex::encode(hasher.finalize()))));
+                    return T::parse_list(&(line + &rest)).map(Some);
+                }
+                item_line_number = line_number;
+            }
+            let text = line.strip_suffix('\n').unwrap_or(&line);
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            match text.strip_suffix('\\') {
+                Some(continued) => item.push_str(continued),
+                None => {
+                    item.push_str(text);
+                    items.push(parse_item(&item, item_line_number)?);
+                    item.clear();
+                }
+            }
+        }
+        // The last line may end with a continuation.
+        if !item.is_empty() {
+            items.push(parse_item(&item, item_line_number)?);
+        }
+        self.record_digest(path, Some((len, hex::encode(hasher.finalize()))));
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Add,
+            items,
+        }]))
     }
 
     pub(crate) fn expand_to_list<T: Parseable>(
         &self,
         value: String,
     ) -> Result<Option<Vec<ListEdit<T>>>, ParseError> {
+        if let Some(fromfiles) = self.concatenated(&value) {
+            return self.expand_each(
+                fromfiles,
+                |f| self.expand_to_list(f),
+                |edit| match edit.action {
+                    ListEditAction::Replace => ListEdit {
+                        action: ListEditAction::Add,
+                        ..edit
+                    },
+                    _ => edit,
+                },
+            );
+        }
+        if let Some((path, optional)) = self.local_fromfile(&value, FromfileType::Text) {
+            return self.stream_list(&path, optional);
+        }
         let (path_opt, value_opt) = self.maybe_expand(value)?;
         if let Some(value) = value_opt {
-            if let Some(items) = try_deserialize(&value, path_opt)? {
+            if let Some(path) = path_opt
+                .as_ref()
+                .filter(|path| matches!(FromfileType::detect(path), FromfileType::Csv))
+            {
+                Ok(Some(vec![ListEdit {
+                    action: ListEditAction::Replace,
+                    items: csv_items(&value, path)?,
+                }]))
+            } else if let Some(items) = try_deserialize(&value, path_opt)? {
                 Ok(Some(vec![ListEdit {
                     action: ListEditAction::Replace,
                     items,
@@ -133,6 +1142,16 @@ impl FromfileExpander {
         &self,
         value: String,
     ) -> Result<Option<Vec<DictEdit>>, ParseError> {
+        if let Some(fromfiles) = self.concatenated(&value) {
+            return self.expand_each(
+                fromfiles,
+                |f| self.expand_to_dict(f),
+                |edit| DictEdit {
+                    action: DictEditAction::Add,
+                    ..edit
+                },
+            );
+        }
         let (path_opt, value_opt) = self.maybe_expand(value)?;
         if let Some(value) = value_opt {
             if let Some(items) = try_deserialize(&value, path_opt)? {
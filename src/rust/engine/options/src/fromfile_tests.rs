@@ -5,9 +5,10 @@ use crate::fromfile::test_util::write_fromfile;
 use crate::fromfile::*;
 use crate::parse::{ParseError, Parseable};
 use crate::{BuildRoot, DictEdit, DictEditAction, ListEdit, ListEditAction, Val};
-use maplit::hashmap;
-use std::collections::HashMap;
+use indexmap::{indexmap, IndexMap};
 use std::fmt::Debug;
+use std::path::PathBuf;
+use std::time::Duration;
 
 macro_rules! check_err {
     ($res:expr, $expected_suffix:expr $(,)?) => {
@@ -150,9 +151,13 @@ fn test_expand_fromfile_to_list() {
         "fromfile.yaml",
     );
 
-    do_test("-42", &[add(vec![-42])], "fromfile.txt");
-    do_test("[10, 12]", &[replace(vec![10, 12])], "fromfile.json");
-    do_test("- 22\n- 44\n", &[replace(vec![22, 44])], "fromfile.yaml");
+    do_test("-42", &[add(vec![-42_i64])], "fromfile.txt");
+    do_test("[10, 12]", &[replace(vec![10_i64, 12])], "fromfile.json");
+    do_test(
+        "- 22\n- 44\n",
+        &[replace(vec![22_i64, 44])],
+        "fromfile.yaml",
+    );
 
     do_test("-5.6", &[add(vec![-5.6])], "fromfile.txt");
     do_test("-[3.14]", &[remove(vec![3.14])], "fromfile.txt");
@@ -229,14 +234,14 @@ fn test_expand_fromfile_to_dict() {
         assert_eq!(*expected, res.unwrap().unwrap())
     }
 
-    fn add(items: HashMap<String, Val>) -> DictEdit {
+    fn add(items: IndexMap<String, Val>) -> DictEdit {
         return DictEdit {
             action: DictEditAction::Add,
             items,
         };
     }
 
-    fn replace(items: HashMap<String, Val>) -> DictEdit {
+    fn replace(items: IndexMap<String, Val>) -> DictEdit {
         return DictEdit {
             action: DictEditAction::Replace,
             items,
@@ -245,20 +250,20 @@ fn test_expand_fromfile_to_dict() {
 
     do_test(
         "{'FOO': 42}",
-        &replace(hashmap! {"FOO".to_string() => Val::Int(42),}),
+        &replace(indexmap! {"FOO".to_string() => Val::Int(42),}),
         "fromfile.txt",
     );
 
     do_test(
         "+{'FOO': [True, False]}",
-        &add(hashmap! {"FOO".to_string() => Val::List(vec![Val::Bool(true), Val::Bool(false)]),}),
+        &add(indexmap! {"FOO".to_string() => Val::List(vec![Val::Bool(true), Val::Bool(false)]),}),
         "fromfile.txt",
     );
 
-    let complex_obj = replace(hashmap! {
-    "FOO".to_string() => Val::Dict(hashmap! {
+    let complex_obj = replace(indexmap! {
+    "FOO".to_string() => Val::Dict(indexmap! {
         "BAR".to_string() => Val::Float(3.14),
-        "BAZ".to_string() => Val::Dict(hashmap! {
+        "BAZ".to_string() => Val::Dict(indexmap! {
             "QUX".to_string() => Val::Bool(true),
             "QUUX".to_string() => Val::List(vec![ Val::Int(1), Val::Int(2)])
         })
@@ -313,7 +318,634 @@ fn test_expand_fromfile_to_dict() {
     // Test an optional fromfile that does exist, to ensure we handle the `?` in this case.
     let res = expand_fromfile("{'FOO': 42}", "@?", "fromfile.txt");
     assert_eq!(
-        replace(hashmap! {"FOO".to_string() => Val::Int(42),}),
+        replace(indexmap! {"FOO".to_string() => Val::Int(42),}),
         res.unwrap().unwrap()
     );
 }
+
+#[test]
+fn test_executable_fromfile() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let write_script = |name: &str, body: &str| {
+        let path = tmpdir.path().join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    };
+    // Scripts run from the build root, at most once each.
+    write_script(
+        "count.sh",
+        "echo x >> count.txt && wc -l < count.txt | tr -d ' '",
+    );
+    write_script("resolves.json.sh", r#"echo '{"a": "a.lock"}'"#);
+    write_script("names.sh", "echo '+[\"c\"]'");
+    write_script("fail.sh", "echo secret; echo oops >&2; exit 2");
+    write_script("slow.sh", "sleep 5");
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_path_buf()));
+
+    // Executable fromfiles must be enabled.
+    check_err!(
+        expander.expand("@!count.sh".to_owned()),
+        "Executable fromfiles are disabled. They can be enabled by `[GLOBAL].executable_fromfiles`, \
+        which may only be set by flags or the env.",
+    );
+    expander.set_script_timeout(Duration::from_secs(10));
+    assert_eq!(
+        Ok(Some("1\n".to_owned())),
+        expander.expand("@!count.sh".to_owned())
+    );
+    assert_eq!(
+        Ok(Some("1\n".to_owned())),
+        expander.expand("@!count.sh".to_owned())
+    );
+    // The format of the output is hinted by the extension preceding the script's own.
+    assert_eq!(
+        Ok(Some(vec![DictEdit {
+            action: DictEditAction::Replace,
+            items: indexmap! {"a".to_owned() => Val::String("a.lock".into())},
+        }])),
+        expander.expand_to_dict("@!resolves.json.sh".to_owned())
+    );
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec!["c".to_owned()],
+        }])),
+        expander.expand_to_list::<String>("@!names.sh".to_owned())
+    );
+    check_err!(
+        expander.expand("@!fail.sh".to_owned()),
+        "The executable fromfile failed with exit status: 2: oops",
+    );
+    assert_eq!(
+        Some(tmpdir.path().join("names.sh")),
+        expander.required_path("@!names.sh")
+    );
+    // Their output may change when they do not, so they are volatile inputs rather than files.
+    assert!(expander
+        .volatile_inputs()
+        .contains(&tmpdir.path().join("resolves.json.sh").display().to_string()));
+    assert!(expander.consumed_files().is_empty());
+
+    expander.set_script_timeout(Duration::from_millis(100));
+    check_err!(
+        expander.expand("@!slow.sh".to_owned()),
+        "The executable fromfile timed out after 100ms.",
+    );
+}
+
+#[test]
+fn test_fromfile_cache() {
+    let (_tmpdir, path) = write_fromfile("constraints.txt", "a==1");
+    let set_modified = |time: std::time::SystemTime| {
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap()
+    };
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+    let expander = FromfileExpander::relative_to_cwd();
+    let cache_hits = || {
+        expander
+            .stats()
+            .snapshot()
+            .counter(crate::Counter::FromfileCacheHits)
+    };
+    let value = format!("@{}", path.display());
+
+    assert_eq!(Ok(Some("a==1".to_owned())), expander.expand(value.clone()));
+    assert_eq!(Ok(Some("a==1".to_owned())), expander.expand(value.clone()));
+    assert_eq!(1, cache_hits());
+
+    // A change to the length or the modification time of the file is noticed.
+    std::fs::write(&path, "a==10").unwrap();
+    assert_eq!(Ok(Some("a==10".to_owned())), expander.expand(value.clone()));
+
+    // But a change to neither is not, unless the file is invalidated.
+    std::fs::write(&path, "a==20").unwrap();
+    set_modified(modified);
+    assert_eq!(Ok(Some("a==20".to_owned())), expander.expand(value.clone()));
+    std::fs::write(&path, "a==30").unwrap();
+    set_modified(modified);
+    assert_eq!(Ok(Some("a==20".to_owned())), expander.expand(value.clone()));
+    invalidate_fromfile_cache(Some(std::slice::from_ref(&path)));
+    assert_eq!(Ok(Some("a==30".to_owned())), expander.expand(value.clone()));
+    assert_eq!(2, cache_hits());
+
+    // A hit shares the cached content, rather than copying it.
+    assert!(std::sync::Arc::ptr_eq(
+        &expander.read(&path).unwrap(),
+        &expander.read(&path).unwrap()
+    ));
+}
+
+#[test]
+fn test_text_list_fromfile() {
+    let (tmpdir, _) = write_fromfile("targets.txt", "src/a:a\r\nsrc/b\n\nsrc/c\n");
+    std::fs::write(tmpdir.path().join("ints.txt"), "1\n2\nthree\n").unwrap();
+    std::fs::write(
+        tmpdir.path().join("literal.txt"),
+        "# Removed.\n-[\n  1,\n]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        tmpdir.path().join("allowlist.txt"),
+        "# Reviewed by the security team.\n\
+        \n\
+        \x20 requests  \n\
+        --index-url=https://example.com/\\\n\
+        #simple\n\
+        \x20 # Pinned.\n\
+        six\\\n",
+    )
+    .unwrap();
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_path_buf()));
+
+    // Each line is an item, which is added to the value.
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec!["src/a:a".to_owned(), "src/b".to_owned(), "src/c".to_owned()],
+        }])),
+        expander.expand_to_list::<String>("@targets.txt".to_owned())
+    );
+    check_err!(
+        expander.expand_to_list::<i64>("@ints.txt".to_owned()),
+        "ints.txt for XXX: line 3: Problem parsing the item int value:\n1:three\n  ^\nExpected \
+        \"+\", \"-\" or ['0'..='9'] at line 1 column 1",
+    );
+    // Unless the file is written in list syntax.
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Remove,
+            items: vec![1],
+        }])),
+        expander.expand_to_list::<i64>("@literal.txt".to_owned())
+    );
+    assert_eq!(
+        Ok(None),
+        expander.expand_to_list::<String>("@?missing.txt".to_owned())
+    );
+    // Blank lines and comments are ignored, and lines may be continued.
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec![
+                "requests".to_owned(),
+                "--index-url=https://example.com/#simple".to_owned(),
+                "six".to_owned(),
+            ],
+        }])),
+        expander.expand_to_list::<String>("@allowlist.txt".to_owned())
+    );
+    let consumed = expander.consumed_files();
+    assert_eq!(
+        Some(&Some(crate::snapshot::digest(
+            b"src/a:a\r\nsrc/b\n\nsrc/c\n"
+        ))),
+        consumed.get(&tmpdir.path().join("targets.txt"))
+    );
+
+    expander.set_max_size(10);
+    check_err!(
+        expander.expand_to_list::<String>("@targets.txt".to_owned()),
+        "The file is 22 bytes, which exceeds the maximum of 10 bytes set by \
+        `[GLOBAL].fromfile_max_size`",
+    );
+    check_err!(
+        expander.expand("@targets.txt".to_owned()),
+        "The file is 22 bytes, which exceeds the maximum of 10 bytes set by \
+        `[GLOBAL].fromfile_max_size`",
+    );
+}
+
+#[test]
+fn test_csv_fromfile() {
+    let (tmpdir, _) = write_fromfile(
+        "owners.csv",
+        "path,team\nsrc/python,\"build, infra\"\nsrc/rust,engine\n",
+    );
+    std::fs::write(tmpdir.path().join("ports.csv"), "port\n80\n443\n").unwrap();
+    std::fs::write(tmpdir.path().join("ragged.csv"), "a,b\n1\n").unwrap();
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_path_buf()));
+
+    // The header row holds the keys of each dict.
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![
+                indexmap! {
+                    "path".to_owned() => Val::String("src/python".into()),
+                    "team".to_owned() => Val::String("build, infra".into()),
+                },
+                indexmap! {
+                    "path".to_owned() => Val::String("src/rust".into()),
+                    "team".to_owned() => Val::String("engine".into()),
+                },
+            ],
+        }])),
+        expander.expand_to_list::<IndexMap<String, Val>>("@owners.csv".to_owned())
+    );
+    // Other lists take their items from a single column.
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![80, 443],
+        }])),
+        expander.expand_to_list::<i64>("@ports.csv".to_owned())
+    );
+    check_err!(
+        expander.expand_to_list::<String>("@owners.csv".to_owned()),
+        "A CSV fromfile for a string list must have a single column, but has 2: path, team",
+    );
+    check_err!(
+        expander.expand_to_list::<IndexMap<String, Val>>("@ragged.csv".to_owned()),
+        "The record on line 2 has 1 fields, but the header has 2",
+    );
+}
+
+#[test]
+fn test_windows_fromfile_paths() {
+    assert!(is_windows_absolute(r"C:\path\to\file"));
+    assert!(is_windows_absolute("c:/path/to/file"));
+    assert!(is_windows_absolute(r"\\server\share\file"));
+    assert!(!is_windows_absolute(r"path\to\file"));
+    assert!(!is_windows_absolute("C:file"));
+    assert!(!is_windows_absolute("/path/to/file"));
+
+    let (tmpdir, _) = write_fromfile("unused.txt", "");
+    std::fs::create_dir(tmpdir.path().join("dir")).unwrap();
+    std::fs::write(tmpdir.path().join("dir").join("a.txt"), "a\n").unwrap();
+    std::fs::write(tmpdir.path().join("dir").join("b.txt"), "b\n").unwrap();
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_path_buf()));
+
+    // Backslashes separate the components of a relative path.
+    assert_eq!(
+        Ok(Some("a\n".to_owned())),
+        expander.expand(r"@dir\a.txt".to_owned())
+    );
+    assert_eq!(
+        Ok(Some("a\nb\n".to_owned())),
+        expander.expand(r"@dir\a.txt+@dir\b.txt".to_owned())
+    );
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec!["a".to_owned()],
+        }])),
+        expander.expand_to_list::<String>(r"@?dir\a.txt".to_owned())
+    );
+    assert_eq!(Ok(None), expander.expand(r"@?dir\missing.txt".to_owned()));
+
+    // Absolute Windows paths are not relative to the build root.
+    if !cfg!(windows) {
+        assert_eq!(
+            Some(PathBuf::from("C:/path/to/file.txt")),
+            expander.required_path(r"@C:\path\to\file.txt")
+        );
+        assert_eq!(
+            Some(PathBuf::from("//server/share/file.txt")),
+            expander.required_path(r"@\\server\share\file.txt")
+        );
+        assert_eq!(
+            vec![
+                PathBuf::from("C:/a.txt"),
+                tmpdir.path().join("dir").join("b.txt")
+            ],
+            expander.required_paths(r"@C:\a.txt+@dir\b.txt")
+        );
+    }
+}
+
+#[test]
+fn test_concatenated_fromfiles() {
+    let (tmpdir, _) = write_fromfile("a.txt", "a\n");
+    std::fs::write(tmpdir.path().join("b.json"), r#"["b", "c"]"#).unwrap();
+    std::fs::write(tmpdir.path().join("c.txt"), "-['a']").unwrap();
+    std::fs::write(tmpdir.path().join("d.json"), r#"{"x": 1, "y": 2}"#).unwrap();
+    std::fs::write(tmpdir.path().join("e.yaml"), "y: 3\n").unwrap();
+    std::fs::write(tmpdir.path().join("bad.json"), "[").unwrap();
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_path_buf()));
+
+    assert_eq!(
+        Ok(Some("a\n[\"b\", \"c\"]".to_owned())),
+        expander.expand("@a.txt+@b.json".to_owned())
+    );
+    // Lists are concatenated in order: later fromfiles add to earlier ones, rather than replacing
+    // them, but may still remove items.
+    assert_eq!(
+        Ok(Some(vec![
+            ListEdit {
+                action: ListEditAction::Replace,
+                items: vec!["b".to_owned(), "c".to_owned()],
+            },
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec!["a".to_owned()],
+            },
+            ListEdit {
+                action: ListEditAction::Remove,
+                items: vec!["a".to_owned()],
+            },
+        ])),
+        expander.expand_to_list::<String>("@b.json+@?missing.txt+@a.txt+@c.txt".to_owned())
+    );
+    // Dicts are merged in order.
+    assert_eq!(
+        Ok(Some(vec![
+            DictEdit {
+                action: DictEditAction::Replace,
+                items: indexmap! {"x".to_owned() => Val::Int(1), "y".to_owned() => Val::Int(2)},
+            },
+            DictEdit {
+                action: DictEditAction::Add,
+                items: indexmap! {"y".to_owned() => Val::Int(3)},
+            },
+        ])),
+        expander.expand_to_dict("@d.json+@e.yaml".to_owned())
+    );
+    // Errors are attributed to the fromfile that caused them.
+    check_err!(
+        expander.expand_to_list::<String>("@a.txt+@bad.json".to_owned()),
+        "EOF while parsing a list at line 1 column 1 (from `@bad.json`)",
+    );
+    assert_eq!(
+        vec![tmpdir.path().join("a.txt"), tmpdir.path().join("b.json")],
+        expander.required_paths("@a.txt+@?c.txt+@b.json")
+    );
+}
+
+#[test]
+fn test_relaxed_json_fromfile() {
+    let content = r#"{
+  // The resolve for tools.
+  "tools": "tools.lock", /* "python": "python.lock", */
+  "url": "https://example.com/a//b",
+}
+"#;
+    let (_tmpdir, fromfile_pathbuf) = write_fromfile("resolves.json5", content);
+    assert_eq!(
+        Ok(Some(vec![DictEdit {
+            action: DictEditAction::Replace,
+            items: indexmap! {
+                "tools".to_owned() => Val::String("tools.lock".into()),
+                "url".to_owned() => Val::String("https://example.com/a//b".into()),
+            },
+        }])),
+        expand_to_dict(format!("@{}", fromfile_pathbuf.display()))
+    );
+
+    let (_tmpdir, fromfile_pathbuf) = write_fromfile("list.json", "[\n  1,\n  2, // two\n]\n");
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![1, 2],
+        }])),
+        expand_to_list::<i64>(format!("@{}", fromfile_pathbuf.display()))
+    );
+
+    // Positions in errors refer to the relaxed content as written.
+    let (_tmpdir, fromfile_pathbuf) = write_fromfile("bad.json", "[\n  1, /* one */ 2 3,\n]\n");
+    check_err!(
+        expand_to_list::<i64>(format!("@{}", fromfile_pathbuf.display())),
+        "expected `,` or `]` at line 2 column 18",
+    );
+
+    assert_eq!("{     \"a\": 1   \n}", relax_json("{/* */\"a\": 1,//\n}"));
+}
+
+#[test]
+fn test_yaml_merge_keys() {
+    let content = r#"
+base: &base
+  image: python
+  env: [CI]
+pinned: &pinned
+  <<: *base
+  version: "3.11"
+jobs:
+  test:
+    <<: [*pinned, {image: ignored, retries: 2}]
+    env: [CI, TEST]
+  lint: *base
+"#;
+    let (_tmpdir, fromfile_pathbuf) = write_fromfile("jobs.yaml", content);
+    let edits = expand_to_dict(format!("@{}", fromfile_pathbuf.display()))
+        .unwrap()
+        .unwrap();
+    let env = |items: &[&str]| Val::List(items.iter().map(|i| Val::String((*i).into())).collect());
+    let base = indexmap! {
+        "image".to_owned() => Val::String("python".into()),
+        "env".to_owned() => env(&["CI"]),
+    };
+    let mut pinned = base.clone();
+    pinned.insert("version".to_owned(), Val::String("3.11".into()));
+    assert_eq!(
+        vec![DictEdit {
+            action: DictEditAction::Replace,
+            items: indexmap! {
+                "base".to_owned() => Val::Dict(base.clone()),
+                "pinned".to_owned() => Val::Dict(pinned),
+                "jobs".to_owned() => Val::Dict(indexmap! {
+                    "test".to_owned() => Val::Dict(indexmap! {
+                        "env".to_owned() => env(&["CI", "TEST"]),
+                        "image".to_owned() => Val::String("python".into()),
+                        "version".to_owned() => Val::String("3.11".into()),
+                        "retries".to_owned() => Val::Int(2),
+                    }),
+                    "lint".to_owned() => Val::Dict(base),
+                }),
+            },
+        }],
+        edits
+    );
+
+    let (_tmpdir, fromfile_pathbuf) = write_fromfile("cycle.yaml", "a: &a\n  b: *a\n");
+    check_err!(
+        expand_to_dict(format!("@{}", fromfile_pathbuf.display())),
+        "an alias may not refer to an anchor that contains it",
+    );
+    let (_tmpdir, fromfile_pathbuf) = write_fromfile("scalar.yaml", "a:\n  <<: 1\n");
+    check_err!(
+        expand_to_dict(format!("@{}", fromfile_pathbuf.display())),
+        "The value of a `<<` key must be a mapping, or a sequence of mappings.",
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_fromfile_io_errors() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let (tmpdir, _) = write_fromfile("unused.txt", "");
+    std::fs::create_dir(tmpdir.path().join("dir.txt")).unwrap();
+    symlink(
+        tmpdir.path().join("missing.txt"),
+        tmpdir.path().join("dangling.txt"),
+    )
+    .unwrap();
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_path_buf()));
+
+    check_err!(
+        expander.expand("@dir.txt".to_owned()),
+        "The path is a directory, not a file",
+    );
+    check_err!(
+        expander.expand_to_list::<String>("@dir.txt".to_owned()),
+        "The path is a directory, not a file",
+    );
+    check_err!(
+        expander.expand("@dangling.txt".to_owned()),
+        &format!(
+            "The file is a dangling symlink to {}, which does not exist",
+            tmpdir.path().join("missing.txt").display()
+        ),
+    );
+    // An optional fromfile that is a dangling symlink does not exist.
+    assert_eq!(Ok(None), expander.expand("@?dangling.txt".to_owned()));
+
+    let unreadable = tmpdir.path().join("unreadable.txt");
+    std::fs::write(&unreadable, "x").unwrap();
+    std::fs::set_permissions(&unreadable, std::fs::Permissions::from_mode(0o000)).unwrap();
+    // NB: Permissions are not enforced for a privileged user.
+    if std::fs::read(&unreadable).is_err() {
+        check_err!(
+            expander.expand("@unreadable.txt".to_owned()),
+            "Permission denied: the file is not readable by the current user",
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_resolve_symlinks() {
+    use std::os::unix::fs::symlink;
+
+    let (tmpdir, _) = write_fromfile("target.txt", "a\n");
+    let root = tmpdir.path().canonicalize().unwrap();
+    symlink(root.join("target.txt"), root.join("link.txt")).unwrap();
+
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(root.clone()));
+    assert_eq!(
+        Ok(Some("a\n".to_owned())),
+        expander.expand("@link.txt".to_owned())
+    );
+    assert_eq!(
+        vec![root.join("link.txt")],
+        expander.consumed_files().into_keys().collect::<Vec<_>>()
+    );
+
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(root.clone()));
+    expander.set_resolve_symlinks(true);
+    assert_eq!(
+        Ok(Some("a\n".to_owned())),
+        expander.expand("@link.txt".to_owned())
+    );
+    expander
+        .expand_to_list::<String>("@?missing.txt".to_owned())
+        .unwrap();
+    assert_eq!(
+        vec![root.join("missing.txt"), root.join("target.txt")],
+        expander.consumed_files().into_keys().collect::<Vec<_>>()
+    );
+    // Errors still refer to the path that the fromfile was given as.
+    std::fs::write(root.join("target.txt"), [0xff]).unwrap();
+    let err = expander
+        .expand("@link.txt".to_owned())
+        .unwrap_err()
+        .render("XXX");
+    assert!(
+        err.starts_with(&format!(
+            "Problem reading {}",
+            root.join("link.txt").display()
+        )),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_bytes_fromfile() {
+    let (tmpdir, _) = write_fromfile("ca.b64", "AP/+\nZm9v\n");
+    std::fs::write(tmpdir.path().join("key.bin"), b"\x00\xff\xfe").unwrap();
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_path_buf()));
+
+    assert_eq!(
+        Ok(Some(b"\x00\xff\xfefoo".to_vec())),
+        expander.expand_to_bytes("@base64:ca.b64".to_owned())
+    );
+    assert_eq!(
+        Ok(Some(b"\x00\xff\xfe".to_vec())),
+        expander.expand_to_bytes("@key.bin".to_owned())
+    );
+    assert_eq!(
+        Ok(Some(b"plain".to_vec())),
+        expander.expand_to_bytes("plain".to_owned())
+    );
+    assert_eq!(
+        Ok(None),
+        expander.expand_to_bytes("@?missing.bin".to_owned())
+    );
+    assert_eq!(
+        Ok(None),
+        expander.expand_to_bytes("@base64:?missing.b64".to_owned())
+    );
+    assert!(expander
+        .consumed_files()
+        .contains_key(&tmpdir.path().join("key.bin")));
+
+    // Bytes that aren't UTF-8 can't be read as a string.
+    assert!(expander.expand("@key.bin".to_owned()).is_err());
+    check_err!(
+        expander.expand_to_bytes("@base64:key.bin".to_owned()),
+        "The file is not valid UTF-8: it has an invalid byte sequence at byte offset 1",
+    );
+    std::fs::write(tmpdir.path().join("bad.b64"), "Zm9v!").unwrap();
+    check_err!(
+        expander.expand_to_bytes("@base64:bad.b64".to_owned()),
+        "Invalid base64 character `!` at offset 4",
+    );
+}
+
+#[test]
+fn test_unicode_fromfile() {
+    let (tmpdir, _) = write_fromfile("targets.txt", "\u{feff}src/a\r\n# Comment\r\nsrc/b\r\n");
+    std::fs::write(
+        tmpdir.path().join("dict.json"),
+        "\u{feff}{\r\n  \"a\": \"ü\"\r\n}\r\n",
+    )
+    .unwrap();
+    std::fs::write(tmpdir.path().join("latin1.txt"), b"caf\xe9\n").unwrap();
+    std::fs::write(tmpdir.path().join("latin1_items.txt"), b"a\nb\ncaf\xe9\n").unwrap();
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_path_buf()));
+
+    // A byte order mark is dropped, and CRLF line endings are handled.
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec!["src/a".to_owned(), "src/b".to_owned()],
+        }])),
+        expander.expand_to_list::<String>("@targets.txt".to_owned())
+    );
+    assert_eq!(
+        Ok(Some(vec![DictEdit {
+            action: DictEditAction::Replace,
+            items: indexmap! {"a".to_owned() => Val::String("ü".into())},
+        }])),
+        expander.expand_to_dict("@dict.json".to_owned())
+    );
+
+    // Content that isn't UTF-8 is reported with the offset of the first invalid byte.
+    check_err!(
+        expander.expand("@latin1.txt".to_owned()),
+        "latin1.txt for XXX: The file is not valid UTF-8: it has an invalid byte sequence at \
+        byte offset 3",
+    );
+    check_err!(
+        expander.expand_to_list::<String>("@latin1_items.txt".to_owned()),
+        "latin1_items.txt for XXX: The file is not valid UTF-8: it has an invalid byte sequence \
+        at byte offset 7",
+    );
+}
@@ -0,0 +1,119 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+
+use crate::id::Scope;
+use crate::registry::{OptionInfo, OptionRegistry};
+use crate::spelling::Spellings;
+
+/// The help for a single option, in the form shown by `--help`.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct OptionHelp {
+    /// The flags that set the option, e.g. `--test-debug` and `--no-test-debug`.
+    pub flags: Vec<String>,
+    pub env_var: String,
+    pub config_key: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub default: String,
+    pub choices: Vec<String>,
+    pub help: String,
+    pub group: Option<String>,
+}
+
+impl OptionHelp {
+    pub(crate) fn new(option: &OptionInfo) -> Self {
+        let spellings = Spellings::new(option);
+        Self {
+            env_var: spellings.env_var().to_owned(),
+            flags: spellings.flags,
+            config_key: spellings.config_key,
+            type_name: option.option_type.type_name().to_owned(),
+            default: option.rendered_default(),
+            choices: option.choices.clone(),
+            help: option.help.clone(),
+            group: option.group.clone(),
+        }
+    }
+}
+
+///
+/// The help for the options of a single scope, split into basic and advanced options, each
+/// listed by group as for `OptionRegistry::groups_in`.
+///
+/// The `Display` impl renders the table shown by `--help`, while `to_json` renders a document for
+/// consumption by tools.
+///
+#[derive(Debug, Serialize)]
+pub struct ScopeHelp {
+    pub scope: String,
+    pub basic: Vec<OptionHelp>,
+    pub advanced: Vec<OptionHelp>,
+    // Whether advanced options are rendered by the `Display` impl.
+    #[serde(skip)]
+    show_advanced: bool,
+}
+
+impl ScopeHelp {
+    pub fn new(registry: &OptionRegistry, scope: &Scope, show_advanced: bool) -> Self {
+        let (advanced, basic): (Vec<_>, Vec<_>) = registry
+            .groups_in(scope)
+            .into_iter()
+            .flat_map(|(_, options)| options)
+            .partition(|option| option.advanced);
+        Self {
+            scope: scope.name().to_owned(),
+            basic: basic.into_iter().map(OptionHelp::new).collect(),
+            advanced: advanced.into_iter().map(OptionHelp::new).collect(),
+            show_advanced,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize help for [{}]: {e}", self.scope))
+    }
+
+    fn fmt_options(f: &mut Formatter<'_>, options: &[OptionHelp]) -> fmt::Result {
+        if options.is_empty() {
+            return writeln!(f, "  (none)");
+        }
+        let mut group = None;
+        for option in options {
+            if option.group.is_some() && option.group != group {
+                group = option.group.clone();
+                writeln!(f)?;
+                writeln!(f, "  {}:", option.group.as_deref().unwrap())?;
+            }
+            writeln!(f)?;
+            writeln!(f, "  {}", option.flags.join(", "))?;
+            writeln!(f, "  {}", option.env_var)?;
+            writeln!(f, "  {}", option.config_key)?;
+            writeln!(f, "      type: {}", option.type_name)?;
+            writeln!(f, "      default: {}", option.default)?;
+            if !option.choices.is_empty() {
+                writeln!(f, "      one of: {}", option.choices.join(", "))?;
+            }
+            for line in option.help.lines() {
+                writeln!(f, "      {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for ScopeHelp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "`{}` options:", self.scope)?;
+        Self::fmt_options(f, &self.basic)?;
+        if self.show_advanced {
+            writeln!(f)?;
+            writeln!(f, "Advanced `{}` options:", self.scope)?;
+            Self::fmt_options(f, &self.advanced)?;
+        }
+        Ok(())
+    }
+}
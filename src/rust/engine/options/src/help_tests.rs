@@ -0,0 +1,170 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::{option_id, OptionHelp, OptionInfo, OptionRegistry, OptionType, Scope, ScopeHelp};
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(
+            option_id!(-'l', "level"),
+            OptionType::String("info".to_owned()),
+        )
+        .choices(&["debug", "info"])
+        .help("The log level."),
+        OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false))
+            .help("Run tests sequentially,\nin an interactive process."),
+        OptionInfo::new(
+            option_id!(["test"], "extra", "env"),
+            OptionType::StringList(vec![]),
+        )
+        .advanced()
+        .help("Additional env vars."),
+        OptionInfo::new(
+            option_id!(["test"], "cache", "dir"),
+            OptionType::String("~/.cache".to_owned()),
+        )
+        .advanced()
+        .default_help_repr("<user cache dir>"),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+#[test]
+fn test_option_help() {
+    let help = ScopeHelp::new(&registry(), &Scope::Global, false);
+    assert_eq!(
+        vec![OptionHelp {
+            flags: vec!["--level".to_owned(), "-l".to_owned()],
+            env_var: "PANTS_LEVEL".to_owned(),
+            config_key: "level".to_owned(),
+            type_name: "str".to_owned(),
+            default: "\"info\"".to_owned(),
+            choices: vec!["debug".to_owned(), "info".to_owned()],
+            help: "The log level.".to_owned(),
+            group: None,
+        }],
+        help.basic
+    );
+    assert!(help.advanced.is_empty());
+}
+
+#[test]
+fn test_display() {
+    let registry = registry();
+    let test = Scope::named("test");
+    assert_eq!(
+        "`test` options:\n\
+         \n  --test-debug, --no-test-debug\
+         \n  PANTS_TEST_DEBUG\
+         \n  debug\
+         \n      type: bool\
+         \n      default: false\
+         \n      Run tests sequentially,\
+         \n      in an interactive process.\n",
+        ScopeHelp::new(&registry, &test, false).to_string()
+    );
+    assert_eq!(
+        "`test` options:\n\
+         \n  --test-debug, --no-test-debug\
+         \n  PANTS_TEST_DEBUG\
+         \n  debug\
+         \n      type: bool\
+         \n      default: false\
+         \n      Run tests sequentially,\
+         \n      in an interactive process.\
+         \n\
+         \nAdvanced `test` options:\n\
+         \n  --test-extra-env\
+         \n  PANTS_TEST_EXTRA_ENV\
+         \n  extra_env\
+         \n      type: list[str]\
+         \n      default: []\
+         \n      Additional env vars.\n\
+         \n  --test-cache-dir\
+         \n  PANTS_TEST_CACHE_DIR\
+         \n  cache_dir\
+         \n      type: str\
+         \n      default: <user cache dir>\n",
+        ScopeHelp::new(&registry, &test, true).to_string()
+    );
+    assert_eq!(
+        "`lint` options:\n  (none)\n",
+        ScopeHelp::new(&registry, &Scope::named("lint"), false).to_string()
+    );
+}
+
+#[test]
+fn test_groups() {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!(["test"], "cache"), OptionType::Bool(true)).group("caching"),
+        OptionInfo::new(option_id!(["test"], "timeout"), OptionType::Int(60)),
+        OptionInfo::new(option_id!(["test"], "remote"), OptionType::Bool(false))
+            .group("remote execution"),
+        OptionInfo::new(option_id!(["test"], "cache", "dir"), OptionType::Int(0)).group("caching"),
+    ] {
+        registry.register(option).unwrap();
+    }
+    let help = ScopeHelp::new(&registry, &Scope::named("test"), false);
+    assert_eq!(
+        vec![
+            None,
+            Some("caching"),
+            Some("caching"),
+            Some("remote execution")
+        ],
+        help.basic
+            .iter()
+            .map(|option| option.group.as_deref())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        "`test` options:\n\
+         \n  --test-timeout\
+         \n  PANTS_TEST_TIMEOUT\
+         \n  timeout\
+         \n      type: int\
+         \n      default: 60\n\
+         \n  caching:\n\
+         \n  --test-cache, --no-test-cache\
+         \n  PANTS_TEST_CACHE\
+         \n  cache\
+         \n      type: bool\
+         \n      default: true\n\
+         \n  --test-cache-dir\
+         \n  PANTS_TEST_CACHE_DIR\
+         \n  cache_dir\
+         \n      type: int\
+         \n      default: 0\n\
+         \n  remote execution:\n\
+         \n  --test-remote, --no-test-remote\
+         \n  PANTS_TEST_REMOTE\
+         \n  remote\
+         \n      type: bool\
+         \n      default: false\n",
+        help.to_string()
+    );
+    let json: serde_json::Value = serde_json::from_str(&help.to_json().unwrap()).unwrap();
+    assert_eq!(serde_json::Value::Null, json["basic"][0]["group"]);
+    assert_eq!("caching", json["basic"][1]["group"]);
+}
+
+#[test]
+fn test_to_json() {
+    let json: serde_json::Value = serde_json::from_str(
+        &ScopeHelp::new(&registry(), &Scope::named("test"), false)
+            .to_json()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!("test", json["scope"]);
+    assert_eq!("list[str]", json["advanced"][0]["type"]);
+    assert_eq!("PANTS_TEST_EXTRA_ENV", json["advanced"][0]["env_var"]);
+    assert_eq!(
+        serde_json::json!(["--test-debug", "--no-test-debug"]),
+        json["basic"][0]["flags"]
+    );
+}
@@ -7,7 +7,9 @@ use std::fmt::{Display, Formatter};
 
 use regex::Regex;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+use crate::intern::InternedString;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Scope {
     Global,
     Scope(String),
@@ -38,12 +40,25 @@ impl Scope {
             Scope::Scope(scope) => scope.as_str(),
         }
     }
+
+    ///
+    /// The names of the scopes that this scope is nested in, from the outermost in, followed by
+    /// this scope's own name. E.g. `test.pytest` is nested in `test`, and so has a lineage of
+    /// `test`, `test.pytest`.
+    ///
+    pub(crate) fn lineage(&self) -> Vec<&str> {
+        let name = self.name();
+        name.match_indices('.')
+            .map(|(i, _)| &name[..i])
+            .chain(std::iter::once(name))
+            .collect()
+    }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct OptionId {
     pub(crate) scope: Scope,
-    pub(crate) name_components: Vec<String>,
+    pub(crate) name_components: Vec<InternedString>,
     pub(crate) short_name: Option<String>,
 }
 
@@ -58,7 +73,7 @@ impl OptionId {
         Name: Iterator<Item = Component>,
     {
         let name_components = name
-            .map(|component| component.as_ref().to_string())
+            .map(|component| InternedString::from(component.as_ref()))
             .collect::<Vec<_>>();
         if name_components.is_empty() {
             return Err(format!(
@@ -127,7 +142,7 @@ impl OptionId {
         self.name_components
             .iter()
             .map(|component| match transform {
-                NameTransform::None => component.to_owned(),
+                NameTransform::None => component.to_string(),
                 NameTransform::ToLower => component.to_ascii_lowercase(),
                 NameTransform::ToUpper => component.to_ascii_uppercase(),
             })
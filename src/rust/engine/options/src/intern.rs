@@ -0,0 +1,140 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+struct Interner {
+    strings: HashSet<Arc<str>>,
+    // The size of `strings` after the last purge of unreferenced entries.
+    purged_len: usize,
+}
+
+lazy_static! {
+    static ref INTERNER: Mutex<Interner> = Mutex::new(Interner {
+        strings: HashSet::new(),
+        purged_len: 0,
+    });
+}
+
+// Below this many entries we never bother purging.
+const MIN_PURGE_LEN: usize = 1024;
+
+fn intern(s: &str) -> Arc<str> {
+    let mut interner = INTERNER.lock().unwrap();
+    if let Some(existing) = interner.strings.get(s) {
+        return existing.clone();
+    }
+    // Strings that are only referenced by the interner are garbage: drop them whenever the set
+    // has doubled in size since the last purge, so that a long-lived process doesn't accumulate
+    // the values of every options parse it ever performed.
+    if interner.strings.len() >= MIN_PURGE_LEN.max(interner.purged_len * 2) {
+        interner.strings.retain(|s| Arc::strong_count(s) > 1);
+        interner.purged_len = interner.strings.len();
+    }
+    let interned: Arc<str> = Arc::from(s);
+    interner.strings.insert(interned.clone());
+    interned
+}
+
+///
+/// An immutable, interned string. Equal strings created in the same process share a single
+/// allocation, which saves memory for the many values and option name components that repeat
+/// across scopes and sources, and lets equality checks short-circuit on pointer equality.
+///
+#[derive(Clone, PartialOrd, Ord)]
+pub struct InternedString(pub(crate) Arc<str>);
+
+impl InternedString {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for InternedString {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for InternedString {}
+
+impl Hash for InternedString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl Deref for InternedString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for InternedString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for InternedString {
+    fn from(s: &str) -> Self {
+        Self(intern(s))
+    }
+}
+
+impl From<String> for InternedString {
+    fn from(s: String) -> Self {
+        Self(intern(&s))
+    }
+}
+
+impl From<&String> for InternedString {
+    fn from(s: &String) -> Self {
+        Self(intern(s))
+    }
+}
+
+impl PartialEq<str> for InternedString {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for InternedString {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl fmt::Debug for InternedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for InternedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for InternedString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for InternedString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::{option_id, InternedString, Val};
+
+#[test]
+fn test_equal_strings_share_storage() {
+    let a = InternedString::from("some/repeated/value");
+    let b = InternedString::from("some/repeated/value".to_string());
+    let c = InternedString::from("some/other/value");
+    assert_eq!(a, b);
+    assert!(Arc::ptr_eq(&a.0, &b.0));
+    assert_ne!(a, c);
+    assert_eq!(a, "some/repeated/value");
+    assert_eq!("some/repeated/value", a.as_str());
+    assert_eq!(
+        1,
+        HashSet::from([a.clone(), b.clone()]).len(),
+        "Equal interned strings should hash equally."
+    );
+}
+
+#[test]
+fn test_option_id_components_are_interned() {
+    let id1 = option_id!(["scope"], "shared", "name");
+    let id2 = option_id!(["other"], "shared", "name");
+    assert!(Arc::ptr_eq(
+        &id1.name_components[0].0,
+        &id2.name_components[0].0
+    ));
+}
+
+#[test]
+fn test_val_string_roundtrip() {
+    let val: Val = serde_json::from_str("[\"x\", {\"k\": \"x\"}]").unwrap();
+    assert_eq!(
+        Val::List(vec![
+            Val::String("x".into()),
+            Val::Dict([("k".to_string(), Val::String("x".into()))].into()),
+        ]),
+        val
+    );
+    assert_eq!(
+        "[\"x\",{\"k\":\"x\"}]",
+        serde_json::to_string(&val).unwrap()
+    );
+}
+
+#[test]
+fn test_purge_keeps_live_strings() {
+    let live = InternedString::from("still-referenced");
+    for i in 0..5000 {
+        let _ = InternedString::from(format!("transient-{i}"));
+    }
+    assert!(Arc::ptr_eq(
+        &live.0,
+        &InternedString::from("still-referenced").0
+    ));
+}
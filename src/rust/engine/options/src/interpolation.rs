@@ -0,0 +1,368 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{HashMap, HashSet};
+
+use crate::filters::BUILTIN_FILTERS;
+use crate::seeds::LazySeeds;
+use crate::shell::ShellInterpolation;
+
+pub(crate) type InterpolationMap = HashMap<String, String>;
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum Token<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+    // The name and the chain of filters of a `%(name|filter|filter:arg)s` placeholder.
+    Filtered(&'a str, &'a str),
+    // The command of a `%(shell <command>)s` placeholder.
+    Command(&'a str),
+    // The condition and the two values of a `%(if:<condition>:<then>:<else>)s` placeholder.
+    Conditional(&'a str, &'a str, &'a str),
+}
+
+const SHELL_PREFIX: &str = "shell ";
+const IF_PREFIX: &str = "if:";
+
+///
+/// Whether the value of a seed counts as true in a condition: i.e. it is neither empty nor a
+/// negative such as `0` or `false`.
+///
+pub(crate) fn is_truthy(value: &str) -> bool {
+    !matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "" | "0" | "false" | "no" | "off"
+    )
+}
+
+///
+/// The name of the seed that a condition tests, e.g. `env.CI` for `env.CI`, `!env.CI` or
+/// `env.CI=true`.
+///
+pub(crate) fn condition_name(condition: &str) -> &str {
+    let condition = condition.strip_prefix('!').unwrap_or(condition);
+    condition
+        .split_once('=')
+        .map_or(condition, |(name, _)| name)
+}
+
+///
+/// Whether the value consists of a single placeholder, and so may be interpolated into a value of
+/// any type, rather than only a string: e.g. `%(concurrency)s` for an int option.
+///
+pub(crate) fn is_whole_placeholder(value: &str) -> bool {
+    matches!(tokenize(value).as_slice(), [token] if !matches!(token, Token::Literal(_)))
+}
+
+fn is_placeholder_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'.'
+}
+
+///
+/// Splits a value into literal text, `%(name)s` placeholders (optionally with filters, as in
+/// `%(name|upper)s`), `%(shell <command>)s` placeholders and `%(if:<condition>:<then>:<else>)s`
+/// placeholders, in a single pass. The filters, commands and conditionals of placeholders extend
+/// to the first `)s`. The `<then>` of a conditional may not contain a `:`, and its `:<else>` may
+/// be omitted.
+///
+/// Text that merely resembles a placeholder (e.g. `%(` without a valid name, or without the
+/// closing `)s`) is treated as literal text.
+///
+pub(crate) fn tokenize(value: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut literal_start = 0;
+    let mut pos = 0;
+    while let Some(offset) = value[pos..].find("%(") {
+        let start = pos + offset;
+        let name_start = start + 2;
+        if value[name_start..].starts_with(SHELL_PREFIX) {
+            let command_start = name_start + SHELL_PREFIX.len();
+            if let Some(command_len) = value[command_start..].find(")s") {
+                let command = value[command_start..command_start + command_len].trim();
+                if !command.is_empty() {
+                    if literal_start < start {
+                        tokens.push(Token::Literal(&value[literal_start..start]));
+                    }
+                    tokens.push(Token::Command(command));
+                    pos = command_start + command_len + 2;
+                    literal_start = pos;
+                    continue;
+                }
+            }
+        }
+        if let Some(conditional) = value[name_start..].strip_prefix(IF_PREFIX) {
+            if let Some(len) = conditional.find(")s") {
+                let mut parts = conditional[..len].splitn(3, ':');
+                let condition = parts.next().unwrap_or_default();
+                if let (false, Some(then)) = (condition_name(condition).is_empty(), parts.next()) {
+                    if literal_start < start {
+                        tokens.push(Token::Literal(&value[literal_start..start]));
+                    }
+                    tokens.push(Token::Conditional(
+                        condition,
+                        then,
+                        parts.next().unwrap_or_default(),
+                    ));
+                    pos = name_start + IF_PREFIX.len() + len + 2;
+                    literal_start = pos;
+                    continue;
+                }
+            }
+        }
+        let name_end = name_start
+            + value[name_start..]
+                .bytes()
+                .take_while(|b| is_placeholder_name_byte(*b))
+                .count();
+        // The end of the placeholder's filters, if it has any, or else of its name.
+        let end = match value[name_end..].strip_prefix('|') {
+            Some(filters) => filters
+                .find(")s")
+                .filter(|len| *len > 0)
+                .map(|len| name_end + 1 + len),
+            None => Some(name_end),
+        };
+        match end {
+            Some(end) if name_end > name_start && value[end..].starts_with(")s") => {
+                if literal_start < start {
+                    tokens.push(Token::Literal(&value[literal_start..start]));
+                }
+                let name = &value[name_start..name_end];
+                if end > name_end {
+                    tokens.push(Token::Filtered(name, &value[name_end + 1..end]));
+                } else {
+                    tokens.push(Token::Placeholder(name));
+                }
+                pos = end + 2;
+                literal_start = pos;
+            }
+            _ => pos = start + 1,
+        }
+    }
+    if literal_start < value.len() {
+        tokens.push(Token::Literal(&value[literal_start..]));
+    }
+    tokens
+}
+
+///
+/// Interpolates `%(name)s` placeholders using a map of replacements, which may themselves contain
+/// placeholders.
+///
+/// Each placeholder is resolved at most once per Interpolator, and resolution uses an explicit
+/// work stack rather than recursion, so that reference cycles are detected and reported rather than
+/// overflowing the stack.
+///
+/// The filters of a placeholder are applied to its resolved value each time that it is used.
+/// The conditions of `%(if:...)s` placeholders test the raw values of the replacements, so that
+/// they cannot be cyclic: a replacement that does not exist is false.
+/// Replacements that are not in the map may be computed on demand by `LazySeeds`.
+/// `%(shell <command>)s` placeholders are an error unless shell interpolation is enabled.
+///
+pub(crate) struct Interpolator<'a> {
+    replacements: &'a InterpolationMap,
+    lazy_seeds: Option<&'a LazySeeds>,
+    shell: Option<&'a ShellInterpolation>,
+    resolved: HashMap<String, String>,
+}
+
+struct Frame<'a> {
+    name: String,
+    // The filters to apply to the output before it is used by the parent frame.
+    filters: Option<&'a str>,
+    tokens: std::vec::IntoIter<Token<'a>>,
+    output: String,
+}
+
+impl<'a> Interpolator<'a> {
+    pub(crate) fn new(replacements: &'a InterpolationMap) -> Self {
+        Self {
+            replacements,
+            lazy_seeds: None,
+            shell: None,
+            resolved: HashMap::new(),
+        }
+    }
+
+    fn filter(&self, name: &str, chain: &str, value: &str) -> Result<String, String> {
+        BUILTIN_FILTERS.apply(value, chain).map_err(|e| {
+            format!("Failed to apply the filters of placeholder `{name}|{chain}`: {e}")
+        })
+    }
+
+    pub(crate) fn lazy_seeds(mut self, lazy_seeds: Option<&'a LazySeeds>) -> Self {
+        self.lazy_seeds = lazy_seeds;
+        self
+    }
+
+    fn replacement(&self, name: &str) -> Option<&'a str> {
+        let replacements: &'a InterpolationMap = self.replacements;
+        replacements
+            .get(name)
+            .map(String::as_str)
+            .or_else(|| self.lazy_seeds.and_then(|lazy_seeds| lazy_seeds.get(name)))
+    }
+
+    pub(crate) fn shell(mut self, shell: Option<&'a ShellInterpolation>) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    fn evaluate(&self, condition: &str) -> bool {
+        let (negated, condition) = match condition.strip_prefix('!') {
+            Some(condition) => (true, condition),
+            None => (false, condition),
+        };
+        let value = self.replacement(condition_name(condition));
+        let holds = match (condition.split_once('='), value) {
+            (Some((_, expected)), Some(value)) => value == expected,
+            (None, Some(value)) => is_truthy(value),
+            (_, None) => false,
+        };
+        holds != negated
+    }
+
+    fn run(&self, command: &str) -> Result<String, String> {
+        match self.shell {
+            Some(shell) => shell.run(command),
+            None => Err(format!(
+                "Cannot run the command `{command}` of a `%(shell ...)s` placeholder, as shell \
+                interpolation is not enabled: set `--shell-interpolation` or \
+                `PANTS_SHELL_INTERPOLATION` to enable it"
+            )),
+        }
+    }
+
+    pub(crate) fn interpolate(&mut self, value: &str) -> Result<String, String> {
+        let tokens = tokenize(value);
+        if tokens
+            .iter()
+            .all(|token| matches!(token, Token::Literal(_)))
+        {
+            return Ok(value.to_owned());
+        }
+        let mut output = String::with_capacity(value.len());
+        for token in tokens {
+            match token {
+                Token::Literal(literal) => output.push_str(literal),
+                Token::Placeholder(name) => output.push_str(self.resolve(name)?),
+                Token::Filtered(name, chain) => {
+                    let value = self.resolve(name)?.to_owned();
+                    output.push_str(&self.filter(name, chain, &value)?)
+                }
+                Token::Command(command) => output.push_str(&self.run(command)?),
+                Token::Conditional(condition, then, otherwise) => {
+                    output.push_str(if self.evaluate(condition) {
+                        then
+                    } else {
+                        otherwise
+                    })
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    fn frame(&self, name: &str, filters: Option<&'a str>) -> Result<Frame<'a>, String> {
+        let replacement = self
+            .replacement(name)
+            .ok_or_else(|| format!("Unknown value for placeholder `{name}`"))?;
+        Ok(Frame {
+            name: name.to_owned(),
+            filters,
+            tokens: tokenize(replacement).into_iter(),
+            output: String::with_capacity(replacement.len()),
+        })
+    }
+
+    // Appends the resolved value of a placeholder to the output of the current frame, or pushes a
+    // frame to resolve it first.
+    fn expand_placeholder(
+        &mut self,
+        placeholder: &'a str,
+        filters: Option<&'a str>,
+        stack: &mut Vec<Frame<'a>>,
+        in_progress: &mut HashSet<String>,
+    ) -> Result<(), String> {
+        if let Some(resolved) = self.resolved.get(placeholder) {
+            let resolved = match filters {
+                Some(chain) => self.filter(placeholder, chain, resolved)?,
+                None => resolved.clone(),
+            };
+            stack.last_mut().unwrap().output.push_str(&resolved);
+        } else if in_progress.contains(placeholder) {
+            let pos = stack.iter().position(|f| f.name == placeholder).unwrap();
+            let cycle = stack[pos..]
+                .iter()
+                .map(|f| format!("`{}`", f.name))
+                .chain(std::iter::once(format!("`{placeholder}`")))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(format!(
+                "Placeholder `{placeholder}` refers to itself: {cycle}"
+            ));
+        } else {
+            stack.push(self.frame(placeholder, filters)?);
+            in_progress.insert(placeholder.to_owned());
+        }
+        Ok(())
+    }
+
+    fn resolve(&mut self, name: &str) -> Result<&str, String> {
+        if !self.resolved.contains_key(name) {
+            let mut stack = vec![self.frame(name, None)?];
+            let mut in_progress = HashSet::from([name.to_owned()]);
+            while !stack.is_empty() {
+                let next = stack.last_mut().unwrap().tokens.next();
+                match next {
+                    Some(Token::Literal(literal)) => {
+                        stack.last_mut().unwrap().output.push_str(literal)
+                    }
+                    Some(Token::Command(command)) => {
+                        let output = self.run(command)?;
+                        stack.last_mut().unwrap().output.push_str(&output)
+                    }
+                    Some(Token::Conditional(condition, then, otherwise)) => {
+                        let output = if self.evaluate(condition) {
+                            then
+                        } else {
+                            otherwise
+                        };
+                        stack.last_mut().unwrap().output.push_str(output)
+                    }
+                    Some(Token::Placeholder(placeholder)) => {
+                        self.expand_placeholder(placeholder, None, &mut stack, &mut in_progress)?
+                    }
+                    Some(Token::Filtered(placeholder, chain)) => self.expand_placeholder(
+                        placeholder,
+                        Some(chain),
+                        &mut stack,
+                        &mut in_progress,
+                    )?,
+                    None => {
+                        let frame = stack.pop().unwrap();
+                        in_progress.remove(&frame.name);
+                        if !stack.is_empty() {
+                            let output = match frame.filters {
+                                Some(chain) => self.filter(&frame.name, chain, &frame.output)?,
+                                None => frame.output.clone(),
+                            };
+                            stack.last_mut().unwrap().output.push_str(&output);
+                        }
+                        self.resolved.insert(frame.name, frame.output);
+                    }
+                }
+            }
+        }
+        Ok(self.resolved.get(name).unwrap())
+    }
+}
+
+// Interpolates a single value with a fresh Interpolator. Useful in tests.
+#[cfg(test)]
+pub(crate) fn interpolate_string(
+    value: String,
+    replacements: &InterpolationMap,
+) -> Result<String, String> {
+    Interpolator::new(replacements).interpolate(&value)
+}
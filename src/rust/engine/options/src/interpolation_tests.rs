@@ -0,0 +1,153 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+
+use crate::interpolation::{tokenize, InterpolationMap, Interpolator, Token};
+
+fn replacements(items: &[(&str, &str)]) -> InterpolationMap {
+    items
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<HashMap<_, _>>()
+}
+
+#[test]
+fn test_tokenize() {
+    assert_eq!(Vec::<Token>::new(), tokenize(""));
+    assert_eq!(vec![Token::Literal("plain")], tokenize("plain"));
+    assert_eq!(
+        vec![
+            Token::Literal("a "),
+            Token::Placeholder("b.c_1"),
+            Token::Placeholder("d"),
+            Token::Literal(" e"),
+        ],
+        tokenize("a %(b.c_1)s%(d)s e")
+    );
+    // Things that aren't quite placeholders are literals.
+    assert_eq!(
+        vec![
+            Token::Literal("100%(x) %()s %(y-z)s %%("),
+            Token::Placeholder("w")
+        ],
+        tokenize("100%(x) %()s %(y-z)s %%(%(w)s")
+    );
+    assert_eq!(
+        vec![
+            Token::Literal("v"),
+            Token::Command("git rev-parse --short HEAD"),
+            Token::Literal("-"),
+            Token::Placeholder("shell"),
+            Token::Literal(" %(shell )s"),
+        ],
+        tokenize("v%(shell  git rev-parse --short HEAD )s-%(shell)s %(shell )s")
+    );
+    assert_eq!(
+        vec![
+            Token::Filtered("a", "upper"),
+            Token::Literal("/"),
+            Token::Filtered("b.c", "join:,|replace:a:)"),
+            Token::Literal("%(d|)s"),
+        ],
+        tokenize("%(a|upper)s/%(b.c|join:,|replace:a:))s%(d|)s")
+    );
+    assert_eq!(
+        vec![
+            Token::Conditional("is_ci", "--ci", "--local"),
+            Token::Literal(" "),
+            Token::Conditional("!env.X=a", "b", "c"),
+            Token::Literal(" %(if:)s %(if:x)s"),
+        ],
+        tokenize("%(if:is_ci:--ci:--local)s %(if:!env.X=a:b:c)s %(if:)s %(if:x)s")
+    );
+    // Windows environment variable references, and the backslashes of Windows paths, are
+    // literals, even when next to a placeholder.
+    assert_eq!(
+        vec![
+            Token::Literal(r"%APPDATA%\"),
+            Token::Placeholder("name"),
+            Token::Literal(r"\%ProgramFiles(x86)%\%USERPROFILE%"),
+            Token::Placeholder("buildroot"),
+        ],
+        tokenize(r"%APPDATA%\%(name)s\%ProgramFiles(x86)%\%USERPROFILE%%(buildroot)s")
+    );
+}
+
+#[test]
+fn test_conditional() {
+    let replacements = replacements(&[
+        ("is_ci", "true"),
+        ("env.MODE", "release"),
+        ("env.EMPTY", ""),
+        ("env.OFF", "False"),
+        ("flags", "%(if:is_ci:--ci:--local)s"),
+    ]);
+    let mut interpolator = Interpolator::new(&replacements);
+    let mut interpolate = |value: &str| interpolator.interpolate(value).unwrap();
+    assert_eq!("--ci", interpolate("%(flags)s"));
+    assert_eq!("-O", interpolate("%(if:env.MODE=release:-O:-g)s"));
+    assert_eq!("-g", interpolate("%(if:env.MODE=debug:-O:-g)s"));
+    assert_eq!("", interpolate("%(if:!is_ci:--local)s"));
+    // Empty, negative and missing values are false.
+    assert_eq!(
+        "no no no",
+        interpolate("%(if:env.EMPTY:yes:no)s %(if:env.OFF:yes:no)s %(if:env.NONE:yes:no)s")
+    );
+    assert_eq!("yes", interpolate("%(if:!env.NONE:yes:no)s"));
+}
+
+#[test]
+fn test_shell_disabled() {
+    let replacements = replacements(&[("sha", "%(shell git rev-parse HEAD)s")]);
+    assert_eq!(
+        "Cannot run the command `git rev-parse HEAD` of a `%(shell ...)s` placeholder, as shell \
+        interpolation is not enabled: set `--shell-interpolation` or `PANTS_SHELL_INTERPOLATION` \
+        to enable it",
+        Interpolator::new(&replacements)
+            .interpolate("v%(sha)s")
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_memoized_nested_resolution() {
+    let replacements = replacements(&[("a", "%(b)s-%(b)s"), ("b", "%(c)s%(c)s"), ("c", "x")]);
+    let mut interpolator = Interpolator::new(&replacements);
+    assert_eq!("xx-xx xx", interpolator.interpolate("%(a)s %(b)s").unwrap());
+    assert_eq!("x", interpolator.interpolate("%(c)s").unwrap());
+}
+
+#[test]
+fn test_deep_chain() {
+    let mut items = (0..10_000)
+        .map(|i| (format!("p{i}"), format!("%(p{})s", i + 1)))
+        .collect::<HashMap<_, _>>();
+    items.insert("p10000".to_string(), "end".to_string());
+    assert_eq!(
+        "end",
+        Interpolator::new(&items).interpolate("%(p0)s").unwrap()
+    );
+}
+
+#[test]
+fn test_cycle() {
+    let replacements = replacements(&[("a", "x%(b)s"), ("b", "%(c)s"), ("c", "%(a)s")]);
+    assert_eq!(
+        "Placeholder `a` refers to itself: `a` -> `b` -> `c` -> `a`",
+        Interpolator::new(&replacements)
+            .interpolate("%(a)s")
+            .unwrap_err()
+    );
+    let replacements = replacements_self();
+    assert_eq!(
+        "Placeholder `me` refers to itself: `me` -> `me`",
+        Interpolator::new(&replacements)
+            .interpolate("%(me)s")
+            .unwrap_err()
+    );
+}
+
+fn replacements_self() -> InterpolationMap {
+    replacements(&[("me", "%(me)s")])
+}
@@ -0,0 +1,304 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use indexmap::IndexMap;
+
+use super::{DictEdit, ListEdit, OptionsSource, RawValue, Val};
+use crate::explain::REDACTED;
+use crate::id::OptionId;
+use crate::parse::{parse_dict, Parseable};
+
+/// The entry of the OS credential store that holds the value of a secret option.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct KeyringEntry {
+    pub service: String,
+    pub account: String,
+}
+
+impl KeyringEntry {
+    pub fn new(service: &str, account: &str) -> Self {
+        Self {
+            service: service.to_owned(),
+            account: account.to_owned(),
+        }
+    }
+}
+
+///
+/// A store of secrets, looked up by service and account. Implemented for the OS credential store
+/// by `OsCredentialStore`, and may be implemented otherwise for tests.
+///
+pub trait CredentialStore: Send + Sync {
+    ///
+    /// Returns the secret stored for the given entry, or None if there is no such entry.
+    /// Errors if the store could not be read.
+    ///
+    fn get(&self, entry: &KeyringEntry) -> Result<Option<String>, String>;
+}
+
+///
+/// The credential store of the OS: the Keychain on macOS, the Secret Service (e.g. GNOME Keyring
+/// or KWallet) on Linux, and the Credential Manager on Windows.
+///
+/// NB: The stores are read via the CLIs that ship with them (`security`, `secret-tool` and
+/// PowerShell respectively), so that secrets never pass through the environment or the disk.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsCredentialStore;
+
+// The env vars via which the entry to read is passed to the PowerShell script, as `-Command` does
+// not bind the args that follow its script to `$args`.
+const SERVICE_ENV_VAR: &str = "PANTS_KEYRING_SERVICE";
+const ACCOUNT_ENV_VAR: &str = "PANTS_KEYRING_ACCOUNT";
+
+// Reads a generic credential from the Windows Credential Manager via CredRead, as the `keyring`
+// Python package writes it: targeted at the service and owned by the account, or else targeted at
+// `account@service`. The secret is stored as UTF-16. Exits with 44 if there is no such credential.
+const CRED_READ_SCRIPT: &str = r#"$ErrorActionPreference = 'Stop'
+[Console]::OutputEncoding = [Text.Encoding]::UTF8
+Add-Type -TypeDefinition @'
+using System;
+using System.ComponentModel;
+using System.Runtime.InteropServices;
+using FILETIME = System.Runtime.InteropServices.ComTypes.FILETIME;
+
+public static class PantsCredentials {
+    const int CRED_TYPE_GENERIC = 1;
+    const int ERROR_NOT_FOUND = 1168;
+
+    [StructLayout(LayoutKind.Sequential, CharSet = CharSet.Unicode)]
+    struct CREDENTIAL {
+        public int Flags;
+        public int Type;
+        public string TargetName;
+        public string Comment;
+        public FILETIME LastWritten;
+        public int CredentialBlobSize;
+        public IntPtr CredentialBlob;
+        public int Persist;
+        public int AttributeCount;
+        public IntPtr Attributes;
+        public string TargetAlias;
+        public string UserName;
+    }
+
+    [DllImport("advapi32.dll", EntryPoint = "CredReadW", CharSet = CharSet.Unicode, SetLastError = true)]
+    static extern bool CredRead(string target, int type, int flags, out IntPtr credential);
+
+    [DllImport("advapi32.dll")]
+    static extern void CredFree(IntPtr credential);
+
+    public static string Read(string target, string userName) {
+        IntPtr ptr;
+        if (!CredRead(target, CRED_TYPE_GENERIC, 0, out ptr)) {
+            int error = Marshal.GetLastWin32Error();
+            if (error == ERROR_NOT_FOUND) {
+                return null;
+            }
+            throw new Win32Exception(error);
+        }
+        try {
+            var credential = (CREDENTIAL)Marshal.PtrToStructure(ptr, typeof(CREDENTIAL));
+            if (userName != null && credential.UserName != userName) {
+                return null;
+            }
+            return Marshal.PtrToStringUni(credential.CredentialBlob, credential.CredentialBlobSize / 2);
+        } finally {
+            CredFree(ptr);
+        }
+    }
+}
+'@
+$service = $env:PANTS_KEYRING_SERVICE
+$account = $env:PANTS_KEYRING_ACCOUNT
+$secret = [PantsCredentials]::Read($service, $account)
+if ($secret -eq $null) {
+    $secret = [PantsCredentials]::Read("$account@$service", $null)
+}
+if ($secret -eq $null) {
+    exit 44
+}
+[Console]::Out.Write($secret)
+"#;
+
+impl OsCredentialStore {
+    // Runs the given PowerShell script, with the entry to read in the env.
+    pub(crate) fn powershell(script: &str, entry: &KeyringEntry) -> Command {
+        let mut command = Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-Command", script])
+            .env(SERVICE_ENV_VAR, &entry.service)
+            .env(ACCOUNT_ENV_VAR, &entry.account);
+        command
+    }
+
+    fn command(entry: &KeyringEntry) -> Command {
+        if cfg!(target_os = "macos") {
+            let mut command = Command::new("security");
+            command.args([
+                "find-generic-password",
+                "-s",
+                &entry.service,
+                "-a",
+                &entry.account,
+                "-w",
+            ]);
+            command
+        } else if cfg!(windows) {
+            Self::powershell(CRED_READ_SCRIPT, entry)
+        } else {
+            let mut command = Command::new("secret-tool");
+            command.args([
+                "lookup",
+                "service",
+                &entry.service,
+                "account",
+                &entry.account,
+            ]);
+            command
+        }
+    }
+}
+
+impl CredentialStore for OsCredentialStore {
+    fn get(&self, entry: &KeyringEntry) -> Result<Option<String>, String> {
+        let mut command = Self::command(entry);
+        let output = command.output().map_err(|e| {
+            format!(
+                "Failed to run {:?} to read the OS credential store: {e}",
+                command.get_program()
+            )
+        })?;
+        if output.status.success() {
+            let secret = String::from_utf8(output.stdout)
+                .map_err(|_| "The secret in the OS credential store is not UTF-8.".to_owned())?;
+            return Ok(Some(secret.trim_end_matches(['\r', '\n']).to_owned()));
+        }
+        // `security` and our PowerShell script exit with 44 for a missing entry, whereas
+        // `secret-tool` exits with 1 and prints nothing.
+        let missing = match output.status.code() {
+            Some(44) => true,
+            Some(1) => output.stderr.is_empty(),
+            _ => false,
+        };
+        if missing {
+            Ok(None)
+        } else {
+            Err(format!(
+                "Failed to read the OS credential store: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+}
+
+///
+/// An options source for secret options, which reads each of them from its entry in a credential
+/// store. Values are read lazily, at most once each.
+///
+pub(crate) struct KeyringReader {
+    entries: HashMap<OptionId, KeyringEntry>,
+    store: Arc<dyn CredentialStore>,
+    cache: Mutex<HashMap<OptionId, Option<String>>>,
+}
+
+impl KeyringReader {
+    pub(crate) fn new(
+        entries: HashMap<OptionId, KeyringEntry>,
+        store: Arc<dyn CredentialStore>,
+    ) -> Self {
+        Self {
+            entries,
+            store,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get_list<T: Parseable>(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<T>>>, String> {
+        self.get_string(id)?
+            .map(|value| T::parse_list(&value).map_err(|e| e.render(self.display(id))))
+            .transpose()
+    }
+}
+
+impl OptionsSource for KeyringReader {
+    fn display(&self, id: &OptionId) -> String {
+        match self.entries.get(id) {
+            Some(entry) => format!("keyring entry {}/{}", entry.service, entry.account),
+            None => format!("keyring entry for {id}"),
+        }
+    }
+
+    fn get_string(&self, id: &OptionId) -> Result<Option<String>, String> {
+        let Some(entry) = self.entries.get(id) else {
+            return Ok(None);
+        };
+        if let Some(value) = self.cache.lock().unwrap().get(id) {
+            return Ok(value.clone());
+        }
+        let value = self
+            .store
+            .get(entry)
+            .map_err(|e| format!("Failed to read the {}: {e}", self.display(id)))?;
+        self.cache.lock().unwrap().insert(id.clone(), value.clone());
+        Ok(value)
+    }
+
+    fn get_bool(&self, id: &OptionId) -> Result<Option<bool>, String> {
+        self.get_string(id)?
+            .map(|value| bool::parse(&value).map_err(|e| e.render(self.display(id))))
+            .transpose()
+    }
+
+    fn get_bool_list(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<bool>>>, String> {
+        self.get_list::<bool>(id)
+    }
+
+    fn get_int_list(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<i64>>>, String> {
+        self.get_list::<i64>(id)
+    }
+
+    fn get_float_list(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<f64>>>, String> {
+        self.get_list::<f64>(id)
+    }
+
+    fn get_string_list(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<String>>>, String> {
+        self.get_list::<String>(id)
+    }
+
+    fn get_dict_list(
+        &self,
+        id: &OptionId,
+    ) -> Result<Option<Vec<ListEdit<IndexMap<String, Val>>>>, String> {
+        self.get_list::<IndexMap<String, Val>>(id)
+    }
+
+    fn get_dict(&self, id: &OptionId) -> Result<Option<Vec<DictEdit>>, String> {
+        self.get_string(id)?
+            .map(|value| {
+                parse_dict(&value)
+                    .map(|edit| vec![edit])
+                    .map_err(|e| e.render(self.display(id)))
+            })
+            .transpose()
+    }
+
+    fn is_secret(&self, id: &OptionId) -> bool {
+        self.entries.contains_key(id)
+    }
+
+    fn get_raw(&self, id: &OptionId) -> Vec<RawValue> {
+        // The secret itself is never revealed.
+        match self.get_string(id) {
+            Ok(Some(_)) => vec![RawValue {
+                location: self.display(id),
+                value: Some(REDACTED.to_owned()),
+            }],
+            _ => vec![],
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::keyring::KeyringReader;
+#[cfg(windows)]
+use crate::keyring::OsCredentialStore;
+use crate::{option_id, CredentialStore, KeyringEntry, ListEdit, ListEditAction, OptionsSource};
+
+#[derive(Default)]
+struct FakeStore {
+    secrets: HashMap<KeyringEntry, String>,
+    reads: AtomicUsize,
+}
+
+impl CredentialStore for FakeStore {
+    fn get(&self, entry: &KeyringEntry) -> Result<Option<String>, String> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        if entry.account == "broken" {
+            return Err("The keyring is locked.".to_owned());
+        }
+        Ok(self.secrets.get(entry).cloned())
+    }
+}
+
+fn reader(store: Arc<FakeStore>) -> KeyringReader {
+    KeyringReader::new(
+        HashMap::from([
+            (
+                option_id!(["auth"], "token"),
+                KeyringEntry::new("pants", "token"),
+            ),
+            (
+                option_id!(["auth"], "scopes"),
+                KeyringEntry::new("pants", "scopes"),
+            ),
+            (
+                option_id!(["auth"], "missing"),
+                KeyringEntry::new("pants", "missing"),
+            ),
+            (
+                option_id!(["auth"], "broken"),
+                KeyringEntry::new("pants", "broken"),
+            ),
+        ]),
+        store,
+    )
+}
+
+fn store() -> Arc<FakeStore> {
+    Arc::new(FakeStore {
+        secrets: HashMap::from([
+            (KeyringEntry::new("pants", "token"), "s3cret".to_owned()),
+            (
+                KeyringEntry::new("pants", "scopes"),
+                "['a', 'b']".to_owned(),
+            ),
+        ]),
+        ..FakeStore::default()
+    })
+}
+
+#[test]
+fn test_get() {
+    let store = store();
+    let reader = reader(store.clone());
+    assert_eq!(
+        Some("s3cret".to_owned()),
+        reader.get_string(&option_id!(["auth"], "token")).unwrap()
+    );
+    assert_eq!(
+        Some(vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec!["a".to_owned(), "b".to_owned()],
+        }]),
+        reader
+            .get_string_list(&option_id!(["auth"], "scopes"))
+            .unwrap()
+    );
+    assert_eq!(
+        None,
+        reader.get_string(&option_id!(["auth"], "missing")).unwrap()
+    );
+    // Options without an entry are never looked up.
+    assert_eq!(
+        None,
+        reader.get_string(&option_id!(["auth"], "other")).unwrap()
+    );
+    assert_eq!(3, store.reads.load(Ordering::SeqCst));
+
+    // Values are only read from the store once.
+    reader.get_string(&option_id!(["auth"], "token")).unwrap();
+    reader.get_string(&option_id!(["auth"], "missing")).unwrap();
+    assert_eq!(3, store.reads.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_errors() {
+    let reader = reader(store());
+    assert_eq!(
+        "Failed to read the keyring entry pants/broken: The keyring is locked.",
+        reader
+            .get_string(&option_id!(["auth"], "broken"))
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_raw_values_are_redacted() {
+    let reader = reader(store());
+    let raw = reader.get_raw(&option_id!(["auth"], "token"));
+    assert_eq!(1, raw.len());
+    assert_eq!("keyring entry pants/token", raw[0].location);
+    assert_eq!(Some("<redacted>".to_owned()), raw[0].value);
+    assert!(reader.get_raw(&option_id!(["auth"], "missing")).is_empty());
+}
+
+#[cfg(windows)]
+#[test]
+fn test_powershell_entry() {
+    // The entry is passed to the script intact, however it is quoted.
+    let entry = KeyringEntry::new("my 'service'", "$account \"x\"");
+    let output = OsCredentialStore::powershell(
+        "[Console]::Out.Write(\"$env:PANTS_KEYRING_SERVICE/$env:PANTS_KEYRING_ACCOUNT\")",
+        &entry,
+    )
+    .output()
+    .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        "my 'service'/$account \"x\"",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
@@ -1,58 +1,301 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+mod arg_splitter;
+#[cfg(test)]
+mod arg_splitter_tests;
+
 mod args;
 #[cfg(test)]
 mod args_tests;
 
+mod base64;
+#[cfg(test)]
+mod base64_tests;
+
 mod build_root;
 #[cfg(test)]
 mod build_root_tests;
 
+mod capture;
+#[cfg(test)]
+mod capture_tests;
+
+mod completion;
+#[cfg(test)]
+mod completion_tests;
+
+mod concurrency;
+#[cfg(test)]
+mod concurrency_tests;
+
 mod config;
 #[cfg(test)]
 mod config_tests;
 
+mod conflicts;
+#[cfg(test)]
+mod conflicts_tests;
+
+mod constraints;
+#[cfg(test)]
+mod constraints_tests;
+
+mod csv;
+#[cfg(test)]
+mod csv_tests;
+
+mod decrypt;
+#[cfg(test)]
+mod decrypt_tests;
+
+mod defaults;
+#[cfg(test)]
+mod defaults_tests;
+
+mod deprecation;
+#[cfg(test)]
+mod deprecation_tests;
+
+mod dump;
+#[cfg(test)]
+mod dump_tests;
+
 mod env;
 #[cfg(test)]
 mod env_tests;
 
+mod explain;
+#[cfg(test)]
+mod explain_tests;
+
+mod export;
+#[cfg(test)]
+mod export_tests;
+
+#[cfg(feature = "cdylib")]
+mod ffi;
+#[cfg(all(test, feature = "cdylib"))]
+mod ffi_tests;
+
+mod filesystem;
+#[cfg(test)]
+mod filesystem_tests;
+
+mod filters;
+#[cfg(test)]
+mod filters_tests;
+
+mod formatter;
+#[cfg(test)]
+mod formatter_tests;
+
 mod fromfile;
 #[cfg(test)]
 mod fromfile_tests;
 
+mod help;
+#[cfg(test)]
+mod help_tests;
+
 mod id;
 #[cfg(test)]
 mod id_tests;
 
+mod intern;
+#[cfg(test)]
+mod intern_tests;
+
+mod interpolation;
+#[cfg(test)]
+mod interpolation_tests;
+
+mod keyring;
+#[cfg(test)]
+mod keyring_tests;
+
+mod lint;
+#[cfg(test)]
+mod lint_tests;
+
+mod lockfile;
+#[cfg(test)]
+mod lockfile_tests;
+
+mod lsp;
+#[cfg(test)]
+mod lsp_tests;
+
+mod merge;
+#[cfg(test)]
+mod merge_tests;
+
+mod nested;
+#[cfg(test)]
+mod nested_tests;
+
+mod order;
+#[cfg(test)]
+mod order_tests;
+
 mod parse;
 #[cfg(test)]
 mod parse_tests;
 
+mod pointer;
+#[cfg(test)]
+mod pointer_tests;
+
+mod redundancy;
+#[cfg(test)]
+mod redundancy_tests;
+
+mod registry;
+#[cfg(test)]
+mod registry_tests;
+
+mod remote;
+#[cfg(test)]
+mod remote_tests;
+
+mod seeds;
+#[cfg(test)]
+mod seeds_tests;
+
+mod semver;
+#[cfg(test)]
+mod semver_tests;
+
+mod shadowing;
+#[cfg(test)]
+mod shadowing_tests;
+
+mod shell;
+#[cfg(test)]
+mod shell_tests;
+
+mod snapshot;
+#[cfg(test)]
+mod snapshot_tests;
+
+mod spec;
+#[cfg(test)]
+mod spec_tests;
+
+mod spelling;
+#[cfg(test)]
+mod spelling_tests;
+
+mod stats;
+#[cfg(test)]
+mod stats_tests;
+
+mod subsystem;
+#[cfg(test)]
+mod subsystem_tests;
+
 #[cfg(test)]
 mod tests;
 
 mod types;
+#[cfg(test)]
+mod types_tests;
+
+mod url;
+#[cfg(test)]
+mod url_tests;
+
+mod user_config;
+#[cfg(test)]
+mod user_config_tests;
+
+mod validate;
+#[cfg(test)]
+mod validate_tests;
 
+mod version;
+#[cfg(test)]
+mod version_tests;
+
+// NB: Allows the code generated by `#[derive(Subsystem)]` to refer to this crate by name.
+extern crate self as options;
+
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
-use serde::Deserialize;
+use indexmap::IndexMap;
+use regex::Regex;
+use serde::{Deserialize, Serialize, Serializer};
+pub use toml::value::Datetime;
 
-pub use self::args::Args;
+pub use self::arg_splitter::{
+    ArgSpan, ArgSplitter, SplitArg, SplitArgs, SplitFlag, NO_GOAL_NAME, UNKNOWN_GOAL_NAME,
+};
 use self::args::ArgsReader;
-pub use self::config::ConfigSource;
+pub use self::args::{Args, PassthroughArgs};
+pub use self::concurrency::{cpu_count, Concurrency};
+pub use self::config::{
+    val_to_toml, ConfigSource, ConfigValidation, ScopeAllowances, UnknownScopePolicy,
+};
 use self::config::{Config, ConfigReader};
+pub use self::conflicts::{ConfigDefinition, TypeConflict};
+pub use self::constraints::{ConstraintViolation, Constraints};
+pub use self::decrypt::{AgeDecryptor, CommandDecryptor, Decryptor};
+use self::decrypt::{MemoizedDecryptor, DEFAULT_DECRYPTION_TIMEOUT};
+pub use self::defaults::{ComputedDefault, DefaultResolver};
+pub use self::deprecation::{DeprecatedUse, DeprecationPolicy};
 pub use self::env::Env;
 use self::env::EnvReader;
-use crate::fromfile::FromfileExpander;
-use crate::parse::Parseable;
+pub use self::explain::{describe_source, Contribution, Edit, Explanation, RawValue};
+use self::explain::{explain_dict, explain_list, explain_scalar};
+use self::keyring::KeyringReader;
+use self::nested::{nested_config_paths, ConfigLoader};
+pub use self::semver::{SemanticVersion, VersionReq};
+use self::shell::ShellInterpolation;
+pub use self::url::Url;
+use crate::fromfile::{FromfileExpander, DEFAULT_MAX_FROMFILE_SIZE};
+use crate::interpolation::is_truthy;
+use crate::parse::{parse_datetime, parse_percentage, Parseable};
+use crate::seeds::LazySeeds;
+use crate::spec::normalize_spec;
 pub use build_root::BuildRoot;
+pub use capture::OptionsCapture;
+pub use completion::{completion_script, Shell};
+pub use export::EnvAssignment;
+pub use filesystem::{FileMetadata, FileSystem, LocalFileSystem, MemoryFileSystem};
+pub use formatter::format_config;
+pub use fromfile::invalidate_fromfile_cache;
+pub use help::{OptionHelp, ScopeHelp};
 pub use id::{OptionId, Scope};
+pub use intern::InternedString;
+pub use keyring::{CredentialStore, KeyringEntry, OsCredentialStore};
+pub use lint::{apply_fixes, Fix, LintFinding, LintRule};
+pub use lockfile::OptionsLock;
+pub use lsp::{serve_lsp, CompletionItem, ConfigLanguageService, Diagnostic, Severity};
+pub use merge::{merge_dict, merge_hashable_list, merge_list, ConflictStrategy, ListPolicy};
+pub use options_derive::Subsystem;
+pub use order::SourceOrder;
+pub use redundancy::Redundancy;
+pub use registry::{OptionInfo, OptionRegistry};
+use remote::RemoteFetcher;
+pub use remote::{AsyncFetcher, FetchError, Fetcher, RemoteError, RemotePolicy};
+pub use shadowing::{ReplaceConflict, Setting, Shadowing};
+pub use snapshot::{OptionsDiff, OptionsSnapshot};
+pub use spelling::Spellings;
+pub use stats::{Counter, OptionsStats, Phase, Timing};
+pub use subsystem::{OptionField, Subsystem};
 pub use types::OptionType;
+use types::{FromVal, ToVal};
+pub use user_config::user_config_files;
+pub use validate::Validator;
+pub use version::{Unavailability, UnavailableOption, Version};
 
 // NB: The legacy Python options parser supported dicts with member_type "Any", which means
 // the values can be arbitrarily-nested lists, tuples and dicts, including heterogeneous
@@ -63,22 +306,47 @@ pub use types::OptionType;
 // We only use this for parsing values in dicts, as in other cases we know that the type must
 // be some scalar or string, or a uniform list of one type of scalar or string, so we can
 // parse as such.
-#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum Val {
     Bool(bool),
     Int(i64),
     Float(f64),
-    String(String),
+    String(InternedString),
     List(Vec<Val>),
-    Dict(HashMap<String, Val>),
+    Dict(IndexMap<String, Val>),
+    // Bytes that need not be UTF-8, such as certificates or key material, which are only read from
+    // `.bin` or `@base64:` fromfiles. They are serialized as base64.
+    #[serde(serialize_with = "base64::serialize")]
+    Bytes(Vec<u8>),
+    // A date or datetime, which may only be written natively in TOML config files (as e.g.
+    // `expiry = 2024-12-31`). It is serialized, and passed to Python, as an RFC 3339 string.
+    #[serde(serialize_with = "serialize_datetime")]
+    Date(Datetime),
+    // The absence of a value: `None` in dict literals, `null` in JSON and YAML, or an option that
+    // was explicitly unset (see `UNSET`).
+    Null,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+fn serialize_datetime<S: Serializer>(
+    datetime: &Datetime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(datetime)
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ListEditAction {
     Replace,
     Add,
     Remove,
+    // Inserts the items at the given index, or at the end if the list is shorter.
+    Insert(usize),
+    // Inserts all but the first item before or after the first occurrence of the first item (the
+    // anchor), or at the end if the anchor is not present.
+    InsertBefore,
+    InsertAfter,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -96,10 +364,14 @@ pub enum DictEditAction {
 #[derive(Clone, Debug, PartialEq)]
 pub struct DictEdit {
     pub action: DictEditAction,
-    pub items: HashMap<String, Val>,
+    pub items: IndexMap<String, Val>,
 }
 
-pub(crate) trait OptionsSource: Send + Sync {
+///
+/// A source of option values. Besides the built-in sources (config files, the keyring, env vars
+/// and flags), embedders may register their own via `OptionParser::register_source`.
+///
+pub trait OptionsSource: Send + Sync {
     ///
     /// Get a display version of the option `id` that most closely matches the syntax used to supply
     /// the id at runtime. For example, an global option of "bob" would display as "--bob" for use in
@@ -136,6 +408,98 @@ pub(crate) trait OptionsSource: Send + Sync {
         }
     }
 
+    ///
+    /// Get the big int option identified by `id` from this source, for the rare options whose
+    /// values may not fit in an i64.
+    /// Errors when this source has an option value for `id` but that value is not an int that fits
+    /// in an i128.
+    ///
+    /// The default implementation looks for a string value for `id` and then attempts to parse it as
+    /// a big int value.
+    ///
+    fn get_big_int(&self, id: &OptionId) -> Result<Option<i128>, String> {
+        if let Some(value) = self.get_string(id)? {
+            i128::parse(&value)
+                .map(Some)
+                .map_err(|e| e.render(self.display(id)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    ///
+    /// Get the bytes option identified by `id` from this source.
+    ///
+    /// The default implementation returns the UTF-8 encoding of any string value for `id`. Sources
+    /// that expand fromfiles should also read raw `.bin` fromfiles and decode `@base64:` fromfiles.
+    ///
+    fn get_bytes(&self, id: &OptionId) -> Result<Option<Vec<u8>>, String> {
+        Ok(self.get_string(id)?.map(String::into_bytes))
+    }
+
+    ///
+    /// Get the path option identified by `id` from this source.
+    ///
+    /// The default implementation returns any string value for `id`. Sources whose values need not
+    /// be UTF-8, such as env vars, should return the raw value.
+    ///
+    fn get_path(&self, id: &OptionId) -> Result<Option<PathBuf>, String> {
+        Ok(self.get_string(id)?.map(PathBuf::from))
+    }
+
+    ///
+    /// Get the concurrency option identified by `id` from this source.
+    /// Errors when this source has an option value for `id` but that value is not `auto`, a
+    /// multiple of the CPU count such as `0.5x`, or a positive int.
+    ///
+    /// The default implementation looks for a string value for `id` and then parses it.
+    ///
+    fn get_concurrency(&self, id: &OptionId) -> Result<Option<Concurrency>, String> {
+        self.get_string(id)?
+            .map(|value| {
+                Concurrency::parse(&value).map_err(|e| {
+                    format!(
+                        "Invalid concurrency `{value}` for {}: {e}",
+                        self.display(id)
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    ///
+    /// Get the date option identified by `id` from this source.
+    /// Errors when this source has an option value for `id` but that value is not an RFC 3339 date
+    /// or datetime.
+    ///
+    /// The default implementation looks for a string value for `id` and then parses it.
+    ///
+    fn get_date(&self, id: &OptionId) -> Result<Option<Datetime>, String> {
+        self.get_string(id)?
+            .map(|value| {
+                parse_datetime(&value)
+                    .map_err(|e| format!("Invalid date `{value}` for {}: {e}", self.display(id)))
+            })
+            .transpose()
+    }
+
+    ///
+    /// Get the percentage option identified by `id` from this source, as a fraction between 0 and 1.
+    /// Errors when this source has an option value for `id` but that value is not a percentage
+    /// such as `80%`, or a fraction such as `0.8`, between 0 and 1.
+    ///
+    /// The default implementation looks for a string value for `id` and then parses it.
+    ///
+    fn get_percentage(&self, id: &OptionId) -> Result<Option<f64>, String> {
+        self.get_string(id)?
+            .map(|value| {
+                parse_percentage(&value).map_err(|e| {
+                    format!("Invalid percentage `{value}` for {}: {e}", self.display(id))
+                })
+            })
+            .transpose()
+    }
+
     ///
     /// Get the float option identified by `id` from this source.
     /// Errors when this source has an option value for `id` but that value is not a float or an int
@@ -185,19 +549,220 @@ pub(crate) trait OptionsSource: Send + Sync {
     ///
     fn get_string_list(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<String>>>, String>;
 
+    ///
+    /// Get the enum list option identified by `id` from this source, each of whose items must be
+    /// one of `choices`.
+    /// Errors when this source has an option value for `id` but that value is not a string list,
+    /// or any of its items (including those to be removed) is not one of the choices.
+    ///
+    /// The default implementation looks for a string list value for `id` and then checks its items.
+    ///
+    fn get_enum_list(
+        &self,
+        id: &OptionId,
+        choices: &[&str],
+    ) -> Result<Option<Vec<ListEdit<String>>>, String> {
+        let edits = self.get_string_list(id)?;
+        if let Some(edits) = &edits {
+            for item in edits.iter().flat_map(|edit| &edit.items) {
+                if !choices.contains(&item.as_str()) {
+                    return Err(format!(
+                        "Invalid value `{item}` for {}: must be one of {}",
+                        self.display(id),
+                        choices.join(", ")
+                    ));
+                }
+            }
+        }
+        Ok(edits)
+    }
+
+    ///
+    /// Get the target spec list option identified by `id` from this source.
+    /// Errors when this source has an option value for `id` but that value is not a string list,
+    /// or any of its items (including those to be removed) is not a valid spec.
+    ///
+    /// The default implementation looks for a string list value for `id` and then normalizes its
+    /// items.
+    ///
+    fn get_spec_list(&self, id: &OptionId) -> Result<Option<Vec<ListEdit<String>>>, String> {
+        self.get_string_list(id)?
+            .map(|edits| {
+                edits
+                    .into_iter()
+                    .map(|edit| {
+                        Ok(ListEdit {
+                            action: edit.action,
+                            items: edit
+                                .items
+                                .iter()
+                                .map(|spec| {
+                                    normalize_spec(spec).map_err(|e| {
+                                        format!(
+                                            "Invalid spec `{spec}` for {}: {e}",
+                                            self.display(id)
+                                        )
+                                    })
+                                })
+                                .collect::<Result<_, _>>()?,
+                        })
+                    })
+                    .collect()
+            })
+            .transpose()
+    }
+
+    ///
+    /// Get the dict list option identified by `id` from this source.
+    /// Errors when this source has an option value for `id` but that value is not a list of dicts.
+    ///
+    #[allow(clippy::type_complexity)]
+    fn get_dict_list(
+        &self,
+        id: &OptionId,
+    ) -> Result<Option<Vec<ListEdit<IndexMap<String, Val>>>>, String>;
+
     ///
     /// Get the dict option identified by `id` from this source.
     /// Errors when this source has an option value for `id` but that value is not a dict.
     ///
     fn get_dict(&self, id: &OptionId) -> Result<Option<Vec<DictEdit>>, String>;
+
+    ///
+    /// Get the raw, unparsed values that this source has for the option identified by `id`, along
+    /// with where each was found, in the order in which they apply.
+    ///
+    fn get_raw(&self, id: &OptionId) -> Vec<RawValue>;
+
+    ///
+    /// Whether the option identified by `id` is explicitly unset in this source, i.e. whether its
+    /// value is `UNSET`.
+    ///
+    /// The default implementation compares the raw value for `id` that applies last to `UNSET`,
+    /// so that nothing is expanded or decrypted to check it.
+    ///
+    fn is_unset(&self, id: &OptionId) -> bool {
+        self.get_raw(id)
+            .pop()
+            .is_some_and(|raw| raw.value.as_deref() == Some(UNSET))
+    }
+
+    ///
+    /// Whether the value of the option identified by `id` in this source is a secret, which must
+    /// not be revealed when explaining the option.
+    ///
+    fn is_secret(&self, _id: &OptionId) -> bool {
+        false
+    }
+
+    ///
+    /// Whether the option identified by `id` is final in this source, so that no source of higher
+    /// precedence may set it.
+    ///
+    fn is_final(&self, _id: &OptionId) -> bool {
+        false
+    }
 }
 
-#[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+///
+/// A kind of built-in source, as ordered by a `SourceOrder`. It is also the precedence of a source
+/// registered via `OptionParser::register_source`: such a source overrides the built-in source of
+/// its kind, and is overridden by the next. E.g. a source with `SourceKind::Env` overrides env
+/// vars, but not flags.
+///
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Config,
+    Keyring,
+    Env,
+    Flag,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
 pub enum Source {
     Default,
-    Config { ordinal: usize, path: String },
+    Config {
+        ordinal: usize,
+        path: String,
+    },
+    Keyring,
     Env,
     Flag,
+    // A source registered by an embedder, identified by its name.
+    Plugin {
+        precedence: SourceKind,
+        name: String,
+    },
+}
+
+impl Source {
+    /// The kind of the source, or of the built-in source that it overrides, if it is not a default.
+    pub fn kind(&self) -> Option<SourceKind> {
+        match self {
+            Source::Default => None,
+            Source::Config { .. } => Some(SourceKind::Config),
+            Source::Keyring => Some(SourceKind::Keyring),
+            Source::Env => Some(SourceKind::Env),
+            Source::Flag => Some(SourceKind::Flag),
+            Source::Plugin { precedence, .. } => Some(*precedence),
+        }
+    }
+
+    // The position of the source in the default order of precedence, before ties between config
+    // files, or between plugins of the same precedence, are broken.
+    fn tier(&self) -> u8 {
+        match self {
+            Source::Default => 0,
+            Source::Config { .. } => 1,
+            Source::Keyring => 3,
+            Source::Env => 5,
+            Source::Flag => 7,
+            Source::Plugin { precedence, .. } => match precedence {
+                SourceKind::Config => 2,
+                SourceKind::Keyring => 4,
+                SourceKind::Env => 6,
+                SourceKind::Flag => 8,
+            },
+        }
+    }
+}
+
+impl Source {
+    // Breaks a tie between two sources of the same position in an order of precedence.
+    fn cmp_within_tier(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (
+                Source::Config { ordinal, path },
+                Source::Config {
+                    ordinal: other_ordinal,
+                    path: other_path,
+                },
+            ) => (ordinal, path).cmp(&(other_ordinal, other_path)),
+            (
+                Source::Plugin { name, .. },
+                Source::Plugin {
+                    name: other_name, ..
+                },
+            ) => name.cmp(other_name),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+impl Ord for Source {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.tier()
+            .cmp(&other.tier())
+            .then_with(|| self.cmp_within_tier(other))
+    }
+}
+
+impl PartialOrd for Source {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 // NB: Must mirror the Rank enum in src/python/pants/option/ranked_value.py.
@@ -218,8 +783,15 @@ impl Source {
                 ordinal: _,
                 path: _,
             } => Rank::CONFIG,
+            // NB: Secrets in the keyring are a form of persistent, per-user config.
+            Source::Keyring => Rank::CONFIG,
             Source::Env => Rank::ENVIRONMENT,
             Source::Flag => Rank::FLAG,
+            Source::Plugin { precedence, .. } => match precedence {
+                SourceKind::Config | SourceKind::Keyring => Rank::CONFIG,
+                SourceKind::Env => Rank::ENVIRONMENT,
+                SourceKind::Flag => Rank::FLAG,
+            },
         }
     }
 }
@@ -261,13 +833,54 @@ pub struct DictOptionValue {
     pub derivation: Option<Vec<(Source, Vec<DictEdit>)>>,
     // The highest-priority source that provided edits for this value.
     pub source: Source,
-    pub value: HashMap<String, Val>,
+    pub value: IndexMap<String, Val>,
 }
 
+///
+/// The value which explicitly unsets an option, in any source: e.g. `--foo=__unset__`,
+/// `PANTS_FOO=__unset__` or `foo = "__unset__"`. An unset option has no value, overriding any
+/// lower-priority source (including the `[DEFAULT]` section) rather than setting an empty string,
+/// list or dict. Getters that require a value fall back to the option's default.
+///
+pub const UNSET: &str = "__unset__";
+
+/// An optional, uncommitted config file which is discovered next to `pants.toml` in the build root
+/// and layered above the other config files (but below any `.pants.rc` files), for settings that
+/// are local to a single checkout.
+pub const LOCAL_CONFIG_FILE: &str = "pants.local.toml";
+
+/// The global options that are read from config files by a parser created with
+/// `OptionParser::bootstrap`.
+pub const BOOTSTRAP_OPTIONS: &[&str] = &["pants_version", "backend_packages", "pythonpath"];
+
+// The union of the schemes supported by the remote providers.
+const REMOTE_ADDRESS_SCHEMES: &[&str] = &["grpc", "grpcs", "http", "https", "file"];
+
+// The sources of a parser, in increasing order of precedence.
+pub(crate) type Sources = IndexMap<Source, Arc<dyn OptionsSource>>;
+
+// NB: Cloning is cheap, as parsed configs and sources are shared rather than copied.
+#[derive(Clone)]
 pub struct OptionParser {
-    sources: BTreeMap<Source, Arc<dyn OptionsSource>>,
+    sources: Sources,
+    source_order: SourceOrder,
+    configs: Vec<Arc<Config>>,
+    fromfile_expander: FromfileExpander,
+    validators: Arc<HashMap<OptionId, Vec<Validator>>>,
+    list_policies: Arc<HashMap<OptionId, ListPolicy>>,
+    computed_defaults: Arc<HashMap<OptionId, ComputedDefault>>,
+    // The types, and so the defaults, of the options registered via `register_defaults`.
+    registered_types: Arc<HashMap<OptionId, OptionType>>,
+    // The names of the registered scopes that are nested in another, via `register_scopes`.
+    nested_scopes: Arc<HashSet<String>>,
     include_derivation: bool,
-    passthrough_args: Option<Vec<String>>,
+    passthrough_args: Option<PassthroughArgs>,
+    // The flags as given, if abbreviated flags may be expanded, which they may not be in CI.
+    abbreviable_args: Option<Arc<ArgsReader>>,
+    deprecation_policy: DeprecationPolicy,
+    // How to read the nested config files of subdirectories, via `for_directory`.
+    config_loader: Option<Arc<ConfigLoader>>,
+    capture: Arc<OptionsCapture>,
 }
 
 impl OptionParser {
@@ -280,30 +893,143 @@ impl OptionParser {
         allow_pantsrc: bool,
         include_derivation: bool,
         buildroot: Option<BuildRoot>,
+    ) -> Result<OptionParser, String> {
+        Self::new_impl(
+            args,
+            env,
+            config_sources,
+            allow_pantsrc,
+            include_derivation,
+            buildroot,
+            Arc::new(LocalFileSystem),
+            None,
+        )
+    }
+
+    ///
+    /// As for `OptionParser::new`, but config files and fromfiles are discovered and read via the
+    /// given filesystem rather than the local one, e.g. for an embedder without a local
+    /// filesystem, such as a browser-based validator compiled to wasm32.
+    ///
+    pub fn new_with_filesystem(
+        args: Args,
+        env: Env,
+        config_sources: Option<Vec<ConfigSource>>,
+        allow_pantsrc: bool,
+        include_derivation: bool,
+        buildroot: Option<BuildRoot>,
+        fs: Arc<dyn FileSystem>,
+    ) -> Result<OptionParser, String> {
+        Self::new_impl(
+            args,
+            env,
+            config_sources,
+            allow_pantsrc,
+            include_derivation,
+            buildroot,
+            fs,
+            None,
+        )
+    }
+
+    ///
+    /// Creates a parser for the cheap first phase of a two-phase parse: the only config values
+    /// read are those of the `BOOTSTRAP_OPTIONS`, whether set in `[GLOBAL]` or `[DEFAULT]`, and
+    /// nothing else in the config files is interpolated. Options other than the bootstrap options
+    /// will only see values from the env and flags. Fromfiles are not expanded, so an option whose
+    /// value is e.g. `@file` fails to parse, and should be taken from the full parse instead. A
+    /// full parser should be created via `OptionParser::new` afterwards.
+    ///
+    pub fn bootstrap(
+        args: Args,
+        env: Env,
+        config_sources: Option<Vec<ConfigSource>>,
+        allow_pantsrc: bool,
+        buildroot: Option<BuildRoot>,
+    ) -> Result<OptionParser, String> {
+        Self::new_impl(
+            args,
+            env,
+            config_sources,
+            allow_pantsrc,
+            false,
+            buildroot,
+            Arc::new(LocalFileSystem),
+            Some(BOOTSTRAP_OPTIONS),
+        )
+    }
+
+    fn new_impl(
+        args: Args,
+        env: Env,
+        config_sources: Option<Vec<ConfigSource>>,
+        allow_pantsrc: bool,
+        include_derivation: bool,
+        buildroot: Option<BuildRoot>,
+        fs: Arc<dyn FileSystem>,
+        bootstrap_options: Option<&'static [&'static str]>,
     ) -> Result<OptionParser, String> {
         let buildroot = buildroot.unwrap_or(BuildRoot::find()?);
         let buildroot_string = buildroot.convert_to_string()?;
         let fromfile_expander = FromfileExpander::relative_to(buildroot);
+        fromfile_expander.set_filesystem(fs.clone());
+        let stats = fromfile_expander.stats().clone();
+        let args = args.expand_response_files(&fromfile_expander)?;
+        // NB: The command line is still read from any response files, but option values are not:
+        // reading an option whose value is a fromfile is an error.
+        let fromfile_expander = if bootstrap_options.is_some() {
+            fromfile_expander.literal()
+        } else {
+            fromfile_expander
+        };
 
+        let user_config_paths = user_config_files(&env);
+        let (arg_strs, env_vars) = (args.arg_strs.clone(), env.env.clone());
         let mut seed_values = HashMap::from_iter(
             env.env
                 .iter()
                 .map(|(k, v)| (format!("env.{k}", k = k), v.clone())),
         );
 
-        let args_reader = ArgsReader::new(args, fromfile_expander.clone());
-        let passthrough_args = args_reader.get_passthrough_args().cloned();
+        let is_ci = env.env.get("CI").is_some_and(|ci| is_truthy(ci));
+        let args_reader = Arc::new(ArgsReader::new(args, fromfile_expander.clone()));
+        if let [first, second, ..] = args_reader.stdin_flags()[..] {
+            return Err(format!(
+                "Only one flag may read its value from stdin with `@-`, but both {first} and \
+                {second} do."
+            ));
+        }
+        // Passthrough args are not bootstrap options, so a bootstrap parse leaves them (and any
+        // fromfiles among them) to the full parse.
+        let passthrough_args = if bootstrap_options.is_some() {
+            None
+        } else {
+            args_reader.expand_passthrough_args()?
+        };
 
         let mut sources: BTreeMap<Source, Arc<dyn OptionsSource>> = BTreeMap::new();
         sources.insert(
             Source::Env,
             Arc::new(EnvReader::new(env, fromfile_expander.clone())),
         );
-        sources.insert(Source::Flag, Arc::new(args_reader));
+        sources.insert(Source::Flag, args_reader.clone());
+        let mut configs = vec![];
         let mut parser = OptionParser {
-            sources: sources.clone(),
+            sources: sources.clone().into_iter().collect(),
+            source_order: SourceOrder::default(),
+            configs: vec![],
+            fromfile_expander: fromfile_expander.clone(),
+            validators: Arc::new(HashMap::new()),
+            list_policies: Arc::new(HashMap::new()),
+            computed_defaults: Arc::new(HashMap::new()),
+            registered_types: Arc::new(HashMap::new()),
+            nested_scopes: Arc::new(HashSet::new()),
             include_derivation: false,
             passthrough_args: None,
+            abbreviable_args: None,
+            deprecation_policy: DeprecationPolicy::default(),
+            config_loader: None,
+            capture: Arc::default(),
         };
 
         fn path_join(prefix: &str, suffix: &str) -> String {
@@ -327,16 +1053,83 @@ impl OptionParser {
             Some(cs) => cs,
             None => {
                 let default_config_path = path_join(&buildroot_string, "pants.toml");
-                let config_paths = parser
+                let mut config_paths = parser
                     .parse_string_list(
                         &option_id!("pants", "config", "files"),
                         vec![default_config_path],
                     )?
-                    .value;
-                config_paths
-                    .iter()
-                    .map(|cp| ConfigSource::from_file(Path::new(&cp)))
-                    .collect::<Result<Vec<_>, _>>()?
+                    .value
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .collect::<Vec<_>>();
+                // Machine-wide and per-user config files are layered beneath the repo's.
+                if allow_pantsrc
+                    && parser
+                        .parse_bool(&option_id!("user", "config"), true)?
+                        .value
+                {
+                    let user_config_paths = user_config_paths
+                        .into_iter()
+                        .filter(|path| fs.is_file(path) && !config_paths.contains(path))
+                        .collect::<Vec<_>>();
+                    config_paths.splice(0..0, user_config_paths);
+                }
+                let local_config_path =
+                    PathBuf::from(path_join(&buildroot_string, LOCAL_CONFIG_FILE));
+                if fs.is_file(&local_config_path) && !config_paths.contains(&local_config_path) {
+                    config_paths.push(local_config_path);
+                }
+                map_concurrently(&config_paths, |cp| ConfigSource::from_filesystem(&*fs, cp))?
+            }
+        };
+
+        // NB: As they let config files run commands, shell interpolation and executable fromfiles
+        // can only be enabled by flags and the env, e.g. as exported by `.pants.bootstrap`. Both
+        // are subject to the same timeout.
+        let timeout = Duration::from_secs(
+            parser
+                .parse_int(&option_id!("shell", "interpolation", "timeout", "secs"), 10)?
+                .value
+                .max(0) as u64,
+        );
+        let shell = if parser
+            .parse_bool(&option_id!("shell", "interpolation"), false)?
+            .value
+        {
+            Some(ShellInterpolation::new(
+                PathBuf::from(&buildroot_string),
+                timeout,
+            ))
+        } else {
+            None
+        };
+        if parser
+            .parse_bool(&option_id!("executable", "fromfiles"), false)?
+            .value
+        {
+            fromfile_expander.set_script_timeout(timeout);
+        }
+        let lazy_seeds = LazySeeds::new(
+            PathBuf::from(&buildroot_string),
+            seed_values.get("env.SOURCE_DATE_EPOCH").cloned(),
+        );
+        let parse_config = |config_source: &ConfigSource, seed_values: &HashMap<String, String>| {
+            match bootstrap_options {
+                Some(option_names) => Config::parse_bootstrap(
+                    config_source,
+                    seed_values,
+                    Some(&lazy_seeds),
+                    option_names,
+                    shell.as_ref(),
+                    &stats,
+                ),
+                None => Config::parse(
+                    config_source,
+                    seed_values,
+                    Some(&lazy_seeds),
+                    shell.as_ref(),
+                    &stats,
+                ),
             }
         };
 
@@ -356,11 +1149,22 @@ impl OptionParser {
             ("user".to_string(), whoami::username()),
             ("pants_workdir".to_string(), subdir("workdir", ".pants.d")?),
             ("pants_distdir".to_string(), subdir("distdir", "dist")?),
+            (
+                "is_ci".to_string(),
+                seed_values
+                    .get("env.CI")
+                    .is_some_and(|ci| is_truthy(ci))
+                    .to_string(),
+            ),
         ]);
 
         let mut ordinal: usize = 0;
-        for config_source in config_sources {
-            let config = Config::parse(&config_source, &seed_values)?;
+        let parsed_configs = map_concurrently(&config_sources, |config_source| {
+            parse_config(config_source, &seed_values)
+        })?;
+        for (config_source, config) in config_sources.iter().zip(parsed_configs) {
+            let config = Arc::new(config);
+            configs.push(config.clone());
             sources.insert(
                 Source::Config {
                     ordinal,
@@ -374,78 +1178,239 @@ impl OptionParser {
             ordinal += 1;
         }
         parser = OptionParser {
-            sources: sources.clone(),
+            sources: sources.clone().into_iter().collect(),
+            source_order: SourceOrder::default(),
+            configs: vec![],
+            fromfile_expander: fromfile_expander.clone(),
+            validators: Arc::new(HashMap::new()),
+            list_policies: Arc::new(HashMap::new()),
+            computed_defaults: Arc::new(HashMap::new()),
+            registered_types: Arc::new(HashMap::new()),
+            nested_scopes: Arc::new(HashSet::new()),
             include_derivation: false,
             passthrough_args: None,
+            abbreviable_args: None,
+            deprecation_policy: DeprecationPolicy::default(),
+            config_loader: None,
+            capture: Arc::default(),
         };
 
-        if allow_pantsrc && parser.parse_bool(&option_id!("pantsrc"), true)?.value {
-            for rcfile in parser
-                .parse_string_list(
-                    &option_id!("pantsrc", "files"),
-                    vec![
+        // NB: Whether config files are strict, and how to decrypt their encrypted values, is
+        // itself read from the (non-strict, undecrypted) config files.
+        let strict_toml = parser
+            .parse_bool(&option_id!("strict", "toml"), false)?
+            .value;
+        let fromfile_max_size = parser
+            .parse_int(
+                &option_id!("fromfile", "max", "size"),
+                DEFAULT_MAX_FROMFILE_SIZE as i64,
+            )?
+            .value;
+        fromfile_expander.set_max_size(fromfile_max_size.max(0) as u64);
+        let fromfile_resolve_symlinks = parser
+            .parse_bool(&option_id!("fromfile", "resolve", "symlinks"), false)?
+            .value;
+        fromfile_expander.set_resolve_symlinks(fromfile_resolve_symlinks);
+        let decryption_command = parser
+            .parse_string_list(&option_id!("decryption", "command"), vec![])?
+            .value;
+        let decryption_timeout = Duration::from_secs(
+            parser
+                .parse_int(
+                    &option_id!("decryption", "timeout", "secs"),
+                    DEFAULT_DECRYPTION_TIMEOUT.as_secs() as i64,
+                )?
+                .value
+                .max(0) as u64,
+        );
+        let decryptor: Option<Arc<dyn Decryptor>> = if decryption_command.is_empty() {
+            parser
+                .parse_string_optional(&option_id!("age", "identity"), None)?
+                .value
+                .map(|identity| {
+                    Arc::new(
+                        AgeDecryptor::new(PathBuf::from(shellexpand::tilde(&identity).as_ref()))
+                            .timeout(decryption_timeout),
+                    ) as Arc<dyn Decryptor>
+                })
+        } else {
+            Some(Arc::new(
+                CommandDecryptor::new(decryption_command)?.timeout(decryption_timeout),
+            ))
+        };
+        // NB: Each distinct value is decrypted once, however many times its option is read.
+        let decryptor = decryptor
+            .map(|decryptor| Arc::new(MemoizedDecryptor::new(decryptor)) as Arc<dyn Decryptor>);
+        if strict_toml || decryptor.is_some() {
+            for (source_type, source) in sources.iter_mut() {
+                if let Source::Config { ordinal, .. } = source_type {
+                    *source = Arc::new(
+                        ConfigReader::new(configs[*ordinal].clone(), fromfile_expander.clone())
+                            .strict(strict_toml)
+                            .decryptor(decryptor.clone()),
+                    );
+                }
+            }
+        }
+
+        if allow_pantsrc && parser.parse_bool(&option_id!("pantsrc"), true)?.value {
+            let rcfiles = parser
+                .parse_string_list(
+                    &option_id!("pantsrc", "files"),
+                    vec![
                         "/etc/pantsrc".to_string(),
                         shellexpand::tilde("~/.pants.rc").to_string(),
                         ".pants.rc".to_string(),
                     ],
                 )?
                 .value
-            {
-                let rcfile_path = Path::new(&rcfile);
-                if rcfile_path.exists() {
-                    let rc_config =
-                        Config::parse(&ConfigSource::from_file(rcfile_path)?, &seed_values)?;
-                    sources.insert(
-                        Source::Config {
-                            ordinal,
-                            path: rcfile,
-                        },
-                        Arc::new(ConfigReader::new(rc_config, fromfile_expander.clone())),
-                    );
-                    ordinal += 1;
-                }
+                .into_iter()
+                .filter(|rcfile| fs.metadata(Path::new(rcfile)).is_ok())
+                .collect::<Vec<_>>();
+            let rc_configs = map_concurrently(&rcfiles, |rcfile| {
+                parse_config(&ConfigSource::from_filesystem(&*fs, rcfile)?, &seed_values)
+            })?;
+            for (rcfile, rc_config) in rcfiles.into_iter().zip(rc_configs) {
+                let rc_config = Arc::new(rc_config);
+                configs.push(rc_config.clone());
+                sources.insert(
+                    Source::Config {
+                        ordinal,
+                        path: rcfile,
+                    },
+                    Arc::new(
+                        ConfigReader::new(rc_config, fromfile_expander.clone())
+                            .strict(strict_toml)
+                            .decryptor(decryptor.clone()),
+                    ),
+                );
+                ordinal += 1;
             }
         }
-        Ok(OptionParser {
-            sources,
+        let capture = Arc::new(OptionsCapture::new(arg_strs, &env_vars, &configs));
+        parser = OptionParser {
+            sources: sources.into_iter().collect(),
+            source_order: SourceOrder::default(),
+            configs,
+            fromfile_expander: fromfile_expander.clone(),
+            validators: Arc::new(HashMap::new()),
+            list_policies: Arc::new(HashMap::new()),
+            computed_defaults: Arc::new(HashMap::new()),
+            registered_types: Arc::new(HashMap::new()),
+            nested_scopes: Arc::new(HashSet::new()),
             include_derivation,
             passthrough_args,
-        })
+            abbreviable_args: (!is_ci).then_some(args_reader),
+            deprecation_policy: DeprecationPolicy::default(),
+            config_loader: Some(Arc::new(ConfigLoader {
+                buildroot: PathBuf::from(&buildroot_string),
+                seed_values,
+                lazy_seeds,
+                shell,
+                stats,
+                bootstrap_options,
+                strict_toml,
+                decryptor,
+                fromfile_expander,
+            })),
+            capture,
+        };
+        parser.deprecation_policy = DeprecationPolicy {
+            as_errors: parser
+                .parse_bool(&option_id!("deprecations", "as", "errors"), false)?
+                .value,
+            allowed: parser
+                .parse_string_list(&option_id!("allowed", "deprecations"), vec![])?
+                .value
+                .into_iter()
+                .collect(),
+        };
+        // NB: The remote addresses are validated up front, so that a typo fails before any
+        // connection is attempted. Which schemes a given remote provider supports is checked later.
+        for id in [
+            option_id!("remote", "store", "address"),
+            option_id!("remote", "execution", "address"),
+        ] {
+            parser.parse_url_optional(&id, None, REMOTE_ADDRESS_SCHEMES)?;
+        }
+        Ok(parser)
+    }
+
+    // Reads an option from the given type of source, accounting the time taken in the statistics.
+    fn read<R>(&self, source_type: &Source, read: impl FnOnce() -> R) -> R {
+        let _span = self
+            .fromfile_expander
+            .stats()
+            .span(Phase::resolving(source_type));
+        read()
     }
 
     #[allow(clippy::type_complexity)]
+    // If `required`, an explicitly unset option resolves to its default rather than to no value.
     fn parse_scalar<T: ToOwned + ?Sized>(
         &self,
         id: &OptionId,
         default: Option<&T>,
+        required: bool,
         getter: fn(&Arc<dyn OptionsSource>, &OptionId) -> Result<Option<T::Owned>, String>,
-    ) -> Result<OptionalOptionValue<T::Owned>, String> {
+    ) -> Result<OptionalOptionValue<T::Owned>, String>
+    where
+        T::Owned: FromVal + ToVal,
+    {
+        self.check_final(id)?;
+        let mut resolved = None;
+        for (source_type, source) in self.sources.iter().rev() {
+            if source.is_unset(id) {
+                if !required {
+                    resolved = Some((source_type.clone(), None));
+                }
+                break;
+            }
+            if let Some(value) = self.read(source_type, || getter(source, id))? {
+                resolved = Some((source_type.clone(), Some(value)));
+                break;
+            }
+        }
+        let default = |compute: bool| -> Result<Option<T::Owned>, String> {
+            Ok(self
+                .default_override::<T::Owned>(id, compute)?
+                .or_else(|| default.map(|x| x.to_owned())))
+        };
+        let (source, value) = match resolved {
+            Some(resolved) => resolved,
+            None => (Source::Default, default(true)?),
+        };
         let mut derivation = None;
         if self.include_derivation {
             let mut derivations = vec![];
+            // NB: A computed default which was not needed is not evaluated, nor derived.
+            let default = if source == Source::Default {
+                value.as_ref().map(|value| value.borrow().to_owned())
+            } else if self.computed_defaults.contains_key(id) {
+                None
+            } else {
+                default(false)?
+            };
             if let Some(def) = default {
-                derivations.push((Source::Default, def.to_owned()));
+                derivations.push((Source::Default, def));
             }
             for (source_type, source) in self.sources.iter() {
-                if let Some(val) = getter(source, id)? {
+                if source.is_unset(id) {
+                    continue;
+                }
+                if let Some(val) = self.read(source_type, || getter(source, id))? {
                     derivations.push((source_type.clone(), val));
                 }
             }
             derivation = Some(derivations);
         }
-        for (source_type, source) in self.sources.iter().rev() {
-            if let Some(value) = getter(source, id)? {
-                return Ok(OptionalOptionValue {
-                    derivation,
-                    source: source_type.clone(),
-                    value: Some(value),
-                });
-            }
+        if let Some(value) = &value {
+            self.validate(id, &source, value)?;
         }
         Ok(OptionalOptionValue {
             derivation,
-            source: Source::Default,
-            value: default.map(|x| x.to_owned()),
+            source,
+            value,
         })
     }
 
@@ -454,7 +1419,9 @@ impl OptionParser {
         id: &OptionId,
         default: Option<bool>,
     ) -> Result<OptionalOptionValue<bool>, String> {
-        self.parse_scalar(id, default.as_ref(), |source, id| source.get_bool(id))
+        self.parse_scalar(id, default.as_ref(), false, |source, id| {
+            source.get_bool(id)
+        })
     }
 
     pub fn parse_int_optional(
@@ -462,7 +1429,37 @@ impl OptionParser {
         id: &OptionId,
         default: Option<i64>,
     ) -> Result<OptionalOptionValue<i64>, String> {
-        self.parse_scalar(id, default.as_ref(), |source, id| source.get_int(id))
+        self.parse_scalar(id, default.as_ref(), false, |source, id| source.get_int(id))
+    }
+
+    pub fn parse_big_int_optional(
+        &self,
+        id: &OptionId,
+        default: Option<i128>,
+    ) -> Result<OptionalOptionValue<i128>, String> {
+        self.parse_scalar(id, default.as_ref(), false, |source, id| {
+            source.get_big_int(id)
+        })
+    }
+
+    pub fn parse_bytes_optional(
+        &self,
+        id: &OptionId,
+        default: Option<&[u8]>,
+    ) -> Result<OptionalOptionValue<Vec<u8>>, String> {
+        self.parse_scalar(id, default, false, |source, id| source.get_bytes(id))
+    }
+
+    ///
+    /// Parses a path. Unlike a string, a path may be set to a value that is not UTF-8, by an env
+    /// var.
+    ///
+    pub fn parse_path_optional(
+        &self,
+        id: &OptionId,
+        default: Option<&Path>,
+    ) -> Result<OptionalOptionValue<PathBuf>, String> {
+        self.parse_scalar(id, default, false, |source, id| source.get_path(id))
     }
 
     pub fn parse_float_optional(
@@ -470,7 +1467,9 @@ impl OptionParser {
         id: &OptionId,
         default: Option<f64>,
     ) -> Result<OptionalOptionValue<f64>, String> {
-        self.parse_scalar(id, default.as_ref(), |source, id| source.get_float(id))
+        self.parse_scalar(id, default.as_ref(), false, |source, id| {
+            source.get_float(id)
+        })
     }
 
     pub fn parse_string_optional(
@@ -478,21 +1477,112 @@ impl OptionParser {
         id: &OptionId,
         default: Option<&str>,
     ) -> Result<OptionalOptionValue<String>, String> {
-        self.parse_scalar(id, default, |source, id| source.get_string(id))
+        self.parse_scalar(id, default, false, |source, id| source.get_string(id))
     }
 
     pub fn parse_bool(&self, id: &OptionId, default: bool) -> Result<OptionValue<bool>, String> {
-        self.parse_bool_optional(id, Some(default))
+        self.parse_scalar(id, Some(&default), true, |source, id| source.get_bool(id))
             .map(OptionalOptionValue::unwrap)
     }
 
     pub fn parse_int(&self, id: &OptionId, default: i64) -> Result<OptionValue<i64>, String> {
-        self.parse_int_optional(id, Some(default))
+        self.parse_scalar(id, Some(&default), true, |source, id| source.get_int(id))
             .map(OptionalOptionValue::unwrap)
     }
 
+    pub fn parse_big_int(&self, id: &OptionId, default: i128) -> Result<OptionValue<i128>, String> {
+        self.parse_scalar(id, Some(&default), true, |source, id| {
+            source.get_big_int(id)
+        })
+        .map(OptionalOptionValue::unwrap)
+    }
+
+    ///
+    /// Parses a date or datetime, for options such as expiry dates.
+    ///
+    pub fn parse_date_optional(
+        &self,
+        id: &OptionId,
+        default: Option<&Datetime>,
+    ) -> Result<OptionalOptionValue<Datetime>, String> {
+        self.parse_scalar(id, default, false, |source, id| source.get_date(id))
+    }
+
+    ///
+    /// Parses a percentage, such as `80%` or `0.8`, to a fraction between 0 and 1.
+    ///
+    pub fn parse_percentage_optional(
+        &self,
+        id: &OptionId,
+        default: Option<f64>,
+    ) -> Result<OptionalOptionValue<f64>, String> {
+        self.check_default_percentage(id, default)?;
+        self.parse_scalar(id, default.as_ref(), false, |source, id| {
+            source.get_percentage(id)
+        })
+    }
+
+    pub fn parse_percentage(
+        &self,
+        id: &OptionId,
+        default: f64,
+    ) -> Result<OptionValue<f64>, String> {
+        self.check_default_percentage(id, Some(default))?;
+        self.parse_scalar(id, Some(&default), true, |source, id| {
+            source.get_percentage(id)
+        })
+        .map(OptionalOptionValue::unwrap)
+    }
+
+    fn check_default_percentage(&self, id: &OptionId, default: Option<f64>) -> Result<(), String> {
+        match default {
+            Some(default) => parse::check_percentage(default)
+                .map(|_| ())
+                .map_err(|e| format!("Invalid default percentage {default} for {id}: {e}")),
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// Parses a concurrency, and resolves it against the number of CPUs, so that each consumer of
+    /// a concurrency option needn't interpret `auto` and `0.5x` itself.
+    ///
+    pub fn parse_concurrency(
+        &self,
+        id: &OptionId,
+        default: Concurrency,
+    ) -> Result<OptionValue<usize>, String> {
+        self.parse_concurrency_for(id, default, cpu_count())
+    }
+
+    ///
+    /// As for `parse_concurrency`, but resolves against the given number of CPUs.
+    ///
+    pub fn parse_concurrency_for(
+        &self,
+        id: &OptionId,
+        default: Concurrency,
+        cpus: usize,
+    ) -> Result<OptionValue<usize>, String> {
+        let value = self
+            .parse_scalar(id, Some(&default), true, |source, id| {
+                source.get_concurrency(id)
+            })
+            .map(OptionalOptionValue::unwrap)?;
+        Ok(OptionValue {
+            derivation: value.derivation.map(|derivation| {
+                derivation
+                    .into_iter()
+                    .map(|(source, concurrency)| (source, concurrency.resolve(cpus)))
+                    .collect()
+            }),
+            source: value.source,
+            value: value.value.resolve(cpus),
+        })
+    }
+
     pub fn parse_float(&self, id: &OptionId, default: f64) -> Result<OptionValue<f64>, String> {
-        self.parse_float_optional(id, Some(default))
+        self.parse_scalar(id, Some(&default), true, |source, id| source.get_float(id))
             .map(OptionalOptionValue::unwrap)
     }
 
@@ -501,30 +1591,53 @@ impl OptionParser {
         id: &OptionId,
         default: &str,
     ) -> Result<OptionValue<String>, String> {
-        self.parse_string_optional(id, Some(default))
+        self.parse_scalar(id, Some(default), true, |source, id| source.get_string(id))
             .map(OptionalOptionValue::unwrap)
     }
 
     #[allow(clippy::type_complexity)]
-    fn parse_list<T: Clone + Debug>(
+    fn parse_list<T: Clone + Debug + FromVal + ToVal>(
         &self,
         id: &OptionId,
         default: Vec<T>,
-        getter: fn(&Arc<dyn OptionsSource>, &OptionId) -> Result<Option<Vec<ListEdit<T>>>, String>,
-        remover: fn(&mut Vec<T>, &Vec<T>),
+        getter: impl Fn(&Arc<dyn OptionsSource>, &OptionId) -> Result<Option<Vec<ListEdit<T>>>, String>,
+        merger: fn(Vec<T>, Vec<ListEdit<T>>, ConflictStrategy) -> Result<Vec<T>, String>,
     ) -> Result<ListOptionValue<T>, String> {
-        let mut list = default;
+        self.check_final(id)?;
+        let mut edits = vec![];
+        let mut highest_priority_source = Source::Default;
+        for (source_type, source) in self.sources.iter() {
+            if source.is_unset(id) {
+                // Discard the edits of lower-priority sources, leaving the default.
+                edits.clear();
+                highest_priority_source = Source::Default;
+            } else if let Some(list_edits) = self.read(source_type, || getter(source, id))? {
+                highest_priority_source = source_type.clone();
+                edits.extend(list_edits);
+            }
+        }
+        // NB: A computed default is only evaluated if it is needed, i.e. if it is not replaced.
+        let compute = !edits
+            .iter()
+            .any(|edit| edit.action == ListEditAction::Replace);
+        let default = self.default_override(id, compute)?.unwrap_or(default);
         let mut derivation = None;
         if self.include_derivation {
-            let mut derivations = vec![(
-                Source::Default,
-                vec![ListEdit {
-                    action: ListEditAction::Replace,
-                    items: list.clone(),
-                }],
-            )];
+            let mut derivations = vec![];
+            if compute || !self.computed_defaults.contains_key(id) {
+                derivations.push((
+                    Source::Default,
+                    vec![ListEdit {
+                        action: ListEditAction::Replace,
+                        items: default.clone(),
+                    }],
+                ));
+            }
             for (source_type, source) in self.sources.iter() {
-                if let Some(list_edits) = getter(source, id)? {
+                if source.is_unset(id) {
+                    continue;
+                }
+                if let Some(list_edits) = self.read(source_type, || getter(source, id))? {
                     if !list_edits.is_empty() {
                         derivations.push((source_type.clone(), list_edits));
                     }
@@ -532,34 +1645,15 @@ impl OptionParser {
             }
             derivation = Some(derivations);
         }
-
-        // Removals from any source apply after adds from any source (but are themselves
-        // overridden by later replacements), so we collect them here and apply them later.
-        let mut removal_lists: Vec<Vec<T>> = vec![];
-
-        let mut highest_priority_source = Source::Default;
-        for (source_type, source) in self.sources.iter() {
-            if let Some(list_edits) = getter(source, id)? {
-                highest_priority_source = source_type.clone();
-                for list_edit in list_edits {
-                    match list_edit.action {
-                        ListEditAction::Replace => {
-                            list = list_edit.items;
-                            removal_lists.clear();
-                        }
-                        ListEditAction::Add => list.extend(list_edit.items),
-                        ListEditAction::Remove => removal_lists.push(list_edit.items),
-                    }
-                }
-            }
-        }
-        for removals in removal_lists {
-            remover(&mut list, &removals);
-        }
+        let policy = self.list_policies.get(id).copied().unwrap_or_default();
+        let value = merger(default, edits, policy.conflict_strategy())
+            .map(|value| policy.normalize(value))
+            .map_err(|e| format!("Invalid value for {id}: {e}"))?;
+        self.validate(id, &highest_priority_source, &value)?;
         Ok(ListOptionValue {
             derivation,
             source: highest_priority_source,
-            value: list,
+            value,
         })
     }
 
@@ -570,16 +1664,13 @@ impl OptionParser {
     // However this is still more than fast enough, and inoculates us against a very unlikely
     // pathological case of a very large removal set.
     #[allow(clippy::type_complexity)]
-    fn parse_list_hashable<T: Clone + Debug + Eq + Hash>(
+    fn parse_list_hashable<T: Clone + Debug + Eq + Hash + FromVal + ToVal>(
         &self,
         id: &OptionId,
         default: Vec<T>,
         getter: fn(&Arc<dyn OptionsSource>, &OptionId) -> Result<Option<Vec<ListEdit<T>>>, String>,
     ) -> Result<ListOptionValue<T>, String> {
-        self.parse_list(id, default, getter, |list, remove| {
-            let to_remove = remove.iter().collect::<HashSet<_>>();
-            list.retain(|item| !to_remove.contains(item));
-        })
+        self.parse_list(id, default, getter, merge_hashable_list)
     }
 
     pub fn parse_bool_list(
@@ -608,9 +1699,7 @@ impl OptionParser {
             id,
             default,
             |source, id| source.get_float_list(id),
-            |list, to_remove| {
-                list.retain(|item| !to_remove.contains(item));
-            },
+            merge_list,
         )
     }
 
@@ -622,50 +1711,1222 @@ impl OptionParser {
         self.parse_list_hashable::<String>(id, default, |source, id| source.get_string_list(id))
     }
 
+    ///
+    /// Parses a list of strings, each of which must be one of `choices`.
+    ///
+    pub fn parse_enum_list(
+        &self,
+        id: &OptionId,
+        default: Vec<String>,
+        choices: &[&str],
+    ) -> Result<ListOptionValue<String>, String> {
+        if let Some(item) = default
+            .iter()
+            .find(|item| !choices.contains(&item.as_str()))
+        {
+            return Err(format!(
+                "Invalid default `{item}` for {id}: must be one of {}",
+                choices.join(", ")
+            ));
+        }
+        self.parse_list(
+            id,
+            default,
+            |source, id| source.get_enum_list(id, choices),
+            merge_hashable_list,
+        )
+    }
+
+    ///
+    /// Parses a list of target specs. The specs of each source are checked and normalized as they
+    /// are read, so that a malformed spec is reported along with the source that it came from,
+    /// rather than when the build graph is constructed, and so that e.g. `dir/` removes `dir`.
+    ///
+    pub fn parse_spec_list(
+        &self,
+        id: &OptionId,
+        default: Vec<String>,
+    ) -> Result<ListOptionValue<String>, String> {
+        let default = default
+            .iter()
+            .map(|spec| {
+                normalize_spec(spec)
+                    .map_err(|e| format!("Invalid default spec `{spec}` for {id}: {e}"))
+            })
+            .collect::<Result<_, _>>()?;
+        self.parse_list_hashable::<String>(id, default, |source, id| source.get_spec_list(id))
+    }
+
+    ///
+    /// Parses a regex. The value is compiled as it is resolved, so that an invalid pattern is
+    /// reported along with the source that it came from, rather than by its consumer.
+    ///
+    pub fn parse_regex_optional(
+        &self,
+        id: &OptionId,
+        default: Option<&str>,
+    ) -> Result<OptionalOptionValue<Regex>, String> {
+        self.convert_string_optional(
+            id,
+            default,
+            |pattern| format!("regex `{pattern}`"),
+            |pattern| Regex::new(pattern).map_err(|e| e.to_string()),
+        )
+    }
+
+    ///
+    /// Parses a list of regexes. As for `parse_regex_optional`, each item is compiled as it is
+    /// resolved, and is reported along with the source that added it. Edits remove regexes with the
+    /// same pattern.
+    ///
+    pub fn parse_regex_list(
+        &self,
+        id: &OptionId,
+        default: Vec<String>,
+    ) -> Result<ListOptionValue<Regex>, String> {
+        self.convert_string_list(
+            id,
+            default,
+            |pattern| format!("regex `{pattern}`"),
+            |pattern| Regex::new(pattern).map_err(|e| e.to_string()),
+        )
+    }
+
+    ///
+    /// Parses a semantic version. As for `parse_regex_optional`, the value is validated along with
+    /// the source that it came from.
+    ///
+    pub fn parse_version_optional(
+        &self,
+        id: &OptionId,
+        default: Option<&str>,
+    ) -> Result<OptionalOptionValue<SemanticVersion>, String> {
+        self.convert_string_optional(
+            id,
+            default,
+            |_| "version".to_owned(),
+            self::semver::parse_version,
+        )
+    }
+
+    ///
+    /// Parses a version requirement, such as `^1.2` or `>=1.2, <2`. As for
+    /// `parse_regex_optional`, the value is validated along with the source that it came from.
+    ///
+    pub fn parse_version_req_optional(
+        &self,
+        id: &OptionId,
+        default: Option<&str>,
+    ) -> Result<OptionalOptionValue<VersionReq>, String> {
+        self.convert_string_optional(
+            id,
+            default,
+            |_| "version requirement".to_owned(),
+            self::semver::parse_version_req,
+        )
+    }
+
+    // Parses a string option, and then converts its value and derivation. Each source is read
+    // once, and only the resolved value must convert, so that an invalid value which is overridden
+    // (or unset) by another source is not an error. A value that fails to convert is reported as
+    // "Invalid <subject> for <where it came from>: <error>".
+    fn convert_string_optional<T>(
+        &self,
+        id: &OptionId,
+        default: Option<&str>,
+        subject: impl Fn(&str) -> String,
+        convert: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<OptionalOptionValue<T>, String> {
+        let OptionalOptionValue {
+            derivation,
+            source,
+            value,
+        } = self.parse_string_optional(id, default)?;
+        let convert = |source: &Source, value: String| {
+            convert(&value).map_err(|e| match self.sources.get(source) {
+                Some(s) => format!("Invalid {} for {}: {e}", subject(&value), s.display(id)),
+                None => format!("Invalid default {} for {id}: {e}", subject(&value)),
+            })
+        };
+        let value = value.map(|value| convert(&source, value)).transpose()?;
+        // NB: An overridden value that does not convert has no bearing on the option, and so is
+        // only warned about.
+        let derivation = derivation.map(|derivation| {
+            derivation
+                .into_iter()
+                .filter_map(|(source, value)| match convert(&source, value) {
+                    Ok(value) => Some((source, value)),
+                    Err(e) => {
+                        log::warn!("{e}");
+                        None
+                    }
+                })
+                .collect()
+        });
+        Ok(OptionalOptionValue {
+            derivation,
+            source,
+            value,
+        })
+    }
+
+    ///
+    /// Parses a string list, and then converts each of its items. As for `convert_string_optional`,
+    /// only the items of the resolved value must convert, and the items of derivation entries that
+    /// don't are warned about and dropped.
+    ///
+    fn convert_string_list<T>(
+        &self,
+        id: &OptionId,
+        default: Vec<String>,
+        subject: impl Fn(&str) -> String,
+        convert: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<ListOptionValue<T>, String> {
+        // The highest-priority source to add each item, recorded as the sources are read, so that an
+        // item that does not convert can be reported against the source that it came from.
+        let added_by = RefCell::new(HashMap::new());
+        let ListOptionValue {
+            derivation,
+            source,
+            value,
+        } = self.parse_list(
+            id,
+            default,
+            |source, id| {
+                let edits = source.get_string_list(id)?;
+                for edit in edits.iter().flatten() {
+                    // NB: The first item of a relative insert is the anchor, which it doesn't add.
+                    let added: &[String] = match edit.action {
+                        ListEditAction::Remove => &[],
+                        ListEditAction::InsertBefore | ListEditAction::InsertAfter => {
+                            edit.items.get(1..).unwrap_or(&[])
+                        }
+                        _ => &edit.items,
+                    };
+                    for item in added {
+                        added_by
+                            .borrow_mut()
+                            .insert(item.clone(), source.display(id));
+                    }
+                }
+                Ok(edits)
+            },
+            merge_hashable_list,
+        )?;
+        let added_by = added_by.into_inner();
+        let error = |display: Option<&String>, item: &str, e: String| match display {
+            Some(display) => format!("Invalid {} for {display}: {e}", subject(item)),
+            None => format!("Invalid default {} for {id}: {e}", subject(item)),
+        };
+        let value = value
+            .into_iter()
+            .map(|item| convert(&item).map_err(|e| error(added_by.get(&item), &item, e)))
+            .collect::<Result<_, _>>()?;
+        // NB: An overridden item that does not convert has no bearing on the option, and so is only
+        // warned about.
+        let derivation = derivation.map(|derivation| {
+            derivation
+                .into_iter()
+                .map(|(source, edits)| {
+                    let display = self.sources.get(&source).map(|s| s.display(id));
+                    let edits = edits
+                        .into_iter()
+                        .map(|edit| ListEdit {
+                            action: edit.action,
+                            items: edit
+                                .items
+                                .iter()
+                                .filter_map(|item| match convert(item) {
+                                    Ok(item) => Some(item),
+                                    Err(e) => {
+                                        log::warn!("{}", error(display.as_ref(), item, e));
+                                        None
+                                    }
+                                })
+                                .collect(),
+                        })
+                        .collect();
+                    (source, edits)
+                })
+                .collect()
+        });
+        Ok(ListOptionValue {
+            derivation,
+            source,
+            value,
+        })
+    }
+
+    ///
+    /// Parses a URL whose scheme is one of `schemes` (or any scheme, when empty). As for
+    /// `parse_regex_optional`, the value is validated along with the source that it came from.
+    /// Warns when the URL embeds credentials.
+    ///
+    pub fn parse_url_optional(
+        &self,
+        id: &OptionId,
+        default: Option<&str>,
+        schemes: &[&str],
+    ) -> Result<OptionalOptionValue<Url>, String> {
+        let value = self.convert_string_optional(
+            id,
+            default,
+            |value| format!("URL `{}`", self::url::redact(value)),
+            |value| Url::parse_with_schemes(value, schemes),
+        )?;
+        if let Some(url) = value.value.as_ref().filter(|url| url.has_credentials()) {
+            let display = match self.sources.get(&value.source) {
+                Some(s) => s.display(id),
+                None => id.to_string(),
+            };
+            log::warn!(
+                "The URL `{url}` for {display} embeds credentials, which may be exposed wherever \
+                it is logged or stored. Consider passing them via headers instead."
+            );
+        }
+        Ok(value)
+    }
+
+    ///
+    /// Parses a list of dicts. Edits add and remove whole dicts, and a dict is only removed by an
+    /// equal dict.
+    ///
+    pub fn parse_dict_list(
+        &self,
+        id: &OptionId,
+        default: Vec<IndexMap<String, Val>>,
+    ) -> Result<ListOptionValue<IndexMap<String, Val>>, String> {
+        // Dicts are not Hash, so we fall back to the brute-force O(N*M) lookups.
+        self.parse_list(
+            id,
+            default,
+            |source, id| source.get_dict_list(id),
+            merge_list,
+        )
+    }
+
     pub fn parse_dict(
         &self,
         id: &OptionId,
-        default: HashMap<String, Val>,
+        default: IndexMap<String, Val>,
     ) -> Result<DictOptionValue, String> {
-        let mut dict = default;
+        self.check_final(id)?;
+        let mut edits = vec![];
+        let mut highest_priority_source = Source::Default;
+        for (source_type, source) in self.sources.iter() {
+            if source.is_unset(id) {
+                // Discard the edits of lower-priority sources, leaving the default.
+                edits.clear();
+                highest_priority_source = Source::Default;
+            } else if let Some(dict_edits) = self.read(source_type, || source.get_dict(id))? {
+                highest_priority_source = source_type.clone();
+                edits.extend(dict_edits);
+            }
+        }
+        // NB: A computed default is only evaluated if it is needed, i.e. if it is not replaced.
+        let compute = !edits
+            .iter()
+            .any(|edit| edit.action == DictEditAction::Replace);
+        let default = self.default_override(id, compute)?.unwrap_or(default);
         let mut derivation = None;
         if self.include_derivation {
-            let mut derivations = vec![(
-                Source::Default,
-                vec![DictEdit {
-                    action: DictEditAction::Replace,
-                    items: dict.clone(),
-                }],
-            )];
+            let mut derivations = vec![];
+            if compute || !self.computed_defaults.contains_key(id) {
+                derivations.push((
+                    Source::Default,
+                    vec![DictEdit {
+                        action: DictEditAction::Replace,
+                        items: default.clone(),
+                    }],
+                ));
+            }
             for (source_type, source) in self.sources.iter() {
-                if let Some(dict_edits) = source.get_dict(id)? {
+                if source.is_unset(id) {
+                    continue;
+                }
+                if let Some(dict_edits) = self.read(source_type, || source.get_dict(id))? {
                     derivations.push((source_type.clone(), dict_edits));
                 }
             }
             derivation = Some(derivations);
         }
-        let mut highest_priority_source = Source::Default;
-        for (source_type, source) in self.sources.iter() {
-            if let Some(dict_edits) = source.get_dict(id)? {
-                highest_priority_source = source_type.clone();
-                for dict_edit in dict_edits {
-                    match dict_edit.action {
-                        DictEditAction::Replace => dict = dict_edit.items,
-                        DictEditAction::Add => dict.extend(dict_edit.items),
-                    }
-                }
-            }
-        }
+        let value = merge_dict(default, edits, ConflictStrategy::LastWins)?;
+        self.validate(id, &highest_priority_source, &value)?;
         Ok(DictOptionValue {
             derivation,
             source: highest_priority_source,
-            value: dict,
+            value,
         })
     }
 
-    pub fn get_passthrough_args(&self) -> Option<&Vec<String>> {
-        self.passthrough_args.as_ref()
+    ///
+    /// Resolves the dict option identified by `id`, and returns the value nested within it at the
+    /// given JSON-pointer-like path of keys and list indices, e.g. `tools/0/version`, or None if
+    /// there is no such value. See `pointer::value_at` for the syntax of paths.
+    ///
+    pub fn get_dict_value_at(&self, id: &OptionId, path: &str) -> Result<Option<Val>, String> {
+        let dict = Val::Dict(self.parse_dict(id, IndexMap::new())?.value);
+        pointer::value_at(&dict, path)
+            .map(|value| value.cloned())
+            .map_err(|e| format!("Failed to look up `{path}` in {id}: {e}"))
+    }
+
+    ///
+    /// Registers a validator for the option identified by `id`, which will be applied whenever
+    /// the option is read via one of the typed getters.
+    ///
+    pub fn register_validator(&mut self, id: OptionId, validator: Validator) {
+        Arc::make_mut(&mut self.validators)
+            .entry(id)
+            .or_default()
+            .push(validator);
+    }
+
+    ///
+    /// Registers the list policies of the options of the registry, which normalize their values,
+    /// e.g. by removing duplicates, after their edits are folded.
+    ///
+    pub fn register_list_policies(&mut self, registry: &OptionRegistry) {
+        let list_policies = Arc::make_mut(&mut self.list_policies);
+        for option in registry.options() {
+            if option.list_policy != ListPolicy::Keep {
+                list_policies.insert(option.id.clone(), option.list_policy);
+            }
+        }
+    }
+
+    ///
+    /// Registers the secret options of the registry, i.e. those with a keyring entry, to be read
+    /// from the given credential store. Values in the store override those in config files, but
+    /// may still be overridden by env vars and flags, e.g. in CI.
+    ///
+    pub fn register_keyring(&mut self, registry: &OptionRegistry, store: Arc<dyn CredentialStore>) {
+        let entries = registry
+            .options()
+            .filter_map(|option| Some((option.id.clone(), option.keyring.clone()?)))
+            .collect::<HashMap<_, _>>();
+        if entries.is_empty() {
+            self.sources.remove(&Source::Keyring);
+        } else {
+            self.sources.insert(
+                Source::Keyring,
+                Arc::new(KeyringReader::new(entries, store)),
+            );
+            self.order_sources();
+        }
+    }
+
+    ///
+    /// Registers an additional source of option values, such as a settings service, which takes
+    /// precedence as described by `SourceKind`. Its values are reported, e.g. when explaining an
+    /// option, under the given name. A source of the same name and precedence is replaced.
+    ///
+    pub fn register_source(
+        &mut self,
+        name: &str,
+        precedence: SourceKind,
+        source: Arc<dyn OptionsSource>,
+    ) {
+        self.sources.insert(
+            Source::Plugin {
+                precedence,
+                name: name.to_owned(),
+            },
+            source,
+        );
+        self.order_sources();
+    }
+
+    ///
+    /// Returns a parser for work rooted at the given directory, which is either absolute or
+    /// relative to the build root. The `pants.toml` files of the directory and of its ancestors
+    /// below the build root are layered above all of this parser's config files, with deeper files
+    /// taking precedence, but below the keyring, env vars and flags. Nested config files may only
+    /// set the options of subsystems and goals, as global options apply to the whole repo.
+    ///
+    pub fn for_directory(&self, dir: &Path) -> Result<OptionParser, String> {
+        let Some(loader) = &self.config_loader else {
+            return Ok(self.clone());
+        };
+        let mut parser = self.clone();
+        let fs = loader.fromfile_expander.filesystem();
+        for path in nested_config_paths(&*fs, &loader.buildroot, dir)? {
+            let (config, reader) = loader.load(&path)?;
+            let source = Source::Config {
+                ordinal: parser.configs.len(),
+                path: path
+                    .strip_prefix(&loader.buildroot)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            parser.configs.push(config);
+            parser.sources.insert(
+                source,
+                Arc::new(reader.nested_scopes(parser.nested_scopes.clone())),
+            );
+        }
+        parser.order_sources();
+        Ok(parser)
+    }
+
+    ///
+    /// Reorders the sources of option values, or drops some of them, e.g. so that config files
+    /// take precedence over env vars in a hermetic CI environment. Sources of the kinds that the
+    /// order omits are discarded, along with any registered sources which take precedence over
+    /// them. The bootstrap options that the parser has already read are unaffected.
+    ///
+    pub fn set_source_order(&mut self, source_order: SourceOrder) {
+        self.source_order = source_order;
+        self.order_sources();
+    }
+
+    ///
+    /// The sources of option values, in increasing order of precedence, for diagnostics.
+    ///
+    pub fn effective_source_order(&self) -> Vec<Source> {
+        self.sources.keys().cloned().collect()
+    }
+
+    fn order_sources(&mut self) {
+        let order = &self.source_order;
+        self.sources
+            .retain(|source, _| order.position(source).is_some());
+        self.sources.sort_by(|a, _, b, _| {
+            order
+                .position(a)
+                .cmp(&order.position(b))
+                .then_with(|| a.cmp_within_tier(b))
+        });
+    }
+
+    ///
+    /// Reads fromfiles, and the nested config files of `for_directory`, from the given filesystem
+    /// rather than the one the parser was created with. Config files that have already been read
+    /// are not affected: to also discover those via a filesystem other than the local one, create
+    /// the parser via `OptionParser::new_with_filesystem`.
+    ///
+    pub fn set_filesystem(&mut self, fs: Arc<dyn FileSystem>) {
+        self.fromfile_expander.set_filesystem(fs);
+    }
+
+    ///
+    /// Enables fromfiles that refer to remote content, e.g. `@https://example.com/args.json`,
+    /// which are fetched via the given fetcher according to the given policy.
+    ///
+    pub fn register_remote_fetcher(&mut self, fetcher: Arc<dyn Fetcher>, policy: RemotePolicy) {
+        self.fromfile_expander
+            .set_remote(RemoteFetcher::new(fetcher, policy));
+    }
+
+    ///
+    /// Enables fromfiles that refer to remote content, as `register_remote_fetcher`, but fetched
+    /// without blocking via the given async fetcher, e.g. on the engine's tokio runtime. The
+    /// content must then be fetched by awaiting `prefetch_remote` before options are read.
+    ///
+    pub fn register_async_remote_fetcher(
+        &mut self,
+        fetcher: Arc<dyn AsyncFetcher>,
+        policy: RemotePolicy,
+    ) {
+        self.fromfile_expander
+            .set_remote(RemoteFetcher::new_async(fetcher, policy));
+    }
+
+    ///
+    /// Fetches, concurrently and without blocking, all the remote content that the registered
+    /// options refer to, so that they may then be read without blocking. Does nothing unless an
+    /// async fetcher was registered via `register_async_remote_fetcher`.
+    ///
+    /// Content that fails to be fetched is not an error here, but when the options that refer to
+    /// it are read.
+    ///
+    pub async fn prefetch_remote(&self, registry: &OptionRegistry) {
+        let Some(remote) = self.fromfile_expander.remote() else {
+            return;
+        };
+        // NB: The remote content that an option refers to is only discovered by resolving it, and
+        // a failure to resolve one value may hide references in others, so we resolve until there
+        // is nothing left to fetch.
+        loop {
+            for option in registry.options() {
+                let _ = self.resolve(&option.id, option.option_type.clone());
+            }
+            if !remote.prefetch_pending().await {
+                return;
+            }
+        }
+    }
+
+    ///
+    /// Registers a function that computes the default value of the option identified by `id`,
+    /// replacing any default passed to the typed getters. The function may read the values of
+    /// other options via the given `DefaultResolver`, so that derived defaults are consistent with
+    /// however those options were set.
+    ///
+    pub fn register_computed_default(
+        &mut self,
+        id: OptionId,
+        computed_default: impl Fn(&DefaultResolver) -> Result<Val, String> + Send + Sync + 'static,
+    ) {
+        Arc::make_mut(&mut self.computed_defaults).insert(id, Arc::new(computed_default));
+    }
+
+    ///
+    /// Registers the defaults of the options of the registry, which replace any defaults passed to
+    /// the typed getters (but not computed defaults), so that the registry is the single source of
+    /// truth for defaults. They are also used by `get_value` and `is_default`.
+    ///
+    pub fn register_defaults(&mut self, registry: &OptionRegistry) {
+        let registered_types = Arc::make_mut(&mut self.registered_types);
+        for option in registry.options() {
+            registered_types.insert(option.id.clone(), option.option_type.clone());
+        }
+    }
+
+    ///
+    /// Registers the scopes of the options of the registry. The sub-table of a nested scope in a
+    /// config file, e.g. `[test.pytest]`, is then read as the section of that scope only, and not
+    /// also as the value of an option `[test] pytest` of the enclosing scope.
+    ///
+    pub fn register_scopes(&mut self, registry: &OptionRegistry) {
+        self.nested_scopes = Arc::new(
+            registry
+                .scopes()
+                .into_iter()
+                .map(Scope::name)
+                .filter(|name| name.contains('.'))
+                .map(str::to_owned)
+                .collect(),
+        );
+        let Some(loader) = &self.config_loader else {
+            return;
+        };
+        for (source, reader) in self.sources.iter_mut() {
+            if let Source::Config { ordinal, .. } = source {
+                *reader = Arc::new(
+                    loader
+                        .reader(self.configs[*ordinal].clone())
+                        .nested_scopes(self.nested_scopes.clone()),
+                );
+            }
+        }
+    }
+
+    ///
+    /// Expands abbreviated flags, i.e. prefixes of the flags of the options of the registry, e.g.
+    /// `--concur` for `--concurrent`. A flag that abbreviates more than one is an error, which
+    /// lists the flags that it abbreviates.
+    ///
+    /// Abbreviations are not expanded in CI (i.e. when the `CI` env var is truthy), since an
+    /// abbreviation that is unambiguous today may become ambiguous when an option is added.
+    ///
+    pub fn expand_abbreviated_flags(&mut self, registry: &OptionRegistry) -> Result<(), String> {
+        // NB: Flags that were dropped from the source order (see `set_source_order`) stay dropped.
+        if let (Some(args_reader), Some(flags)) =
+            (&self.abbreviable_args, self.sources.get_mut(&Source::Flag))
+        {
+            *flags = Arc::new(args_reader.expand_abbreviations(registry)?);
+        }
+        Ok(())
+    }
+
+    // The default of the option identified by `id`, if it is computed or registered, which
+    // overrides the default passed to a getter. A computed default is only evaluated if `compute`,
+    // i.e. if the value of the option depends on it, as it may be expensive, or fail.
+    fn default_override<T: FromVal>(
+        &self,
+        id: &OptionId,
+        compute: bool,
+    ) -> Result<Option<T>, String> {
+        if let Some(computed_default) = self.computed_defaults.get(id) {
+            if !compute {
+                return Ok(None);
+            }
+            let value = defaults::compute(self, id, computed_default)?;
+            return T::from_val(value)
+                .map(Some)
+                .map_err(|e| format!("Invalid computed default for {id}: {e}"));
+        }
+        let Some(option_type) = self.registered_types.get(id) else {
+            return Ok(None);
+        };
+        T::from_val(option_type.default_val())
+            .map(Some)
+            .map_err(|e| format!("The registered default of {id} does not match its getter: {e}"))
+    }
+
+    ///
+    /// Whether the option identified by `id` takes its default value, i.e. no source sets it, or
+    /// it is explicitly unset. The option is resolved as its registered type, if any (see
+    /// `register_defaults`), and otherwise as for `get_value`.
+    ///
+    pub fn is_default(&self, id: &OptionId) -> Result<bool, String> {
+        let source = match self.registered_types.get(id) {
+            Some(option_type) => self.resolve(id, option_type.clone())?.1,
+            None => self.get_value(id)?.source,
+        };
+        Ok(source == Source::Default)
+    }
+
+    ///
+    /// Errors if the option identified by `id` is final in one source (see `is_final`), but is
+    /// nonetheless set by a source of higher precedence.
+    ///
+    fn check_final(&self, id: &OptionId) -> Result<(), String> {
+        let mut sources = self.sources.iter();
+        let Some(final_source) = sources
+            .by_ref()
+            .find_map(|(source_type, source)| source.is_final(id).then_some(source_type))
+        else {
+            return Ok(());
+        };
+        for (source_type, source) in sources {
+            if !source.get_raw(id).is_empty() {
+                return Err(format!(
+                    "{} may not be set (by {}), as {id} is final in {}.",
+                    source.display(id),
+                    describe_source(source_type),
+                    describe_source(final_source)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate<T: ToVal>(&self, id: &OptionId, source: &Source, value: &T) -> Result<(), String> {
+        let Some(validators) = self.validators.get(id) else {
+            return Ok(());
+        };
+        let value = value.to_val();
+        // NB: A secret value, e.g. one that was decrypted, is not revealed by the problem with it.
+        let is_secret = self.sources.values().any(|source| source.is_secret(id));
+        for validator in validators {
+            let problem = if is_secret {
+                validator.check_redacted(&value)
+            } else {
+                validator.check(&value)
+            };
+            if let Some(problem) = problem {
+                let display = match self.sources.get(source) {
+                    Some(s) => s.display(id),
+                    None => id.to_string(),
+                };
+                return Err(format!(
+                    "Invalid value for {display} (from {}): {problem}",
+                    describe_source(source)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Explains how the value of the option identified by `id` is derived, given its type and
+    /// default value. The explanation is computed regardless of `include_derivation`.
+    ///
+    pub fn explain(&self, id: &OptionId, option_type: OptionType) -> Result<Explanation, String> {
+        let parser = OptionParser {
+            include_derivation: true,
+            ..self.clone()
+        };
+        let sources = &self.sources;
+        Ok(match option_type {
+            OptionType::Bool(default) => {
+                explain_scalar(id, sources, parser.parse_bool(id, default)?)
+            }
+            OptionType::Int(default) => explain_scalar(id, sources, parser.parse_int(id, default)?),
+            OptionType::Float(default) => {
+                explain_scalar(id, sources, parser.parse_float(id, default)?)
+            }
+            OptionType::String(default) => {
+                explain_scalar(id, sources, parser.parse_string(id, &default)?)
+            }
+            OptionType::BoolList(default) => {
+                explain_list(id, sources, parser.parse_bool_list(id, default)?)
+            }
+            OptionType::IntList(default) => {
+                explain_list(id, sources, parser.parse_int_list(id, default)?)
+            }
+            OptionType::FloatList(default) => {
+                explain_list(id, sources, parser.parse_float_list(id, default)?)
+            }
+            OptionType::StringList(default) => {
+                explain_list(id, sources, parser.parse_string_list(id, default)?)
+            }
+            OptionType::DictList(default) => {
+                explain_list(id, sources, parser.parse_dict_list(id, default)?)
+            }
+            OptionType::Dict(default) => explain_dict(id, sources, parser.parse_dict(id, default)?),
+        })
+    }
+
+    ///
+    /// The args after `--`, if any, along with the scope of the goal that they are passed through
+    /// to. Any fromfiles among them, as in `-- @args.txt`, are expanded into the args they contain.
+    ///
+    pub fn get_passthrough_args(&self) -> Option<&PassthroughArgs> {
+        self.passthrough_args.as_ref()
+    }
+
+    ///
+    /// Creates a snapshot of the resolved values of every option of the registry, recording the
+    /// inputs to this parser, to be persisted. See `OptionsSnapshot`.
+    ///
+    pub fn snapshot(&self, registry: &OptionRegistry) -> Result<OptionsSnapshot, String> {
+        self.resolve_snapshot(
+            registry
+                .options()
+                .map(|option| (option.id.clone(), option.option_type.clone())),
+        )
+    }
+
+    ///
+    /// Creates a snapshot containing the resolved values of the given options, each of the given
+    /// type and default. The snapshots of two differently configured parsers can be compared with
+    /// `OptionsSnapshot::diff`.
+    ///
+    pub fn resolve_snapshot(
+        &self,
+        options: impl IntoIterator<Item = (OptionId, OptionType)>,
+    ) -> Result<OptionsSnapshot, String> {
+        let values = options
+            .into_iter()
+            .map(|(id, option_type)| {
+                let (value, _) = self.resolve(&id, option_type)?;
+                // NB: Secret values are never persisted, but their digests let changes be detected.
+                let value = if self.sources.values().any(|source| source.is_secret(&id)) {
+                    snapshot::redacted(&value)
+                } else {
+                    value
+                };
+                Ok((id, value))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        // NB: Resolving may expand fromfiles, so we only snapshot the consumed files afterwards.
+        let env_vars_read = capture::env_vars_read(&self.configs);
+        let mut snapshot = OptionsSnapshot::new(
+            snapshot::inputs_digest(&self.capture.args, &self.capture.env, &env_vars_read),
+            env_vars_read,
+            self.consumed_files(),
+            self.remote_inputs(),
+            self.fromfile_expander.volatile_inputs(),
+        );
+        for (id, value) in values {
+            snapshot.insert(&id, value);
+        }
+        Ok(snapshot)
+    }
+
+    ///
+    /// Resolves the value of the option identified by `id`, of the given type and default, along
+    /// with the source it was resolved from.
+    ///
+    pub(crate) fn resolve(
+        &self,
+        id: &OptionId,
+        option_type: OptionType,
+    ) -> Result<(Val, Source), String> {
+        fn resolved<T: ToVal>(value: OptionValue<T>) -> (Val, Source) {
+            (value.value.to_val(), value.source)
+        }
+        fn resolved_list<T: ToVal>(value: ListOptionValue<T>) -> (Val, Source) {
+            (value.value.to_val(), value.source)
+        }
+        Ok(match option_type {
+            OptionType::Bool(default) => resolved(self.parse_bool(id, default)?),
+            OptionType::Int(default) => resolved(self.parse_int(id, default)?),
+            OptionType::Float(default) => resolved(self.parse_float(id, default)?),
+            OptionType::String(default) => resolved(self.parse_string(id, &default)?),
+            OptionType::BoolList(default) => resolved_list(self.parse_bool_list(id, default)?),
+            OptionType::IntList(default) => resolved_list(self.parse_int_list(id, default)?),
+            OptionType::FloatList(default) => resolved_list(self.parse_float_list(id, default)?),
+            OptionType::StringList(default) => resolved_list(self.parse_string_list(id, default)?),
+            OptionType::DictList(default) => resolved_list(self.parse_dict_list(id, default)?),
+            OptionType::Dict(default) => {
+                let value = self.parse_dict(id, default)?;
+                (Val::Dict(value.value), value.source)
+            }
+        })
+    }
+
+    ///
+    /// Returns the resolved value of the option identified by `id`, with all edits applied,
+    /// without needing to know its declared type. Returns a value of None if no source provides a
+    /// value for the option and it has no computed or registered default, and a value of
+    /// `Val::Null` if the option is explicitly unset.
+    ///
+    /// The type is inferred from the values provided by the sources, preferring scalars to lists to
+    /// dicts, and falling back to a string. E.g. `--foo=5` and `--foo=1` are ints, `--foo=yes` is a
+    /// bool, `--foo=+[5]` is a list of ints, and `--foo=bar` is a string. Generic tooling should
+    /// prefer the typed getters when the type is known, as e.g. a string option that was set to `5`
+    /// will be reported as an int.
+    ///
+    pub fn get_value(&self, id: &OptionId) -> Result<OptionalOptionValue<Val>, String> {
+        let raw_values = self
+            .sources
+            .values()
+            .flat_map(|source| source.get_raw(id))
+            .collect::<Vec<_>>();
+        if raw_values.is_empty() {
+            let value = match self.computed_defaults.get(id) {
+                Some(computed_default) => Some(defaults::compute(self, id, computed_default)?),
+                None => self
+                    .registered_types
+                    .get(id)
+                    .map(|option_type| option_type.default_val()),
+            };
+            return Ok(OptionalOptionValue {
+                derivation: None,
+                source: Source::Default,
+                value,
+            });
+        }
+        if let Some((source_type, _)) = self
+            .sources
+            .iter()
+            .rev()
+            .find(|(_, source)| !source.get_raw(id).is_empty())
+            .filter(|(_, source)| source.is_unset(id))
+        {
+            return Ok(OptionalOptionValue {
+                derivation: None,
+                source: source_type.clone(),
+                value: Some(Val::Null),
+            });
+        }
+
+        let looks_like_list = raw_values.iter().any(|raw| {
+            let value = raw.value.as_deref().unwrap_or("").trim_start();
+            let value = value.strip_prefix(['+', '-']).unwrap_or(value);
+            value.starts_with(['[', '('])
+                || value.starts_with("{add = [")
+                || value.starts_with("{remove = [")
+        });
+        // NB: Ints are preferred to bools, which may also be spelled `1` and `0`.
+        let mut candidates = vec![
+            OptionType::Int(0),
+            OptionType::Bool(false),
+            OptionType::Float(0.0),
+        ];
+        if looks_like_list {
+            candidates.extend([
+                OptionType::IntList(vec![]),
+                OptionType::FloatList(vec![]),
+                OptionType::BoolList(vec![]),
+                OptionType::DictList(vec![]),
+                OptionType::StringList(vec![]),
+            ]);
+        }
+        candidates.extend([
+            OptionType::Dict(IndexMap::new()),
+            OptionType::String(String::new()),
+        ]);
+        // NB: The candidates are resolved without computed defaults or validators, so that neither
+        // is applied to a type that the option does not have. In particular, an invalid value must
+        // not be inferred to be of another type instead.
+        let inferring = OptionParser {
+            computed_defaults: Arc::default(),
+            validators: Arc::default(),
+            ..self.clone()
+        };
+        for candidate in candidates {
+            if let Ok((value, source)) = inferring.resolve(id, candidate) {
+                self.validate(id, &source, &value)?;
+                return Ok(OptionalOptionValue {
+                    derivation: None,
+                    source,
+                    value: Some(value),
+                });
+            }
+        }
+        Err(format!(
+            "Could not infer the type of {id} from its values: {}",
+            raw_values
+                .iter()
+                .map(|raw| format!("{} in {}", raw.value.as_deref().unwrap_or(""), raw.location))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+
+    ///
+    /// Returns the highest-priority source that explicitly provides a value for the option
+    /// identified by `id`, if any. An option that is explicitly unset has no such source. Its
+    /// `kind` tells e.g. whether the option was set by a flag, rather than in a config file.
+    ///
+    pub fn explicit_source(&self, id: &OptionId) -> Option<Source> {
+        self.sources
+            .iter()
+            .rev()
+            .find(|(_, source)| !source.get_raw(id).is_empty())
+            .filter(|(_, source)| !source.is_unset(id))
+            .map(|(source_type, _)| source_type.clone())
+    }
+
+    ///
+    /// Whether any source explicitly provides a value for the option identified by `id`, e.g. for
+    /// a subsystem that behaves differently when the user opts in rather than inheriting a default.
+    /// A value that equals the default still counts as explicitly set. Unlike `is_default`, this
+    /// does not resolve the option's value.
+    ///
+    pub fn is_explicitly_set(&self, id: &OptionId) -> bool {
+        self.explicit_source(id).is_some()
+    }
+
+    ///
+    /// Overrides the policy for deprecated options, which is otherwise read from
+    /// `[GLOBAL].deprecations_as_errors` and `[GLOBAL].allowed_deprecations`.
+    ///
+    pub fn set_deprecation_policy(&mut self, policy: DeprecationPolicy) {
+        self.deprecation_policy = policy;
+    }
+
+    ///
+    /// Checks whether any of the deprecated options in the registry are set, returning their uses
+    /// as warnings. If the deprecation policy treats them as errors, then any use of a deprecated
+    /// option that is not allowlisted is an error instead.
+    ///
+    pub fn check_deprecations(
+        &self,
+        registry: &OptionRegistry,
+    ) -> Result<Vec<DeprecatedUse>, String> {
+        deprecation::check(self, registry, &self.deprecation_policy)
+    }
+
+    ///
+    /// Checks that the options of the registry which are set exist in the given running version
+    /// of Pants, returning those which are only introduced in a later version, or which have
+    /// already been removed.
+    ///
+    pub fn check_versions(
+        &self,
+        registry: &OptionRegistry,
+        running: &Version,
+    ) -> Vec<UnavailableOption> {
+        version::check(self, registry, running)
+    }
+
+    ///
+    /// Checks that the options of the registry which are set in both the DEFAULT section and the
+    /// section of their scope are set to consistent kinds of value, returning the conflicts in
+    /// which either value is not valid for the registered type of the option, to be reported as
+    /// warnings. This should be called once the options are registered.
+    ///
+    pub fn check_config_types(&self, registry: &OptionRegistry) -> Vec<TypeConflict> {
+        conflicts::check(&self.configs, registry)
+    }
+
+    ///
+    /// Reports the values of the options of the registry which are explicitly set, but make no
+    /// difference: those equal to the value already set by a source of lower precedence, or to
+    /// the option's default. Such values may be pruned from config files, or are a sign of
+    /// confusion about which source takes effect.
+    ///
+    pub fn check_redundancy(&self, registry: &OptionRegistry) -> Result<Vec<Redundancy>, String> {
+        redundancy::check(self, registry)
+    }
+
+    ///
+    /// Reports every setting of the options of the registry which has no effect because a source
+    /// of higher precedence replaces it, along with the setting that replaces it, to explain why a
+    /// change to e.g. `pants.toml` does not take effect.
+    ///
+    pub fn check_shadowing(&self, registry: &OptionRegistry) -> Result<Vec<Shadowing>, String> {
+        shadowing::check(self, registry)
+    }
+
+    ///
+    /// Reports every pair of sources which both replace the value of a list or dict option of the
+    /// registry with different values, e.g. two config files which each set the full list, to catch
+    /// such conflicts early rather than letting the latter silently win.
+    ///
+    pub fn check_replace_conflicts(
+        &self,
+        registry: &OptionRegistry,
+    ) -> Result<Vec<ReplaceConflict>, String> {
+        shadowing::check_replace_conflicts(self, registry)
+    }
+
+    ///
+    /// Checks the given cross-option constraints against the values of this parser, returning
+    /// any violations.
+    ///
+    pub fn check_constraints(
+        &self,
+        constraints: &Constraints,
+    ) -> Result<Vec<ConstraintViolation>, String> {
+        constraints.check(self)
+    }
+
+    ///
+    /// The args of this parser, with any response files (e.g. `@args.rsp`) expanded, which is how
+    /// they should be split into goals, specs and flags.
+    ///
+    pub fn get_args(&self) -> &[String] {
+        &self.capture.args
+    }
+
+    ///
+    /// The args and env vars that this parser consumed, to be replayed via `from_capture`.
+    ///
+    pub fn capture(&self) -> OptionsCapture {
+        self.capture.as_ref().clone()
+    }
+
+    ///
+    /// Creates a parser that resolves options from the args and env vars of a capture, rather than
+    /// those of the current process, along with the config files of the given build root.
+    ///
+    pub fn from_capture(
+        capture: &OptionsCapture,
+        allow_pantsrc: bool,
+        include_derivation: bool,
+        buildroot: Option<BuildRoot>,
+    ) -> Result<OptionParser, String> {
+        Self::new(
+            capture.args(),
+            capture.env(),
+            None,
+            allow_pantsrc,
+            include_derivation,
+            buildroot,
+        )
+    }
+
+    ///
+    /// Renders the effective config, i.e. the resolved value of every option of the registry, as
+    /// TOML in the form of `pants.toml`. If `annotate`, each value is preceded by a comment noting
+    /// where it came from, e.g. `# from pants.ci.toml:42`, `# from PANTS_LEVEL` or `# default`, so
+    /// that the dump documents the precedence of the sources.
+    ///
+    /// Secret values, and values which TOML cannot represent (such as `None`), are commented out.
+    ///
+    pub fn dump_config(&self, registry: &OptionRegistry, annotate: bool) -> Result<String, String> {
+        dump::dump(self, registry, annotate)
+    }
+
+    ///
+    /// Renders the options of the registry that are set to other than their defaults (e.g. by a
+    /// canonical `pants.toml`) as assignments of the env vars that would set them to the same
+    /// values, e.g. to generate a wrapper script or the env of a container. Secret options are not
+    /// exported.
+    ///
+    pub fn export_env(&self, registry: &OptionRegistry) -> Result<Vec<EnvAssignment>, String> {
+        export::export(self, registry)
+    }
+
+    ///
+    /// Resolves every option of the registry, to record them, and the digests of every file and
+    /// env var that they were derived from, in a lockfile.
+    ///
+    pub fn lock(&self, registry: &OptionRegistry) -> Result<OptionsLock, String> {
+        lockfile::lock(self, registry)
+    }
+
+    ///
+    /// Checks that every option of the registry resolves exactly as recorded in the given lockfile,
+    /// from the same files and env vars, e.g. to ensure that a release build is reproducible.
+    ///
+    pub fn verify_lock(&self, registry: &OptionRegistry, lock: &OptionsLock) -> Result<(), String> {
+        lock.verify(&self.lock(registry)?)
+    }
+
+    ///
+    /// Returns every file consumed so far while resolving options: the config files, and any
+    /// fromfiles expanded by the options read until now. Each path is mapped to the digest of its
+    /// content, or to None for an optional (@?) fromfile that did not exist.
+    ///
+    /// NB: Fromfiles are expanded lazily, so this should be called after all options of interest
+    /// have been read.
+    ///
+    pub fn consumed_files(&self) -> BTreeMap<PathBuf, Option<String>> {
+        let mut consumed_files = self.fromfile_expander.consumed_files();
+        consumed_files.extend(self.configs.iter().map(|config| {
+            (
+                config.path().to_path_buf(),
+                Some(config.digest().to_owned()),
+            )
+        }));
+        consumed_files
+    }
+
+    ///
+    /// Returns every remote fromfile fetched so far while resolving options, by its reference as
+    /// written, e.g. `sha256=<hex>+https://example.com/args.json`. Each is mapped to the digest of
+    /// its content, or to None for an optional (@?) remote fromfile that was unavailable.
+    ///
+    pub fn remote_inputs(&self) -> BTreeMap<String, Option<String>> {
+        self.fromfile_expander.remote_inputs()
+    }
+
+    ///
+    /// Statistics about where option parsing has spent its time so far: reading and interpolating
+    /// config files, reading fromfiles, and resolving options from each type of source.
+    ///
+    pub fn stats(&self) -> OptionsStats {
+        self.fromfile_expander.stats().snapshot()
+    }
+
+    ///
+    /// Lints all config files against the registered options, reporting values that are redundant
+    /// with defaults or with lower priority config files, values of deprecated options, unused
+    /// DEFAULT keys, and fromfiles that do not exist. Fixable findings may be applied to the
+    /// content of their config file with `apply_fixes`.
+    ///
+    pub fn lint_config(&self, registry: &OptionRegistry) -> Vec<LintFinding> {
+        lint::lint(&self.configs, &self.fromfile_expander, registry)
+    }
+
+    ///
+    /// Validates the sections and keys of all config files against the given known scopes and
+    /// their option names. Sections that match none of the known scopes are checked against the
+    /// `allowances`, so that scopes which will only be registered later (such as those of
+    /// third-party plugins) need not fail validation.
+    ///
+    pub fn validate_config(
+        &self,
+        section_to_valid_keys: &HashMap<String, HashSet<String>>,
+        allowances: &ScopeAllowances,
+    ) -> ConfigValidation {
+        let mut validation = ConfigValidation::default();
+        for config in self.configs.iter() {
+            config.validate(section_to_valid_keys, allowances, &mut validation);
+        }
+        validation
+    }
+}
+
+///
+/// Applies `f` to each of the given items, concurrently if there is more than one, and returns the
+/// results in the order of the items. Used to read and parse multiple config files (which may be
+/// large and generated) in parallel, while still merging them deterministically.
+///
+/// If any application fails, the error for the earliest such item is returned.
+///
+fn map_concurrently<T: Sync, R: Send>(
+    items: &[T],
+    f: impl Fn(&T) -> Result<R, String> + Sync,
+) -> Result<Vec<R>, String> {
+    // NB: There are no threads on wasm32, where spawning one panics.
+    if items.len() <= 1 || cfg!(target_arch = "wasm32") {
+        return items.iter().map(f).collect();
     }
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles = items
+            .iter()
+            .map(|item| scope.spawn(move || f(item)))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    })
 }
 
 pub fn render_choice(items: &[&str]) -> Option<String> {
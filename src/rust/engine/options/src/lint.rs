@@ -0,0 +1,248 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use toml::Value;
+
+use crate::config::{toml_value_to_val, Config, DEFAULT_SECTION, INHERIT_DEFAULT_KEY};
+use crate::fromfile::FromfileExpander;
+use crate::id::NameTransform;
+use crate::interpolation::{condition_name, tokenize, Token};
+use crate::registry::OptionRegistry;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LintRule {
+    // A value identical to the option's default.
+    RedundantDefault,
+    // A value identical to the one already set by a config file of lower priority.
+    Repeated,
+    // A value for a deprecated option.
+    Deprecated,
+    // A DEFAULT key that is neither an option nor referenced by any placeholder.
+    DeadDefaultKey,
+    // A fromfile reference (`@path`) to a file that does not exist.
+    UnreachableFromfile,
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LintRule::RedundantDefault => "redundant-default",
+            LintRule::Repeated => "repeated",
+            LintRule::Deprecated => "deprecated",
+            LintRule::DeadDefaultKey => "dead-default-key",
+            LintRule::UnreachableFromfile => "unreachable-fromfile",
+        };
+        write!(f, "{name}")
+    }
+}
+
+///
+/// A machine-applicable fix for a finding: the removal of the (1-based) line of its config file
+/// which sets the offending value. See `apply_fixes`.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Fix {
+    pub line: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LintFinding {
+    pub rule: LintRule,
+    pub path: PathBuf,
+    // The 1-based line of the finding, if it could be located.
+    pub line: Option<usize>,
+    pub message: String,
+    pub fix: Option<Fix>,
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": [{}] {}", self.rule, self.message)
+    }
+}
+
+///
+/// Applies the given fixes to the content of the config file that they were found in, returning
+/// the fixed content. Fixes for lines that do not exist are ignored.
+///
+pub fn apply_fixes(content: &str, fixes: &[Fix]) -> String {
+    let removed_lines = fixes.iter().map(|fix| fix.line).collect::<HashSet<_>>();
+    let mut fixed = String::with_capacity(content.len());
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        if !removed_lines.contains(&(i + 1)) {
+            fixed.push_str(line);
+        }
+    }
+    fixed
+}
+
+// A fix to remove the given line of the config, as long as it sets a single value and nothing else.
+fn removal_fix(config: &Config, line: Option<usize>) -> Option<Fix> {
+    let line = line?;
+    let text = config.content().lines().nth(line - 1)?.trim();
+    if text.starts_with('[') {
+        return None;
+    }
+    match text.parse::<Value>() {
+        Ok(Value::Table(table)) if table.len() == 1 => Some(Fix { line }),
+        _ => None,
+    }
+}
+
+fn collect_strings<'a>(value: &'a Value, strings: &mut Vec<&'a str>) {
+    match value {
+        Value::String(s) => strings.push(s),
+        Value::Array(values) => values.iter().for_each(|v| collect_strings(v, strings)),
+        Value::Table(table) => table.values().for_each(|v| collect_strings(v, strings)),
+        _ => (),
+    }
+}
+
+///
+/// Lints the given config files, in increasing order of priority, against the registered options.
+///
+pub(crate) fn lint(
+    configs: &[Arc<Config>],
+    fromfile_expander: &FromfileExpander,
+    registry: &OptionRegistry,
+) -> Vec<LintFinding> {
+    let mut findings = vec![];
+    let finding =
+        |rule, config: &Config, line: Option<usize>, message: String, fixable: bool| LintFinding {
+            rule,
+            path: config.path().to_path_buf(),
+            line,
+            message,
+            fix: if fixable {
+                removal_fix(config, line)
+            } else {
+                None
+            },
+        };
+
+    // The value of each option as last set by a config file, and the keys set by DEFAULT sections,
+    // which a value equal to the option's default may be overriding.
+    let mut set_values: HashMap<String, &Value> = HashMap::new();
+    let mut defaulted_keys: HashSet<&str> = HashSet::new();
+    for config in configs {
+        if let Some(Value::Table(default_section)) = config.section(DEFAULT_SECTION) {
+            defaulted_keys.extend(default_section.keys().map(String::as_str));
+        }
+        for option in registry.options() {
+            let section = option.id.scope.name();
+            let key = option.id.name("_", NameTransform::None);
+            let Some(value) = config.section(section).and_then(|table| table.get(&key)) else {
+                continue;
+            };
+            let line = config.line_of(section, &key);
+            let display = format!("[{section}] {key}");
+            if let Some(deprecation) = &option.deprecated {
+                findings.push(finding(
+                    LintRule::Deprecated,
+                    config,
+                    line,
+                    format!("{display} is deprecated: {deprecation}"),
+                    false,
+                ));
+            }
+            let id = option.id.to_string();
+            match set_values.get(&id) {
+                Some(previous) if *previous == value => findings.push(finding(
+                    LintRule::Repeated,
+                    config,
+                    line,
+                    format!("{display} repeats the value set by a previous config file."),
+                    true,
+                )),
+                None if !defaulted_keys.contains(key.as_str())
+                    && toml_value_to_val(value) == option.option_type.default_val() =>
+                {
+                    findings.push(finding(
+                        LintRule::RedundantDefault,
+                        config,
+                        line,
+                        format!("{display} is set to its default value."),
+                        true,
+                    ))
+                }
+                _ => (),
+            }
+            set_values.insert(id, value);
+        }
+    }
+
+    let option_keys = registry
+        .options()
+        .map(|option| option.id.name("_", NameTransform::None))
+        .collect::<HashSet<_>>();
+    let referenced_keys = configs
+        .iter()
+        .flat_map(|config| tokenize(config.content()))
+        .filter_map(|token| match token {
+            Token::Placeholder(name) | Token::Filtered(name, _) => Some(name),
+            Token::Conditional(condition, _, _) => Some(condition_name(condition)),
+            Token::Literal(_) | Token::Command(_) => None,
+        })
+        .collect::<HashSet<_>>();
+    for config in configs {
+        let Some(Value::Table(default_section)) = config.section(DEFAULT_SECTION) else {
+            continue;
+        };
+        for key in default_section.keys() {
+            if key == INHERIT_DEFAULT_KEY
+                || option_keys.contains(key)
+                || referenced_keys.contains(key.as_str())
+            {
+                continue;
+            }
+            findings.push(finding(
+                LintRule::DeadDefaultKey,
+                config,
+                config.line_of(DEFAULT_SECTION, key),
+                format!(
+                    "[{DEFAULT_SECTION}] {key} is neither an option nor referenced by a placeholder."
+                ),
+                true,
+            ));
+        }
+    }
+
+    for config in configs {
+        for (section, table) in config.sections() {
+            let Value::Table(table) = table else {
+                continue;
+            };
+            for (key, value) in table {
+                let mut strings = vec![];
+                collect_strings(value, &mut strings);
+                for path in strings
+                    .into_iter()
+                    .flat_map(|s| fromfile_expander.required_paths(s))
+                {
+                    if !path.exists() {
+                        findings.push(finding(
+                            LintRule::UnreachableFromfile,
+                            config,
+                            config.line_of(section, key),
+                            format!(
+                                "[{section}] {key} refers to the fromfile {}, which does not exist.",
+                                path.display()
+                            ),
+                            false,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    findings
+}
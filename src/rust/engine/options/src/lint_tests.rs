@@ -0,0 +1,42 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::PathBuf;
+
+use crate::{apply_fixes, Fix, LintFinding, LintRule};
+
+#[test]
+fn test_apply_fixes() {
+    let content = "[GLOBAL]\nlevel = 'info'\n\n[test]\ntimeout = 10\ndebug = false";
+    assert_eq!(
+        "[GLOBAL]\n\n[test]\ntimeout = 10\n",
+        apply_fixes(
+            content,
+            &[Fix { line: 2 }, Fix { line: 6 }, Fix { line: 42 }]
+        )
+    );
+    assert_eq!(content, apply_fixes(content, &[]));
+}
+
+#[test]
+fn test_display_finding() {
+    let finding = LintFinding {
+        rule: LintRule::RedundantDefault,
+        path: PathBuf::from("pants.toml"),
+        line: Some(2),
+        message: "[GLOBAL] level is set to its default value.".to_owned(),
+        fix: Some(Fix { line: 2 }),
+    };
+    assert_eq!(
+        "pants.toml:2: [redundant-default] [GLOBAL] level is set to its default value.",
+        finding.to_string()
+    );
+    let finding = LintFinding {
+        line: None,
+        ..finding
+    };
+    assert_eq!(
+        "pants.toml: [redundant-default] [GLOBAL] level is set to its default value.",
+        finding.to_string()
+    );
+}
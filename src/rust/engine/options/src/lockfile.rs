@@ -0,0 +1,171 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::registry::OptionRegistry;
+use crate::snapshot::{deserialize_values, diff_values, digest, redacted, serialize_values};
+use crate::{OptionParser, Source, Val};
+
+// Records the files or env vars whose digests differ between a lockfile and the current lock.
+fn diff_digests(
+    differences: &mut String,
+    kind: &str,
+    locked: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) {
+    for (key, digest) in locked {
+        match current.get(key) {
+            None => writeln!(differences, "- {kind} {key}").unwrap(),
+            Some(current_digest) if current_digest != digest => {
+                writeln!(differences, "~ {kind} {key} has changed").unwrap()
+            }
+            Some(_) => {}
+        }
+    }
+    for key in current.keys().filter(|key| !locked.contains_key(*key)) {
+        writeln!(differences, "+ {kind} {key}").unwrap();
+    }
+}
+
+///
+/// A record, conventionally written to `options.lock`, of the resolved value of every registered
+/// option, along with the digests of every file and env var that contributed to them. A release
+/// build may verify that it resolves its options exactly as recorded, via
+/// `OptionParser::verify_lock`.
+///
+/// Secret values, and the values of env vars, are recorded only as digests, so that a lockfile may
+/// be committed or published.
+///
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OptionsLock {
+    #[serde(
+        serialize_with = "serialize_values",
+        deserialize_with = "deserialize_values"
+    )]
+    pub(crate) values: BTreeMap<String, Val>,
+    // Relative to the build root, where possible. Optional fromfiles that did not exist are None.
+    pub(crate) files: BTreeMap<PathBuf, Option<String>>,
+    // The remote fromfiles, by their references as written. Those that were unavailable are None.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) remote: BTreeMap<String, Option<String>>,
+    pub(crate) env: BTreeMap<String, String>,
+}
+
+pub(crate) fn lock(
+    parser: &OptionParser,
+    registry: &OptionRegistry,
+) -> Result<OptionsLock, String> {
+    let mut lock = OptionsLock::default();
+    let env = parser.sources.get(&Source::Env);
+    for option in registry.options() {
+        let id = &option.id;
+        let (value, _) = parser.resolve(id, option.option_type.clone())?;
+        let value = if parser.sources.values().any(|source| source.is_secret(id)) {
+            redacted(&value)
+        } else {
+            value
+        };
+        lock.values.insert(id.to_string(), value);
+        for raw in env.map(|env| env.get_raw(id)).unwrap_or_default() {
+            if let Some(value) = raw.value {
+                lock.env.insert(raw.location, digest(value.as_bytes()));
+            }
+        }
+    }
+    // NB: Resolving may expand fromfiles, so we only record the consumed files afterwards.
+    let build_root = parser.fromfile_expander.build_root();
+    lock.files = parser
+        .consumed_files()
+        .into_iter()
+        .map(|(path, digest)| {
+            let path = path.strip_prefix(build_root).unwrap_or(&path).to_path_buf();
+            (path, digest)
+        })
+        .collect();
+    lock.remote = parser.remote_inputs();
+    Ok(lock)
+}
+
+impl OptionsLock {
+    fn file_digests(&self) -> BTreeMap<String, String> {
+        self.files
+            .iter()
+            .map(|(path, digest)| {
+                let digest = digest.clone().unwrap_or_else(|| "<missing>".to_owned());
+                (path.display().to_string(), digest)
+            })
+            .collect()
+    }
+
+    fn remote_digests(&self) -> BTreeMap<String, String> {
+        self.remote
+            .iter()
+            .map(|(target, digest)| {
+                let digest = digest.clone().unwrap_or_else(|| "<unavailable>".to_owned());
+                (target.clone(), digest)
+            })
+            .collect()
+    }
+
+    pub fn write_to(&self, path: &Path) -> Result<(), String> {
+        let mut content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize options lockfile: {e}"))?;
+        content.push('\n');
+        fs::write(path, content).map_err(|e| {
+            format!(
+                "Failed to write options lockfile to {}: {e}",
+                path.display()
+            )
+        })
+    }
+
+    pub fn read_from(path: &Path) -> Result<OptionsLock, String> {
+        let content = fs::read(path).map_err(|e| {
+            format!(
+                "Failed to read options lockfile from {}: {e}",
+                path.display()
+            )
+        })?;
+        serde_json::from_slice(&content).map_err(|e| {
+            format!(
+                "Failed to deserialize options lockfile from {}: {e}",
+                path.display()
+            )
+        })
+    }
+
+    ///
+    /// Checks that the `current` lock, as created for the current environment, matches this one,
+    /// returning an error that describes every difference if not.
+    ///
+    pub fn verify(&self, current: &OptionsLock) -> Result<(), String> {
+        let mut differences = diff_values(&self.values, &current.values).to_string();
+        diff_digests(
+            &mut differences,
+            "file",
+            &self.file_digests(),
+            &current.file_digests(),
+        );
+        diff_digests(
+            &mut differences,
+            "remote fromfile",
+            &self.remote_digests(),
+            &current.remote_digests(),
+        );
+        diff_digests(&mut differences, "env var", &self.env, &current.env);
+        if differences.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "The options resolve differently than recorded in the lockfile:\n{}",
+                differences.trim_end()
+            ))
+        }
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use tempfile::TempDir;
+
+use crate::snapshot::digest;
+use crate::tests;
+use crate::{
+    option_id, BuildRoot, ConfigSource, CredentialStore, KeyringEntry, OptionInfo, OptionParser,
+    OptionRegistry, OptionType, OptionsLock, Val,
+};
+
+struct Store;
+
+impl CredentialStore for Store {
+    fn get(&self, _entry: &KeyringEntry) -> Result<Option<String>, String> {
+        Ok(Some("hunter2".to_owned()))
+    }
+}
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!(["scope"], "foo"), OptionType::Int(0)),
+        OptionInfo::new(
+            option_id!(["scope"], "bar"),
+            OptionType::String("".to_owned()),
+        ),
+        OptionInfo::new(
+            option_id!(["scope"], "baz"),
+            OptionType::String("".to_owned()),
+        ),
+        OptionInfo::new(
+            option_id!(["scope"], "token"),
+            OptionType::String("".to_owned()),
+        )
+        .keyring("pants", "token"),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+fn parser(buildroot: &Path, env: &[(&str, &str)]) -> OptionParser {
+    let mut parser = tests::parser_in(
+        BuildRoot::find_from(buildroot).unwrap(),
+        &[],
+        env,
+        vec![ConfigSource::from_file(buildroot.join("pants.toml")).unwrap()],
+    );
+    parser.register_keyring(&registry(), Arc::new(Store));
+    parser
+}
+
+#[test]
+fn test_lock() {
+    let buildroot = TempDir::new().unwrap();
+    let config = "[scope]\nfoo = 1\nbar = '@bar.txt'\n";
+    fs::write(buildroot.path().join("pants.toml"), config).unwrap();
+    fs::write(buildroot.path().join("bar.txt"), "BAR").unwrap();
+
+    let lock = parser(buildroot.path(), &[("PANTS_SCOPE_BAZ", "baz")])
+        .lock(&registry())
+        .unwrap();
+    assert_eq!(
+        BTreeMap::from([
+            ("[scope] foo".to_owned(), Val::Int(1)),
+            ("[scope] bar".to_owned(), Val::String("BAR".into())),
+            ("[scope] baz".to_owned(), Val::String("baz".into())),
+            (
+                "[scope] token".to_owned(),
+                Val::String(
+                    format!("<redacted> sha256:{}", digest("\"hunter2\"".as_bytes())).into()
+                )
+            ),
+        ]),
+        lock.values
+    );
+    assert_eq!(
+        BTreeMap::from([
+            (PathBuf::from("pants.toml"), Some(digest(config.as_bytes()))),
+            (PathBuf::from("bar.txt"), Some(digest(b"BAR"))),
+        ]),
+        lock.files
+    );
+    assert_eq!(
+        BTreeMap::from([("PANTS_SCOPE_BAZ".to_owned(), digest(b"baz"))]),
+        lock.env
+    );
+
+    let lockfile = buildroot.path().join("options.lock");
+    lock.write_to(&lockfile).unwrap();
+    assert_eq!(lock, OptionsLock::read_from(&lockfile).unwrap());
+}
+
+#[test]
+fn test_verify_lock() {
+    let buildroot = TempDir::new().unwrap();
+    fs::write(
+        buildroot.path().join("pants.toml"),
+        "[scope]\nfoo = 1\nbar = '@bar.txt'\n",
+    )
+    .unwrap();
+    fs::write(buildroot.path().join("bar.txt"), "BAR").unwrap();
+    let env = [("PANTS_SCOPE_BAZ", "baz")];
+    let lock = parser(buildroot.path(), &env).lock(&registry()).unwrap();
+
+    parser(buildroot.path(), &env)
+        .verify_lock(&registry(), &lock)
+        .unwrap();
+
+    fs::write(buildroot.path().join("bar.txt"), "BAR2").unwrap();
+    assert_eq!(
+        "The options resolve differently than recorded in the lockfile:\n\
+        ~ [scope] bar: \"BAR\" -> \"BAR2\"\n\
+        ~ [scope] baz: \"baz\" -> \"\"\n\
+        ~ file bar.txt has changed\n\
+        - env var PANTS_SCOPE_BAZ",
+        parser(buildroot.path(), &[])
+            .verify_lock(&registry(), &lock)
+            .unwrap_err()
+    );
+
+    // A change to a file that doesn't change any values is still a difference.
+    fs::write(buildroot.path().join("bar.txt"), "BAR").unwrap();
+    fs::write(
+        buildroot.path().join("pants.toml"),
+        "[scope]\nfoo = 1\nbar = '@bar.txt'\n# A comment.\n",
+    )
+    .unwrap();
+    assert_eq!(
+        "The options resolve differently than recorded in the lockfile:\n\
+        ~ file pants.toml has changed",
+        parser(buildroot.path(), &env)
+            .verify_lock(&registry(), &lock)
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_verify_lock_with_date() {
+    let buildroot = TempDir::new().unwrap();
+    fs::write(
+        buildroot.path().join("pants.toml"),
+        "[scope]\nexpiry = {at = 2024-12-31, bytes = '@base64:key.b64'}\n",
+    )
+    .unwrap();
+    fs::write(buildroot.path().join("key.b64"), "AAE=").unwrap();
+    let mut registry = OptionRegistry::new();
+    registry
+        .register(OptionInfo::new(
+            option_id!(["scope"], "expiry"),
+            OptionType::Dict(IndexMap::new()),
+        ))
+        .unwrap();
+
+    // A lock that is read back verifies against the same options.
+    let lockfile = buildroot.path().join("options.lock");
+    parser(buildroot.path(), &[])
+        .lock(&registry)
+        .unwrap()
+        .write_to(&lockfile)
+        .unwrap();
+    let lock = OptionsLock::read_from(&lockfile).unwrap();
+    assert_eq!(
+        Some(&Val::Date("2024-12-31".parse().unwrap())),
+        match &lock.values["[scope] expiry"] {
+            Val::Dict(dict) => dict.get("at"),
+            _ => None,
+        }
+    );
+    parser(buildroot.path(), &[])
+        .verify_lock(&registry, &lock)
+        .unwrap();
+}
@@ -0,0 +1,438 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::config::{FINAL_KEY, INHERIT_DEFAULT_KEY};
+use crate::help::OptionHelp;
+use crate::id::{NameTransform, Scope};
+use crate::interpolation::{tokenize, Token};
+use crate::registry::{OptionInfo, OptionRegistry};
+use crate::seeds::LAZY_SEED_NAMES;
+
+static DEFAULT_SECTION: &str = "DEFAULT";
+
+// NB: These must be kept in sync with the seed values of `OptionParser::new`. Placeholders of the
+// form `%(env.NAME)s` are also always available.
+const SEED_VALUE_NAMES: &[&str] = &[
+    "buildroot",
+    "homedir",
+    "user",
+    "pants_workdir",
+    "pants_distdir",
+    "is_ci",
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    Error = 1,
+    Warning = 2,
+}
+
+/// A problem found in a config file, on a single line. Lines and columns are 0-based.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn to_lsp(&self) -> Value {
+        json!({
+            "range": {
+                "start": {"line": self.line, "character": self.start},
+                "end": {"line": self.line, "character": self.end},
+            },
+            "severity": self.severity as u8,
+            "source": "pants",
+            "message": self.message,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompletionItem {
+    pub label: String,
+    pub detail: Option<String>,
+}
+
+// A line of a config file, as far as this module is concerned.
+enum Line<'a> {
+    // A `[section]` header: the section name, and the column range of the name.
+    Header(&'a str, usize, usize),
+    // A `key = value` or `key.add = value` line: the key, and the column range of the key.
+    Key(&'a str, usize, usize),
+    Other,
+}
+
+fn classify(line: &str) -> Line<'_> {
+    let indent = line.len() - line.trim_start().len();
+    let trimmed = line.trim();
+    if trimmed.starts_with("[[") {
+        return Line::Other;
+    }
+    if let Some(header) = trimmed.strip_prefix('[') {
+        let header = header.split(']').next().unwrap_or(header);
+        let name = header.trim().trim_matches('"');
+        let start = indent + 1 + header.find(name).unwrap_or(0);
+        return Line::Header(name, start, start + name.len());
+    }
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return Line::Other;
+    }
+    let key_end = trimmed.find(['=', '.']).unwrap_or(trimmed.len());
+    let key = trimmed[..key_end].trim().trim_matches('"');
+    if key.is_empty() || trimmed[key_end..].is_empty() {
+        return Line::Other;
+    }
+    Line::Key(key, indent, indent + key.len())
+}
+
+// The section that each line belongs to, if any.
+fn sections_by_line(text: &str) -> Vec<Option<&str>> {
+    let mut section = None;
+    text.lines()
+        .map(|line| {
+            if let Line::Header(name, _, _) = classify(line) {
+                section = Some(name);
+            }
+            section
+        })
+        .collect()
+}
+
+///
+/// Provides diagnostics, hover help and completions for `pants.toml` files, based on the options
+/// in a registry. Analysis is line-based, so that it is robust to files which are being edited
+/// and so fail to parse.
+///
+pub struct ConfigLanguageService<'a> {
+    registry: &'a OptionRegistry,
+}
+
+impl<'a> ConfigLanguageService<'a> {
+    pub fn new(registry: &'a OptionRegistry) -> Self {
+        Self { registry }
+    }
+
+    fn is_known_section(&self, section: &str) -> bool {
+        section == DEFAULT_SECTION
+            || section == Scope::Global.name()
+            || self
+                .registry
+                .scopes()
+                .iter()
+                .any(|scope| scope.name() == section)
+    }
+
+    fn option(&self, section: &str, key: &str) -> Option<&'a OptionInfo> {
+        let scope = Scope::named(section);
+        self.registry.options().find(|option| {
+            option.id.scope == scope && option.id.name("_", NameTransform::None) == key
+        })
+    }
+
+    // Whether the section is the sub-table of a known option, e.g. `[GLOBAL.backend_packages]`.
+    fn is_option_table(&self, section: &str) -> bool {
+        section
+            .rsplit_once('.')
+            .is_some_and(|(scope, key)| self.option(scope, key).is_some())
+    }
+
+    pub fn diagnostics(&self, text: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        if let Err(e) = text.parse::<toml::Value>() {
+            let (line, col) = e.line_col().unwrap_or((0, 0));
+            diagnostics.push(Diagnostic {
+                line,
+                start: col,
+                end: col + 1,
+                severity: Severity::Error,
+                message: e.to_string(),
+            });
+        }
+
+        // The names that placeholders may refer to, per section.
+        let mut keys_by_section: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for (line, section) in text.lines().zip(sections_by_line(text)) {
+            if let (Line::Key(key, _, _), Some(section)) = (classify(line), section) {
+                keys_by_section.entry(section).or_default().insert(key);
+            }
+        }
+        let no_keys = HashSet::new();
+        let default_keys = keys_by_section.get(DEFAULT_SECTION).unwrap_or(&no_keys);
+
+        for (i, (line, section)) in text.lines().zip(sections_by_line(text)).enumerate() {
+            match classify(line) {
+                Line::Header(name, start, end)
+                    if !self.is_known_section(name) && !self.is_option_table(name) =>
+                {
+                    diagnostics.push(Diagnostic {
+                        line: i,
+                        start,
+                        end,
+                        severity: Severity::Warning,
+                        message: format!("Unknown section [{name}]"),
+                    });
+                }
+                Line::Key(key, start, end) => match section {
+                    Some(section)
+                        if section != DEFAULT_SECTION
+                            && key != INHERIT_DEFAULT_KEY
+                            && key != FINAL_KEY
+                            && self.is_known_section(section)
+                            && self.option(section, key).is_none() =>
+                    {
+                        diagnostics.push(Diagnostic {
+                            line: i,
+                            start,
+                            end,
+                            severity: Severity::Error,
+                            message: format!("Invalid option '{key}' under [{section}]"),
+                        });
+                    }
+                    _ => (),
+                },
+                _ => (),
+            }
+            let section_keys = section
+                .and_then(|section| keys_by_section.get(section))
+                .unwrap_or(&no_keys);
+            let mut search_from = 0;
+            for token in tokenize(line) {
+                let (name, placeholder) = match token {
+                    Token::Placeholder(name) => (name, format!("%({name})s")),
+                    Token::Filtered(name, filters) => (name, format!("%({name}|{filters})s")),
+                    // NB: The seed of a condition need not exist, so it is not checked.
+                    Token::Literal(_) | Token::Command(_) | Token::Conditional(..) => continue,
+                };
+                let start = search_from + line[search_from..].find(&placeholder).unwrap_or(0);
+                search_from = start + placeholder.len();
+                if name.starts_with("env.")
+                    || SEED_VALUE_NAMES.contains(&name)
+                    || LAZY_SEED_NAMES.contains(&name)
+                    || default_keys.contains(name)
+                    || section_keys.contains(name)
+                {
+                    continue;
+                }
+                diagnostics.push(Diagnostic {
+                    line: i,
+                    start,
+                    end: search_from,
+                    severity: Severity::Error,
+                    message: format!("Unknown placeholder '{name}'"),
+                });
+            }
+        }
+        diagnostics
+    }
+
+    ///
+    /// Returns help for the section or option at the given position, as markdown.
+    ///
+    pub fn hover(&self, text: &str, line: usize, character: usize) -> Option<String> {
+        let line_text = text.lines().nth(line)?;
+        match classify(line_text) {
+            Line::Header(name, start, end) if (start..=end).contains(&character) => {
+                let count = self.registry.options_in(&Scope::named(name)).count();
+                self.is_known_section(name)
+                    .then(|| format!("**[{name}]**: {count} registered options"))
+            }
+            Line::Key(key, start, end) if (start..=end).contains(&character) => {
+                let section = sections_by_line(text).get(line).copied().flatten()?;
+                let help = OptionHelp::new(self.option(section, key)?);
+                let mut hover = format!(
+                    "**{}** ({}, default: `{}`)",
+                    help.flags.join(", "),
+                    help.type_name,
+                    help.default
+                );
+                if !help.choices.is_empty() {
+                    hover.push_str(&format!("\n\nOne of: {}", help.choices.join(", ")));
+                }
+                if !help.help.is_empty() {
+                    hover.push_str(&format!("\n\n{}", help.help));
+                }
+                if let Some(group) = &help.group {
+                    hover.push_str(&format!("\n\nGroup: {group}"));
+                }
+                hover.push_str(&format!("\n\nEnv var: `{}`", help.env_var));
+                Some(hover)
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// Completes section names in a header, and otherwise the names of the options of the
+    /// enclosing section.
+    ///
+    pub fn completions(&self, text: &str, line: usize) -> Vec<CompletionItem> {
+        let line_text = text.lines().nth(line).unwrap_or("");
+        if line_text.trim_start().starts_with('[') {
+            return [DEFAULT_SECTION, Scope::Global.name()]
+                .into_iter()
+                .chain(self.registry.scopes().into_iter().map(Scope::name))
+                .map(|name| CompletionItem {
+                    label: name.to_owned(),
+                    detail: None,
+                })
+                .collect();
+        }
+        // The section of a line being typed is that of the closest preceding line.
+        let sections = sections_by_line(text);
+        let Some(section) = sections.get(line).or(sections.last()).copied().flatten() else {
+            return vec![];
+        };
+        self.registry
+            .options_in(&Scope::named(section))
+            .map(|option| CompletionItem {
+                label: option.id.name("_", NameTransform::None),
+                detail: Some(option.option_type.type_name().to_owned()),
+            })
+            .collect()
+    }
+}
+
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>, String> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input
+            .read_line(&mut header)
+            .map_err(|e| format!("Failed to read LSP message header: {e}"))?
+            == 0
+        {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = Some(
+                length
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|e| format!("Invalid Content-Length `{length}`: {e}"))?,
+            );
+        }
+    }
+    let content_length =
+        content_length.ok_or_else(|| "LSP message had no Content-Length header".to_owned())?;
+    let mut content = vec![0; content_length];
+    input
+        .read_exact(&mut content)
+        .map_err(|e| format!("Failed to read LSP message: {e}"))?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse LSP message: {e}"))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<(), String> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())
+        .and_then(|()| output.flush())
+        .map_err(|e| format!("Failed to write LSP message: {e}"))
+}
+
+///
+/// Serves the Language Server Protocol over the given streams (usually stdin and stdout) until
+/// the client sends `exit`. Documents are synced in full on each change.
+///
+pub fn serve_lsp(
+    registry: &OptionRegistry,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> Result<(), String> {
+    let service = ConfigLanguageService::new(registry);
+    let mut documents: HashMap<String, String> = HashMap::new();
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let position = &params["position"];
+        let line = position["line"].as_u64().unwrap_or(0) as usize;
+        let character = position["character"].as_u64().unwrap_or(0) as usize;
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "completionProvider": {"triggerCharacters": ["["]},
+                },
+                "serverInfo": {"name": "pants"},
+            }),
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let text = if method == "textDocument/didOpen" {
+                    params["textDocument"]["text"].as_str()
+                } else {
+                    params["contentChanges"][0]["text"].as_str()
+                };
+                documents.insert(uri.to_owned(), text.unwrap_or_default().to_owned());
+                let diagnostics = service
+                    .diagnostics(&documents[uri])
+                    .iter()
+                    .map(Diagnostic::to_lsp)
+                    .collect::<Vec<_>>();
+                write_message(
+                    &mut output,
+                    &json!({
+                        "jsonrpc": "2.0",
+                        "method": "textDocument/publishDiagnostics",
+                        "params": {"uri": uri, "diagnostics": diagnostics},
+                    }),
+                )?;
+                continue;
+            }
+            "textDocument/didClose" => {
+                documents.remove(uri);
+                continue;
+            }
+            "textDocument/hover" => documents
+                .get(uri)
+                .and_then(|text| service.hover(text, line, character))
+                .map_or(
+                    Value::Null,
+                    |hover| json!({"contents": {"kind": "markdown", "value": hover}}),
+                ),
+            "textDocument/completion" => Value::Array(
+                documents
+                    .get(uri)
+                    .map(|text| service.completions(text, line))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|item| json!({"label": item.label, "detail": item.detail}))
+                    .collect(),
+            ),
+            "shutdown" => Value::Null,
+            "exit" => return Ok(()),
+            _ => {
+                // Notifications that we don't handle are ignored, while requests get an error.
+                if !message["id"].is_null() {
+                    write_message(
+                        &mut output,
+                        &json!({
+                            "jsonrpc": "2.0",
+                            "id": message["id"],
+                            "error": {"code": -32601, "message": format!("Unsupported method {method}")},
+                        }),
+                    )?;
+                }
+                continue;
+            }
+        };
+        write_message(
+            &mut output,
+            &json!({"jsonrpc": "2.0", "id": message["id"], "result": result}),
+        )?;
+    }
+    Ok(())
+}
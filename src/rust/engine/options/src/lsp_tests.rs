@@ -0,0 +1,183 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::io::Cursor;
+
+use serde_json::{json, Value};
+
+use crate::{
+    option_id, serve_lsp, CompletionItem, ConfigLanguageService, Diagnostic, OptionInfo,
+    OptionRegistry, OptionType, Severity,
+};
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!("level"), OptionType::String("info".to_owned()))
+            .choices(&["debug", "info"])
+            .help("The log level."),
+        OptionInfo::new(
+            option_id!("backend", "packages"),
+            OptionType::StringList(vec![]),
+        ),
+        OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false)),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+#[test]
+fn test_diagnostics() {
+    let registry = registry();
+    let service = ConfigLanguageService::new(&registry);
+    assert_eq!(
+        Vec::<Diagnostic>::new(),
+        service.diagnostics(
+            "[DEFAULT]\n\
+             anything = 'x'\n\
+             [GLOBAL]\n\
+             level = '%(anything)s'\n\
+             backend_packages.add = ['%(buildroot)s/%(env.HOME)s']\n\
+             [test]\n\
+             inherit_default = false\n\
+             debug = true\n"
+        )
+    );
+    assert_eq!(
+        vec![
+            Diagnostic {
+                line: 1,
+                start: 0,
+                end: 3,
+                severity: Severity::Error,
+                message: "Invalid option 'bad' under [GLOBAL]".to_owned(),
+            },
+            Diagnostic {
+                line: 1,
+                start: 7,
+                end: 15,
+                severity: Severity::Error,
+                message: "Unknown placeholder 'nope'".to_owned(),
+            },
+            Diagnostic {
+                line: 2,
+                start: 1,
+                end: 8,
+                severity: Severity::Warning,
+                message: "Unknown section [unknown]".to_owned(),
+            },
+        ],
+        service.diagnostics("[GLOBAL]\nbad = '%(nope)s'\n[unknown]\n")
+    );
+    let syntax_errors = service.diagnostics("[GLOBAL]\nlevel = \n");
+    assert_eq!(1, syntax_errors.len());
+    assert_eq!(1, syntax_errors[0].line);
+}
+
+#[test]
+fn test_hover() {
+    let registry = registry();
+    let service = ConfigLanguageService::new(&registry);
+    let text = "[GLOBAL]\nlevel = 'debug'\n[test]\ndebug = true\n";
+    assert_eq!(
+        Some(
+            "**--level** (str, default: `\"info\"`)\n\n\
+             One of: debug, info\n\n\
+             The log level.\n\n\
+             Env var: `PANTS_LEVEL`"
+                .to_owned()
+        ),
+        service.hover(text, 1, 2)
+    );
+    assert_eq!(None, service.hover(text, 1, 10));
+    assert_eq!(
+        Some("**[test]**: 1 registered options".to_owned()),
+        service.hover(text, 2, 2)
+    );
+    assert!(service
+        .hover(text, 3, 0)
+        .unwrap()
+        .starts_with("**--test-debug, --no-test-debug** (bool, default: `false`)"));
+}
+
+#[test]
+fn test_completions() {
+    let registry = registry();
+    let service = ConfigLanguageService::new(&registry);
+    let labels =
+        |items: Vec<CompletionItem>| items.into_iter().map(|item| item.label).collect::<Vec<_>>();
+    assert_eq!(
+        vec!["DEFAULT", "GLOBAL", "test"],
+        labels(service.completions("[GLOBAL]\n[", 1))
+    );
+    assert_eq!(
+        vec!["level", "backend_packages"],
+        labels(service.completions("[GLOBAL]\nlevel = 'info'\nba", 2))
+    );
+    assert_eq!(
+        vec![CompletionItem {
+            label: "debug".to_owned(),
+            detail: Some("bool".to_owned())
+        }],
+        service.completions("[test]\n", 1)
+    );
+}
+
+fn frame(message: Value) -> String {
+    let content = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{content}", content.len())
+}
+
+fn unframe(output: &[u8]) -> Vec<Value> {
+    let output = std::str::from_utf8(output).unwrap();
+    output
+        .split("Content-Length: ")
+        .skip(1)
+        .map(|message| serde_json::from_str(message.split_once("\r\n\r\n").unwrap().1).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_serve() {
+    let uri = "file:///repo/pants.toml";
+    let input = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+        json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": {"uri": uri, "text": "[GLOBAL]\nbad = 1\n"}
+        }}),
+        json!({"jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+            "textDocument": {"uri": uri},
+            "contentChanges": [{"text": "[GLOBAL]\nlevel = 'info'\n"}]
+        }}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {
+            "textDocument": {"uri": uri}, "position": {"line": 1, "character": 1}
+        }}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "textDocument/definition", "params": {}}),
+        json!({"jsonrpc": "2.0", "id": 4, "method": "shutdown"}),
+        json!({"jsonrpc": "2.0", "method": "exit"}),
+    ]
+    .into_iter()
+    .map(frame)
+    .collect::<String>();
+    let mut output = vec![];
+    serve_lsp(&registry(), Cursor::new(input), &mut output).unwrap();
+    let messages = unframe(&output);
+
+    assert_eq!(6, messages.len());
+    assert_eq!(true, messages[0]["result"]["capabilities"]["hoverProvider"]);
+    assert_eq!(
+        "Invalid option 'bad' under [GLOBAL]",
+        messages[1]["params"]["diagnostics"][0]["message"]
+    );
+    assert_eq!(json!([]), messages[2]["params"]["diagnostics"]);
+    assert!(messages[3]["result"]["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .starts_with("**--level**"));
+    assert_eq!(-32601, messages[4]["error"]["code"]);
+    assert_eq!(
+        json!({"jsonrpc": "2.0", "id": 4, "result": null}),
+        messages[5]
+    );
+}
@@ -0,0 +1,259 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use indexmap::IndexMap;
+
+use super::{DictEdit, DictEditAction, ListEdit, ListEditAction, Val};
+use crate::types::ToVal;
+
+///
+/// How to resolve conflicts when folding edits into a final value.
+///
+/// For lists, a conflict is an item that appears more than once in the merged value. For dicts, a
+/// conflict is a key that an `Add` edit sets to a different value than the one it already has.
+/// A `Replace` edit discards everything before it, and so never conflicts with anything.
+///
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ConflictStrategy {
+    /// Lists keep every item, including duplicates. Later dict values override earlier ones.
+    #[default]
+    LastWins,
+    /// Any conflict is an error.
+    Error,
+    /// Lists keep only the first occurrence of each item. Dicts behave as for `LastWins`, since
+    /// their keys are unique.
+    Dedupe,
+}
+
+///
+/// How a list option is normalized after its edits are folded, as registered via
+/// `OptionInfo::list_policy`. E.g. `Dedupe` ensures that an item added by several sources, such
+/// as a backend package, appears only once.
+///
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ListPolicy {
+    /// The list is kept as folded, including any duplicates.
+    #[default]
+    Keep,
+    /// Only the first occurrence of each item is kept.
+    Dedupe,
+    /// The items are sorted (strings lexically, and numbers numerically), and deduplicated.
+    Sort,
+    /// Any item that appears more than once is an error.
+    ErrorOnDuplicate,
+}
+
+impl ListPolicy {
+    pub(crate) fn conflict_strategy(self) -> ConflictStrategy {
+        match self {
+            ListPolicy::Keep => ConflictStrategy::LastWins,
+            ListPolicy::Dedupe | ListPolicy::Sort => ConflictStrategy::Dedupe,
+            ListPolicy::ErrorOnDuplicate => ConflictStrategy::Error,
+        }
+    }
+
+    // Applies the normalization, if any, that the conflict strategy does not.
+    pub(crate) fn normalize<T: ToVal>(self, mut list: Vec<T>) -> Vec<T> {
+        if self == ListPolicy::Sort {
+            list.sort_by(|a, b| compare_vals(&a.to_val(), &b.to_val()));
+        }
+        list
+    }
+}
+
+// A total order of values, for sorting lists. Values of different types are ordered by their
+// renderings.
+fn compare_vals(a: &Val, b: &Val) -> Ordering {
+    match (a, b) {
+        (Val::Bool(a), Val::Bool(b)) => a.cmp(b),
+        (Val::Int(a), Val::Int(b)) => a.cmp(b),
+        (Val::Float(a), Val::Float(b)) => a.total_cmp(b),
+        (Val::String(a), Val::String(b)) => a.as_str().cmp(b.as_str()),
+        (a, b) => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+// Folds the edits in order. Removals from any edit apply after adds from any edit (but are
+// themselves overridden by later replacements), so we collect them and apply them at the end.
+fn fold_list<T: PartialEq>(
+    default: Vec<T>,
+    edits: impl IntoIterator<Item = ListEdit<T>>,
+    remover: impl Fn(&mut Vec<T>, &Vec<T>),
+) -> Vec<T> {
+    let mut list = default;
+    let mut removal_lists: Vec<Vec<T>> = vec![];
+    for list_edit in edits {
+        match list_edit.action {
+            ListEditAction::Replace => {
+                list = list_edit.items;
+                removal_lists.clear();
+            }
+            ListEditAction::Add => list.extend(list_edit.items),
+            ListEditAction::Remove => removal_lists.push(list_edit.items),
+            ListEditAction::Insert(index) => {
+                let index = index.min(list.len());
+                list.splice(index..index, list_edit.items);
+            }
+            ListEditAction::InsertBefore | ListEditAction::InsertAfter => {
+                let mut items = list_edit.items.into_iter();
+                let Some(anchor) = items.next() else {
+                    continue;
+                };
+                match list.iter().position(|item| *item == anchor) {
+                    Some(position) => {
+                        let index = if list_edit.action == ListEditAction::InsertBefore {
+                            position
+                        } else {
+                            position + 1
+                        };
+                        list.splice(index..index, items);
+                    }
+                    None => list.extend(items),
+                }
+            }
+        }
+    }
+    for removals in removal_lists {
+        remover(&mut list, &removals);
+    }
+    list
+}
+
+fn duplicate_error<T: Debug>(item: &T) -> String {
+    format!("Item {item:?} appears more than once in the merged list")
+}
+
+///
+/// Folds a sequence of list edits, in increasing order of priority, into a final list.
+///
+/// Works for any item type, but removals and duplicate detection are O(N*M). Prefer
+/// `merge_hashable_list` for types that are `Eq + Hash`.
+///
+pub fn merge_list<T: Debug + PartialEq>(
+    default: Vec<T>,
+    edits: impl IntoIterator<Item = ListEdit<T>>,
+    strategy: ConflictStrategy,
+) -> Result<Vec<T>, String> {
+    let list = fold_list(default, edits, |list, to_remove| {
+        list.retain(|item| !to_remove.contains(item))
+    });
+    match strategy {
+        ConflictStrategy::LastWins => Ok(list),
+        ConflictStrategy::Error => {
+            for (i, item) in list.iter().enumerate() {
+                if list[..i].contains(item) {
+                    return Err(duplicate_error(item));
+                }
+            }
+            Ok(list)
+        }
+        ConflictStrategy::Dedupe => {
+            let mut deduped: Vec<T> = Vec::with_capacity(list.len());
+            for item in list {
+                if !deduped.contains(&item) {
+                    deduped.push(item);
+                }
+            }
+            Ok(deduped)
+        }
+    }
+}
+
+///
+/// Folds a sequence of list edits, in increasing order of priority, into a final list, using a
+/// HashSet for removals and duplicate detection.
+///
+pub fn merge_hashable_list<T: Debug + Eq + Hash>(
+    default: Vec<T>,
+    edits: impl IntoIterator<Item = ListEdit<T>>,
+    strategy: ConflictStrategy,
+) -> Result<Vec<T>, String> {
+    let mut list = fold_list(default, edits, |list, remove| {
+        let to_remove = remove.iter().collect::<HashSet<_>>();
+        list.retain(|item| !to_remove.contains(item));
+    });
+    match strategy {
+        ConflictStrategy::LastWins => {}
+        ConflictStrategy::Error => {
+            let mut seen = HashSet::new();
+            if let Some(item) = list.iter().find(|item| !seen.insert(*item)) {
+                return Err(duplicate_error(item));
+            }
+        }
+        ConflictStrategy::Dedupe => {
+            let mut seen = HashSet::new();
+            let keep = list
+                .iter()
+                .map(|item| seen.insert(item))
+                .collect::<Vec<_>>();
+            let mut keep = keep.into_iter();
+            list.retain(|_| keep.next().unwrap());
+        }
+    }
+    Ok(list)
+}
+
+///
+/// Finds the dict and key that an added `key` targets. A dotted key, e.g. `outer.inner`, that the
+/// dict does not itself contain, but whose prefix names a nested dict, targets a key within that
+/// nested dict, so that adding it edits the nested dict rather than replacing it.
+///
+fn nested_target<'a, 'k>(
+    dict: &'a mut IndexMap<String, Val>,
+    key: &'k str,
+) -> (&'a mut IndexMap<String, Val>, &'k str) {
+    let split = if dict.contains_key(key) {
+        None
+    } else {
+        key.match_indices('.')
+            .map(|(i, _)| i)
+            .find(|i| matches!(dict.get(&key[..*i]), Some(Val::Dict(_))))
+    };
+    match split {
+        Some(i) => match dict.get_mut(&key[..i]) {
+            Some(Val::Dict(nested)) => nested_target(nested, &key[i + 1..]),
+            _ => unreachable!("The prefix of the key was found to name a nested dict."),
+        },
+        None => (dict, key),
+    }
+}
+
+///
+/// Folds a sequence of dict edits, in increasing order of priority, into a final dict.
+///
+/// Dotted keys in `Add` edits may target keys within nested dicts: see `nested_target`.
+///
+pub fn merge_dict(
+    default: IndexMap<String, Val>,
+    edits: impl IntoIterator<Item = DictEdit>,
+    strategy: ConflictStrategy,
+) -> Result<IndexMap<String, Val>, String> {
+    let mut dict = default;
+    for dict_edit in edits {
+        match dict_edit.action {
+            DictEditAction::Replace => dict = dict_edit.items,
+            DictEditAction::Add => {
+                for (key, value) in dict_edit.items {
+                    let (target, target_key) = nested_target(&mut dict, &key);
+                    if strategy == ConflictStrategy::Error {
+                        match target.get(target_key) {
+                            Some(existing) if *existing != value => {
+                                return Err(format!(
+                                    "Conflicting values for key `{key}`: {existing} and {value}"
+                                ));
+                            }
+                            _ => {}
+                        }
+                    }
+                    target.insert(target_key.to_owned(), value);
+                }
+            }
+        }
+    }
+    Ok(dict)
+}
@@ -0,0 +1,179 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use indexmap::IndexMap;
+
+use crate::{
+    merge_dict, merge_hashable_list, merge_list, ConflictStrategy, DictEdit, DictEditAction,
+    ListEdit, ListEditAction, Val,
+};
+
+fn edit<T>(action: ListEditAction, items: Vec<T>) -> ListEdit<T> {
+    ListEdit { action, items }
+}
+
+fn list_edits() -> Vec<ListEdit<i64>> {
+    vec![
+        edit(ListEditAction::Add, vec![1, 2]),
+        edit(ListEditAction::Remove, vec![3]),
+        edit(ListEditAction::Add, vec![2, 3, 4]),
+    ]
+}
+
+#[test]
+fn test_merge_list_last_wins() {
+    let expected = vec![0, 1, 2, 2, 4];
+    assert_eq!(
+        expected,
+        merge_hashable_list(vec![0], list_edits(), ConflictStrategy::LastWins).unwrap()
+    );
+    assert_eq!(
+        expected,
+        merge_list(vec![0], list_edits(), ConflictStrategy::LastWins).unwrap()
+    );
+
+    // A replace discards both the preceding items and the preceding removals.
+    let mut edits = list_edits();
+    edits.push(edit(ListEditAction::Replace, vec![3, 5]));
+    assert_eq!(
+        vec![3, 5],
+        merge_hashable_list(vec![0], edits, ConflictStrategy::LastWins).unwrap()
+    );
+}
+
+#[test]
+fn test_merge_list_dedupe() {
+    let expected = vec![0, 1, 2, 4];
+    assert_eq!(
+        expected,
+        merge_hashable_list(vec![0], list_edits(), ConflictStrategy::Dedupe).unwrap()
+    );
+    assert_eq!(
+        expected,
+        merge_list(vec![0], list_edits(), ConflictStrategy::Dedupe).unwrap()
+    );
+}
+
+#[test]
+fn test_merge_list_error() {
+    let expected = "Item 2 appears more than once in the merged list";
+    assert_eq!(
+        expected,
+        merge_hashable_list(vec![0], list_edits(), ConflictStrategy::Error).unwrap_err()
+    );
+    assert_eq!(
+        expected,
+        merge_list(vec![0], list_edits(), ConflictStrategy::Error).unwrap_err()
+    );
+    assert_eq!(
+        vec![1.0, 2.5],
+        merge_list(
+            vec![1.0],
+            vec![edit(ListEditAction::Add, vec![2.5])],
+            ConflictStrategy::Error
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_merge_list_inserts() {
+    let edits = vec![
+        edit(ListEditAction::Insert(1), vec![10, 11]),
+        edit(ListEditAction::InsertBefore, vec![3, 20]),
+        edit(ListEditAction::InsertAfter, vec![3, 30]),
+        // A missing anchor appends, as does an index past the end of the list.
+        edit(ListEditAction::InsertAfter, vec![99, 40]),
+        edit(ListEditAction::Insert(99), vec![50]),
+    ];
+    assert_eq!(
+        vec![1, 10, 11, 2, 20, 3, 30, 40, 50],
+        merge_list(vec![1, 2, 3], edits.clone(), ConflictStrategy::LastWins).unwrap()
+    );
+    assert_eq!(
+        vec![1, 10, 11, 2, 20, 3, 30, 40, 50],
+        merge_hashable_list(vec![1, 2, 3], edits, ConflictStrategy::LastWins).unwrap()
+    );
+}
+
+fn dict_edit(action: DictEditAction, items: &[(&str, i64)]) -> DictEdit {
+    DictEdit {
+        action,
+        items: items
+            .iter()
+            .map(|(k, v)| (k.to_string(), Val::Int(*v)))
+            .collect(),
+    }
+}
+
+fn dict(items: &[(&str, i64)]) -> IndexMap<String, Val> {
+    dict_edit(DictEditAction::Replace, items).items
+}
+
+#[test]
+fn test_merge_dict() {
+    let edits = || {
+        vec![
+            dict_edit(DictEditAction::Add, &[("a", 1), ("b", 2)]),
+            dict_edit(DictEditAction::Add, &[("b", 3)]),
+        ]
+    };
+    let expected = dict(&[("z", 0), ("a", 1), ("b", 3)]);
+    assert_eq!(
+        expected,
+        merge_dict(dict(&[("z", 0)]), edits(), ConflictStrategy::LastWins).unwrap()
+    );
+    assert_eq!(
+        expected,
+        merge_dict(dict(&[("z", 0)]), edits(), ConflictStrategy::Dedupe).unwrap()
+    );
+    assert_eq!(
+        "Conflicting values for key `b`: 2 and 3",
+        merge_dict(dict(&[("z", 0)]), edits(), ConflictStrategy::Error).unwrap_err()
+    );
+
+    // Re-adding an identical value, or replacing outright, is not a conflict.
+    assert_eq!(
+        dict(&[("b", 4)]),
+        merge_dict(
+            dict(&[("b", 2)]),
+            vec![
+                dict_edit(DictEditAction::Add, &[("b", 2)]),
+                dict_edit(DictEditAction::Replace, &[("b", 4)]),
+            ],
+            ConflictStrategy::Error
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_merge_dict_nested_keys() {
+    let default = IndexMap::from([
+        ("outer".to_owned(), Val::Dict(dict(&[("a", 1), ("b", 2)]))),
+        ("x.y".to_owned(), Val::Int(0)),
+    ]);
+    let edits = || {
+        vec![dict_edit(
+            DictEditAction::Add,
+            &[("outer.b", 3), ("outer.c", 4), ("x.y", 5), ("new.key", 6)],
+        )]
+    };
+    // Dotted keys edit nested dicts, unless the dict has the dotted key itself, or there is no
+    // nested dict to edit.
+    assert_eq!(
+        IndexMap::from([
+            (
+                "outer".to_owned(),
+                Val::Dict(dict(&[("a", 1), ("b", 3), ("c", 4)]))
+            ),
+            ("x.y".to_owned(), Val::Int(5)),
+            ("new.key".to_owned(), Val::Int(6)),
+        ]),
+        merge_dict(default.clone(), edits(), ConflictStrategy::LastWins).unwrap()
+    );
+    assert_eq!(
+        "Conflicting values for key `outer.b`: 2 and 3",
+        merge_dict(default, edits(), ConflictStrategy::Error).unwrap_err()
+    );
+}
@@ -0,0 +1,123 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::{Config, ConfigReader, ConfigSource, DEFAULT_SECTION, GLOBAL_SECTION};
+use crate::decrypt::Decryptor;
+use crate::filesystem::FileSystem;
+use crate::fromfile::FromfileExpander;
+use crate::seeds::LazySeeds;
+use crate::shell::ShellInterpolation;
+use crate::stats::ParseStats;
+
+const NESTED_CONFIG_FILE: &str = "pants.toml";
+
+///
+/// Everything needed to parse and read a config file in the same way as the parser's own config
+/// files, retained so that the `pants.toml` files of subdirectories can be read on demand.
+///
+pub(crate) struct ConfigLoader {
+    pub(crate) buildroot: PathBuf,
+    pub(crate) seed_values: HashMap<String, String>,
+    pub(crate) lazy_seeds: LazySeeds,
+    pub(crate) shell: Option<ShellInterpolation>,
+    pub(crate) stats: ParseStats,
+    pub(crate) bootstrap_options: Option<&'static [&'static str]>,
+    pub(crate) strict_toml: bool,
+    pub(crate) decryptor: Option<Arc<dyn Decryptor>>,
+    pub(crate) fromfile_expander: FromfileExpander,
+}
+
+impl ConfigLoader {
+    ///
+    /// Parses the nested config file at the given path, which may not set global options, as
+    /// those apply to the whole repo.
+    ///
+    pub(crate) fn load(&self, path: &Path) -> Result<(Arc<Config>, ConfigReader), String> {
+        let config_source =
+            ConfigSource::from_filesystem(&*self.fromfile_expander.filesystem(), path)?;
+        let config = match self.bootstrap_options {
+            Some(option_names) => Config::parse_bootstrap(
+                &config_source,
+                &self.seed_values,
+                Some(&self.lazy_seeds),
+                option_names,
+                self.shell.as_ref(),
+                &self.stats,
+            ),
+            None => Config::parse(
+                &config_source,
+                &self.seed_values,
+                Some(&self.lazy_seeds),
+                self.shell.as_ref(),
+                &self.stats,
+            ),
+        }?;
+        if let Some((section, _)) = config
+            .sections()
+            .find(|(section, _)| *section == GLOBAL_SECTION || *section == DEFAULT_SECTION)
+        {
+            return Err(format!(
+                "The nested config file {} may only set the options of subsystems and goals, but \
+                has a [{section}] section.",
+                path.display()
+            ));
+        }
+        let config = Arc::new(config);
+        Ok((config.clone(), self.reader(config)))
+    }
+
+    ///
+    /// Returns a reader of the given config, with the same settings as the parser's own readers.
+    ///
+    pub(crate) fn reader(&self, config: Arc<Config>) -> ConfigReader {
+        ConfigReader::new(config, self.fromfile_expander.clone())
+            .strict(self.strict_toml)
+            .decryptor(self.decryptor.clone())
+    }
+}
+
+///
+/// The nested config files that apply to work rooted at the given directory, which is either
+/// absolute or relative to the build root: those in each of its ancestors below the build root,
+/// and in itself, from the shallowest to the deepest.
+///
+pub(crate) fn nested_config_paths(
+    fs: &dyn FileSystem,
+    buildroot: &Path,
+    dir: &Path,
+) -> Result<Vec<PathBuf>, String> {
+    let relative_dir = if dir.is_absolute() {
+        dir.strip_prefix(buildroot).map_err(|_| {
+            format!(
+                "The directory {} is not within the build root {}.",
+                dir.display(),
+                buildroot.display()
+            )
+        })?
+    } else {
+        dir
+    };
+    let mut ancestor = buildroot.to_path_buf();
+    let mut paths = vec![];
+    for component in relative_dir.components() {
+        match component {
+            Component::Normal(name) => ancestor.push(name),
+            Component::CurDir => continue,
+            _ => {
+                return Err(format!(
+                    "The directory {} must be a path within the build root, without `..`.",
+                    dir.display()
+                ))
+            }
+        }
+        let path = ancestor.join(NESTED_CONFIG_FILE);
+        if fs.is_file(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
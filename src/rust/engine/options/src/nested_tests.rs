@@ -0,0 +1,67 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fs::{self, File};
+use std::path::Path;
+
+use tempfile::TempDir;
+
+use crate::nested::nested_config_paths;
+use crate::LocalFileSystem;
+
+#[test]
+fn test_nested_config_paths() {
+    let buildroot = TempDir::new().unwrap();
+    let root = buildroot.path();
+    fs::create_dir_all(root.join("src/team/lib/util")).unwrap();
+    File::create(root.join("pants.toml")).unwrap();
+    File::create(root.join("src/pants.toml")).unwrap();
+    File::create(root.join("src/team/lib/pants.toml")).unwrap();
+    // A directory named like a config file is ignored.
+    fs::create_dir(root.join("src/team/pants.toml")).unwrap();
+
+    let expected = vec![
+        root.join("src/pants.toml"),
+        root.join("src/team/lib/pants.toml"),
+    ];
+    assert_eq!(
+        expected,
+        nested_config_paths(&LocalFileSystem, root, Path::new("src/team/lib/util")).unwrap()
+    );
+    assert_eq!(
+        expected,
+        nested_config_paths(&LocalFileSystem, root, &root.join("src/team/lib")).unwrap()
+    );
+    assert_eq!(
+        expected,
+        nested_config_paths(&LocalFileSystem, root, Path::new("./src/team/lib")).unwrap()
+    );
+    assert_eq!(
+        vec![root.join("src/pants.toml")],
+        nested_config_paths(&LocalFileSystem, root, Path::new("src/team")).unwrap()
+    );
+    // The build root's own config file is not nested.
+    assert!(nested_config_paths(&LocalFileSystem, root, Path::new(""))
+        .unwrap()
+        .is_empty());
+    assert!(nested_config_paths(&LocalFileSystem, root, root)
+        .unwrap()
+        .is_empty());
+    // Directories that don't exist have no config files.
+    assert_eq!(
+        vec![root.join("src/pants.toml")],
+        nested_config_paths(&LocalFileSystem, root, Path::new("src/other/dir")).unwrap()
+    );
+
+    assert_eq!(
+        format!(
+            "The directory /elsewhere is not within the build root {}.",
+            root.display()
+        ),
+        nested_config_paths(&LocalFileSystem, root, Path::new("/elsewhere")).unwrap_err()
+    );
+    assert_eq!(
+        "The directory src/../.. must be a path within the build root, without `..`.",
+        nested_config_paths(&LocalFileSystem, root, Path::new("src/../..")).unwrap_err()
+    );
+}
@@ -0,0 +1,114 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt;
+
+use crate::{Source, SourceKind};
+
+///
+/// The order of precedence of the kinds of source of option values, from lowest to highest. By
+/// default, config files (and then the keyring) are overridden by env vars, which are overridden
+/// by flags. Defaults always have the lowest precedence.
+///
+/// An order is built up from the default, e.g. `SourceOrder::default().above(SourceKind::Config,
+/// SourceKind::Env)` so that config files override env vars, or `without(SourceKind::Env)` to
+/// ignore env vars entirely. See `OptionParser::set_source_order`.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceOrder {
+    kinds: Vec<SourceKind>,
+}
+
+impl Default for SourceOrder {
+    fn default() -> Self {
+        Self {
+            kinds: vec![
+                SourceKind::Config,
+                SourceKind::Keyring,
+                SourceKind::Env,
+                SourceKind::Flag,
+            ],
+        }
+    }
+}
+
+impl SourceOrder {
+    ///
+    /// An order of the given kinds of source, from lowest to highest precedence. Kinds that are
+    /// not given are dropped.
+    ///
+    pub fn new(kinds: impl IntoIterator<Item = SourceKind>) -> Result<Self, String> {
+        let mut order = Self { kinds: vec![] };
+        for kind in kinds {
+            if order.kinds.contains(&kind) {
+                return Err(format!(
+                    "The {} source appears more than once in the source order.",
+                    describe_kind(kind)
+                ));
+            }
+            order.kinds.push(kind);
+        }
+        Ok(order)
+    }
+
+    ///
+    /// Moves `kind` to just above `other`, so that it takes precedence over `other`, but not
+    /// over any kind that `other` was already overridden by. A kind that was dropped is restored.
+    /// Has no effect if `other` was dropped.
+    ///
+    pub fn above(mut self, kind: SourceKind, other: SourceKind) -> Self {
+        if kind == other || !self.kinds.contains(&other) {
+            return self;
+        }
+        self.kinds.retain(|k| *k != kind);
+        let index = self.kinds.iter().position(|k| *k == other).unwrap();
+        self.kinds.insert(index + 1, kind);
+        self
+    }
+
+    /// Drops `kind`, so that its values are ignored.
+    pub fn without(mut self, kind: SourceKind) -> Self {
+        self.kinds.retain(|k| *k != kind);
+        self
+    }
+
+    /// The kinds of source, from lowest to highest precedence.
+    pub fn kinds(&self) -> &[SourceKind] {
+        &self.kinds
+    }
+
+    ///
+    /// The position of the source in this order, or None if its kind is dropped. A registered
+    /// source is positioned just above the built-in source of its kind, and so is dropped with it.
+    ///
+    pub(crate) fn position(&self, source: &Source) -> Option<usize> {
+        let Some(kind) = source.kind() else {
+            return Some(0);
+        };
+        let index = self.kinds.iter().position(|k| *k == kind)?;
+        match source {
+            Source::Plugin { .. } => Some(2 * index + 2),
+            _ => Some(2 * index + 1),
+        }
+    }
+}
+
+fn describe_kind(kind: SourceKind) -> &'static str {
+    match kind {
+        SourceKind::Config => "config",
+        SourceKind::Keyring => "keyring",
+        SourceKind::Env => "env",
+        SourceKind::Flag => "flags",
+    }
+}
+
+impl fmt::Display for SourceOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kinds = self
+            .kinds
+            .iter()
+            .map(|kind| describe_kind(*kind))
+            .collect::<Vec<_>>();
+        write!(f, "{}", kinds.join(" < "))
+    }
+}
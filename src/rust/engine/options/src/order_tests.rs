@@ -0,0 +1,73 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::{Source, SourceKind, SourceOrder};
+
+#[test]
+fn test_source_order() {
+    let order = SourceOrder::default();
+    assert_eq!("config < keyring < env < flags", order.to_string());
+
+    // Config files override env vars, but not flags.
+    let order = order.above(SourceKind::Config, SourceKind::Env);
+    assert_eq!("keyring < env < config < flags", order.to_string());
+    let order = order.without(SourceKind::Keyring);
+    assert_eq!(
+        &[SourceKind::Env, SourceKind::Config, SourceKind::Flag],
+        order.kinds()
+    );
+    // A dropped kind may be restored, but nothing may be placed above a dropped kind.
+    assert_eq!(
+        "env < keyring < config < flags",
+        order
+            .clone()
+            .above(SourceKind::Keyring, SourceKind::Env)
+            .to_string()
+    );
+    assert_eq!(
+        order,
+        order.clone().above(SourceKind::Env, SourceKind::Keyring)
+    );
+
+    assert_eq!(
+        "env < flags",
+        SourceOrder::new([SourceKind::Env, SourceKind::Flag])
+            .unwrap()
+            .to_string()
+    );
+    assert_eq!(
+        Err("The env source appears more than once in the source order.".to_owned()),
+        SourceOrder::new([SourceKind::Env, SourceKind::Flag, SourceKind::Env])
+    );
+}
+
+#[test]
+fn test_position() {
+    let order = SourceOrder::default()
+        .above(SourceKind::Config, SourceKind::Env)
+        .without(SourceKind::Keyring);
+    let plugin = |precedence| Source::Plugin {
+        precedence,
+        name: "plugin".to_owned(),
+    };
+    let positions = [
+        Source::Default,
+        Source::Env,
+        plugin(SourceKind::Env),
+        Source::Config {
+            ordinal: 0,
+            path: "pants.toml".to_owned(),
+        },
+        plugin(SourceKind::Config),
+        Source::Flag,
+    ]
+    .iter()
+    .map(|source| order.position(source))
+    .collect::<Vec<_>>();
+    assert_eq!(
+        vec![Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)],
+        positions
+    );
+    assert_eq!(None, order.position(&Source::Keyring));
+    assert_eq!(None, order.position(&plugin(SourceKind::Keyring)));
+}
@@ -1,14 +1,24 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-use super::{DictEdit, DictEditAction, ListEdit, ListEditAction, Val};
+use super::{Datetime, DictEdit, DictEditAction, ListEdit, ListEditAction, Val};
 use crate::render_choice;
 
+use indexmap::IndexMap;
 use serde::de::DeserializeOwned;
-use std::collections::HashMap;
 use std::fmt::Display;
 use std::path::Path;
 
+// Option values are parsed as a subset of Python literal syntax:
+//
+// - `True`/`False` in any case (and for standalone bools, `yes`/`no`, `on`/`off` or `1`/`0`), ints (which may be hex, octal or binary) and floats, with optional
+//   `_` digit separators, and `None` (or `null`). Complex numbers are not supported.
+// - Single- or double-quoted strings with Python's escape sequences, except `\u`, `\U` and
+//   `\N{...}`. String prefixes (e.g. `r''` or `b''`) and triple-quoted strings are not supported.
+// - Lists and tuples (which are both parsed as lists), and dicts with string keys, which may have
+//   trailing commas and `//` or `/* */` comments.
+//
+// Sets, expressions and names other than those above are not supported.
 peg::parser! {
     grammar option_value_parser() for str {
         use peg::ParseLiteral;
@@ -16,8 +26,16 @@ peg::parser! {
         rule whitespace() -> ()
             = quiet!{ " " / "\n" / "\r" / "\t" }
 
+        // As in relaxed JSON, `//` and `/* */` comments may appear wherever whitespace may within
+        // a list or dict, but not before an implicitly added value, which may be any string.
+        rule comment() -> ()
+            = quiet!{ ("//" (!"\n" [_])*) / ("/*" (!"*/" [_])* "*/") }
+
+        rule ws() -> ()
+            = whitespace() / comment()
+
         rule value_with_ws<T>(parse_value: rule<T>) -> T
-            = whitespace()* value:parse_value() whitespace()* { value }
+            = ws()* value:parse_value() ws()* { value }
 
         rule false() -> bool
             = quiet!{ ("F"/"f") ("A"/"a") ("L"/"l") ("S"/"s") ("E"/"e") } { false }
@@ -25,8 +43,26 @@ peg::parser! {
         rule true() -> bool
             = quiet!{ ("T"/"t") ("R"/"r") ("U"/"u") ("E"/"e") } { true }
 
+        // Standalone bools may also be spelled as is common in env vars and the like. But these
+        // spellings aren't accepted within dicts, where e.g. `1` must remain an int.
+        rule on() -> bool
+            = quiet!{
+                (("Y"/"y") ("E"/"e") ("S"/"s")) / (("O"/"o") ("N"/"n")) / "1"
+            } { true }
+
+        rule off() -> bool
+            = quiet!{
+                (("N"/"n") ("O"/"o")) / (("O"/"o") ("F"/"f") ("F"/"f")) / "0"
+            } { false }
+
         pub(crate) rule bool() -> bool
-            = b:(true() / false() / expected!("'true' or 'false'")) { b }
+            = b:(
+                true() / false() / on() / off() /
+                expected!("'true', 'false', 'yes', 'no', 'on', 'off', '1' or '0' (in any case)")
+            ) { b }
+
+        rule dict_bool() -> bool
+            = true() / false()
 
         // Python numeric literals can include digit-separator underscores. It's unlikely
         // that anyone relies on those in option values, but since the old Python options
@@ -34,8 +70,31 @@ peg::parser! {
         rule digitpart() -> &'input str
             = dp:$(['0'..='9'] ("_"? ['0'..='9'])*) { dp }
 
+        // As in Python, hex, octal and binary ints are prefixed with `0x`, `0o` or `0b`, after which
+        // digit-separator underscores may also appear.
+        rule radix_digitpart() -> &'input str
+            = quiet!{
+                dp:$("0" (
+                    (("x" / "X") ("_"? ['0'..='9' | 'a'..='f' | 'A'..='F'])+) /
+                    (("o" / "O") ("_"? ['0'..='7'])+) /
+                    (("b" / "B") ("_"? ['0'..='1'])+)
+                )) { dp }
+            }
+
+        rule int_literal() -> &'input str
+            = i:$(("+" / "-")? (radix_digitpart() / digitpart())) { i }
+
         pub(crate) rule int() -> i64
-            = i:$(("+" / "-")?digitpart()) { i.replace('_', "").parse::<i64>().unwrap() }
+            = i:int_literal() {?
+                parse_int(i)
+                    .and_then(|i| i64::try_from(i).ok())
+                    .ok_or("an int between -9223372036854775808 and 9223372036854775807")
+            }
+
+        // For the rare options whose values may not fit in an i64, such as byte counts reported by
+        // external systems.
+        pub(crate) rule big_int() -> i128
+            = i:int_literal() {? parse_int(i).ok_or("an int that fits in 128 bits") }
 
         pub(crate) rule float() -> f64
             = f:$(("+" / "-")?digitpart() "." digitpart()? (("e" / "E") ("+" / "-") digitpart())?) {
@@ -136,7 +195,7 @@ peg::parser! {
         rule tuple_items<T>(parse_value: rule<T>) -> Vec<T>
             = tuple_start()
             items:value_with_ws(&parse_value) ** ","
-            ","? whitespace()*
+            ","? ws()*
             tuple_end() {
                 items
             }
@@ -152,7 +211,7 @@ peg::parser! {
         rule list_items<T>(parse_value: rule<T>) -> Vec<T>
             = list_start()
             items:value_with_ws(&parse_value) ** ","
-            ","? whitespace()*
+            ","? ws()*
             list_end() {
                 items
             }
@@ -167,8 +226,40 @@ peg::parser! {
                 ListEdit { action, items }
             }
 
+        // Inserts the items at an index, e.g. `+0[...]`, or before or after the first occurrence of
+        // an anchor item, e.g. `+<'a'[...]` or `+>'a'[...]`. The anchor is kept as the first item.
+        rule list_insert<T>(parse_value: rule<T>) -> ListEdit<T>
+            = whitespace()* quiet!{ "+" } edit:(
+                list_insert_at(&parse_value) / list_insert_relative(&parse_value)
+            ) whitespace()* { edit }
+
+        rule list_insert_at<T>(parse_value: rule<T>) -> ListEdit<T>
+            = index:$(['0'..='9']+) items:items(&parse_value) {?
+                let index = index.parse::<usize>().or(Err("a list index"))?;
+                Ok(ListEdit { action: ListEditAction::Insert(index), items })
+            }
+
+        rule list_insert_relative<T>(parse_value: rule<T>) -> ListEdit<T>
+            = marker:$("<" / ">") ws()* anchor:parse_value() items:items(&parse_value) {
+                let action = if marker == "<" {
+                    ListEditAction::InsertBefore
+                } else {
+                    ListEditAction::InsertAfter
+                };
+                ListEdit { action, items: std::iter::once(anchor).chain(items).collect() }
+            }
+
+        rule list_insert_start() -> ()
+            = whitespace()* "+" (['0'..='9'] / "<" / ">")
+
         rule list_edits<T>(parse_value: rule<T>) -> Vec<ListEdit<T>>
-            = e:list_edit(&parse_value) ++ "," { e }
+            = e:(list_insert(&parse_value) / list_edit(&parse_value)) ++ "," { e }
+
+        // Edits that start with an insertion, which must be tried before an implicit add, as e.g.
+        // `+0['a']` would otherwise be taken as a single string. If they don't parse as edits, they
+        // may still be an implicit add, e.g. of `+1`.
+        rule inserting_list_edits<T>(parse_value: rule<T>) -> Vec<ListEdit<T>>
+            = &list_insert_start() e:list_edits(&parse_value) ![_] { e }
 
         rule list_replace<T>(parse_value: rule<T>) -> Vec<ListEdit<T>>
             = items:items(&parse_value) {
@@ -185,41 +276,52 @@ peg::parser! {
             }
 
         rule scalar_list_edits<T>(parse_scalar: rule<T>) -> Vec<ListEdit<T>>
-            = implicit_add(&parse_scalar) / list_replace(&parse_scalar) / list_edits(&parse_scalar)
+            = inserting_list_edits(&parse_scalar) / implicit_add(&parse_scalar) /
+              list_replace(&parse_scalar) / list_edits(&parse_scalar)
 
         pub(crate) rule bool_list_edits() -> Vec<ListEdit<bool>> = scalar_list_edits(<bool()>)
 
         pub(crate) rule int_list_edits() -> Vec<ListEdit<i64>> = scalar_list_edits(<int()>)
 
-        pub(crate) rule float_list_edits() -> Vec<ListEdit<f64>> = scalar_list_edits(<float()>)
+        pub(crate) rule big_int_list_edits() -> Vec<ListEdit<i128>> =
+            scalar_list_edits(<big_int()>)
+
+        // As for scalar floats, we coerce ints in float lists.
+        rule float_or_int() -> f64 = float() / i:int() { i as f64 }
+
+        pub(crate) rule float_list_edits() -> Vec<ListEdit<f64>> =
+            scalar_list_edits(<float_or_int()>)
 
         // Make `--foo=` yield an implicit add of an empty string.
         rule empty_string_string_list() -> Vec<ListEdit<String>>
             = ![_] { vec![ListEdit { action: ListEditAction::Add, items: vec!["".to_string()] }] }
 
         pub(crate) rule string_list_edits() -> Vec<ListEdit<String>>
-            = empty_string_string_list() / implicit_add(<unquoted_string()>) /
+            = empty_string_string_list() / inserting_list_edits(<quoted_string()>) /
+              implicit_add(<unquoted_string()>) /
               list_replace(<quoted_string()>) / list_edits(<quoted_string()>)
 
         // Heterogeneous values embedded in dicts. Note that float_val() must precede int_val() so that
         // the integer prefix of a float is not interpreted as an int.
         rule val() -> Val
-            = v:(bool_val() / float_val() / int_val() / string_val() / list_val() / tuple_val() / dict_val()) {
+            = v:(bool_val() / float_val() / int_val() / string_val() / list_val() / tuple_val() / dict_val() /
+                 null_val()) {
             v
         }
 
-        rule bool_val() -> Val = x:bool() { Val::Bool(x) }
+        rule bool_val() -> Val = x:dict_bool() { Val::Bool(x) }
         rule float_val() -> Val = x:float() { Val::Float(x) }
         rule int_val() -> Val = x:int() { Val::Int(x) }
-        rule string_val() -> Val = x:quoted_string() { Val::String(x) }
+        rule string_val() -> Val = x:quoted_string() { Val::String(x.into()) }
         rule list_val() -> Val = items:list_items(<val()>) { Val::List(items) }
         rule tuple_val() -> Val = items:tuple_items(<val()>) { Val::List(items) }
         rule dict_val() -> Val = whitespace()* d:dict() { Val::Dict(d) }
+        rule null_val() -> Val = ("None" / "null") { Val::Null }
 
-        rule dict() -> HashMap<String, Val>
+        pub(crate) rule dict() -> IndexMap<String, Val>
             = dict_start()
             items:dict_item() ** ","
-            whitespace()* ","? whitespace()*
+            ws()* ","? ws()*
             dict_end()
             whitespace()* {
                 items.into_iter().collect()
@@ -234,7 +336,7 @@ peg::parser! {
             / expected!("the end of a dict indicated by '}'")
 
         rule dict_item() -> (String, Val)
-            = whitespace()* key:quoted_string() whitespace()* ":" whitespace()* value:val() whitespace()* {
+            = ws()* key:quoted_string() ws()* ":" ws()* value:val() ws()* {
                 (key, value)
             }
 
@@ -245,9 +347,32 @@ peg::parser! {
                     items: d,
                 }
             }
+
+        // Edits to a list of dicts operate on whole dicts, so e.g. `-[{"a": 1}]` removes only
+        // entries equal to `{"a": 1}`.
+        pub(crate) rule dict_list_edits() -> Vec<ListEdit<IndexMap<String, Val>>>
+            = inserting_list_edits(<dict()>) / implicit_add(<dict()>) / list_replace(<dict()>) /
+              list_edits(<dict()>)
     }
 }
 
+// Converts the text of an int literal, which is known to be well-formed, to an i128, unless it
+// overflows.
+fn parse_int(text: &str) -> Option<i128> {
+    let text = text.replace('_', "");
+    let (sign, magnitude) = match text.strip_prefix(['+', '-']) {
+        Some(magnitude) => (&text[..1], magnitude),
+        None => ("", text.as_str()),
+    };
+    let (radix, digits) = match magnitude.get(..2) {
+        Some("0x" | "0X") => (16, &magnitude[2..]),
+        Some("0o" | "0O") => (8, &magnitude[2..]),
+        Some("0b" | "0B") => (2, &magnitude[2..]),
+        _ => (10, magnitude),
+    };
+    i128::from_str_radix(&format!("{sign}{digits}"), radix).ok()
+}
+
 mod err {
     #[derive(Debug, Eq, PartialEq)]
     pub(crate) struct ParseError {
@@ -277,6 +402,11 @@ mod err {
 
 pub(crate) use err::ParseError;
 
+// Attributes an error to one of the several fromfiles that a value concatenates.
+pub(crate) fn attribute_parse_err(err: ParseError, fromfile: &str) -> ParseError {
+    ParseError::new(format!("{} (from `{fromfile}`)", err.render("{name}")))
+}
+
 pub(crate) fn mk_parse_err(err: impl Display, path: &Path) -> ParseError {
     ParseError::new(format!(
         "Problem reading {path} for {{name}}: {err}",
@@ -284,51 +414,202 @@ pub(crate) fn mk_parse_err(err: impl Display, path: &Path) -> ParseError {
     ))
 }
 
-fn format_parse_error(
-    type_id: &str,
-    value: &str,
-    parse_error: peg::error::ParseError<peg::str::LineCol>,
-) -> ParseError {
-    let value_with_marker = value
+pub(crate) fn unexpanded_fromfile_err(value: &str) -> ParseError {
+    ParseError::new(format!(
+        "The value `{value}` of {{name}} refers to a fromfile, which a bootstrap parse does not \
+        read. Take the value of {{name}} from a full parse instead."
+    ))
+}
+
+fn mark_position(value: &str, position: (usize, usize)) -> String {
+    value
         .split('\n')
         .enumerate()
         .map(|(index, line)| (index + 1, line))
         .map(|(line_no, line)| {
-            if line_no == parse_error.location.line {
-                format!(
-                    "{}:{}\n  {}^",
-                    line_no,
-                    line,
-                    "-".repeat(parse_error.location.column - 1)
-                )
+            if line_no == position.0 {
+                format!("{}:{}\n  {}^", line_no, line, "-".repeat(position.1 - 1))
             } else {
                 format!("{line_no}:{line}")
             }
         })
         .collect::<Vec<_>>()
-        .join("\n");
+        .join("\n")
+}
 
+///
+/// Finds the first structural problem in a list or dict literal: an unterminated string, or an
+/// unbalanced bracket. These are reported in preference to the parser's expectations, which for
+/// such problems typically point at the end of the value rather than at their cause.
+///
+/// Returns the 1-based line and column of the problem, along with a description of it.
+///
+fn diagnose_literal(value: &str) -> Option<((usize, usize), String)> {
+    let mut brackets: Vec<(char, (usize, usize))> = vec![];
+    let mut string: Option<(char, (usize, usize))> = None;
+    let (mut line, mut column) = (1, 0);
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        column += 1;
+        let position = (line, column);
+        if c == '\n' {
+            line += 1;
+            column = 0;
+        }
+        if let Some((quote, _)) = string {
+            if c == '\\' && chars.next_if(|c| *c != '\n').is_some() {
+                column += 1;
+            } else if c == quote {
+                string = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => string = Some((c, position)),
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|c| *c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                column += 1;
+                let mut prev = None;
+                for c in chars.by_ref() {
+                    column += 1;
+                    if c == '\n' {
+                        line += 1;
+                        column = 0;
+                    }
+                    if prev == Some('*') && c == '/' {
+                        break;
+                    }
+                    prev = Some(c);
+                }
+            }
+            '[' | '(' | '{' => brackets.push((c, position)),
+            ']' | ')' | '}' => {
+                let open = match c {
+                    ']' => '[',
+                    ')' => '(',
+                    _ => '{',
+                };
+                match brackets.pop() {
+                    Some((bracket, _)) if bracket == open => (),
+                    Some((bracket, (l, col))) => {
+                        return Some((
+                            position,
+                            format!(
+                                "Mismatched `{c}`, where the `{bracket}` at line {l} column {col} \
+                                 must be closed first"
+                            ),
+                        ))
+                    }
+                    None => return Some((position, format!("Unmatched `{c}`"))),
+                }
+            }
+            _ => (),
+        }
+    }
+    if let Some((_, position)) = string {
+        return Some((position, "Unterminated string".to_owned()));
+    }
+    brackets
+        .pop()
+        .map(|(bracket, position)| (position, format!("Unclosed `{bracket}`")))
+}
+
+fn format_parse_error(
+    type_id: &str,
+    value: &str,
+    parse_error: peg::error::ParseError<peg::str::LineCol>,
+) -> ParseError {
     let mut choices = parse_error.expected.tokens().collect::<Vec<_>>();
     // N.B.: It appears to be the case that the peg parser parses alternatives concurrently and so
     // the ordering of choices is observed to be unstable. As such sort them for consistent error
     // messages.
     choices.sort_unstable();
 
+    format_problem(
+        type_id,
+        value,
+        (parse_error.location.line, parse_error.location.column),
+        format!(
+            "Expected {}",
+            render_choice(choices.as_slice()).unwrap_or_else(|| "nothing".to_owned())
+        ),
+    )
+}
+
+fn format_problem(
+    type_id: &str,
+    value: &str,
+    position: (usize, usize),
+    problem: String,
+) -> ParseError {
     ParseError::new(format!(
         "\
-    Problem parsing {{name}} {type_id} value:\n{value_with_marker}\nExpected {choices} at \
+    Problem parsing {{name}} {type_id} value:\n{value_with_marker}\n{problem} at \
     line {line} column {column}\
     ",
-        type_id = type_id,
-        value_with_marker = value_with_marker,
-        choices = render_choice(choices.as_slice()).unwrap_or_else(|| "nothing".to_owned()),
-        line = parse_error.location.line,
-        column = parse_error.location.column,
+        value_with_marker = mark_position(value, position),
+        line = position.0,
+        column = position.1,
     ))
 }
 
+// Formats a failure to parse a list or dict literal, preferring a structural diagnosis.
+fn format_literal_parse_error(
+    type_id: &str,
+    value: &str,
+    parse_error: peg::error::ParseError<peg::str::LineCol>,
+) -> ParseError {
+    match diagnose_literal(value) {
+        Some((position, problem)) => format_problem(type_id, value, position, problem),
+        None => format_parse_error(type_id, value, parse_error),
+    }
+}
+
 pub(crate) fn parse_dict(value: &str) -> Result<DictEdit, ParseError> {
-    option_value_parser::dict_edit(value).map_err(|e| format_parse_error("dict", value, e))
+    option_value_parser::dict_edit(value).map_err(|e| format_literal_parse_error("dict", value, e))
+}
+
+///
+/// Parses a percentage, given either as e.g. `80%` or as a fraction such as `0.8`, to a fraction
+/// between 0 and 1.
+///
+pub(crate) fn parse_percentage(value: &str) -> Result<f64, String> {
+    let value = value.trim();
+    let fraction = match value.strip_suffix('%') {
+        Some(percent) => percent
+            .trim_end()
+            .parse::<f64>()
+            .map(|percent| percent / 100.0),
+        None => value.parse::<f64>(),
+    }
+    .map_err(|_| "Expected a percentage such as `80%`, or a fraction such as `0.8`".to_owned())?;
+    check_percentage(fraction)
+}
+
+pub(crate) fn check_percentage(fraction: f64) -> Result<f64, String> {
+    if (0.0..=1.0).contains(&fraction) {
+        Ok(fraction)
+    } else {
+        Err("Expected a percentage between 0% and 100%".to_owned())
+    }
+}
+
+///
+/// Parses an RFC 3339 date, such as `2024-12-31`, or datetime, such as `2024-12-31T23:59:59Z`,
+/// with or without an offset.
+///
+pub(crate) fn parse_datetime(value: &str) -> Result<Datetime, String> {
+    value
+        .trim()
+        .parse::<Datetime>()
+        .ok()
+        .filter(|datetime| datetime.date.is_some())
+        .ok_or_else(|| {
+            "Expected an RFC 3339 date or datetime, such as `2024-12-31` or \
+            `2024-12-31T23:59:59Z`"
+                .to_owned()
+        })
 }
 
 pub(crate) trait Parseable: Sized + DeserializeOwned {
@@ -344,7 +625,7 @@ pub(crate) trait Parseable: Sized + DeserializeOwned {
         value: &str,
         e: peg::error::ParseError<peg::str::LineCol>,
     ) -> ParseError {
-        format_parse_error(&format!("{} list", Self::OPTION_TYPE), value, e)
+        format_literal_parse_error(&format!("{} list", Self::OPTION_TYPE), value, e)
     }
 }
 
@@ -374,6 +655,19 @@ impl Parseable for i64 {
     }
 }
 
+impl Parseable for i128 {
+    const OPTION_TYPE: &'static str = "big int";
+
+    fn parse(value: &str) -> Result<i128, ParseError> {
+        option_value_parser::big_int(value).map_err(|e| Self::format_parse_error(value, e))
+    }
+
+    fn parse_list(value: &str) -> Result<Vec<ListEdit<i128>>, ParseError> {
+        option_value_parser::big_int_list_edits(value)
+            .map_err(|e| Self::format_list_parse_error(value, e))
+    }
+}
+
 impl Parseable for f64 {
     const OPTION_TYPE: &'static str = "float";
 
@@ -399,3 +693,17 @@ impl Parseable for String {
             .map_err(|e| Self::format_list_parse_error(value, e))
     }
 }
+
+impl Parseable for IndexMap<String, Val> {
+    const OPTION_TYPE: &'static str = "dict";
+
+    fn parse(value: &str) -> Result<IndexMap<String, Val>, ParseError> {
+        option_value_parser::dict(value)
+            .map_err(|e| format_literal_parse_error(Self::OPTION_TYPE, value, e))
+    }
+
+    fn parse_list(value: &str) -> Result<Vec<ListEdit<IndexMap<String, Val>>>, ParseError> {
+        option_value_parser::dict_list_edits(value)
+            .map_err(|e| Self::format_list_parse_error(value, e))
+    }
+}
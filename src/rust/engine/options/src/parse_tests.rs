@@ -3,7 +3,7 @@
 
 use crate::parse::*;
 use crate::{DictEdit, DictEditAction, ListEdit, ListEditAction, Val};
-use std::collections::HashMap;
+use indexmap::IndexMap;
 use std::fmt::Debug;
 
 // Helper macro (and associated functions) to print multiline parse errors.
@@ -106,12 +106,34 @@ fn test_parse_bool() {
     check_bool(false, "False");
     check_bool(false, "FALSE");
 
+    check_bool(true, "yes");
+    check_bool(true, "On");
+    check_bool(true, "1");
+    check_bool(false, "NO");
+    check_bool(false, "off");
+    check_bool(false, "0");
+
     assert_eq!(
-        "Problem parsing foo bool value:\n1:1\n  ^\nExpected 'true' or 'false' \
+        "Problem parsing foo bool value:\n1:2\n  ^\nExpected 'true', 'false', 'yes', 'no', 'on', 'off', '1' or '0' (in any case) \
         at line 1 column 1"
             .to_owned(),
-        bool::parse("1").unwrap_err().render("foo")
-    )
+        bool::parse("2").unwrap_err().render("foo")
+    );
+    check!(
+        vec![scalar_list_edit(ListEditAction::Replace, [true, false])],
+        bool::parse_list("[yes, 0]")
+    );
+    // Within dicts, only `True` and `False` are bools.
+    check!(
+        DictEdit {
+            action: DictEditAction::Replace,
+            items: IndexMap::from([
+                ("a".to_string(), Val::Int(1)),
+                ("b".to_string(), Val::Bool(true)),
+            ]),
+        },
+        parse_dict("{'a': 1, 'b': true}")
+    );
 }
 
 #[test]
@@ -141,6 +163,26 @@ fn test_parse_int() {
             .to_owned(),
         i64::parse("12badint").unwrap_err().render("foo")
     );
+
+    check_int(16, "0x10");
+    check_int(255, "0XfF");
+    check_int(-16, "-0x_10");
+    check_int(493, "0o755");
+    check_int(5, "+0b101");
+    check_int(1_000_000, "1_000_000");
+    check_int(9223372036854775807, "0x7fff_ffff_ffff_ffff");
+    assert_eq!(
+        "Problem parsing foo int value:\n1:9223372036854775808\n  -------------------^\n\
+         Expected \"_\", ['0'..='9'] or an int between -9223372036854775808 and 9223372036854775807 at line 1 column 20"
+            .to_owned(),
+        i64::parse("9223372036854775808").unwrap_err().render("foo")
+    );
+    assert!(i64::parse("0x8000000000000000").is_err());
+    assert!(i64::parse("0o8").is_err());
+    check!(
+        vec![scalar_list_edit(ListEditAction::Replace, [16, 8])],
+        i64::parse_list("[0x10, 0o10]")
+    );
 }
 
 #[test]
@@ -267,6 +309,10 @@ fn test_parse_float_list_replace() {
         vec![scalar_list_edit(ListEditAction::Replace, [42.0, -1.27e+7])],
         f64::parse_list("[42.0,-127.0e+5]")
     );
+    check!(
+        vec![scalar_list_edit(ListEditAction::Replace, [1.0, 2.5, -3.0])],
+        f64::parse_list("[1, 2.5, -3]")
+    );
 }
 
 #[test]
@@ -371,6 +417,44 @@ fn test_parse_scalar_list_edits_whitespace() {
     );
 }
 
+#[test]
+fn test_parse_list_inserts() {
+    check!(
+        vec![
+            string_list_edit(ListEditAction::Insert(0), ["foo", "bar"]),
+            string_list_edit(ListEditAction::InsertBefore, ["baz", "qux"]),
+            string_list_edit(ListEditAction::InsertAfter, ["baz", "quux"]),
+            string_list_edit(ListEditAction::Remove, ["corge"]),
+        ],
+        String::parse_list("+0['foo', 'bar'],+<'baz'['qux'], +> 'baz' ['quux'],-['corge']")
+    );
+    check!(
+        vec![
+            string_list_edit(ListEditAction::Add, ["foo"]),
+            string_list_edit(ListEditAction::Insert(12), ["bar"]),
+        ],
+        String::parse_list("+['foo'],+12['bar']")
+    );
+    check!(
+        vec![scalar_list_edit(ListEditAction::InsertAfter, vec![1, 2, 3])],
+        i64::parse_list("+>1[2, 3]")
+    );
+
+    // Values which don't parse as insertions may still be implicit adds.
+    check!(
+        vec![string_list_edit(ListEditAction::Add, ["+1"])],
+        String::parse_list("+1")
+    );
+    check!(
+        vec![string_list_edit(ListEditAction::Add, ["+<foo"])],
+        String::parse_list("+<foo")
+    );
+    check!(
+        vec![scalar_list_edit(ListEditAction::Add, vec![1])],
+        i64::parse_list("+1")
+    );
+}
+
 #[test]
 fn test_parse_string_list_implicit_add() {
     check!(
@@ -565,11 +649,11 @@ or '-' indicating `remove` at line 2 column 10"
     )
 }
 
-fn mk_hashmap(items: &Vec<(&str, &str)>) -> HashMap<String, Val> {
-    HashMap::<_, _>::from_iter(
+fn mk_hashmap(items: &Vec<(&str, &str)>) -> IndexMap<String, Val> {
+    IndexMap::<_, _>::from_iter(
         items
             .iter()
-            .map(|(k, v)| (k.to_string(), Val::String(v.to_string()))),
+            .map(|(k, v)| (k.to_string(), Val::String((*v).into()))),
     )
 }
 
@@ -599,6 +683,53 @@ fn test_parse_dict_simple() {
     );
 }
 
+#[test]
+fn test_parse_dict_null() {
+    check!(
+        DictEdit {
+            action: DictEditAction::Replace,
+            items: IndexMap::from([
+                ("foo".to_string(), Val::Null),
+                ("bar".to_string(), Val::List(vec![Val::Null])),
+            ]),
+        },
+        parse_dict("{'foo': None, 'bar': [None]}")
+    );
+}
+
+#[test]
+fn test_parse_dict_relaxed_json() {
+    check!(
+        DictEdit {
+            action: DictEditAction::Replace,
+            items: IndexMap::from([
+                ("foo".to_string(), Val::Null),
+                (
+                    "bar".to_string(),
+                    Val::List(vec![Val::Int(1), Val::String("http://x".into())])
+                ),
+            ]),
+        },
+        parse_dict(
+            r#"{
+            // The foo.
+            "foo": null, /* Not None. */
+            "bar": [1, /* one */ "http://x", // trailing
+            ],
+        }"#
+        )
+    );
+    check!(
+        vec![string_list_edit(ListEditAction::Replace, ["foo"])],
+        String::parse_list("['foo', // bar\n]")
+    );
+    // An implicitly added string may look like a comment.
+    check!(
+        vec![string_list_edit(ListEditAction::Add, ["// foo"])],
+        String::parse_list("// foo")
+    );
+}
+
 #[test]
 fn test_parse_dict_add() {
     check!(
@@ -623,17 +754,14 @@ fn test_parse_dict_whitespace() {
 
 #[test]
 fn test_parse_dict_of_list_of_string() {
-    let mut expected = HashMap::<String, Val>::new();
+    let mut expected = IndexMap::<String, Val>::new();
     expected.insert(
         "foo".to_string(),
-        Val::List(vec![
-            Val::String("foo1".to_string()),
-            Val::String("foo2".to_string()),
-        ]),
+        Val::List(vec![Val::String("foo1".into()), Val::String("foo2".into())]),
     );
     expected.insert(
         "bar".to_string(),
-        Val::List(vec![Val::String("bar1".to_string())]),
+        Val::List(vec![Val::String("bar1".into())]),
     );
     expected.insert("baz".to_string(), Val::List(vec![]));
 
@@ -648,21 +776,18 @@ fn test_parse_dict_of_list_of_string() {
 
 #[test]
 fn test_parse_heterogeneous_dict() {
-    let mut nested = HashMap::<String, Val>::new();
+    let mut nested = IndexMap::<String, Val>::new();
     nested.insert("x".to_string(), Val::Float(3.14));
-    nested.insert(
-        "y".to_string(),
-        Val::List(vec![Val::String("y1".to_string())]),
-    );
-    let mut expected = HashMap::<String, Val>::new();
+    nested.insert("y".to_string(), Val::List(vec![Val::String("y1".into())]));
+    let mut expected = IndexMap::<String, Val>::new();
     expected.insert(
         "foo".to_string(),
-        Val::List(vec![Val::Int(42), Val::String("foo1".to_string())]),
+        Val::List(vec![Val::Int(42), Val::String("foo1".into())]),
     );
     expected.insert(
         "bar".to_string(),
         Val::List(vec![
-            Val::String("bar1".to_string()),
+            Val::String("bar1".into()),
             Val::Bool(true),
             Val::List(vec![]),
         ]),
@@ -688,3 +813,69 @@ fn test_parse_heterogeneous_dict() {
         )
     );
 }
+
+#[test]
+fn test_parse_literal_error_diagnosis() {
+    assert_eq!(
+        "\
+Problem parsing foo dict value:
+1:{'a': 1, 'b: 2}
+  ---------^
+Unterminated string at line 1 column 10",
+        parse_dict("{'a': 1, 'b: 2}").unwrap_err().render("foo")
+    );
+    assert_eq!(
+        "\
+Problem parsing foo int list value:
+1:[1, [2, 3)]
+  ---------^
+Mismatched `)`, where the `[` at line 1 column 5 must be closed first at line 1 column 10",
+        i64::parse_list("[1, [2, 3)]").unwrap_err().render("foo")
+    );
+    assert_eq!(
+        "\
+Problem parsing foo string list value:
+1:+['a', // ']'
+  -^
+2:
+Unclosed `[` at line 1 column 2",
+        String::parse_list("+['a', // ']'\n")
+            .unwrap_err()
+            .render("foo")
+    );
+    assert_eq!(
+        "\
+Problem parsing foo dict value:
+1:{'a': \"\\\"}\"}}
+  ------------^
+Unmatched `}` at line 1 column 13",
+        parse_dict("{'a': \"\\\"}\"}}").unwrap_err().render("foo")
+    );
+    // Otherwise, the parser's expectations are reported.
+    assert!(parse_dict("{'a' 1}")
+        .unwrap_err()
+        .render("foo")
+        .ends_with("Expected \":\" at line 1 column 6"));
+}
+
+#[test]
+fn test_parse_percentage() {
+    assert_eq!(Ok(0.8), parse_percentage("80%"));
+    assert_eq!(Ok(0.8), parse_percentage(" 80 % "));
+    assert_eq!(Ok(0.8), parse_percentage("0.8"));
+    assert_eq!(Ok(0.125), parse_percentage("12.5%"));
+    assert_eq!(Ok(0.0), parse_percentage("0%"));
+    assert_eq!(Ok(1.0), parse_percentage("1"));
+
+    let not_a_percentage =
+        Err("Expected a percentage such as `80%`, or a fraction such as `0.8`".to_owned());
+    assert_eq!(not_a_percentage, parse_percentage("eighty"));
+    assert_eq!(not_a_percentage, parse_percentage("%"));
+    assert_eq!(not_a_percentage, parse_percentage("80%%"));
+
+    let out_of_range = Err("Expected a percentage between 0% and 100%".to_owned());
+    assert_eq!(out_of_range, parse_percentage("150%"));
+    assert_eq!(out_of_range, parse_percentage("-0.1"));
+    assert_eq!(out_of_range, parse_percentage("80"));
+    assert_eq!(out_of_range, parse_percentage("NaN"));
+}
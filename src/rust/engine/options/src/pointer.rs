@@ -0,0 +1,51 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::Val;
+
+///
+/// Returns the value nested within `value` at the given path, which is a JSON-pointer-like
+/// sequence of dict keys and list indices separated by `/`, e.g. `tools/0/version`. As in a JSON
+/// pointer, a `/` or `~` within a key is escaped as `~1` or `~0` respectively. A leading `/` is
+/// optional, and the empty path refers to `value` itself.
+///
+/// Returns None if a key or index is not present, and an error if the path descends into a
+/// scalar.
+///
+pub(crate) fn value_at<'a>(value: &'a Val, path: &str) -> Result<Option<&'a Val>, String> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    if path.is_empty() {
+        return Ok(Some(value));
+    }
+    let mut current = value;
+    let mut traversed = String::new();
+    for segment in path.split('/') {
+        let key = segment.replace("~1", "/").replace("~0", "~");
+        let next = match current {
+            Val::Dict(dict) => dict.get(&key),
+            Val::List(list) => match segment.parse::<usize>() {
+                Ok(index) => list.get(index),
+                Err(_) => {
+                    return Err(format!(
+                        "Expected a list index at `/{traversed}`, but got `{segment}`."
+                    ))
+                }
+            },
+            _ => {
+                return Err(format!(
+                    "Cannot look up `{segment}` in the value {current} at `/{traversed}`, which is \
+                    neither a dict nor a list."
+                ))
+            }
+        };
+        match next {
+            Some(next) => current = next,
+            None => return Ok(None),
+        }
+        if !traversed.is_empty() {
+            traversed.push('/');
+        }
+        traversed.push_str(segment);
+    }
+    Ok(Some(current))
+}
@@ -0,0 +1,43 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use indexmap::indexmap;
+
+use crate::pointer::value_at;
+use crate::Val;
+
+#[test]
+fn test_value_at() {
+    let value = Val::Dict(indexmap! {
+        "tools".to_owned() => Val::List(vec![
+            Val::Dict(indexmap! {"version".to_owned() => Val::String("1.2".into())}),
+        ]),
+        "a/b".to_owned() => Val::Int(1),
+        "c~d".to_owned() => Val::Int(2),
+    });
+    assert_eq!(Some(&value), value_at(&value, "").unwrap());
+    assert_eq!(Some(&value), value_at(&value, "/").unwrap());
+    assert_eq!(
+        Some(&Val::String("1.2".into())),
+        value_at(&value, "tools/0/version").unwrap()
+    );
+    assert_eq!(
+        Some(&Val::String("1.2".into())),
+        value_at(&value, "/tools/0/version").unwrap()
+    );
+    assert_eq!(Some(&Val::Int(1)), value_at(&value, "a~1b").unwrap());
+    assert_eq!(Some(&Val::Int(2)), value_at(&value, "c~0d").unwrap());
+
+    assert_eq!(None, value_at(&value, "missing/key").unwrap());
+    assert_eq!(None, value_at(&value, "tools/1/version").unwrap());
+
+    assert_eq!(
+        "Expected a list index at `/tools`, but got `first`.",
+        value_at(&value, "tools/first").unwrap_err()
+    );
+    assert_eq!(
+        "Cannot look up `major` in the value \"1.2\" at `/tools/0/version`, which is neither a \
+        dict nor a list.",
+        value_at(&value, "tools/0/version/major").unwrap_err()
+    );
+}
@@ -0,0 +1,89 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt;
+
+use crate::explain::{describe_source, Edit};
+use crate::registry::OptionRegistry;
+use crate::{ListEditAction, OptionId, OptionParser, Source, Val};
+
+///
+/// A value that an option is explicitly set to in some source, but which makes no difference:
+/// it is the value that the option already had from a source of lower precedence, or its default.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redundancy {
+    pub id: OptionId,
+    pub source: Source,
+    // Where the value was found: a flag, an env var name, or a config file path, line and section.
+    pub location: String,
+    pub value: Val,
+    // The source of lower precedence whose value is repeated, or None for the default.
+    pub repeats: Option<Source>,
+}
+
+impl fmt::Display for Redundancy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is set to {} by {}, ",
+            self.id, self.value, self.location
+        )?;
+        match &self.repeats {
+            Some(source) => write!(
+                f,
+                "which repeats the value from {}.",
+                describe_source(source)
+            ),
+            None => write!(f, "which is its default value."),
+        }
+    }
+}
+
+// The value that an edit replaces an option's value with, if it consists of a single replacement.
+fn replacement(edits: &[Edit]) -> Option<&Val> {
+    match edits {
+        [edit] if edit.action == ListEditAction::Replace => Some(&edit.value),
+        _ => None,
+    }
+}
+
+pub(crate) fn check(
+    parser: &OptionParser,
+    registry: &OptionRegistry,
+) -> Result<Vec<Redundancy>, String> {
+    let mut redundancies = vec![];
+    for option in registry.options() {
+        let id = &option.id;
+        // NB: Secret values are redacted, and so cannot be compared.
+        if parser.sources.values().any(|source| source.is_secret(id)) {
+            continue;
+        }
+        let explanation = parser.explain(id, option.option_type.clone())?;
+        // The value in effect before each contribution, if it is known. It is not known after a
+        // contribution that edits a list or dict, rather than replacing it.
+        let mut previous = explanation.default.map(|value| (None, value));
+        for contribution in explanation.contributions {
+            let value = replacement(&contribution.edits).cloned();
+            match (value, &previous) {
+                // A redundant value is attributed to the source that it repeats, which remains the
+                // source of the value in effect.
+                (Some(value), Some((repeats, previous_value))) if value == *previous_value => {
+                    redundancies.push(Redundancy {
+                        id: id.clone(),
+                        location: contribution
+                            .raw_values
+                            .last()
+                            .map(|raw| raw.location.clone())
+                            .unwrap_or_else(|| describe_source(&contribution.source)),
+                        source: contribution.source,
+                        value,
+                        repeats: repeats.clone(),
+                    });
+                }
+                (value, _) => previous = value.map(|value| (Some(contribution.source), value)),
+            }
+        }
+    }
+    Ok(redundancies)
+}
@@ -0,0 +1,91 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::tests::parser;
+use crate::{option_id, OptionInfo, OptionRegistry, OptionType, Redundancy, Source, Val};
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!("level"), OptionType::String("info".to_owned())),
+        OptionInfo::new(option_id!(["test"], "timeout"), OptionType::Int(60)),
+        OptionInfo::new(option_id!(["test"], "args"), OptionType::StringList(vec![])),
+        OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false)),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+fn config() -> Source {
+    Source::Config {
+        ordinal: 0,
+        path: "pants.toml".to_owned(),
+    }
+}
+
+#[test]
+fn test_redundancy() {
+    let redundancies = parser(
+        &["--test-timeout=30", "--test-args=['-v']"],
+        &[("PANTS_LEVEL", "info"), ("PANTS_TEST_TIMEOUT", "30")],
+        "[test]\ntimeout = 30\nargs = \"+['-v']\"\ndebug = true\n",
+    )
+    .check_redundancy(&registry())
+    .unwrap();
+    assert_eq!(
+        vec![
+            Redundancy {
+                id: option_id!("level"),
+                source: Source::Env,
+                location: "PANTS_LEVEL".to_owned(),
+                value: Val::String("info".into()),
+                repeats: None,
+            },
+            Redundancy {
+                id: option_id!(["test"], "timeout"),
+                source: Source::Env,
+                location: "PANTS_TEST_TIMEOUT".to_owned(),
+                value: Val::Int(30),
+                repeats: Some(config()),
+            },
+            Redundancy {
+                id: option_id!(["test"], "timeout"),
+                source: Source::Flag,
+                location: "--test-timeout".to_owned(),
+                value: Val::Int(30),
+                repeats: Some(config()),
+            },
+        ],
+        redundancies
+    );
+    assert_eq!(
+        "[test] timeout is set to 30 by --test-timeout, which repeats the value from config file \
+        pants.toml.",
+        redundancies[2].to_string()
+    );
+    assert_eq!(
+        "[GLOBAL] level is set to \"info\" by PANTS_LEVEL, which is its default value.",
+        redundancies[0].to_string()
+    );
+
+    // A value which replaces a list after it was edited cannot be compared.
+    assert!(
+        parser(&["--test-args=['-v']"], &[], "[test]\nargs = \"+['-v']\"\n")
+            .check_redundancy(&registry())
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(
+        vec![Redundancy {
+            id: option_id!(["test"], "args"),
+            source: Source::Flag,
+            location: "--test-args".to_owned(),
+            value: Val::List(vec![Val::String("-v".into())]),
+            repeats: Some(config()),
+        }],
+        parser(&["--test-args=['-v']"], &[], "[test]\nargs = ['-v']\n")
+            .check_redundancy(&registry())
+            .unwrap()
+    );
+}
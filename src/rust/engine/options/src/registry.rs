@@ -0,0 +1,237 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::id::{NameTransform, OptionId, Scope};
+use crate::keyring::KeyringEntry;
+use crate::merge::ListPolicy;
+use crate::version::Version;
+use crate::OptionType;
+
+///
+/// An option registered with an `OptionRegistry`: its id and type (which carries its default),
+/// for enum-valued options the values that it may take, and the metadata used to render help.
+///
+#[derive(Clone, Debug)]
+pub struct OptionInfo {
+    pub id: OptionId,
+    pub option_type: OptionType,
+    pub choices: Vec<String>,
+    pub help: String,
+    // Advanced options are only shown by advanced help.
+    pub advanced: bool,
+    // The group of related options in its scope (e.g. "caching") under which help lists the option.
+    pub group: Option<String>,
+    // Overrides the rendering of the default, e.g. for defaults which depend on the environment.
+    pub default_help_repr: Option<String>,
+    // If the option is deprecated, a message describing what to use instead.
+    pub deprecated: Option<String>,
+    // For secret options, the entry of the OS credential store that holds the value.
+    pub keyring: Option<KeyringEntry>,
+    // The range of Pants versions in which the option exists: from `introduced_in`, and until
+    // (but excluding) `removed_in`.
+    pub introduced_in: Option<Version>,
+    pub removed_in: Option<Version>,
+    // For list options, how the list is normalized after its edits are folded.
+    pub list_policy: ListPolicy,
+}
+
+impl OptionInfo {
+    pub fn new(id: OptionId, option_type: OptionType) -> Self {
+        Self {
+            id,
+            option_type,
+            choices: vec![],
+            help: String::new(),
+            advanced: false,
+            group: None,
+            default_help_repr: None,
+            deprecated: None,
+            keyring: None,
+            introduced_in: None,
+            removed_in: None,
+            list_policy: ListPolicy::default(),
+        }
+    }
+
+    pub fn choices(mut self, choices: &[&str]) -> Self {
+        self.choices = choices.iter().map(|choice| choice.to_string()).collect();
+        self
+    }
+
+    pub fn help(mut self, help: &str) -> Self {
+        self.help = help.to_owned();
+        self
+    }
+
+    pub fn advanced(mut self) -> Self {
+        self.advanced = true;
+        self
+    }
+
+    pub fn group(mut self, group: &str) -> Self {
+        self.group = Some(group.to_owned());
+        self
+    }
+
+    pub fn default_help_repr(mut self, default_help_repr: &str) -> Self {
+        self.default_help_repr = Some(default_help_repr.to_owned());
+        self
+    }
+
+    pub fn deprecated(mut self, message: &str) -> Self {
+        self.deprecated = Some(message.to_owned());
+        self
+    }
+
+    pub fn keyring(mut self, service: &str, account: &str) -> Self {
+        self.keyring = Some(KeyringEntry::new(service, account));
+        self
+    }
+
+    pub fn introduced_in(mut self, version: Version) -> Self {
+        self.introduced_in = Some(version);
+        self
+    }
+
+    pub fn removed_in(mut self, version: Version) -> Self {
+        self.removed_in = Some(version);
+        self
+    }
+
+    pub fn list_policy(mut self, list_policy: ListPolicy) -> Self {
+        self.list_policy = list_policy;
+        self
+    }
+
+    /// The default of this option, as shown in help.
+    pub fn rendered_default(&self) -> String {
+        self.default_help_repr
+            .clone()
+            .unwrap_or_else(|| self.option_type.default_val().to_string())
+    }
+
+    pub(crate) fn is_bool(&self) -> bool {
+        matches!(self.option_type, OptionType::Bool(_))
+    }
+
+    ///
+    /// The flag that sets this option from any position on the command line, e.g. `--level` for a
+    /// global option or `--test-output` for an option of the `test` scope. If `negated`, the flag
+    /// that sets a bool option to false, e.g. `--no-test-debug`.
+    ///
+    pub(crate) fn flag(&self, negated: bool) -> String {
+        let scope = match &self.id.scope {
+            Scope::Global => "".to_owned(),
+            Scope::Scope(scope) => format!("{}-", scope.to_ascii_lowercase()),
+        };
+        self.flag_with_prefix(&scope, negated)
+    }
+
+    ///
+    /// The flag that sets this option when it follows its scope on the command line, e.g.
+    /// `--output` after `test`.
+    ///
+    pub(crate) fn scoped_flag(&self, negated: bool) -> String {
+        self.flag_with_prefix("", negated)
+    }
+
+    /// The single-character flag for this option, if it has one, e.g. `-l`.
+    pub(crate) fn short_flag(&self) -> Option<String> {
+        self.id
+            .short_name
+            .as_ref()
+            .map(|short_name| format!("-{short_name}"))
+    }
+
+    fn flag_with_prefix(&self, prefix: &str, negated: bool) -> String {
+        format!(
+            "--{}{prefix}{}",
+            if negated { "no-" } else { "" },
+            self.id.name("-", NameTransform::ToLower)
+        )
+    }
+}
+
+///
+/// The options known to Rust code, in the order in which they were registered.
+///
+/// NB: Options in general are registered in Python, which may describe them to this registry in
+/// order to use the functionality built on it, such as shell completion.
+///
+#[derive(Clone, Debug, Default)]
+pub struct OptionRegistry {
+    options: Vec<OptionInfo>,
+}
+
+impl OptionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, option: OptionInfo) -> Result<(), String> {
+        if self.get(&option.id).is_some() {
+            return Err(format!("Option {} is already registered.", option.id));
+        }
+        if option.group.as_deref() == Some("") {
+            return Err(format!("Option {} has an empty group.", option.id));
+        }
+        if option.list_policy != ListPolicy::Keep && !option.option_type.is_list() {
+            return Err(format!(
+                "Option {} has a list policy, but is not a list option.",
+                option.id
+            ));
+        }
+        self.options.push(option);
+        Ok(())
+    }
+
+    pub fn get(&self, id: &OptionId) -> Option<&OptionInfo> {
+        self.options.iter().find(|option| &option.id == id)
+    }
+
+    pub fn options(&self) -> impl Iterator<Item = &OptionInfo> {
+        self.options.iter()
+    }
+
+    pub fn options_in<'a>(&'a self, scope: &'a Scope) -> impl Iterator<Item = &'a OptionInfo> {
+        self.options
+            .iter()
+            .filter(move |option| &option.id.scope == scope)
+    }
+
+    ///
+    /// The scopes of the registered options, other than the global scope, in the order in which
+    /// they were first registered.
+    ///
+    pub fn scopes(&self) -> Vec<&Scope> {
+        let mut scopes: Vec<&Scope> = vec![];
+        for option in &self.options {
+            if option.id.scope != Scope::Global && !scopes.contains(&&option.id.scope) {
+                scopes.push(&option.id.scope);
+            }
+        }
+        scopes
+    }
+
+    ///
+    /// The options of the given scope, by group: first the options that have no group (if any),
+    /// then each group in the order in which its first option was registered. Within each group,
+    /// options are in the order in which they were registered.
+    ///
+    pub fn groups_in(&self, scope: &Scope) -> Vec<(Option<&str>, Vec<&OptionInfo>)> {
+        let mut groups: Vec<(Option<&str>, Vec<&OptionInfo>)> = vec![(None, vec![])];
+        for option in self
+            .options
+            .iter()
+            .filter(|option| &option.id.scope == scope)
+        {
+            let group = option.group.as_deref();
+            match groups.iter_mut().find(|(name, _)| *name == group) {
+                Some((_, options)) => options.push(option),
+                None => groups.push((group, vec![option])),
+            }
+        }
+        groups.retain(|(_, options)| !options.is_empty());
+        groups
+    }
+}
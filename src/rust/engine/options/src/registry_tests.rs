@@ -0,0 +1,142 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::{option_id, ListPolicy, OptionInfo, OptionRegistry, OptionType, Scope};
+
+#[test]
+fn test_register() {
+    let mut registry = OptionRegistry::new();
+    registry
+        .register(
+            OptionInfo::new(option_id!("level"), OptionType::String("info".to_owned()))
+                .choices(&["debug", "info"]),
+        )
+        .unwrap();
+    registry
+        .register(OptionInfo::new(
+            option_id!(["test"], "debug"),
+            OptionType::Bool(false),
+        ))
+        .unwrap();
+    registry
+        .register(OptionInfo::new(
+            option_id!(["lint"], "only"),
+            OptionType::StringList(vec![]),
+        ))
+        .unwrap();
+    registry
+        .register(OptionInfo::new(
+            option_id!(["test"], "output"),
+            OptionType::String("failed".to_owned()),
+        ))
+        .unwrap();
+
+    assert_eq!(
+        vec!["debug".to_owned(), "info".to_owned()],
+        registry.get(&option_id!("level")).unwrap().choices
+    );
+    assert!(registry.get(&option_id!(["test"], "level")).is_none());
+    assert_eq!(
+        vec![&Scope::named("test"), &Scope::named("lint")],
+        registry.scopes()
+    );
+    assert_eq!(
+        vec![
+            option_id!(["test"], "debug"),
+            option_id!(["test"], "output")
+        ],
+        registry
+            .options_in(&Scope::named("test"))
+            .map(|option| option.id.clone())
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        "Option [test] debug is already registered.",
+        registry
+            .register(OptionInfo::new(
+                option_id!(["test"], "debug"),
+                OptionType::Bool(true)
+            ))
+            .unwrap_err()
+    );
+    assert_eq!(
+        "Option [test] level has a list policy, but is not a list option.",
+        registry
+            .register(
+                OptionInfo::new(
+                    option_id!(["test"], "level"),
+                    OptionType::String("info".to_owned())
+                )
+                .list_policy(ListPolicy::Dedupe)
+            )
+            .unwrap_err()
+    );
+}
+
+#[test]
+fn test_flags() {
+    let debug = OptionInfo::new(
+        option_id!(-'d', ["test"], "debug", "mode"),
+        OptionType::Bool(false),
+    );
+    assert_eq!("--test-debug-mode", debug.flag(false));
+    assert_eq!("--no-test-debug-mode", debug.flag(true));
+    assert_eq!("--debug-mode", debug.scoped_flag(false));
+    assert_eq!("--no-debug-mode", debug.scoped_flag(true));
+    assert_eq!(Some("-d".to_owned()), debug.short_flag());
+
+    let level = OptionInfo::new(option_id!("level"), OptionType::String("info".to_owned()));
+    assert_eq!("--level", level.flag(false));
+    assert_eq!("--level", level.scoped_flag(false));
+    assert_eq!(None, level.short_flag());
+}
+
+#[test]
+fn test_groups() {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!(["test"], "remote"), OptionType::Bool(false))
+            .group("remote execution"),
+        OptionInfo::new(option_id!(["test"], "cache"), OptionType::Bool(true)).group("caching"),
+        OptionInfo::new(option_id!(["test"], "timeout"), OptionType::Int(60)),
+        OptionInfo::new(option_id!(["test"], "cache", "dir"), OptionType::Int(0)).group("caching"),
+        OptionInfo::new(option_id!(["lint"], "only"), OptionType::Int(0)).group("caching"),
+    ] {
+        registry.register(option).unwrap();
+    }
+    let groups = registry
+        .groups_in(&Scope::named("test"))
+        .into_iter()
+        .map(|(group, options)| {
+            (
+                group,
+                options
+                    .iter()
+                    .map(|option| option.id.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (None, vec!["[test] timeout".to_owned()]),
+            (Some("remote execution"), vec!["[test] remote".to_owned()]),
+            (
+                Some("caching"),
+                vec!["[test] cache".to_owned(), "[test] cache_dir".to_owned()]
+            ),
+        ],
+        groups
+    );
+    assert!(registry.groups_in(&Scope::Global).is_empty());
+
+    assert_eq!(
+        "Option [test] debug has an empty group.",
+        registry
+            .register(
+                OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false)).group("")
+            )
+            .unwrap_err()
+    );
+}
@@ -0,0 +1,501 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::warn;
+
+use crate::snapshot::digest;
+use crate::stats::{Counter, ParseStats, Phase};
+
+///
+/// Whether the value refers to remote content, e.g. the `https://...` of `@https://...`, or the
+/// `sha256=<hex>+https://...` of a hash-pinned `@sha256=<hex>+https://...`.
+///
+pub(crate) fn is_remote(value: &str) -> bool {
+    let url = split_pin(value).1;
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+///
+/// Splits the expected sha256 digest, if any, off the front of a reference to remote content,
+/// e.g. `sha256=<hex>+https://...`.
+///
+pub(crate) fn split_pin(value: &str) -> (Option<&str>, &str) {
+    value
+        .strip_prefix("sha256=")
+        .and_then(|rest| rest.split_once('+'))
+        .map_or((None, value), |(pin, url)| (Some(pin), url))
+}
+
+/// Why a single attempt to fetch remote content failed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FetchError {
+    // The content could not be reached, e.g. due to a timeout or a refused connection. Such
+    // failures may be transient, so are retried.
+    Network(String),
+    // The content was reached, but was not usable, e.g. due to an HTTP 404 or a body that is not
+    // UTF-8. Such failures are not retried.
+    Content(String),
+}
+
+///
+/// Fetches remote content. Implemented by the embedding engine, which owns the HTTP client, so
+/// that option parsing shares its proxy and TLS configuration.
+///
+pub trait Fetcher: Send + Sync {
+    /// Fetches the content at the given URL, giving up after the given timeout.
+    fn fetch(&self, url: &str, timeout: Duration) -> Result<String, FetchError>;
+}
+
+///
+/// Fetches remote content without blocking, e.g. on the engine's tokio runtime. Since options are
+/// read synchronously, remote content fetched this way must be prefetched, via
+/// `OptionParser::prefetch_remote`, before the options that refer to it are read.
+///
+#[async_trait]
+pub trait AsyncFetcher: Send + Sync {
+    /// Fetches the content at the given URL, giving up after the given timeout.
+    async fn fetch(&self, url: &str, timeout: Duration) -> Result<String, FetchError>;
+}
+
+///
+/// How remote content is fetched: the timeout of each attempt, how many attempts to make with
+/// what backoff between them, and whether to work offline from previously fetched copies.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RemotePolicy {
+    pub timeout: Duration,
+    pub max_attempts: u32,
+    // The delay before the first retry, which doubles for each subsequent retry, up to `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    // If offline, nothing is fetched, and the last copy fetched into the cache is used instead.
+    pub offline: bool,
+    // Where the last fetched copy of each URL is kept. If None, offline mode has nothing to use.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for RemotePolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            offline: false,
+            cache_dir: None,
+        }
+    }
+}
+
+impl RemotePolicy {
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn retries(
+        mut self,
+        max_attempts: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    pub fn cache_dir(mut self, cache_dir: PathBuf) -> Self {
+        self.cache_dir = Some(cache_dir);
+        self
+    }
+
+    fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2_u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+}
+
+/// Why remote content could not be obtained, after applying the `RemotePolicy`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RemoteError {
+    Network {
+        url: String,
+        attempts: u32,
+        message: String,
+    },
+    Content {
+        url: String,
+        message: String,
+    },
+    // Offline, with no cached copy to use.
+    Offline {
+        url: String,
+    },
+    // No fetcher was registered by the embedding engine.
+    Unsupported {
+        url: String,
+    },
+    // The content is fetched asynchronously, but was read before it was prefetched.
+    NotPrefetched {
+        url: String,
+    },
+    // The content was pinned to a digest that it does not have.
+    DigestMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+    InvalidPin {
+        url: String,
+        pin: String,
+    },
+}
+
+impl fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RemoteError::Network {
+                url,
+                attempts,
+                message,
+            } => write!(
+                f,
+                "Network error fetching {url} (after {attempts} attempt{}): {message}",
+                if *attempts == 1 { "" } else { "s" }
+            ),
+            RemoteError::Content { url, message } => {
+                write!(f, "Unusable content fetched from {url}: {message}")
+            }
+            RemoteError::Offline { url } => {
+                write!(
+                    f,
+                    "Cannot fetch {url} while offline, and it has not been fetched before."
+                )
+            }
+            RemoteError::Unsupported { url } => {
+                write!(
+                    f,
+                    "Cannot fetch {url}: remote content is not supported here."
+                )
+            }
+            RemoteError::NotPrefetched { url } => {
+                write!(
+                    f,
+                    "Cannot fetch {url} while reading options: remote content that is fetched \
+                    asynchronously must first be prefetched via `OptionParser::prefetch_remote`."
+                )
+            }
+            RemoteError::DigestMismatch {
+                url,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "The content of {url} does not match its pin: expected sha256 {expected}, but got \
+                {actual}. If the change is expected, update the reference to \
+                `@sha256={actual}+{url}`."
+            ),
+            RemoteError::InvalidPin { url, pin } => write!(
+                f,
+                "Invalid pin `sha256={pin}` for {url}: expected 64 hexadecimal digits."
+            ),
+        }
+    }
+}
+
+// A URL, and the sha256 digest that its content is pinned to, if any.
+type PinnedUrl = (String, Option<String>);
+
+// The content fetched from a URL, once it has been. It is locked while the URL is fetched, so that
+// concurrent reads of the URL wait for its content rather than fetching it again.
+type FetchedContent = Arc<Mutex<Option<String>>>;
+
+// How remote content is fetched: by blocking on each fetch, or by prefetching without blocking.
+#[derive(Clone)]
+enum Transport {
+    Blocking(Arc<dyn Fetcher>),
+    Async(Arc<dyn AsyncFetcher>),
+}
+
+///
+/// Fetches remote content according to a `RemotePolicy`. Each URL is fetched at most once, as
+/// options may be read many times.
+///
+#[derive(Clone)]
+pub(crate) struct RemoteFetcher {
+    transport: Transport,
+    policy: RemotePolicy,
+    fetched: Arc<Mutex<HashMap<String, FetchedContent>>>,
+    // For an async transport: the URLs (and pins) that were read before they were prefetched, and
+    // the errors of prefetches that failed, which are reported when the URL is read.
+    pending: Arc<Mutex<BTreeSet<PinnedUrl>>>,
+    failed: Arc<Mutex<HashMap<String, RemoteError>>>,
+    stats: ParseStats,
+}
+
+impl fmt::Debug for RemoteFetcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RemoteFetcher")
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RemoteFetcher {
+    pub(crate) fn new(fetcher: Arc<dyn Fetcher>, policy: RemotePolicy) -> Self {
+        Self::with_transport(Transport::Blocking(fetcher), policy)
+    }
+
+    pub(crate) fn new_async(fetcher: Arc<dyn AsyncFetcher>, policy: RemotePolicy) -> Self {
+        Self::with_transport(Transport::Async(fetcher), policy)
+    }
+
+    fn with_transport(transport: Transport, policy: RemotePolicy) -> Self {
+        Self {
+            transport,
+            policy,
+            fetched: Arc::default(),
+            pending: Arc::default(),
+            failed: Arc::default(),
+            stats: ParseStats::default(),
+        }
+    }
+
+    /// Records fetches, and fetches served from memory, in the given statistics.
+    pub(crate) fn stats(mut self, stats: ParseStats) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    fn cache_path(&self, url: &str) -> Option<PathBuf> {
+        self.policy
+            .cache_dir
+            .as_ref()
+            .map(|cache_dir| cache_dir.join(digest(url.as_bytes())))
+    }
+
+    ///
+    /// Fetches the content at the given URL, which must have the given sha256 digest, if any.
+    /// Content that does not match its pin is neither used nor cached.
+    ///
+    /// With an async transport, this only serves content that was prefetched (or, offline, that
+    /// was cached): other URLs are recorded, to be prefetched by `prefetch_pending`.
+    ///
+    pub(crate) fn fetch(&self, url: &str, sha256: Option<&str>) -> Result<String, RemoteError> {
+        check_pin(url, sha256)?;
+        let fetched = self.fetched_content(url);
+        let mut fetched = fetched.lock().unwrap();
+        if let Some(content) = self.memoized(&fetched) {
+            return verify_pin(url, content, sha256);
+        }
+        if self.policy.offline {
+            let _span = self.stats.span(Phase::RemoteFetch);
+            self.stats.increment(Counter::RemoteFetches, 1);
+            return self.memoize(&mut fetched, url, sha256, self.read_cache(url));
+        }
+        let fetcher = match &self.transport {
+            Transport::Blocking(fetcher) => fetcher,
+            Transport::Async(_) => {
+                if let Some(err) = self.failed.lock().unwrap().get(url) {
+                    return Err(err.clone());
+                }
+                self.pending
+                    .lock()
+                    .unwrap()
+                    .insert((url.to_owned(), sha256.map(str::to_owned)));
+                return Err(RemoteError::NotPrefetched {
+                    url: url.to_owned(),
+                });
+            }
+        };
+        let _span = self.stats.span(Phase::RemoteFetch);
+        self.stats.increment(Counter::RemoteFetches, 1);
+        let mut attempts = 0;
+        let content = loop {
+            attempts += 1;
+            let result = fetcher.fetch(url, self.policy.timeout);
+            match self.attempted(url, attempts, result) {
+                ControlFlow::Break(content) => break content,
+                ControlFlow::Continue(backoff) => std::thread::sleep(backoff),
+            }
+        };
+        self.memoize(&mut fetched, url, sha256, content)
+    }
+
+    ///
+    /// Fetches every URL that was read before it was prefetched, concurrently, so that it may be
+    /// read again without blocking. Returns whether there were any such URLs.
+    ///
+    /// A failed prefetch is not an error here: it is reported when the URL is next read, so that
+    /// an optional (@?) fromfile may still be ignored.
+    ///
+    pub(crate) async fn prefetch_pending(&self) -> bool {
+        let Transport::Async(fetcher) = &self.transport else {
+            return false;
+        };
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let prefetches = pending.iter().map(|(url, sha256)| async move {
+            let fetched = self
+                .prefetch(fetcher.as_ref(), url, sha256.as_deref())
+                .await;
+            if let Err(err) = fetched {
+                self.failed.lock().unwrap().insert(url.clone(), err);
+            }
+        });
+        futures::future::join_all(prefetches).await;
+        !pending.is_empty()
+    }
+
+    async fn prefetch(
+        &self,
+        fetcher: &dyn AsyncFetcher,
+        url: &str,
+        sha256: Option<&str>,
+    ) -> Result<String, RemoteError> {
+        let fetched = self.fetched_content(url);
+        if let Some(content) = self.memoized(&fetched.lock().unwrap()) {
+            return verify_pin(url, content, sha256);
+        }
+        let _span = self.stats.span(Phase::RemoteFetch);
+        self.stats.increment(Counter::RemoteFetches, 1);
+        let mut attempts = 0;
+        let content = loop {
+            attempts += 1;
+            let result = fetcher.fetch(url, self.policy.timeout).await;
+            match self.attempted(url, attempts, result) {
+                ControlFlow::Break(content) => break content,
+                ControlFlow::Continue(backoff) => tokio::time::sleep(backoff).await,
+            }
+        };
+        // NB: The content is not locked while it is awaited, as a concurrent prefetch of the same
+        // URL (with another pin) would block the executor that the awaited fetch runs on.
+        let memoized = self.memoize(&mut fetched.lock().unwrap(), url, sha256, content);
+        memoized
+    }
+
+    // The content fetched from the given URL, which is created empty if the URL hasn't been read.
+    fn fetched_content(&self, url: &str) -> FetchedContent {
+        self.fetched
+            .lock()
+            .unwrap()
+            .entry(url.to_owned())
+            .or_default()
+            .clone()
+    }
+
+    fn memoized(&self, fetched: &Option<String>) -> Option<String> {
+        if fetched.is_some() {
+            self.stats.increment(Counter::RemoteCacheHits, 1);
+        }
+        fetched.clone()
+    }
+
+    // Memoizes freshly fetched content, if it matches its pin.
+    fn memoize(
+        &self,
+        fetched: &mut Option<String>,
+        url: &str,
+        sha256: Option<&str>,
+        content: Result<String, RemoteError>,
+    ) -> Result<String, RemoteError> {
+        let content = verify_pin(url, content?, sha256)?;
+        if !self.policy.offline {
+            self.write_cache(url, &content);
+        }
+        *fetched = Some(content.clone());
+        Ok(content)
+    }
+
+    // Decides the outcome of an attempt to fetch the given URL: whether to give up, or to retry
+    // after a backoff.
+    fn attempted(
+        &self,
+        url: &str,
+        attempts: u32,
+        result: Result<String, FetchError>,
+    ) -> ControlFlow<Result<String, RemoteError>, Duration> {
+        match result {
+            Ok(content) => ControlFlow::Break(Ok(content)),
+            Err(FetchError::Content(message)) => ControlFlow::Break(Err(RemoteError::Content {
+                url: url.to_owned(),
+                message,
+            })),
+            Err(FetchError::Network(message)) if attempts >= self.policy.max_attempts => {
+                ControlFlow::Break(Err(RemoteError::Network {
+                    url: url.to_owned(),
+                    attempts,
+                    message,
+                }))
+            }
+            Err(FetchError::Network(message)) => {
+                let backoff = self.policy.backoff(attempts - 1);
+                warn!("Failed to fetch {url}, retrying in {backoff:?}: {message}");
+                ControlFlow::Continue(backoff)
+            }
+        }
+    }
+
+    fn read_cache(&self, url: &str) -> Result<String, RemoteError> {
+        self.cache_path(url)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .ok_or_else(|| RemoteError::Offline {
+                url: url.to_owned(),
+            })
+    }
+
+    fn write_cache(&self, url: &str, content: &str) {
+        let Some(cache_path) = self.cache_path(url) else {
+            return;
+        };
+        // NB: The cache is only needed offline, so failing to write it is not an error.
+        let written = cache_path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&cache_path, content));
+        if let Err(e) = written {
+            warn!("Failed to cache the content of {url}: {e}");
+        }
+    }
+}
+
+fn check_pin(url: &str, sha256: Option<&str>) -> Result<(), RemoteError> {
+    match sha256 {
+        Some(pin) if pin.len() != 64 || !pin.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Err(RemoteError::InvalidPin {
+                url: url.to_owned(),
+                pin: pin.to_owned(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn verify_pin(url: &str, content: String, sha256: Option<&str>) -> Result<String, RemoteError> {
+    if let Some(pin) = sha256 {
+        let actual = digest(content.as_bytes());
+        if !actual.eq_ignore_ascii_case(pin) {
+            return Err(RemoteError::DigestMismatch {
+                url: url.to_owned(),
+                expected: pin.to_owned(),
+                actual,
+            });
+        }
+    }
+    Ok(content)
+}
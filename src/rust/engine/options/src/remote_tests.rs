@@ -0,0 +1,404 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tempfile::TempDir;
+
+use crate::remote::RemoteFetcher;
+use crate::snapshot::digest;
+use crate::tests::{parser, parser_in};
+use crate::{
+    option_id, Args, AsyncFetcher, BuildRoot, ConfigSource, Env, FetchError, Fetcher, OptionInfo,
+    OptionRegistry, OptionType, OptionsSnapshot, RemoteError, RemotePolicy, Val,
+};
+
+// Replies to each fetch with the next of its responses, repeating the last one.
+struct FakeFetcher {
+    responses: Mutex<Vec<Result<String, FetchError>>>,
+    fetches: Mutex<Vec<(String, Duration)>>,
+}
+
+impl FakeFetcher {
+    fn new(responses: Vec<Result<&str, FetchError>>) -> Arc<Self> {
+        Arc::new(Self {
+            responses: Mutex::new(
+                responses
+                    .into_iter()
+                    .rev()
+                    .map(|r| r.map(str::to_owned))
+                    .collect(),
+            ),
+            fetches: Mutex::default(),
+        })
+    }
+
+    fn fetch_count(&self) -> usize {
+        self.fetches.lock().unwrap().len()
+    }
+}
+
+impl Fetcher for FakeFetcher {
+    fn fetch(&self, url: &str, timeout: Duration) -> Result<String, FetchError> {
+        self.fetches.lock().unwrap().push((url.to_owned(), timeout));
+        let mut responses = self.responses.lock().unwrap();
+        if responses.len() > 1 {
+            responses.pop().unwrap()
+        } else {
+            responses[0].clone()
+        }
+    }
+}
+
+// Replies to each fetch with the response for its URL, or a 404 if it has none.
+struct FakeAsyncFetcher {
+    responses: HashMap<String, String>,
+    fetches: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl AsyncFetcher for FakeAsyncFetcher {
+    async fn fetch(&self, url: &str, _timeout: Duration) -> Result<String, FetchError> {
+        self.fetches.lock().unwrap().push(url.to_owned());
+        tokio::task::yield_now().await;
+        self.responses
+            .get(url)
+            .cloned()
+            .ok_or_else(|| FetchError::Content("HTTP 404".to_owned()))
+    }
+}
+
+fn policy() -> RemotePolicy {
+    RemotePolicy::default()
+        .timeout(Duration::from_secs(5))
+        .retries(3, Duration::ZERO, Duration::ZERO)
+}
+
+const URL: &str = "https://example.com/args.json";
+
+#[test]
+fn test_retry_network_errors() {
+    let fetcher = FakeFetcher::new(vec![
+        Err(FetchError::Network("timed out".to_owned())),
+        Ok("content"),
+    ]);
+    let remote = RemoteFetcher::new(fetcher.clone(), policy());
+    assert_eq!(Ok("content".to_owned()), remote.fetch(URL, None));
+    assert_eq!(
+        vec![
+            (URL.to_owned(), Duration::from_secs(5)),
+            (URL.to_owned(), Duration::from_secs(5))
+        ],
+        *fetcher.fetches.lock().unwrap()
+    );
+
+    let fetcher = FakeFetcher::new(vec![Err(FetchError::Network("timed out".to_owned()))]);
+    let err = RemoteFetcher::new(fetcher.clone(), policy())
+        .fetch(URL, None)
+        .unwrap_err();
+    assert_eq!(
+        RemoteError::Network {
+            url: URL.to_owned(),
+            attempts: 3,
+            message: "timed out".to_owned()
+        },
+        err
+    );
+    assert_eq!(
+        "Network error fetching https://example.com/args.json (after 3 attempts): timed out",
+        err.to_string()
+    );
+}
+
+#[test]
+fn test_content_errors_are_not_retried() {
+    let fetcher = FakeFetcher::new(vec![Err(FetchError::Content("HTTP 404".to_owned()))]);
+    let err = RemoteFetcher::new(fetcher.clone(), policy())
+        .fetch(URL, None)
+        .unwrap_err();
+    assert_eq!(
+        "Unusable content fetched from https://example.com/args.json: HTTP 404",
+        err.to_string()
+    );
+    assert_eq!(1, fetcher.fetch_count());
+}
+
+#[test]
+fn test_offline() {
+    let cache_dir = TempDir::new().unwrap();
+    let policy = policy().cache_dir(cache_dir.path().to_path_buf());
+
+    let fetcher = FakeFetcher::new(vec![]);
+    let offline = RemoteFetcher::new(fetcher.clone(), policy.clone().offline(true));
+    assert_eq!(
+        Err(RemoteError::Offline {
+            url: URL.to_owned()
+        }),
+        offline.fetch(URL, None)
+    );
+
+    // Once fetched online, the last fetched copy is used offline, without fetching.
+    let online = RemoteFetcher::new(FakeFetcher::new(vec![Ok("v1")]), policy);
+    assert_eq!(Ok("v1".to_owned()), online.fetch(URL, None));
+    assert_eq!(Ok("v1".to_owned()), offline.fetch(URL, None));
+    assert_eq!(0, fetcher.fetch_count());
+}
+
+#[test]
+fn test_remote_fromfiles() {
+    let mut option_parser = parser(
+        &[],
+        &[],
+        &format!("[scope]\nnames = '@{URL}'\nother = '@?https://example.com/x'\n"),
+    );
+    let names = option_id!(["scope"], "names");
+    let err = option_parser.parse_string_list(&names, vec![]).unwrap_err();
+    assert!(
+        err.ends_with("remote content is not supported here."),
+        "{err}"
+    );
+
+    option_parser.register_remote_fetcher(
+        FakeFetcher::new(vec![
+            Ok(r#"["a", "b"]"#),
+            Err(FetchError::Content("HTTP 404".to_owned())),
+        ]),
+        policy(),
+    );
+    assert_eq!(
+        vec!["a".to_owned(), "b".to_owned()],
+        option_parser
+            .parse_string_list(&names, vec![])
+            .unwrap()
+            .value
+    );
+    // Optional remote fromfiles may be unavailable.
+    assert_eq!(
+        "default",
+        option_parser
+            .parse_string(&option_id!(["scope"], "other"), "default")
+            .unwrap()
+            .value
+    );
+    // Remote fromfiles are recorded apart from the files on the filesystem.
+    assert_eq!(
+        BTreeMap::from([
+            (URL.to_owned(), Some(digest(r#"["a", "b"]"#.as_bytes()))),
+            ("https://example.com/x".to_owned(), None),
+        ]),
+        option_parser.remote_inputs()
+    );
+    assert!(option_parser
+        .consumed_files()
+        .keys()
+        .all(|path| !path.starts_with("https:")));
+}
+
+const CONTENT_SHA256: &str = "ed7002b439e9ac845f22357d822bac1444730fbdb6016d3ec9432297b9ec9f73";
+
+#[test]
+fn test_pinned_content() {
+    let cache_dir = TempDir::new().unwrap();
+    let policy = policy().cache_dir(cache_dir.path().to_path_buf());
+    let remote = RemoteFetcher::new(FakeFetcher::new(vec![Ok("content")]), policy.clone());
+    assert_eq!(
+        Ok("content".to_owned()),
+        remote.fetch(URL, Some(&CONTENT_SHA256.to_ascii_uppercase()))
+    );
+
+    // Content which does not match its pin is an error, and is not cached for offline use.
+    let remote = RemoteFetcher::new(FakeFetcher::new(vec![Ok("v2")]), policy.clone());
+    let expected = "0".repeat(64);
+    let err = remote
+        .fetch("https://example.com/v2", Some(&expected))
+        .unwrap_err();
+    assert_eq!(
+        format!(
+            "The content of https://example.com/v2 does not match its pin: expected sha256 \
+            {expected}, but got fb04dcb6970e4c3d1873de51fd5a50d7bb46b3383113602665c350ec40b5f990. \
+            If the change is expected, update the reference to \
+            `@sha256=fb04dcb6970e4c3d1873de51fd5a50d7bb46b3383113602665c350ec40b5f990+https://example.com/v2`."
+        ),
+        err.to_string()
+    );
+    let offline = RemoteFetcher::new(FakeFetcher::new(vec![]), policy.offline(true));
+    assert!(matches!(
+        offline.fetch("https://example.com/v2", None),
+        Err(RemoteError::Offline { .. })
+    ));
+
+    assert_eq!(
+        Err(RemoteError::InvalidPin {
+            url: URL.to_owned(),
+            pin: "abc".to_owned()
+        }),
+        remote.fetch(URL, Some("abc"))
+    );
+}
+
+#[test]
+fn test_pinned_fromfiles() {
+    let mut option_parser = parser(
+        &[],
+        &[],
+        &format!(
+            "[scope]\n\
+        pinned = '@sha256=3554d2b8a1e34099053865de8576d1460b807430ec8c6b85315c9607ba93d308+{URL}'\n\
+        stale = '@?sha256={}+{URL}'\n",
+            "1".repeat(64)
+        ),
+    );
+    option_parser.register_remote_fetcher(FakeFetcher::new(vec![Ok(r#"["a", "b"]"#)]), policy());
+    assert_eq!(
+        vec!["a".to_owned(), "b".to_owned()],
+        option_parser
+            .parse_string_list(&option_id!(["scope"], "pinned"), vec![])
+            .unwrap()
+            .value
+    );
+    // A pin mismatch is an error even for an optional fromfile.
+    let err = option_parser
+        .parse_string_list(&option_id!(["scope"], "stale"), vec![])
+        .unwrap_err();
+    assert!(
+        err.starts_with(&format!(
+            "Problem reading {URL} for [scope] stale: The content of {URL} does not match its pin"
+        )),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn test_async_remote_fromfiles() {
+    let mut option_parser = parser(
+        &[],
+        &[],
+        &format!(
+            "[scope]\n\
+        names = '@{URL}'\n\
+        other = '@?https://example.com/x'\n\
+        missing = '@https://example.com/missing'\n"
+        ),
+    );
+    let fetcher = Arc::new(FakeAsyncFetcher {
+        responses: HashMap::from([(URL.to_owned(), r#"["a", "b"]"#.to_owned())]),
+        fetches: Mutex::default(),
+    });
+    option_parser.register_async_remote_fetcher(fetcher.clone(), policy());
+
+    // Remote content may not be read before it is prefetched.
+    let names = option_id!(["scope"], "names");
+    let err = option_parser.parse_string_list(&names, vec![]).unwrap_err();
+    assert!(
+        err.ends_with("must first be prefetched via `OptionParser::prefetch_remote`."),
+        "{err}"
+    );
+
+    let mut registry = OptionRegistry::new();
+    registry
+        .register(OptionInfo::new(
+            names.clone(),
+            OptionType::StringList(vec![]),
+        ))
+        .unwrap();
+    for id in [
+        option_id!(["scope"], "other"),
+        option_id!(["scope"], "missing"),
+    ] {
+        registry
+            .register(OptionInfo::new(
+                id,
+                OptionType::String("default".to_owned()),
+            ))
+            .unwrap();
+    }
+    option_parser.prefetch_remote(&registry).await;
+    let mut fetches = fetcher.fetches.lock().unwrap().clone();
+    fetches.sort();
+    assert_eq!(
+        vec![
+            URL.to_owned(),
+            "https://example.com/missing".to_owned(),
+            "https://example.com/x".to_owned()
+        ],
+        fetches
+    );
+
+    assert_eq!(
+        vec!["a".to_owned(), "b".to_owned()],
+        option_parser
+            .parse_string_list(&names, vec![])
+            .unwrap()
+            .value
+    );
+    // Failed prefetches are reported when read, and optional remote fromfiles may be unavailable.
+    assert_eq!(
+        "default",
+        option_parser
+            .parse_string(&option_id!(["scope"], "other"), "default")
+            .unwrap()
+            .value
+    );
+    let err = option_parser
+        .parse_string(&option_id!(["scope"], "missing"), "default")
+        .unwrap_err();
+    assert!(
+        err.ends_with("Unusable content fetched from https://example.com/missing: HTTP 404"),
+        "{err}"
+    );
+    // Prefetching again fetches nothing new.
+    option_parser.prefetch_remote(&registry).await;
+    assert_eq!(3, fetcher.fetches.lock().unwrap().len());
+}
+
+#[test]
+fn test_concurrent_reads_fetch_once() {
+    let fetcher = FakeFetcher::new(vec![Ok("content")]);
+    let remote = RemoteFetcher::new(fetcher.clone(), policy());
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| assert_eq!(Ok("content".to_owned()), remote.fetch(URL, None)));
+        }
+    });
+    assert_eq!(1, fetcher.fetch_count());
+}
+
+#[test]
+fn test_snapshot_of_remote_fromfiles() {
+    let buildroot = TempDir::new().unwrap();
+    fs::write(buildroot.path().join("BUILDROOT"), "").unwrap();
+    let config_path = buildroot.path().join("pants.toml");
+    let snapshot = |value: String| {
+        fs::write(&config_path, format!("[scope]\nvalue = '{value}'\n")).unwrap();
+        let mut option_parser = parser_in(
+            BuildRoot::find_from(buildroot.path()).unwrap(),
+            &[],
+            &[],
+            vec![ConfigSource::from_file(&config_path).unwrap()],
+        );
+        option_parser.register_remote_fetcher(FakeFetcher::new(vec![Ok("content")]), policy());
+        option_parser
+            .resolve_snapshot(vec![(
+                option_id!(["scope"], "value"),
+                OptionType::String("".to_owned()),
+            )])
+            .unwrap()
+    };
+    let is_fresh = |snapshot: &OptionsSnapshot| {
+        snapshot.is_fresh(&Args::new(vec![]), &Env::new(HashMap::new()))
+    };
+
+    // Pinned content cannot change, so a snapshot is known to be fresh without fetching it again.
+    let pinned = snapshot(format!("@sha256={CONTENT_SHA256}+{URL}"));
+    assert_eq!(
+        Some(&Val::String("content".into())),
+        pinned.get(&option_id!(["scope"], "value"))
+    );
+    assert!(is_fresh(&pinned));
+    // Whereas unpinned content may have changed at any time.
+    assert!(!is_fresh(&snapshot(format!("@{URL}"))));
+}
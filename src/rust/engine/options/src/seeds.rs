@@ -0,0 +1,74 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+// NB: These must be kept in sync with `LazySeeds::get`.
+pub(crate) const LAZY_SEED_NAMES: &[&str] = &["git_branch", "git_commit", "source_date_epoch"];
+
+///
+/// Seed values that are expensive to compute, and so are only computed if a placeholder refers to
+/// them, at most once per parse.
+///
+/// A value that cannot be computed (e.g. a git seed outside of a git repository) is empty, and so
+/// is false in a condition.
+///
+pub(crate) struct LazySeeds {
+    buildroot: PathBuf,
+    source_date_epoch_env: Option<String>,
+    git_branch: OnceLock<String>,
+    git_commit: OnceLock<String>,
+    source_date_epoch: OnceLock<String>,
+}
+
+impl LazySeeds {
+    pub(crate) fn new(buildroot: PathBuf, source_date_epoch_env: Option<String>) -> Self {
+        Self {
+            buildroot,
+            source_date_epoch_env,
+            git_branch: OnceLock::new(),
+            git_commit: OnceLock::new(),
+            source_date_epoch: OnceLock::new(),
+        }
+    }
+
+    fn git(&self, args: &[&str]) -> String {
+        Command::new("git")
+            .args(args)
+            .current_dir(&self.buildroot)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&str> {
+        let value = match name {
+            "git_branch" => self.git_branch.get_or_init(|| {
+                // A detached HEAD is not on any branch.
+                let branch = self.git(&["rev-parse", "--abbrev-ref", "HEAD"]);
+                if branch == "HEAD" {
+                    String::new()
+                } else {
+                    branch
+                }
+            }),
+            "git_commit" => self
+                .git_commit
+                .get_or_init(|| self.git(&["rev-parse", "HEAD"])),
+            // See https://reproducible-builds.org/specs/source-date-epoch/.
+            "source_date_epoch" => self.source_date_epoch.get_or_init(|| {
+                self.source_date_epoch_env
+                    .clone()
+                    .unwrap_or_else(|| self.git(&["log", "-1", "--format=%ct"]))
+            }),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
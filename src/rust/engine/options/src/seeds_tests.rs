@@ -0,0 +1,78 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::Path;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+use crate::interpolation::{InterpolationMap, Interpolator};
+use crate::seeds::LazySeeds;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=pants",
+            "-c",
+            "user.email=pants@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_COMMITTER_DATE", "1700000000 +0000")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_git_seeds() {
+    let dir = TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q", "-b", "feature"]);
+    git(
+        dir.path(),
+        &["commit", "-q", "--allow-empty", "-m", "initial"],
+    );
+
+    let seeds = LazySeeds::new(dir.path().to_path_buf(), None);
+    assert_eq!(Some("feature"), seeds.get("git_branch"));
+    let commit = seeds.get("git_commit").unwrap();
+    assert_eq!(40, commit.len());
+    assert_eq!(Some("1700000000"), seeds.get("source_date_epoch"));
+    assert_eq!(None, seeds.get("git_tag"));
+    let replacements = InterpolationMap::new();
+    assert_eq!(
+        Ok(format!("feature@{commit}")),
+        Interpolator::new(&replacements)
+            .lazy_seeds(Some(&seeds))
+            .interpolate("%(git_branch)s@%(git_commit)s")
+    );
+
+    // A detached HEAD is on no branch.
+    git(dir.path(), &["checkout", "-q", "--detach"]);
+    assert_eq!(
+        Some(""),
+        LazySeeds::new(dir.path().to_path_buf(), None).get("git_branch")
+    );
+
+    // SOURCE_DATE_EPOCH takes precedence over the commit time.
+    assert_eq!(
+        Some("1"),
+        LazySeeds::new(dir.path().to_path_buf(), Some("1".to_owned())).get("source_date_epoch")
+    );
+}
+
+#[test]
+fn test_outside_git() {
+    let dir = TempDir::new().unwrap();
+    let seeds = LazySeeds::new(dir.path().to_path_buf(), None);
+    assert_eq!(Some(""), seeds.get("git_commit"));
+
+    let replacements = InterpolationMap::from([("tag".to_owned(), "dev".to_owned())]);
+    assert_eq!(
+        Ok("dev-local".to_owned()),
+        Interpolator::new(&replacements)
+            .lazy_seeds(Some(&seeds))
+            .interpolate("%(tag)s-%(git_commit)s%(if:!git_commit:local)s")
+    );
+}
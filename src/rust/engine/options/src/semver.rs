@@ -0,0 +1,25 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+///
+/// A semantic version, such as `1.2.3`, `1.2.3-rc.1` or `1.2.3+build.5`, for options which pin the
+/// version of a tool. A requirement on one is written as for Cargo: one or more comma-separated
+/// comparators, such as `^1.2`, `~1.2.3`, `1.*` or `>=1.2, <2`.
+///
+/// Note that this is distinct from `Version`, which is a Pants version.
+///
+pub use ::semver::{Version as SemanticVersion, VersionReq};
+
+///
+/// Parses a semantic version, which may be written with a leading `v`, as in `v1.2.3`.
+///
+pub(crate) fn parse_version(text: &str) -> Result<SemanticVersion, String> {
+    let text = text.trim();
+    SemanticVersion::parse(text.strip_prefix('v').unwrap_or(text))
+        .map_err(|e| format!("`{text}` is not a semantic version: {e}"))
+}
+
+pub(crate) fn parse_version_req(text: &str) -> Result<VersionReq, String> {
+    let text = text.trim();
+    VersionReq::parse(text).map_err(|e| format!("`{text}` is not a version requirement: {e}"))
+}
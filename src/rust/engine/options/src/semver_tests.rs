@@ -0,0 +1,38 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::semver::{parse_version, parse_version_req, SemanticVersion};
+
+#[test]
+fn test_parse_version() {
+    let v = parse_version(" v1.2.3-rc.1+build.5 ").unwrap();
+    assert_eq!((1, 2, 3), (v.major, v.minor, v.patch));
+    assert!(!v.pre.is_empty());
+    assert_eq!("1.2.3-rc.1+build.5", v.to_string());
+    assert!(parse_version("1.0.0-rc.1").unwrap() < parse_version("1.0.0").unwrap());
+
+    for invalid in ["", "1.2", "1.2.3.4", "01.2.3", "1.2.x"] {
+        let err = parse_version(invalid).unwrap_err();
+        assert!(
+            err.starts_with(&format!("`{invalid}` is not a semantic version: ")),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn test_parse_version_req() {
+    let req = parse_version_req(" >=1.2, <2 ").unwrap();
+    assert!(req.matches(&SemanticVersion::new(1, 9, 9)));
+    assert!(!req.matches(&SemanticVersion::new(2, 0, 0)));
+    // As for Cargo, a bare version is a caret requirement.
+    assert!(parse_version_req("1.2")
+        .unwrap()
+        .matches(&SemanticVersion::new(1, 9, 0)));
+
+    let err = parse_version_req("^1.2, nope").unwrap_err();
+    assert!(
+        err.starts_with("`^1.2, nope` is not a version requirement: "),
+        "{err}"
+    );
+}
@@ -0,0 +1,145 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::explain::{describe_source, Contribution, Edit};
+use crate::registry::OptionRegistry;
+use crate::{ListEditAction, OptionId, OptionParser, OptionType, Source};
+
+/// The value that a single source sets an option to, or the edits that it makes to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Setting {
+    pub source: Source,
+    // Where the value was found: a flag, an env var name, or a config file path, line and section.
+    pub location: String,
+    pub edits: Vec<Edit>,
+}
+
+impl Setting {
+    fn new(contribution: &Contribution) -> Self {
+        Self {
+            source: contribution.source.clone(),
+            location: contribution
+                .raw_values
+                .last()
+                .map(|raw| raw.location.clone())
+                .unwrap_or_else(|| describe_source(&contribution.source)),
+            edits: contribution.edits.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.edits.iter().join(", "), self.location)
+    }
+}
+
+///
+/// A setting of an option which has no effect, because a source of higher precedence replaces
+/// the option's value: e.g. a value in `pants.toml` which an env var overrides.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shadowing {
+    pub id: OptionId,
+    pub shadowed: Setting,
+    pub by: Setting,
+}
+
+impl fmt::Display for Shadowing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} is overridden by {}",
+            self.id, self.shadowed, self.by
+        )
+    }
+}
+
+///
+/// Two sources which each replace the value of a list or dict option with a different value, of
+/// which the second silently wins: e.g. two config files which each set the full list of backends.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplaceConflict {
+    pub id: OptionId,
+    pub first: Setting,
+    pub second: Setting,
+}
+
+impl fmt::Display for ReplaceConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} is replaced by {}",
+            self.id, self.first, self.second
+        )
+    }
+}
+
+// Whether a contribution discards the value of the option so far, rather than editing it.
+fn replaces(contribution: &Contribution) -> bool {
+    contribution
+        .edits
+        .first()
+        .is_some_and(|edit| edit.action == ListEditAction::Replace)
+}
+
+pub(crate) fn check(
+    parser: &OptionParser,
+    registry: &OptionRegistry,
+) -> Result<Vec<Shadowing>, String> {
+    let mut shadowings = vec![];
+    for option in registry.options() {
+        let explanation = parser.explain(&option.id, option.option_type.clone())?;
+        // The contributions since the last that replaced the value, which a replacement shadows.
+        let mut in_effect: Vec<&Contribution> = vec![];
+        for contribution in &explanation.contributions {
+            if replaces(contribution) {
+                for shadowed in in_effect.drain(..) {
+                    // NB: An identical value is redundant, rather than shadowed.
+                    if shadowed.edits != contribution.edits {
+                        shadowings.push(Shadowing {
+                            id: option.id.clone(),
+                            shadowed: Setting::new(shadowed),
+                            by: Setting::new(contribution),
+                        });
+                    }
+                }
+            }
+            in_effect.push(contribution);
+        }
+    }
+    Ok(shadowings)
+}
+
+pub(crate) fn check_replace_conflicts(
+    parser: &OptionParser,
+    registry: &OptionRegistry,
+) -> Result<Vec<ReplaceConflict>, String> {
+    let mut conflicts = vec![];
+    for option in registry.options() {
+        if !option.option_type.is_list() && !matches!(option.option_type, OptionType::Dict(_)) {
+            continue;
+        }
+        let explanation = parser.explain(&option.id, option.option_type.clone())?;
+        let mut replacements = explanation.contributions.iter().filter(|c| replaces(c));
+        let Some(mut previous) = replacements.next() else {
+            continue;
+        };
+        for replacement in replacements {
+            if replacement.edits != previous.edits {
+                conflicts.push(ReplaceConflict {
+                    id: option.id.clone(),
+                    first: Setting::new(previous),
+                    second: Setting::new(replacement),
+                });
+            }
+            previous = replacement;
+        }
+    }
+    Ok(conflicts)
+}
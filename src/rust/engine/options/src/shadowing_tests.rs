@@ -0,0 +1,163 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::tests::parser;
+use crate::{
+    option_id, Edit, ListEditAction, OptionInfo, OptionRegistry, OptionType, ReplaceConflict,
+    Setting, Shadowing, Source, Val,
+};
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!(["test"], "timeout"), OptionType::Int(60)),
+        OptionInfo::new(option_id!(["test"], "args"), OptionType::StringList(vec![])),
+        OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false)),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+fn setting(source: Source, location: &str, action: ListEditAction, value: Val) -> Setting {
+    Setting {
+        source,
+        location: location.to_owned(),
+        edits: vec![Edit { action, value }],
+    }
+}
+
+#[test]
+fn test_shadowing() {
+    let config = Source::Config {
+        ordinal: 0,
+        path: "pants.toml".to_owned(),
+    };
+    let shadowings = parser(
+        &["--test-timeout=30", "--test-args=['-x']"],
+        &[("PANTS_TEST_TIMEOUT", "10"), ("PANTS_TEST_ARGS", "+['-v']")],
+        "[test]\ntimeout = 20\nargs = ['-s']\ndebug = true\n",
+    )
+    .check_shadowing(&registry())
+    .unwrap();
+    let args =
+        |values: &[&str]| Val::List(values.iter().map(|v| Val::String((*v).into())).collect());
+    assert_eq!(
+        vec![
+            Shadowing {
+                id: option_id!(["test"], "timeout"),
+                shadowed: setting(
+                    config.clone(),
+                    "pants.toml:2 [test]",
+                    ListEditAction::Replace,
+                    Val::Int(20)
+                ),
+                by: setting(
+                    Source::Env,
+                    "PANTS_TEST_TIMEOUT",
+                    ListEditAction::Replace,
+                    Val::Int(10)
+                ),
+            },
+            Shadowing {
+                id: option_id!(["test"], "timeout"),
+                shadowed: setting(
+                    Source::Env,
+                    "PANTS_TEST_TIMEOUT",
+                    ListEditAction::Replace,
+                    Val::Int(10)
+                ),
+                by: setting(
+                    Source::Flag,
+                    "--test-timeout",
+                    ListEditAction::Replace,
+                    Val::Int(30)
+                ),
+            },
+            // Both the config value and the env var's addition to it are discarded by the flag.
+            Shadowing {
+                id: option_id!(["test"], "args"),
+                shadowed: setting(
+                    config.clone(),
+                    "pants.toml:3 [test]",
+                    ListEditAction::Replace,
+                    args(&["-s"])
+                ),
+                by: setting(
+                    Source::Flag,
+                    "--test-args",
+                    ListEditAction::Replace,
+                    args(&["-x"])
+                ),
+            },
+            Shadowing {
+                id: option_id!(["test"], "args"),
+                shadowed: setting(
+                    Source::Env,
+                    "PANTS_TEST_ARGS",
+                    ListEditAction::Add,
+                    args(&["-v"])
+                ),
+                by: setting(
+                    Source::Flag,
+                    "--test-args",
+                    ListEditAction::Replace,
+                    args(&["-x"])
+                ),
+            },
+        ],
+        shadowings
+    );
+    assert_eq!(
+        "[test] timeout: 20 (pants.toml:2 [test]) is overridden by 10 (PANTS_TEST_TIMEOUT)",
+        shadowings[0].to_string()
+    );
+
+    // Neither list additions nor identical values shadow anything.
+    assert!(parser(
+        &["--test-timeout=20", "--test-args=+['-x']"],
+        &[],
+        "[test]\ntimeout = 20\nargs = ['-s']\n",
+    )
+    .check_shadowing(&registry())
+    .unwrap()
+    .is_empty());
+}
+
+#[test]
+fn test_replace_conflicts() {
+    let conflicts = parser(
+        &["--test-args=['-v']"],
+        &[("PANTS_TEST_ARGS", "['-v']"), ("PANTS_TEST_TIMEOUT", "10")],
+        "[test]\ntimeout = 20\nargs = ['-s']\n",
+    )
+    .check_replace_conflicts(&registry())
+    .unwrap();
+    // Scalars, and identical replacements, are not conflicts.
+    let args = Val::List(vec![Val::String("-s".into())]);
+    assert_eq!(
+        vec![ReplaceConflict {
+            id: option_id!(["test"], "args"),
+            first: setting(
+                Source::Config {
+                    ordinal: 0,
+                    path: "pants.toml".to_owned(),
+                },
+                "pants.toml:3 [test]",
+                ListEditAction::Replace,
+                args
+            ),
+            second: setting(
+                Source::Env,
+                "PANTS_TEST_ARGS",
+                ListEditAction::Replace,
+                Val::List(vec![Val::String("-v".into())])
+            ),
+        }],
+        conflicts
+    );
+    assert_eq!(
+        "[test] args: [\"-s\"] (pants.toml:3 [test]) is replaced by [\"-v\"] (PANTS_TEST_ARGS)",
+        conflicts[0].to_string()
+    );
+}
@@ -0,0 +1,156 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The most of a failed command's stderr that is reported.
+const MAX_STDERR_LEN: usize = 500;
+
+///
+/// Runs the commands of `%(shell ...)s` placeholders, substituting their stdout (without trailing
+/// newlines), as for `$(...)` in a shell. Each command is run at most once per parse, from the
+/// build root, and is killed if it does not complete within the timeout.
+///
+/// NB: Errors describe the command as written and its stderr, but never its stdout, which may be
+/// a secret.
+///
+#[derive(Debug)]
+pub(crate) struct ShellInterpolation {
+    cwd: PathBuf,
+    timeout: Duration,
+    results: Mutex<HashMap<String, Result<String, String>>>,
+}
+
+impl ShellInterpolation {
+    pub(crate) fn new(cwd: PathBuf, timeout: Duration) -> Self {
+        Self {
+            cwd,
+            timeout,
+            results: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn run(&self, command: &str) -> Result<String, String> {
+        if let Some(result) = self.results.lock().unwrap().get(command) {
+            return result.clone();
+        }
+        let result = self
+            .run_uncached(command)
+            .map_err(|e| format!("Command `{command}` of a `%(shell ...)s` placeholder {e}"));
+        self.results
+            .lock()
+            .unwrap()
+            .insert(command.to_owned(), result.clone());
+        result
+    }
+
+    fn run_uncached(&self, command: &str) -> Result<String, String> {
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        shell.arg(command).current_dir(&self.cwd);
+        let stdout = run_with_timeout(shell, self.timeout)?;
+        let stdout = String::from_utf8(stdout).map_err(|_| "wrote output that is not UTF-8.")?;
+        Ok(stdout.trim_end_matches(['\r', '\n']).to_owned())
+    }
+}
+
+///
+/// Runs the command without any stdin, killing it if it does not complete within the timeout, and
+/// returns its stdout. Errors complete a sentence about the command, e.g. "failed with exit status:
+/// 2: <stderr>", and never include its stdout.
+///
+pub(crate) fn run_with_timeout(command: Command, timeout: Duration) -> Result<Vec<u8>, String> {
+    run_with_input(command, None, timeout)
+}
+
+///
+/// As for `run_with_timeout`, but writes the given input, if any, to the stdin of the command.
+///
+pub(crate) fn run_with_input(
+    mut command: Command,
+    input: Option<Vec<u8>>,
+    timeout: Duration,
+) -> Result<Vec<u8>, String> {
+    let stdin = if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    };
+    let mut child = command
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not be run: {e}"))?;
+    // NB: The input is written, and the output is read, concurrently, so that a command which
+    // writes output before it has read all of its input does not block on a full pipe while we
+    // block on another.
+    let written = input.map(|input| {
+        let mut stdin = child.stdin.take().unwrap();
+        thread::spawn(move || stdin.write_all(&input))
+    });
+    let stdout = read_in_background(child.stdout.take().unwrap());
+    let stderr = read_in_background(child.stderr.take().unwrap());
+    let status = wait_with_timeout(&mut child, timeout)?;
+    let stdout = stdout.join().unwrap();
+    let stderr = stderr.join().unwrap();
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let stderr = stderr.trim();
+        let stderr = match stderr.char_indices().nth(MAX_STDERR_LEN) {
+            Some((end, _)) => format!("{}...", &stderr[..end]),
+            None => stderr.to_owned(),
+        };
+        return Err(format!("failed with {status}: {stderr}"));
+    }
+    // NB: A command which fails may exit before reading its input, so its exit status is checked
+    // before any error writing to it.
+    if let Some(written) = written {
+        written
+            .join()
+            .unwrap()
+            .map_err(|e| format!("could not be written to: {e}"))?;
+    }
+    Ok(stdout)
+}
+
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = vec![];
+        // NB: A failed read leaves the output that was read so far, which is all we can report.
+        let _ = pipe.read_to_end(&mut output);
+        output
+    })
+}
+
+fn wait_with_timeout(
+    child: &mut Child,
+    timeout: Duration,
+) -> Result<std::process::ExitStatus, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {timeout:?}."));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(5)),
+            Err(e) => return Err(format!("could not be waited for: {e}")),
+        }
+    }
+}
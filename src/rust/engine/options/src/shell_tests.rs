@@ -0,0 +1,103 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tempfile::TempDir;
+
+use crate::interpolation::{InterpolationMap, Interpolator};
+use crate::shell::ShellInterpolation;
+use crate::{option_id, Args, BuildRoot, ConfigSource, Env, OptionParser};
+
+#[test]
+fn test_run() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("version.txt"), "1.2.3\n\n").unwrap();
+    let shell = ShellInterpolation::new(dir.path().to_path_buf(), Duration::from_secs(10));
+    // Commands run from the given directory, and trailing newlines are stripped.
+    assert_eq!(Ok("1.2.3".to_owned()), shell.run("cat version.txt"));
+
+    // Each command is run at most once.
+    let count = "echo x >> count.txt && wc -l < count.txt | tr -d ' '";
+    assert_eq!(Ok("1".to_owned()), shell.run(count));
+    assert_eq!(Ok("1".to_owned()), shell.run(count));
+
+    let replacements =
+        InterpolationMap::from([("version".to_owned(), "%(shell cat version.txt)s".to_owned())]);
+    assert_eq!(
+        Ok("v1.2.3 (1)".to_owned()),
+        Interpolator::new(&replacements)
+            .shell(Some(&shell))
+            .interpolate(&format!("v%(version)s (%(shell {count})s)"))
+    );
+}
+
+#[test]
+fn test_failure() {
+    let shell = ShellInterpolation::new(PathBuf::from("."), Duration::from_secs(10));
+    // The output of a failed command is not reported, as it may be a secret.
+    assert_eq!(
+        Err(
+            "Command `echo hunter2; echo oops >&2; exit 3` of a `%(shell ...)s` placeholder \
+            failed with exit status: 3: oops"
+                .to_owned()
+        ),
+        shell.run("echo hunter2; echo oops >&2; exit 3")
+    );
+
+    let shell = ShellInterpolation::new(PathBuf::from("."), Duration::from_millis(100));
+    assert_eq!(
+        Err(
+            "Command `sleep 10` of a `%(shell ...)s` placeholder timed out after 100ms.".to_owned()
+        ),
+        shell.run("sleep 10")
+    );
+}
+
+#[test]
+fn test_option_parser() {
+    let buildroot = TempDir::new().unwrap();
+    fs::write(buildroot.path().join("BUILDROOT"), "").unwrap();
+    fs::write(buildroot.path().join("level.txt"), "debug\n").unwrap();
+    let parser = |env: &[(&str, &str)]| {
+        OptionParser::new(
+            Args::new(vec![]),
+            Env::new(
+                env.iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            Some(vec![ConfigSource {
+                path: PathBuf::from("pants.toml"),
+                content: "[GLOBAL]\nlevel = '%(shell cat level.txt)s'\n".to_owned(),
+            }]),
+            false,
+            false,
+            Some(BuildRoot::find_from(buildroot.path()).unwrap()),
+        )
+    };
+
+    assert_eq!(
+        "debug",
+        parser(&[("PANTS_SHELL_INTERPOLATION", "true")])
+            .unwrap()
+            .parse_string(&option_id!("level"), "info")
+            .unwrap()
+            .value
+    );
+    let err = parser(&[]).err().unwrap();
+    assert!(
+        err.starts_with(
+            "Cannot run the command `cat level.txt` of a `%(shell ...)s` placeholder, as shell \
+            interpolation is not enabled"
+        ),
+        "{err}"
+    );
+    assert!(
+        err.ends_with("in config file pants.toml, section GLOBAL, key level"),
+        "{err}"
+    );
+}
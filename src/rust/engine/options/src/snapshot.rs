@@ -0,0 +1,367 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use indexmap::IndexMap;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+use super::{Args, Env, OptionId, Val};
+use crate::base64;
+use crate::explain::REDACTED;
+use crate::remote::split_pin;
+
+pub(crate) fn digest(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+///
+/// Stands in for a secret value when persisting it. The digest lets a change to the value still be
+/// detected, without the value itself being written to disk.
+///
+pub(crate) fn redacted(value: &Val) -> Val {
+    Val::String(format!("{REDACTED} sha256:{}", digest(value.to_string().as_bytes())).into())
+}
+
+///
+/// Computes a digest of the args that a parse was (or would be) performed with, and of those of its
+/// env vars that it reads: the env vars of options (i.e. prefixed with `PANTS_`), and the given
+/// others. Other env vars, e.g. `PWD`, have no bearing on the options.
+///
+pub(crate) fn inputs_digest<'a>(
+    args: &[String],
+    env: impl IntoIterator<Item = (&'a String, &'a String)>,
+    env_vars_read: &BTreeSet<String>,
+) -> String {
+    let mut hasher = Sha256::new();
+    for arg in args {
+        hasher.update(arg.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update([0]);
+    let env_vars = env
+        .into_iter()
+        .filter(|(name, _)| name.starts_with("PANTS_") || env_vars_read.contains(*name))
+        .collect::<BTreeMap<_, _>>();
+    for (k, v) in env_vars {
+        hasher.update(k.as_bytes());
+        hasher.update([0]);
+        hasher.update(v.as_bytes());
+        hasher.update([0]);
+    }
+    hex::encode(hasher.finalize())
+}
+
+///
+/// A persistable record of resolved option values, along with the digests of everything that
+/// contributed to them.
+///
+/// A snapshot is created from a fully parsed `OptionParser` via `OptionParser::snapshot`, which
+/// resolves every registered option, and written to disk. A later process may read it back and,
+/// if `is_fresh` holds for its own args and env, use the values without parsing. Otherwise it
+/// should fall back to a full parse (and write a new snapshot).
+///
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OptionsSnapshot {
+    pub(crate) inputs_digest: String,
+    // The env vars other than those of options that were read, whether or not they were set.
+    pub(crate) env_vars_read: BTreeSet<String>,
+    pub(crate) file_digests: BTreeMap<PathBuf, Option<String>>,
+    // The remote fromfiles that were fetched, by their references as written.
+    pub(crate) remote_digests: BTreeMap<String, Option<String>>,
+    // The inputs whose content cannot be checked, e.g. the output of executable fromfiles.
+    pub(crate) volatile_inputs: BTreeSet<String>,
+    #[serde(
+        serialize_with = "serialize_values",
+        deserialize_with = "deserialize_values"
+    )]
+    values: BTreeMap<String, Val>,
+}
+
+impl OptionsSnapshot {
+    pub(crate) fn new(
+        inputs_digest: String,
+        env_vars_read: BTreeSet<String>,
+        file_digests: BTreeMap<PathBuf, Option<String>>,
+        remote_digests: BTreeMap<String, Option<String>>,
+        volatile_inputs: BTreeSet<String>,
+    ) -> Self {
+        Self {
+            inputs_digest,
+            env_vars_read,
+            file_digests,
+            remote_digests,
+            volatile_inputs,
+            values: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, id: &OptionId, value: Val) {
+        self.values.insert(id.to_string(), value);
+    }
+
+    pub fn get(&self, id: &OptionId) -> Option<&Val> {
+        self.values.get(&id.to_string())
+    }
+
+    pub fn write_to(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize options snapshot: {e}"))?;
+        fs::write(path, content).map_err(|e| {
+            format!(
+                "Failed to write options snapshot to {}: {e}",
+                path.display()
+            )
+        })
+    }
+
+    pub fn read_from(path: &Path) -> Result<OptionsSnapshot, String> {
+        let content = fs::read(path).map_err(|e| {
+            format!(
+                "Failed to read options snapshot from {}: {e}",
+                path.display()
+            )
+        })?;
+        serde_json::from_slice(&content).map_err(|e| {
+            format!(
+                "Failed to deserialize options snapshot from {}: {e}",
+                path.display()
+            )
+        })
+    }
+
+    ///
+    /// Compares the resolved values in this snapshot with those in `other`, e.g. to compare the
+    /// options of two branches, or of a local and a CI configuration.
+    ///
+    pub fn diff(&self, other: &OptionsSnapshot) -> OptionsDiff {
+        diff_values(&self.values, &other.values)
+    }
+
+    ///
+    /// Returns true if a parse with the given args and env would see exactly the inputs that this
+    /// snapshot was created from: i.e., the args and the env vars that were read are identical,
+    /// every contributing file still exists with the same content, and every optional fromfile
+    /// that was missing still is.
+    ///
+    /// Remote fromfiles are not fetched to check them: the content of a remote fromfile is only
+    /// known not to have changed if it is pinned to its digest, as in `@sha256=<hex>+https://...`.
+    /// A snapshot with volatile inputs, such as the output of an executable fromfile, is never
+    /// fresh.
+    ///
+    pub fn is_fresh(&self, args: &Args, env: &Env) -> bool {
+        self.volatile_inputs.is_empty()
+            && self.inputs_digest == inputs_digest(&args.arg_strs, &env.env, &self.env_vars_read)
+            && self
+                .file_digests
+                .iter()
+                .all(|(path, expected)| match (fs::read(path), expected) {
+                    (Ok(content), Some(expected)) => digest(&content) == *expected,
+                    (Err(e), None) => e.kind() == io::ErrorKind::NotFound,
+                    _ => false,
+                })
+            && self.remote_digests.iter().all(|(target, digest)| {
+                match (split_pin(target).0, digest) {
+                    (Some(pin), Some(digest)) => pin.eq_ignore_ascii_case(digest),
+                    _ => false,
+                }
+            })
+    }
+}
+
+// Compares two sets of resolved values, keyed by option.
+pub(crate) fn diff_values(
+    values: &BTreeMap<String, Val>,
+    other_values: &BTreeMap<String, Val>,
+) -> OptionsDiff {
+    let mut diff = OptionsDiff::default();
+    for (key, value) in values {
+        match other_values.get(key) {
+            None => {
+                diff.removed.insert(key.clone(), value.clone());
+            }
+            Some(other_value) if other_value != value => {
+                diff.changed
+                    .insert(key.clone(), (value.clone(), other_value.clone()));
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, value) in other_values {
+        if !values.contains_key(key) {
+            diff.added.insert(key.clone(), value.clone());
+        }
+    }
+    diff
+}
+
+///
+/// The differences between the resolved values of two snapshots, keyed by option (i.e. by scope
+/// and name). Changed values are recorded as (old, new) pairs.
+///
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct OptionsDiff {
+    pub added: BTreeMap<String, Val>,
+    pub removed: BTreeMap<String, Val>,
+    pub changed: BTreeMap<String, (Val, Val)>,
+}
+
+impl OptionsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Display for OptionsDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.removed {
+            writeln!(f, "- {key} = {value}")?;
+        }
+        for (key, value) in &self.added {
+            writeln!(f, "+ {key} = {value}")?;
+        }
+        for (key, (old, new)) in &self.changed {
+            writeln!(f, "~ {key}: {old} -> {new}")?;
+        }
+        Ok(())
+    }
+}
+
+// The keys of the single entry that bytes and dates are persisted as.
+const BYTES_KEY: &str = "$bytes";
+const DATE_KEY: &str = "$date";
+
+///
+/// Serializes resolved values such that they round-trip via `deserialize_values`: bytes and dates,
+/// which `Val` serializes as strings, are persisted as `{"$bytes": "<base64>"}` and
+/// `{"$date": "<RFC 3339>"}`, and the keys of dicts that start with `$` are escaped with another
+/// `$`, so that they are not mistaken for either.
+///
+pub(crate) fn serialize_values<S: Serializer>(
+    values: &BTreeMap<String, Val>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(values.iter().map(|(key, value)| (key, Persisted(value))))
+}
+
+pub(crate) fn deserialize_values<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Val>, D::Error> {
+    Ok(BTreeMap::<String, Restored>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, value)| (key, value.0))
+        .collect())
+}
+
+struct Persisted<'a>(&'a Val);
+
+impl Serialize for Persisted<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Val::List(items) => serializer.collect_seq(items.iter().map(Persisted)),
+            Val::Dict(dict) => serializer.collect_map(dict.iter().map(|(key, value)| {
+                if key.starts_with('$') {
+                    (format!("${key}"), Persisted(value))
+                } else {
+                    (key.clone(), Persisted(value))
+                }
+            })),
+            Val::Bytes(bytes) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(BYTES_KEY, &base64::encode(bytes))?;
+                map.end()
+            }
+            Val::Date(datetime) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(DATE_KEY, &datetime.to_string())?;
+                map.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+struct Restored(Val);
+
+impl<'de> Deserialize<'de> for Restored {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RestoredVisitor).map(Restored)
+    }
+}
+
+struct RestoredVisitor;
+
+impl<'de> Visitor<'de> for RestoredVisitor {
+    type Value = Val;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a persisted option value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Val, E> {
+        Ok(Val::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Val, E> {
+        Ok(Val::Int(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Val, E> {
+        Ok(i64::try_from(value).map_or(Val::Float(value as f64), Val::Int))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Val, E> {
+        Ok(Val::Float(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Val, E> {
+        Ok(Val::String(value.into()))
+    }
+
+    fn visit_unit<E>(self) -> Result<Val, E> {
+        Ok(Val::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Val, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(Restored(item)) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Val::List(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Val, A::Error> {
+        let mut entries = IndexMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, Restored(value))) = map.next_entry::<String, Restored>()? {
+            entries.insert(key, value);
+        }
+        if entries.len() == 1 {
+            match entries.first() {
+                Some((key, Val::String(encoded))) if key == BYTES_KEY => {
+                    return base64::decode(encoded)
+                        .map(Val::Bytes)
+                        .map_err(de::Error::custom);
+                }
+                Some((key, Val::String(datetime))) if key == DATE_KEY => {
+                    return datetime.parse().map(Val::Date).map_err(de::Error::custom);
+                }
+                _ => {}
+            }
+        }
+        Ok(Val::Dict(
+            entries
+                .into_iter()
+                .map(|(key, value)| match key.strip_prefix('$') {
+                    Some(unescaped) => (unescaped.to_owned(), value),
+                    None => (key, value),
+                })
+                .collect(),
+        ))
+    }
+}
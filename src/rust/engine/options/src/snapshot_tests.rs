@@ -0,0 +1,226 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use tempfile::TempDir;
+
+use crate::snapshot::digest;
+use crate::{
+    option_id, Args, BuildRoot, ConfigSource, CredentialStore, Env, KeyringEntry, OptionInfo,
+    OptionParser, OptionRegistry, OptionType, OptionsSnapshot, Val,
+};
+
+fn args() -> Args {
+    Args::new(vec!["--foo=bar".to_string()])
+}
+
+fn env() -> Env {
+    Env::new(HashMap::from([
+        ("PANTS_LEVEL".to_string(), "debug".to_string()),
+        ("PWD".to_string(), "/repo".to_string()),
+    ]))
+}
+
+#[test]
+fn test_snapshot_roundtrip_and_freshness() {
+    let buildroot = TempDir::new().unwrap();
+    let config_path = buildroot.path().join("pants.toml");
+    fs::write(
+        &config_path,
+        "[GLOBAL]\nfoo = 'baz'\nbar = '%(if:env.USER_BAR:a:b)s'\n",
+    )
+    .unwrap();
+
+    let option_parser = OptionParser::new(
+        args(),
+        env(),
+        Some(vec![ConfigSource::from_file(&config_path).unwrap()]),
+        false,
+        false,
+        Some(BuildRoot::find_from(buildroot.path()).unwrap()),
+    )
+    .unwrap();
+    let foo = option_id!("foo");
+    let mut registry = OptionRegistry::new();
+    registry
+        .register(OptionInfo::new(
+            foo.clone(),
+            OptionType::String("".to_owned()),
+        ))
+        .unwrap();
+    let snapshot = option_parser.snapshot(&registry).unwrap();
+
+    let snapshot_path = buildroot.path().join("options.snapshot");
+    snapshot.write_to(&snapshot_path).unwrap();
+    let reloaded = OptionsSnapshot::read_from(&snapshot_path).unwrap();
+    assert_eq!(snapshot, reloaded);
+    assert_eq!(Some(&Val::String("bar".into())), reloaded.get(&foo));
+    assert!(reloaded.is_fresh(&args(), &env()));
+
+    // A change to the args or env invalidates the snapshot.
+    assert!(!reloaded.is_fresh(&Args::new(vec![]), &env()));
+    assert!(!reloaded.is_fresh(&args(), &Env::new(HashMap::new())));
+    let with_env_var = |name: &str| {
+        let mut env = env();
+        env.env.insert(name.to_owned(), "value".to_owned());
+        env
+    };
+    assert!(!reloaded.is_fresh(&args(), &with_env_var("PANTS_OTHER")));
+    assert!(!reloaded.is_fresh(&args(), &with_env_var("USER_BAR")));
+    // But not a change to an env var that neither options nor config files read.
+    assert!(reloaded.is_fresh(&args(), &with_env_var("OLDPWD")));
+    let mut without_pwd = env();
+    without_pwd.env.remove("PWD");
+    assert!(reloaded.is_fresh(&args(), &without_pwd));
+
+    // As does a change to any contributing file.
+    fs::write(&config_path, "[GLOBAL]\nfoo = 'qux'\n").unwrap();
+    assert!(!reloaded.is_fresh(&args(), &env()));
+    fs::remove_file(&config_path).unwrap();
+    assert!(!reloaded.is_fresh(&args(), &env()));
+}
+
+struct Store;
+
+impl CredentialStore for Store {
+    fn get(&self, _entry: &KeyringEntry) -> Result<Option<String>, String> {
+        Ok(Some("hunter2".to_owned()))
+    }
+}
+
+#[test]
+fn test_snapshot_redacts_secrets() {
+    let buildroot = TempDir::new().unwrap();
+    let mut option_parser = OptionParser::new(
+        Args::new(vec![]),
+        Env::new(HashMap::new()),
+        Some(vec![]),
+        false,
+        false,
+        Some(BuildRoot::find_from(buildroot.path()).unwrap()),
+    )
+    .unwrap();
+    let token = option_id!(["auth"], "token");
+    let mut registry = OptionRegistry::new();
+    registry
+        .register(
+            OptionInfo::new(token.clone(), OptionType::String("".to_owned()))
+                .keyring("pants", "token"),
+        )
+        .unwrap();
+    option_parser.register_keyring(&registry, Arc::new(Store));
+    let snapshot = option_parser.snapshot(&registry).unwrap();
+    assert_eq!(
+        Some(&Val::String(
+            format!("<redacted> sha256:{}", digest("\"hunter2\"".as_bytes())).into()
+        )),
+        snapshot.get(&token)
+    );
+
+    let snapshot_path = buildroot.path().join("options.snapshot");
+    snapshot.write_to(&snapshot_path).unwrap();
+    assert!(!fs::read_to_string(&snapshot_path)
+        .unwrap()
+        .contains("hunter2"));
+}
+
+#[test]
+fn test_diff() {
+    let resolve = |config: &str| {
+        let buildroot = TempDir::new().unwrap();
+        let config_path = buildroot.path().join("pants.toml");
+        fs::write(&config_path, config).unwrap();
+        OptionParser::new(
+            Args::new(vec![]),
+            Env::new(HashMap::new()),
+            Some(vec![ConfigSource::from_file(&config_path).unwrap()]),
+            false,
+            false,
+            Some(BuildRoot::find_from(buildroot.path()).unwrap()),
+        )
+        .unwrap()
+        .resolve_snapshot(vec![
+            (option_id!("level"), OptionType::String("info".to_owned())),
+            (option_id!("jobs"), OptionType::Int(1)),
+            (
+                option_id!(["python"], "tags"),
+                OptionType::StringList(vec![]),
+            ),
+        ])
+        .unwrap()
+    };
+
+    let main = resolve("[GLOBAL]\nlevel = 'warn'\n");
+    let branch = resolve("[GLOBAL]\njobs = 4\n[python]\ntags = ['a']\n");
+    assert!(main.diff(&main).is_empty());
+
+    let diff = main.diff(&branch);
+    assert_eq!(
+        BTreeMap::from([
+            ("[GLOBAL] jobs".to_owned(), (Val::Int(1), Val::Int(4))),
+            (
+                "[GLOBAL] level".to_owned(),
+                (Val::String("warn".into()), Val::String("info".into()))
+            ),
+            (
+                "[python] tags".to_owned(),
+                (Val::List(vec![]), Val::List(vec![Val::String("a".into())]))
+            ),
+        ]),
+        diff.changed
+    );
+    assert_eq!(
+        "~ [GLOBAL] jobs: 1 -> 4\n~ [GLOBAL] level: \"warn\" -> \"info\"\n~ [python] tags: [] -> [\"a\"]\n",
+        diff.to_string()
+    );
+
+    let mut partial = OptionsSnapshot::default();
+    partial.insert(&option_id!("jobs"), Val::Int(1));
+    partial.insert(&option_id!("other"), Val::Bool(true));
+    let diff = partial.diff(&main);
+    assert_eq!(
+        "- [GLOBAL] other = true\n+ [GLOBAL] level = \"warn\"\n+ [python] tags = []\n",
+        diff.to_string()
+    );
+}
+
+#[test]
+fn test_values_roundtrip() {
+    let values = [
+        Val::Bool(true),
+        Val::Int(-1),
+        Val::Float(1.5),
+        Val::String("2024-12-31".into()),
+        Val::List(vec![Val::Int(1), Val::String("a".into())]),
+        Val::Bytes(vec![0, 1, 0xff]),
+        Val::Date("2024-12-31T23:59:59Z".parse().unwrap()),
+        Val::Dict(IndexMap::from([
+            ("bytes".to_owned(), Val::Bytes(b"AAE=".to_vec())),
+            ("date".to_owned(), Val::Date("2024-12-31".parse().unwrap())),
+            ("$bytes".to_owned(), Val::String("AAE=".into())),
+            ("$date".to_owned(), Val::Int(1)),
+        ])),
+        Val::Dict(IndexMap::from([(
+            "$date".to_owned(),
+            Val::String("2024-12-31".into()),
+        )])),
+        Val::Null,
+    ];
+    let buildroot = TempDir::new().unwrap();
+    let snapshot_path = buildroot.path().join("options.snapshot");
+    for value in values {
+        let mut snapshot = OptionsSnapshot::default();
+        snapshot.insert(&option_id!("foo"), value.clone());
+        snapshot.write_to(&snapshot_path).unwrap();
+        assert_eq!(
+            Some(&value),
+            OptionsSnapshot::read_from(&snapshot_path)
+                .unwrap()
+                .get(&option_id!("foo"))
+        );
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+// The characters that may not appear in a target name, as for addresses in the engine.
+const BANNED_TARGET_NAME_CHARS: &[char] = &['@', '!', '?', '/', '\\', ':', '='];
+
+///
+/// Checks the basic syntax of a target spec, such as `dir::`, `dir:tgt`, `dir/file.py` or
+/// `!dir::`, and normalizes its path: backslashes become slashes, a leading `//` or `./` and any
+/// trailing `/` are dropped, and `.` and `..` components are resolved.
+///
+/// This doesn't check that the spec matches anything: that is only known once the build graph is
+/// constructed.
+///
+pub(crate) fn normalize_spec(spec: &str) -> Result<String, String> {
+    let spec = spec.trim();
+    let (ignore, spec) = match spec.strip_prefix('!') {
+        Some(spec) => ("!", spec),
+        None => ("", spec),
+    };
+    if spec.is_empty() {
+        return Err("it is empty".to_owned());
+    }
+    let spec = spec.replace('\\', "/");
+    let (path, target) = match spec.split_once(':') {
+        Some((path, target)) => (path, Some(target)),
+        None => (spec.as_str(), None),
+    };
+
+    let relative_path = match path.strip_prefix("//") {
+        Some(path) => path,
+        None if path.starts_with('/') => {
+            return Err("it is an absolute path, rather than relative to the build root".to_owned())
+        }
+        None => path,
+    };
+    let mut components: Vec<&str> = vec![];
+    for component in relative_path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if components.pop().is_none() {
+                    return Err("it refers to a path outside of the build root".to_owned());
+                }
+            }
+            component => components.push(component),
+        }
+    }
+    let path = components.join("/");
+
+    let target = match target {
+        None => String::new(),
+        Some(":") => "::".to_owned(),
+        Some(target) if target.starts_with(':') => {
+            return Err("`::` must end the spec".to_owned());
+        }
+        Some(target) => {
+            // A target name may be followed by a generated name (`#gen`) or parameters (`@k=v`).
+            let name_end = target.find(['#', '@']).unwrap_or(target.len());
+            let name = &target[..name_end];
+            if let Some(c) = name.chars().find(|c| BANNED_TARGET_NAME_CHARS.contains(c)) {
+                return Err(format!("the target name `{name}` may not contain `{c}`"));
+            }
+            if name.is_empty() && name_end < target.len() {
+                return Err("it has no target name".to_owned());
+            }
+            format!(":{target}")
+        }
+    };
+    if path.is_empty() && target.is_empty() {
+        return Err("it refers to the build root, rather than to a target or file".to_owned());
+    }
+    Ok(format!("{ignore}{path}{target}"))
+}
@@ -0,0 +1,47 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::spec::normalize_spec;
+
+#[test]
+fn test_normalize_spec() {
+    let normalize = |spec| normalize_spec(spec).unwrap();
+    assert_eq!("::", normalize("::"));
+    assert_eq!("src/python::", normalize("src/python::"));
+    assert_eq!("src/python::", normalize("./src//python/::"));
+    assert_eq!("src/python:", normalize("src/python/:"));
+    assert_eq!("src/python:lib", normalize("//src/python:lib"));
+    assert_eq!(":lib", normalize(":lib"));
+    assert_eq!("src/python", normalize("src/python/"));
+    assert_eq!("src/python/app.py", normalize("src\\python\\app.py"));
+    assert_eq!("src/app.py", normalize("src/python/../app.py"));
+    assert_eq!("src/*.py", normalize("src/*.py"));
+    assert_eq!("!tests::", normalize("!tests::"));
+    assert_eq!("!tests/app.py", normalize(" !./tests/app.py "));
+    assert_eq!("src:lib#gen/file.py", normalize("src:lib#gen/file.py"));
+    assert_eq!("src:lib@resolve=a", normalize("src:lib@resolve=a"));
+}
+
+#[test]
+fn test_invalid_spec() {
+    let err = |spec| normalize_spec(spec).unwrap_err();
+    assert_eq!("it is empty", err(""));
+    assert_eq!("it is empty", err("!"));
+    assert_eq!(
+        "it is an absolute path, rather than relative to the build root",
+        err("/src/python::")
+    );
+    assert_eq!(
+        "it refers to a path outside of the build root",
+        err("../src::")
+    );
+    assert_eq!("`::` must end the spec", err("src:::"));
+    assert_eq!("`::` must end the spec", err("src::lib"));
+    assert_eq!("the target name `a:b` may not contain `:`", err("src:a:b"));
+    assert_eq!("the target name `a/b` may not contain `/`", err("src:a/b"));
+    assert_eq!("it has no target name", err("src:#gen"));
+    assert_eq!(
+        "it refers to the build root, rather than to a target or file",
+        err("//")
+    );
+}
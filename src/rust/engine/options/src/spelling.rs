@@ -0,0 +1,117 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use serde::Serialize;
+
+use crate::env::EnvReader;
+use crate::id::{NameTransform, Scope};
+use crate::registry::{OptionInfo, OptionRegistry};
+
+///
+/// The ways to spell an option: its flags, the env vars that set it, and its key in config. These
+/// are shared by help, shell completion and error messages, so that they never disagree.
+///
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Spellings {
+    // The flags that set the option from any position on the command line, e.g. `--test-debug`,
+    // then for bool options `--no-test-debug`, then any short flag.
+    pub flags: Vec<String>,
+    // The flags that set the option when it follows its scope on the command line, e.g. `--debug`
+    // and `--no-debug` after `test`.
+    pub scoped_flags: Vec<String>,
+    // The env vars that set the option, starting with the one shown in help.
+    pub env_vars: Vec<String>,
+    pub config_section: String,
+    pub config_key: String,
+}
+
+impl Spellings {
+    pub fn new(option: &OptionInfo) -> Self {
+        let negations: &[bool] = if option.is_bool() {
+            &[false, true]
+        } else {
+            &[false]
+        };
+        let preferred_env_var = EnvReader::env_var_name(&option.id);
+        let mut env_vars = vec![preferred_env_var.clone()];
+        env_vars.extend(
+            EnvReader::env_var_names(&option.id)
+                .into_iter()
+                .filter(|env_var| env_var != &preferred_env_var),
+        );
+        Self {
+            flags: negations
+                .iter()
+                .map(|negated| option.flag(*negated))
+                .chain(option.short_flag())
+                .collect(),
+            scoped_flags: negations
+                .iter()
+                .map(|negated| option.scoped_flag(*negated))
+                .collect(),
+            env_vars,
+            config_section: option.id.scope.name().to_owned(),
+            config_key: option.id.name("_", NameTransform::None),
+        }
+    }
+
+    /// The env var shown in help: e.g. `PANTS_LEVEL` rather than `PANTS_GLOBAL_LEVEL`.
+    pub fn env_var(&self) -> &str {
+        &self.env_vars[0]
+    }
+
+    ///
+    /// A hint for an error message about a flag, which lists the other ways to set the option, e.g.
+    /// "It can also be set by the env var PANTS_TEST_OUTPUT, or by `output` in the [test] section of
+    /// config."
+    ///
+    pub fn hint(&self) -> String {
+        format!(
+            "It can also be set by the env var {}, or by `{}` in the [{}] section of config.",
+            self.env_var(),
+            self.config_key,
+            self.config_section
+        )
+    }
+}
+
+impl OptionRegistry {
+    ///
+    /// The options that the given env var sets, e.g. `PANTS_TEST_OUTPUT`. Since both the dashes
+    /// and the dots of scope names are spelled as underscores, an env var may set more than one:
+    /// e.g. `PANTS_A_B_C` sets both `[a-b] c` and `[a] b_c`.
+    ///
+    pub fn options_for_env_var(&self, env_var: &str) -> Vec<&OptionInfo> {
+        self.options()
+            .filter(|option| {
+                EnvReader::env_var_names(&option.id)
+                    .iter()
+                    .any(|name| name == env_var)
+            })
+            .collect()
+    }
+
+    ///
+    /// The options that the given flag sets, in the context of the scope that precedes it on the
+    /// command line, after which the flags of its options need not be qualified by it. The flag may
+    /// include its value, as in `--test-output=all` or `-ldebug`.
+    ///
+    /// As for env vars, a flag may set more than one option: e.g. `--a-b-c` sets both `[a-b] c` and
+    /// `[a] b_c`.
+    ///
+    pub fn options_for_flag(&self, flag: &str, context: &Scope) -> Vec<&OptionInfo> {
+        let flag = if flag.starts_with("--") {
+            flag.split('=').next().unwrap()
+        } else {
+            flag.get(..2).unwrap_or(flag)
+        };
+        self.options()
+            .filter(|option| {
+                let spellings = Spellings::new(option);
+                spellings.flags.iter().any(|f| f == flag)
+                    || (&option.id.scope == context
+                        && spellings.scoped_flags.iter().any(|f| f == flag))
+            })
+            .collect()
+    }
+}
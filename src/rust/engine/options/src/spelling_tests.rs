@@ -0,0 +1,112 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::{option_id, OptionInfo, OptionRegistry, OptionType, Scope, Spellings};
+
+fn strs(strs: &[&str]) -> Vec<String> {
+    strs.iter().map(|s| s.to_string()).collect()
+}
+
+fn registry() -> OptionRegistry {
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(
+            option_id!(-'l', "level"),
+            OptionType::String("info".to_owned()),
+        ),
+        OptionInfo::new(
+            option_id!("pants", "workdir"),
+            OptionType::String(".pants.d".to_owned()),
+        ),
+        OptionInfo::new(option_id!(["test"], "debug"), OptionType::Bool(false)),
+        OptionInfo::new(option_id!(["a-b"], "c"), OptionType::Int(0)),
+        OptionInfo::new(option_id!(["a"], "b", "c"), OptionType::Int(0)),
+        OptionInfo::new(option_id!(["a.b"], "d"), OptionType::Int(0)),
+    ] {
+        registry.register(option).unwrap();
+    }
+    registry
+}
+
+#[test]
+fn test_spellings() {
+    let registry = registry();
+    let spellings = registry.options().map(Spellings::new).collect::<Vec<_>>();
+
+    assert_eq!(
+        Spellings {
+            flags: strs(&["--level", "-l"]),
+            scoped_flags: strs(&["--level"]),
+            env_vars: strs(&["PANTS_LEVEL", "PANTS_GLOBAL_LEVEL"]),
+            config_section: "GLOBAL".to_owned(),
+            config_key: "level".to_owned(),
+        },
+        spellings[0]
+    );
+    assert_eq!(
+        strs(&[
+            "PANTS_PANTS_WORKDIR",
+            "PANTS_GLOBAL_PANTS_WORKDIR",
+            "PANTS_WORKDIR"
+        ]),
+        spellings[1].env_vars
+    );
+    assert_eq!(
+        Spellings {
+            flags: strs(&["--test-debug", "--no-test-debug"]),
+            scoped_flags: strs(&["--debug", "--no-debug"]),
+            env_vars: strs(&["PANTS_TEST_DEBUG"]),
+            config_section: "test".to_owned(),
+            config_key: "debug".to_owned(),
+        },
+        spellings[2]
+    );
+    assert_eq!(strs(&["--a.b-d"]), spellings[5].flags);
+    assert_eq!(strs(&["PANTS_A_B_D"]), spellings[5].env_vars);
+    assert_eq!(
+        "It can also be set by the env var PANTS_TEST_DEBUG, or by `debug` in the [test] section \
+        of config.",
+        spellings[2].hint()
+    );
+}
+
+#[test]
+fn test_options_for_env_var() {
+    let registry = registry();
+    let ids = |env_var| {
+        registry
+            .options_for_env_var(env_var)
+            .into_iter()
+            .map(|option| option.id.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["[GLOBAL] level"], ids("PANTS_LEVEL"));
+    assert_eq!(vec!["[GLOBAL] level"], ids("PANTS_GLOBAL_LEVEL"));
+    assert_eq!(vec!["[GLOBAL] pants_workdir"], ids("PANTS_WORKDIR"));
+    assert_eq!(vec!["[test] debug"], ids("PANTS_TEST_DEBUG"));
+    assert_eq!(vec!["[a-b] c", "[a] b_c"], ids("PANTS_A_B_C"));
+    assert_eq!(vec!["[a.b] d"], ids("PANTS_A_B_D"));
+    assert!(ids("PANTS_TEST_LEVEL").is_empty());
+    assert!(ids("pants_level").is_empty());
+}
+
+#[test]
+fn test_options_for_flag() {
+    let registry = registry();
+    let ids = |flag, context: &Scope| {
+        registry
+            .options_for_flag(flag, context)
+            .into_iter()
+            .map(|option| option.id.to_string())
+            .collect::<Vec<_>>()
+    };
+    let test = Scope::named("test");
+    assert_eq!(vec!["[GLOBAL] level"], ids("--level=debug", &Scope::Global));
+    assert_eq!(vec!["[GLOBAL] level"], ids("-ldebug", &test));
+    assert_eq!(vec!["[test] debug"], ids("--no-test-debug", &Scope::Global));
+    assert_eq!(vec!["[test] debug"], ids("--debug", &test));
+    // Unqualified flags are only those of the scope that precedes them.
+    assert!(ids("--debug", &Scope::Global).is_empty());
+    assert_eq!(vec!["[a-b] c", "[a] b_c"], ids("--a-b-c", &Scope::Global));
+    assert!(ids("--unknown", &Scope::Global).is_empty());
+}
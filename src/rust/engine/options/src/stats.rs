@@ -0,0 +1,188 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::Source;
+
+/// A phase of option parsing, whose time is accounted for separately.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Phase {
+    // Reading and parsing config files, including their interpolation.
+    ConfigParse,
+    // Interpolating the values of config files.
+    Interpolation,
+    FromfileRead,
+    RemoteFetch,
+    // Reading the values of options from each kind of source.
+    ConfigResolution,
+    KeyringResolution,
+    EnvResolution,
+    FlagResolution,
+    // Reading the values of options from sources registered by embedders.
+    PluginResolution,
+}
+
+impl Phase {
+    pub(crate) fn resolving(source: &Source) -> Self {
+        match source {
+            Source::Default | Source::Config { .. } => Phase::ConfigResolution,
+            Source::Keyring => Phase::KeyringResolution,
+            Source::Env => Phase::EnvResolution,
+            Source::Flag => Phase::FlagResolution,
+            Source::Plugin { .. } => Phase::PluginResolution,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Counter {
+    ConfigFilesRead,
+    ConfigBytesRead,
+    FromfilesRead,
+    FromfileBytesRead,
+    // Fromfiles which were unchanged since they were last read, so were served from memory.
+    FromfileCacheHits,
+    RemoteFetches,
+    // Remote content which had already been fetched, so was served from memory.
+    RemoteCacheHits,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Phase::ConfigParse => "config_parse",
+            Phase::Interpolation => "interpolation",
+            Phase::FromfileRead => "fromfile_read",
+            Phase::RemoteFetch => "remote_fetch",
+            Phase::ConfigResolution => "config_resolution",
+            Phase::KeyringResolution => "keyring_resolution",
+            Phase::EnvResolution => "env_resolution",
+            Phase::FlagResolution => "flag_resolution",
+            Phase::PluginResolution => "plugin_resolution",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl fmt::Display for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Counter::ConfigFilesRead => "config_files_read",
+            Counter::ConfigBytesRead => "config_bytes_read",
+            Counter::FromfilesRead => "fromfiles_read",
+            Counter::FromfileBytesRead => "fromfile_bytes_read",
+            Counter::FromfileCacheHits => "fromfile_cache_hits",
+            Counter::RemoteFetches => "remote_fetches",
+            Counter::RemoteCacheHits => "remote_cache_hits",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The number of spans of a phase, and the total time spent in them.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Timing {
+    pub count: u64,
+    pub total: Duration,
+}
+
+///
+/// Statistics about where option parsing spent its time, as shown by `--stats`.
+///
+/// NB: Spans may nest (interpolation happens while parsing a config file, and fromfiles are read
+/// while resolving options), and may run concurrently, so the timings do not sum to wall time.
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OptionsStats {
+    pub counters: BTreeMap<Counter, u64>,
+    pub timings: BTreeMap<Phase, Timing>,
+}
+
+impl OptionsStats {
+    pub fn counter(&self, counter: Counter) -> u64 {
+        self.counters.get(&counter).copied().unwrap_or(0)
+    }
+
+    pub fn timing(&self, phase: Phase) -> Timing {
+        self.timings.get(&phase).copied().unwrap_or_default()
+    }
+}
+
+impl fmt::Display for OptionsStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Option parsing:")?;
+        for (counter, value) in &self.counters {
+            writeln!(f, "  {counter}: {value}")?;
+        }
+        for (phase, timing) in &self.timings {
+            writeln!(
+                f,
+                "  {phase}: {:.3}ms over {} span{}",
+                timing.total.as_secs_f64() * 1000.0,
+                timing.count,
+                if timing.count == 1 { "" } else { "s" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Records the statistics of a parse. Clones share their records, so that every reader of an
+/// `OptionParser` contributes to the same statistics.
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ParseStats {
+    stats: Arc<Mutex<OptionsStats>>,
+}
+
+impl ParseStats {
+    pub(crate) fn increment(&self, counter: Counter, amount: u64) {
+        *self
+            .stats
+            .lock()
+            .unwrap()
+            .counters
+            .entry(counter)
+            .or_default() += amount;
+    }
+
+    pub(crate) fn record(&self, phase: Phase, elapsed: Duration) {
+        let mut stats = self.stats.lock().unwrap();
+        let timing = stats.timings.entry(phase).or_default();
+        timing.count += 1;
+        timing.total += elapsed;
+    }
+
+    /// Starts a span of the given phase, which is recorded when the returned guard is dropped.
+    pub(crate) fn span(&self, phase: Phase) -> Span {
+        Span {
+            stats: self,
+            phase,
+            // NB: There is no clock on wasm32, where `Instant::now` panics.
+            start: (!cfg!(target_arch = "wasm32")).then(Instant::now),
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> OptionsStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+pub(crate) struct Span<'a> {
+    stats: &'a ParseStats,
+    phase: Phase,
+    start: Option<Instant>,
+}
+
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            self.stats.record(self.phase, start.elapsed());
+        }
+    }
+}
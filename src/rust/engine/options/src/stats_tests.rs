@@ -0,0 +1,129 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tempfile::TempDir;
+
+use crate::stats::ParseStats;
+use crate::{option_id, Args, BuildRoot, ConfigSource, Counter, Env, OptionParser, Phase, Timing};
+
+#[test]
+fn test_shared_stats() {
+    let stats = ParseStats::default();
+    let shared = stats.clone();
+    stats.increment(Counter::FromfilesRead, 1);
+    shared.increment(Counter::FromfilesRead, 2);
+    shared.increment(Counter::FromfileBytesRead, 10);
+    {
+        let _span = shared.span(Phase::FromfileRead);
+    }
+    stats.record(Phase::FromfileRead, Duration::from_millis(5));
+
+    let snapshot = stats.snapshot();
+    assert_eq!(3, snapshot.counter(Counter::FromfilesRead));
+    assert_eq!(10, snapshot.counter(Counter::FromfileBytesRead));
+    assert_eq!(0, snapshot.counter(Counter::RemoteFetches));
+    let timing = snapshot.timing(Phase::FromfileRead);
+    assert_eq!(2, timing.count);
+    assert!(timing.total >= Duration::from_millis(5));
+    assert_eq!(Timing::default(), snapshot.timing(Phase::RemoteFetch));
+}
+
+#[test]
+fn test_display() {
+    let stats = ParseStats::default();
+    stats.increment(Counter::ConfigFilesRead, 2);
+    stats.increment(Counter::ConfigBytesRead, 1024);
+    stats.record(Phase::ConfigParse, Duration::from_micros(1500));
+    stats.record(Phase::EnvResolution, Duration::from_micros(250));
+    stats.record(Phase::EnvResolution, Duration::from_micros(250));
+    assert_eq!(
+        "Option parsing:\n  \
+        config_files_read: 2\n  \
+        config_bytes_read: 1024\n  \
+        config_parse: 1.500ms over 1 span\n  \
+        env_resolution: 0.500ms over 2 spans\n",
+        stats.snapshot().to_string()
+    );
+}
+
+#[test]
+fn test_option_parser_stats() {
+    let buildroot = TempDir::new().unwrap();
+    fs::write(buildroot.path().join("BUILDROOT"), "").unwrap();
+    fs::write(buildroot.path().join("names.json"), r#"["a", "b"]"#).unwrap();
+    let config_sources = vec![
+        ConfigSource {
+            path: PathBuf::from("pants.toml"),
+            content: "[scope]\nnames = '@names.json'\n".to_owned(),
+        },
+        ConfigSource {
+            path: PathBuf::from("pants.ci.toml"),
+            content: "[DEFAULT]\nname = 'c'\n[scope]\nnames.add = ['%(name)s']\n".to_owned(),
+        },
+    ];
+    let config_bytes = config_sources
+        .iter()
+        .map(|cs| cs.content.len() as u64)
+        .sum::<u64>();
+    let option_parser = OptionParser::new(
+        Args::new(vec!["--scope-flag=flagval".to_owned()]),
+        Env::new(HashMap::from([(
+            "PANTS_SCOPE_ENV".to_owned(),
+            "envval".to_owned(),
+        )])),
+        Some(config_sources),
+        false,
+        false,
+        Some(BuildRoot::find_from(buildroot.path()).unwrap()),
+    )
+    .unwrap();
+
+    let stats = option_parser.stats();
+    assert_eq!(2, stats.counter(Counter::ConfigFilesRead));
+    assert_eq!(config_bytes, stats.counter(Counter::ConfigBytesRead));
+    assert_eq!(2, stats.timing(Phase::ConfigParse).count);
+    assert!(stats.timing(Phase::Interpolation).count >= 3);
+    assert_eq!(0, stats.counter(Counter::FromfilesRead));
+
+    assert_eq!(
+        vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+        option_parser
+            .parse_string_list(&option_id!(["scope"], "names"), vec![])
+            .unwrap()
+            .value
+    );
+    let before = option_parser.stats();
+    let fromfiles_read = before.counter(Counter::FromfilesRead);
+    assert!(fromfiles_read >= 1);
+    assert_eq!(
+        10 * fromfiles_read,
+        before.counter(Counter::FromfileBytesRead)
+    );
+    assert_eq!(fromfiles_read, before.timing(Phase::FromfileRead).count);
+
+    option_parser
+        .parse_string(&option_id!(["scope"], "env"), "")
+        .unwrap();
+    option_parser
+        .parse_string(&option_id!(["scope"], "flag"), "")
+        .unwrap();
+    let after = option_parser.stats();
+    // Each option is read from the flags, and then from the env if not set there, and so on.
+    assert_eq!(
+        before.timing(Phase::FlagResolution).count + 2,
+        after.timing(Phase::FlagResolution).count
+    );
+    assert_eq!(
+        before.timing(Phase::EnvResolution).count + 1,
+        after.timing(Phase::EnvResolution).count
+    );
+    assert_eq!(
+        before.timing(Phase::ConfigResolution).count,
+        after.timing(Phase::ConfigResolution).count
+    );
+}
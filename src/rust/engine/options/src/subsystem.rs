@@ -0,0 +1,171 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use indexmap::IndexMap;
+
+use crate::{OptionId, OptionInfo, OptionParser, OptionRegistry, OptionType, Val};
+
+///
+/// A struct holding the options of a single scope, which it declares to an `OptionRegistry` and
+/// then reads from an `OptionParser`. Engine-native subsystems should derive this via
+/// `#[derive(Subsystem)]`, rather than implementing it by hand, e.g.:
+///
+/// ```ignore
+/// #[derive(Subsystem)]
+/// #[options(scope = "pantsd")]
+/// struct PantsdOptions {
+///     #[option(default = 60.0, help = "The timeout for pantsd to start.")]
+///     timeout: f64,
+///     #[option(name = "max-memory-usage")]
+///     max_memory: i64,
+/// }
+/// ```
+///
+pub trait Subsystem: Sized {
+    fn register(registry: &mut OptionRegistry) -> Result<(), String>;
+
+    fn parse(parser: &OptionParser) -> Result<Self, String>;
+}
+
+///
+/// A type that a field of a `Subsystem` may have: it determines the `OptionType` that the field
+/// is registered with, and the getter of `OptionParser` that reads it.
+///
+pub trait OptionField: Sized {
+    fn option_type(default: &Self) -> OptionType;
+
+    fn option_info(id: OptionId, default: &Self) -> OptionInfo {
+        OptionInfo::new(id, Self::option_type(default))
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String>;
+}
+
+impl OptionField for bool {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::Bool(*default)
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_bool(id, default)?.value)
+    }
+}
+
+impl OptionField for i64 {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::Int(*default)
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_int(id, default)?.value)
+    }
+}
+
+impl OptionField for f64 {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::Float(*default)
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_float(id, default)?.value)
+    }
+}
+
+impl OptionField for String {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::String(default.clone())
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_string(id, &default)?.value)
+    }
+}
+
+impl OptionField for Vec<bool> {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::BoolList(default.clone())
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_bool_list(id, default)?.value)
+    }
+}
+
+impl OptionField for Vec<i64> {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::IntList(default.clone())
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_int_list(id, default)?.value)
+    }
+}
+
+impl OptionField for Vec<f64> {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::FloatList(default.clone())
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_float_list(id, default)?.value)
+    }
+}
+
+impl OptionField for Vec<String> {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::StringList(default.clone())
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_string_list(id, default)?.value)
+    }
+}
+
+impl OptionField for Vec<IndexMap<String, Val>> {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::DictList(default.clone())
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_dict_list(id, default)?.value)
+    }
+}
+
+impl OptionField for IndexMap<String, Val> {
+    fn option_type(default: &Self) -> OptionType {
+        OptionType::Dict(default.clone())
+    }
+
+    fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+        Ok(parser.parse_dict(id, default)?.value)
+    }
+}
+
+// An optional scalar is registered with the default of its type, but shown in help as `None` if
+// it has no default.
+macro_rules! optional_option_field {
+    ($t:ty, $getter:ident, $as_default:expr) => {
+        impl OptionField for Option<$t> {
+            fn option_type(default: &Self) -> OptionType {
+                <$t as OptionField>::option_type(&default.clone().unwrap_or_default())
+            }
+
+            fn option_info(id: OptionId, default: &Self) -> OptionInfo {
+                let info = OptionInfo::new(id, Self::option_type(default));
+                match default {
+                    Some(_) => info,
+                    None => info.default_help_repr("None"),
+                }
+            }
+
+            fn read(parser: &OptionParser, id: &OptionId, default: Self) -> Result<Self, String> {
+                Ok(parser.$getter(id, $as_default(&default))?.value)
+            }
+        }
+    };
+}
+
+optional_option_field!(bool, parse_bool_optional, |d: &Option<bool>| *d);
+optional_option_field!(i64, parse_int_optional, |d: &Option<i64>| *d);
+optional_option_field!(f64, parse_float_optional, |d: &Option<f64>| *d);
+optional_option_field!(String, parse_string_optional, Option::<String>::as_deref);
@@ -0,0 +1,119 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use indexmap::IndexMap;
+
+use crate::tests::parser;
+use crate::{option_id, OptionRegistry, Subsystem, Val};
+
+#[derive(Debug, PartialEq, Subsystem)]
+#[options(scope = "test")]
+struct TestOptions {
+    #[option(default = 60, help = "The timeout.")]
+    timeout: i64,
+    #[option(short = 'd', advanced, group = "debugging")]
+    debug: bool,
+    #[option(default = "info")]
+    log_level: String,
+    #[option(name = "extra-env-vars")]
+    env: IndexMap<String, Val>,
+    #[option(default = vec!["-x".to_owned()])]
+    args: Vec<String>,
+    #[option(deprecated = "Use `timeout` instead.")]
+    max_time: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Subsystem)]
+struct GlobalOptions {
+    #[option(name = "pants-workdir", default = ".pants.d")]
+    workdir: String,
+}
+
+#[test]
+fn test_register() {
+    let mut registry = OptionRegistry::new();
+    TestOptions::register(&mut registry).unwrap();
+    GlobalOptions::register(&mut registry).unwrap();
+
+    assert_eq!(
+        vec![
+            option_id!(["test"], "timeout"),
+            option_id!(-'d', ["test"], "debug"),
+            option_id!(["test"], "log", "level"),
+            option_id!(["test"], "extra", "env", "vars"),
+            option_id!(["test"], "args"),
+            option_id!(["test"], "max", "time"),
+            option_id!("pants", "workdir"),
+        ],
+        registry
+            .options()
+            .map(|option| option.id.clone())
+            .collect::<Vec<_>>()
+    );
+
+    let timeout = registry.get(&option_id!(["test"], "timeout")).unwrap();
+    assert_eq!(Val::Int(60), timeout.option_type.default_val());
+    assert_eq!("The timeout.", timeout.help);
+    let debug = registry.get(&option_id!(-'d', ["test"], "debug")).unwrap();
+    assert!(debug.advanced);
+    assert_eq!(Some("debugging"), debug.group.as_deref());
+    assert_eq!(None, timeout.group);
+    assert_eq!(
+        Val::List(vec![Val::String("-x".into())]),
+        registry
+            .get(&option_id!(["test"], "args"))
+            .unwrap()
+            .option_type
+            .default_val()
+    );
+    let max_time = registry.get(&option_id!(["test"], "max", "time")).unwrap();
+    assert_eq!("None", max_time.rendered_default());
+    assert_eq!(
+        Some("Use `timeout` instead.".to_owned()),
+        max_time.deprecated
+    );
+
+    // A subsystem may not be registered twice.
+    assert_eq!(
+        "Option [test] timeout is already registered.",
+        TestOptions::register(&mut registry).unwrap_err()
+    );
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        TestOptions {
+            timeout: 60,
+            debug: false,
+            log_level: "info".to_owned(),
+            env: IndexMap::default(),
+            args: vec!["-x".to_owned()],
+            max_time: None,
+        },
+        TestOptions::parse(&parser(&[], &[], "")).unwrap()
+    );
+
+    let parser = parser(
+        &["--test-debug", "--test-args=+['-v']"],
+        &[("PANTS_TEST_LOG_LEVEL", "debug")],
+        "[test]\ntimeout = 30\nmax_time = 2.5\nextra_env_vars = { FOO = 'bar' }\n",
+    );
+    assert_eq!(
+        TestOptions {
+            timeout: 30,
+            debug: true,
+            log_level: "debug".to_owned(),
+            env: IndexMap::from([("FOO".to_owned(), Val::String("bar".into()))]),
+            args: vec!["-x".to_owned(), "-v".to_owned()],
+            max_time: Some(2.5),
+        },
+        TestOptions::parse(&parser).unwrap()
+    );
+    assert_eq!(
+        GlobalOptions {
+            workdir: ".pants.d".to_owned()
+        },
+        GlobalOptions::parse(&parser).unwrap()
+    );
+}
@@ -1,17 +1,98 @@
 // Copyright 2023 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+
+use crate::parse::parse_datetime;
+use crate::{Concurrency, Datetime, Val};
+
 /// A primitive enum for option types, containing the default value for the option.
 ///
-/// Currently only used for `pantsd` fingerprinting, which is defined in Rust. Options in general
-/// are registered in Python (see in particular `global_options.py`).
+/// Currently only used for `pantsd` fingerprinting, for explaining option values, and to describe
+/// options to an `OptionRegistry`. Options in general are registered in Python (see in particular
+/// `global_options.py`).
+#[derive(Clone, Debug)]
 pub enum OptionType {
     Bool(bool),
     Int(i64),
     Float(f64),
     String(String),
+    BoolList(Vec<bool>),
+    IntList(Vec<i64>),
+    FloatList(Vec<f64>),
     StringList(Vec<String>),
-    // NB: Notably missing is `Dict`: but that type is not yet supported by the Rust parser.
+    DictList(Vec<IndexMap<String, Val>>),
+    Dict(IndexMap<String, Val>),
+}
+
+impl OptionType {
+    /// The name of the type, as shown in help.
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            OptionType::Bool(_) => "bool",
+            OptionType::Int(_) => "int",
+            OptionType::Float(_) => "float",
+            OptionType::String(_) => "str",
+            OptionType::BoolList(_) => "list[bool]",
+            OptionType::IntList(_) => "list[int]",
+            OptionType::FloatList(_) => "list[float]",
+            OptionType::StringList(_) => "list[str]",
+            OptionType::DictList(_) => "list[dict]",
+            OptionType::Dict(_) => "dict",
+        }
+    }
+
+    ///
+    /// The option type with the given name, as shown in help (e.g. `list[str]`), and the given
+    /// default, for embedders which describe options by name.
+    ///
+    pub fn from_type_name(type_name: &str, default: Val) -> Result<OptionType, String> {
+        fn convert<T: FromVal>(type_name: &str, default: Val) -> Result<T, String> {
+            T::from_val(default)
+                .map_err(|e| format!("Invalid default for an option of type {type_name}: {e}"))
+        }
+        Ok(match type_name {
+            "bool" => OptionType::Bool(convert(type_name, default)?),
+            "int" => OptionType::Int(convert(type_name, default)?),
+            "float" => OptionType::Float(convert(type_name, default)?),
+            "str" => OptionType::String(convert(type_name, default)?),
+            "list[bool]" => OptionType::BoolList(convert(type_name, default)?),
+            "list[int]" => OptionType::IntList(convert(type_name, default)?),
+            "list[float]" => OptionType::FloatList(convert(type_name, default)?),
+            "list[str]" => OptionType::StringList(convert(type_name, default)?),
+            "list[dict]" => OptionType::DictList(convert(type_name, default)?),
+            "dict" => OptionType::Dict(convert(type_name, default)?),
+            _ => return Err(format!("Unknown option type: {type_name}")),
+        })
+    }
+
+    pub(crate) fn is_list(&self) -> bool {
+        matches!(
+            self,
+            OptionType::BoolList(_)
+                | OptionType::IntList(_)
+                | OptionType::FloatList(_)
+                | OptionType::StringList(_)
+                | OptionType::DictList(_)
+        )
+    }
+
+    pub(crate) fn default_val(&self) -> Val {
+        match self {
+            OptionType::Bool(default) => default.to_val(),
+            OptionType::Int(default) => default.to_val(),
+            OptionType::Float(default) => default.to_val(),
+            OptionType::String(default) => default.to_val(),
+            OptionType::BoolList(default) => default.to_val(),
+            OptionType::IntList(default) => default.to_val(),
+            OptionType::FloatList(default) => default.to_val(),
+            OptionType::StringList(default) => default.to_val(),
+            OptionType::DictList(default) => default.to_val(),
+            OptionType::Dict(default) => default.to_val(),
+        }
+    }
 }
 
 impl From<bool> for OptionType {
@@ -49,3 +130,205 @@ impl From<Vec<&str>> for OptionType {
         OptionType::StringList(v.into_iter().map(|s| s.to_owned()).collect())
     }
 }
+
+/// Converts a typed option value to the equivalent `Val`.
+pub(crate) trait ToVal {
+    fn to_val(&self) -> Val;
+}
+
+impl ToVal for Val {
+    fn to_val(&self) -> Val {
+        self.clone()
+    }
+}
+
+impl ToVal for bool {
+    fn to_val(&self) -> Val {
+        Val::Bool(*self)
+    }
+}
+
+impl ToVal for i64 {
+    fn to_val(&self) -> Val {
+        Val::Int(*self)
+    }
+}
+
+// NB: `Val` has no big int variant, so a big int that doesn't fit in an i64 is a string.
+impl ToVal for i128 {
+    fn to_val(&self) -> Val {
+        match i64::try_from(*self) {
+            Ok(i) => Val::Int(i),
+            Err(_) => Val::String(self.to_string().into()),
+        }
+    }
+}
+
+impl ToVal for Vec<u8> {
+    fn to_val(&self) -> Val {
+        Val::Bytes(self.clone())
+    }
+}
+
+impl ToVal for f64 {
+    fn to_val(&self) -> Val {
+        Val::Float(*self)
+    }
+}
+
+impl ToVal for Concurrency {
+    fn to_val(&self) -> Val {
+        match self {
+            Concurrency::Absolute(count) => Val::Int(*count as i64),
+            concurrency => Val::String(concurrency.to_string().into()),
+        }
+    }
+}
+
+impl ToVal for Datetime {
+    fn to_val(&self) -> Val {
+        Val::Date(self.clone())
+    }
+}
+
+impl ToVal for String {
+    fn to_val(&self) -> Val {
+        Val::String(self.into())
+    }
+}
+
+// NB: A path that is not UTF-8 is represented lossily.
+impl ToVal for PathBuf {
+    fn to_val(&self) -> Val {
+        Val::String(self.to_string_lossy().as_ref().into())
+    }
+}
+
+impl<T: ToVal> ToVal for Vec<T> {
+    fn to_val(&self) -> Val {
+        Val::List(self.iter().map(ToVal::to_val).collect())
+    }
+}
+
+impl ToVal for IndexMap<String, Val> {
+    fn to_val(&self) -> Val {
+        Val::Dict(self.clone())
+    }
+}
+
+/// Converts a `Val` to a typed option value, if it is of the expected type.
+pub(crate) trait FromVal: Sized {
+    fn from_val(val: Val) -> Result<Self, String>;
+}
+
+fn unexpected(expected: &str, val: &Val) -> String {
+    format!("Expected {expected} but got {val}")
+}
+
+impl FromVal for bool {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::Bool(b) => Ok(b),
+            val => Err(unexpected("a bool", &val)),
+        }
+    }
+}
+
+impl FromVal for i64 {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::Int(i) => Ok(i),
+            val => Err(unexpected("an int", &val)),
+        }
+    }
+}
+
+impl FromVal for i128 {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::Int(i) => Ok(i.into()),
+            Val::String(s) => s
+                .parse()
+                .map_err(|_| unexpected("a big int", &Val::String(s))),
+            val => Err(unexpected("a big int", &val)),
+        }
+    }
+}
+
+impl FromVal for Vec<u8> {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::Bytes(b) => Ok(b),
+            Val::String(s) => Ok(s.as_str().as_bytes().to_vec()),
+            val => Err(unexpected("bytes", &val)),
+        }
+    }
+}
+
+impl FromVal for f64 {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::Float(f) => Ok(f),
+            Val::Int(i) => Ok(i as f64),
+            val => Err(unexpected("a float", &val)),
+        }
+    }
+}
+
+impl FromVal for String {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::String(s) => Ok(s.to_string()),
+            val => Err(unexpected("a string", &val)),
+        }
+    }
+}
+
+impl FromVal for PathBuf {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::String(s) => Ok(PathBuf::from(s.as_str())),
+            val => Err(unexpected("a path", &val)),
+        }
+    }
+}
+
+impl FromVal for Datetime {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::Date(d) => Ok(d),
+            Val::String(s) => parse_datetime(&s).map_err(|_| unexpected("a date", &Val::String(s))),
+            val => Err(unexpected("a date", &val)),
+        }
+    }
+}
+
+impl FromVal for Concurrency {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::Int(i) if i > 0 => Ok(Concurrency::Absolute(i as usize)),
+            Val::String(s) => {
+                Concurrency::parse(&s).map_err(|_| unexpected("a concurrency", &Val::String(s)))
+            }
+            val => Err(unexpected("a concurrency", &val)),
+        }
+    }
+}
+
+impl<T: FromVal> FromVal for Vec<T> {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::List(items) => items.into_iter().map(T::from_val).collect(),
+            val => Err(unexpected("a list", &val)),
+        }
+    }
+}
+
+impl FromVal for IndexMap<String, Val> {
+    fn from_val(val: Val) -> Result<Self, String> {
+        match val {
+            Val::Dict(d) => Ok(d),
+            val => Err(unexpected("a dict", &val)),
+        }
+    }
+}
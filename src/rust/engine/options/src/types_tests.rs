@@ -0,0 +1,47 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use indexmap::IndexMap;
+
+use crate::{OptionType, Val};
+
+#[test]
+fn test_from_type_name() {
+    for (type_name, default) in [
+        ("bool", Val::Bool(true)),
+        ("int", Val::Int(3)),
+        ("float", Val::Float(0.5)),
+        ("str", Val::String("foo".into())),
+        ("list[int]", Val::List(vec![Val::Int(1), Val::Int(2)])),
+        ("list[str]", Val::List(vec![])),
+        (
+            "list[dict]",
+            Val::List(vec![Val::Dict(IndexMap::from([(
+                "a".to_owned(),
+                Val::Int(1),
+            )]))]),
+        ),
+        ("dict", Val::Dict(IndexMap::default())),
+    ] {
+        let option_type = OptionType::from_type_name(type_name, default.clone()).unwrap();
+        assert_eq!(type_name, option_type.type_name());
+        assert_eq!(default, option_type.default_val());
+    }
+
+    // An int is a valid default for a float.
+    assert_eq!(
+        Val::Float(1.0),
+        OptionType::from_type_name("float", Val::Int(1))
+            .unwrap()
+            .default_val()
+    );
+    assert_eq!(
+        "Invalid default for an option of type list[int]: Expected an int but got \"1\"",
+        OptionType::from_type_name("list[int]", Val::List(vec![Val::String("1".into())]))
+            .unwrap_err()
+    );
+    assert_eq!(
+        "Unknown option type: set",
+        OptionType::from_type_name("set", Val::List(vec![])).unwrap_err()
+    );
+}
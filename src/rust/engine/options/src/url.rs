@@ -0,0 +1,106 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt;
+
+use ::url::ParseError;
+
+///
+/// A URL given as an option value, such as `[GLOBAL].remote_store_address`. It is parsed by the
+/// `url` crate, and must have a host, other than a `file://` URL such as `file:///path/to/cache`.
+///
+/// A URL may embed credentials, so it displays with them redacted. Use `as_str` for the URL
+/// including them.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Url(::url::Url);
+
+const NO_SCHEME: &str = "it has no scheme, as in `https://example.com`";
+
+impl Url {
+    pub fn parse(value: &str) -> Result<Url, String> {
+        let url = ::url::Url::parse(value).map_err(|e| match e {
+            ParseError::RelativeUrlWithoutBase => NO_SCHEME.to_owned(),
+            ParseError::EmptyHost => "it has no host".to_owned(),
+            e => e.to_string(),
+        })?;
+        // NB: A value such as `localhost:8980` parses as a URL whose scheme is `localhost`.
+        if url.cannot_be_a_base() {
+            return Err(NO_SCHEME.to_owned());
+        }
+        if url.host_str().map_or(true, str::is_empty) && url.scheme() != "file" {
+            return Err("it has no host".to_owned());
+        }
+        Ok(Url(url))
+    }
+
+    ///
+    /// Parses a URL whose scheme must be one of `schemes`, unless that is empty.
+    ///
+    pub fn parse_with_schemes(value: &str, schemes: &[&str]) -> Result<Url, String> {
+        let url = Url::parse(value)?;
+        if !schemes.is_empty() && !schemes.contains(&url.scheme()) {
+            return Err(format!(
+                "its scheme must be one of {}, but is `{}`",
+                schemes
+                    .iter()
+                    .map(|s| format!("`{s}`"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                url.scheme()
+            ));
+        }
+        Ok(url)
+    }
+
+    /// The URL, including any credentials.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// The scheme of the URL, in lowercase.
+    pub fn scheme(&self) -> &str {
+        self.0.scheme()
+    }
+
+    /// The host of the URL, which is bracketed if it is an IPv6 address.
+    pub fn host(&self) -> &str {
+        self.0.host_str().unwrap_or_default()
+    }
+
+    /// The port of the URL, or else the default port of its scheme, if it has a known one.
+    pub fn port(&self) -> Option<u16> {
+        self.0.port_or_known_default()
+    }
+
+    pub fn has_credentials(&self) -> bool {
+        !self.0.username().is_empty() || self.0.password().is_some()
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.has_credentials() {
+            return write!(f, "{}", self.0);
+        }
+        // NB: A URL with credentials has a host, and so they may always be replaced.
+        let mut redacted = self.0.clone();
+        let _ = redacted.set_password(None);
+        let _ = redacted.set_username("***");
+        write!(f, "{redacted}")
+    }
+}
+
+///
+/// Redacts the credentials of a value that may not be a valid URL, for use in error messages.
+///
+pub(crate) fn redact(value: &str) -> String {
+    let Some((scheme, rest)) = value.split_once("://") else {
+        return value.to_owned();
+    };
+    let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+    match authority.rsplit_once('@') {
+        Some((_, host_port)) => format!("{scheme}://***@{host_port}{}", &rest[authority.len()..]),
+        None => value.to_owned(),
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::PathBuf;
+
+use crate::env::Env;
+
+const CONFIG_DIR: &str = "pants";
+const CONFIG_FILE: &str = "pants.toml";
+
+///
+/// The machine-wide and per-user config files that are layered beneath the repo's config files,
+/// in increasing order of precedence. The paths are returned whether or not they exist.
+///
+/// The machine-wide file is `/etc/pants/pants.toml` (`%PROGRAMDATA%\pants\pants.toml` on
+/// Windows). The per-user file is `$XDG_CONFIG_HOME/pants/pants.toml`, falling back to
+/// `~/.config/pants/pants.toml` (`~/Library/Application Support/pants/pants.toml` on macOS and
+/// `%APPDATA%\pants\pants.toml` on Windows). The env vars are read as paths, so they need not
+/// be UTF-8.
+///
+pub fn user_config_files(env: &Env) -> Vec<PathBuf> {
+    config_files_for(std::env::consts::OS, env)
+}
+
+pub(crate) fn config_files_for(os: &str, env: &Env) -> Vec<PathBuf> {
+    let var = |name: &str| {
+        env.get_os(name)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    let (system_dir, user_dir) = if os == "windows" {
+        (var("PROGRAMDATA"), var("APPDATA"))
+    } else {
+        // Per the XDG spec, a relative `$XDG_CONFIG_HOME` is invalid and should be ignored.
+        let xdg_config_home = var("XDG_CONFIG_HOME").filter(|p| p.is_absolute());
+        let user_dir = match (xdg_config_home, var("HOME")) {
+            (Some(xdg_config_home), _) => Some(xdg_config_home),
+            (None, Some(home)) if os == "macos" => {
+                Some(home.join("Library").join("Application Support"))
+            }
+            (None, Some(home)) => Some(home.join(".config")),
+            (None, None) => None,
+        };
+        (Some(PathBuf::from("/etc")), user_dir)
+    };
+    [system_dir, user_dir]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(CONFIG_DIR).join(CONFIG_FILE))
+        .collect()
+}
@@ -0,0 +1,121 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::PathBuf;
+
+use crate::env::Env;
+use crate::user_config::config_files_for;
+
+fn env(vars: &[(&str, &str)]) -> Env {
+    Env::new(
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    )
+}
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn test_linux() {
+    assert_eq!(
+        paths(&["/etc/pants/pants.toml", "/home/jo/.config/pants/pants.toml"]),
+        config_files_for("linux", &env(&[("HOME", "/home/jo")]))
+    );
+    assert_eq!(
+        paths(&["/etc/pants/pants.toml", "/xdg/pants/pants.toml"]),
+        config_files_for(
+            "linux",
+            &env(&[("HOME", "/home/jo"), ("XDG_CONFIG_HOME", "/xdg")])
+        )
+    );
+    // A relative or empty XDG_CONFIG_HOME is ignored.
+    assert_eq!(
+        paths(&["/etc/pants/pants.toml", "/home/jo/.config/pants/pants.toml"]),
+        config_files_for(
+            "linux",
+            &env(&[("HOME", "/home/jo"), ("XDG_CONFIG_HOME", "xdg")])
+        )
+    );
+    assert_eq!(
+        paths(&["/etc/pants/pants.toml", "/home/jo/.config/pants/pants.toml"]),
+        config_files_for(
+            "linux",
+            &env(&[("HOME", "/home/jo"), ("XDG_CONFIG_HOME", "")])
+        )
+    );
+    // Without a home directory, only the machine-wide config is used.
+    assert_eq!(
+        paths(&["/etc/pants/pants.toml"]),
+        config_files_for("linux", &env(&[]))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_home() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let home = OsStr::from_bytes(b"/home/j\xf6");
+    let mut env = env(&[]);
+    env.non_utf8_values
+        .insert("HOME".to_owned(), home.to_os_string());
+    assert_eq!(
+        vec![
+            PathBuf::from("/etc/pants/pants.toml"),
+            PathBuf::from(home).join(".config/pants/pants.toml")
+        ],
+        config_files_for("linux", &env)
+    );
+}
+
+#[test]
+fn test_macos() {
+    assert_eq!(
+        paths(&[
+            "/etc/pants/pants.toml",
+            "/Users/jo/Library/Application Support/pants/pants.toml"
+        ]),
+        config_files_for("macos", &env(&[("HOME", "/Users/jo")]))
+    );
+    assert_eq!(
+        paths(&["/etc/pants/pants.toml", "/xdg/pants/pants.toml"]),
+        config_files_for(
+            "macos",
+            &env(&[("HOME", "/Users/jo"), ("XDG_CONFIG_HOME", "/xdg")])
+        )
+    );
+}
+
+#[test]
+fn test_windows() {
+    let expected = [
+        PathBuf::from("C:\\ProgramData")
+            .join("pants")
+            .join("pants.toml"),
+        PathBuf::from("C:\\Users\\jo\\AppData\\Roaming")
+            .join("pants")
+            .join("pants.toml"),
+    ];
+    assert_eq!(
+        expected.to_vec(),
+        config_files_for(
+            "windows",
+            &env(&[
+                ("PROGRAMDATA", "C:\\ProgramData"),
+                ("APPDATA", "C:\\Users\\jo\\AppData\\Roaming"),
+                ("HOME", "/home/jo"),
+            ])
+        )
+    );
+    assert_eq!(
+        expected[1..].to_vec(),
+        config_files_for(
+            "windows",
+            &env(&[("APPDATA", "C:\\Users\\jo\\AppData\\Roaming")])
+        )
+    );
+}
@@ -0,0 +1,119 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use regex::Regex;
+
+use super::Val;
+use crate::explain::REDACTED;
+
+///
+/// A check applied to the value of an option whenever it is read via one of the typed getters of
+/// `OptionParser`. Register validators via `OptionParser::register_validator`.
+///
+/// Bounds and patterns apply to scalar values, and to each item of list values.
+///
+#[derive(Clone, Debug)]
+pub enum Validator {
+    IntRange { min: Option<i64>, max: Option<i64> },
+    FloatRange { min: Option<f64>, max: Option<f64> },
+    Matches(Regex),
+    NonEmpty,
+}
+
+impl Validator {
+    /// Requires the value to match the given regex, which is anchored at neither end.
+    pub fn matches(pattern: &str) -> Result<Self, String> {
+        Regex::new(pattern)
+            .map(Validator::Matches)
+            .map_err(|e| format!("Invalid pattern `{pattern}`: {e}"))
+    }
+
+    ///
+    /// Returns a description of the problem with `value`, if any.
+    ///
+    pub(crate) fn check(&self, value: &Val) -> Option<String> {
+        self.describe_problem(value, false)
+    }
+
+    ///
+    /// As `check`, but the description does not reveal `value`, which is a secret.
+    ///
+    pub(crate) fn check_redacted(&self, value: &Val) -> Option<String> {
+        self.describe_problem(value, true)
+    }
+
+    fn describe_problem(&self, value: &Val, redact: bool) -> Option<String> {
+        let shown = |value: &Val| {
+            if redact {
+                REDACTED.to_owned()
+            } else {
+                value.to_string()
+            }
+        };
+        match (self, value) {
+            (Validator::NonEmpty, Val::String(s)) if s.is_empty() => {
+                Some("the value must not be empty".to_owned())
+            }
+            (Validator::NonEmpty, Val::List(l)) if l.is_empty() => {
+                Some("the value must not be empty".to_owned())
+            }
+            (Validator::NonEmpty, Val::Dict(d)) if d.is_empty() => {
+                Some("the value must not be empty".to_owned())
+            }
+            (Validator::NonEmpty, _) => None,
+            (_, Val::List(items)) => items
+                .iter()
+                .find_map(|item| self.describe_problem(item, redact)),
+            (Validator::IntRange { min, max }, Val::Int(i)) => {
+                check_range(*i, *min, *max, redact, |i| i.to_string())
+            }
+            (Validator::FloatRange { min, max }, Val::Float(f)) => {
+                check_range(*f, *min, *max, redact, |f| format!("{f:?}"))
+            }
+            (Validator::Matches(regex), Val::String(s)) => {
+                if regex.is_match(s) {
+                    None
+                } else {
+                    Some(format!(
+                        "{} does not match the pattern `{}`",
+                        shown(value),
+                        regex.as_str()
+                    ))
+                }
+            }
+            (validator, value) => Some(format!(
+                "{validator:?} cannot validate the value {}",
+                shown(value)
+            )),
+        }
+    }
+}
+
+fn check_range<T: PartialOrd>(
+    value: T,
+    min: Option<T>,
+    max: Option<T>,
+    redact: bool,
+    display: impl Fn(&T) -> String,
+) -> Option<String> {
+    let shown = || {
+        if redact {
+            REDACTED.to_owned()
+        } else {
+            display(&value)
+        }
+    };
+    match (min, max) {
+        (Some(min), _) if value < min => Some(format!(
+            "{} is less than the minimum of {}",
+            shown(),
+            display(&min)
+        )),
+        (_, Some(max)) if value > max => Some(format!(
+            "{} is greater than the maximum of {}",
+            shown(),
+            display(&max)
+        )),
+        _ => None,
+    }
+}
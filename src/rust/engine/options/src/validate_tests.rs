@@ -0,0 +1,111 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::tests;
+use crate::{option_id, OptionParser, Val, Validator};
+
+#[test]
+fn test_check() {
+    let range = Validator::IntRange {
+        min: Some(1),
+        max: Some(4),
+    };
+    assert_eq!(None, range.check(&Val::Int(1)));
+    assert_eq!(
+        Some("0 is less than the minimum of 1".to_owned()),
+        range.check(&Val::Int(0))
+    );
+    assert_eq!(
+        Some("5 is greater than the maximum of 4".to_owned()),
+        range.check(&Val::List(vec![Val::Int(2), Val::Int(5)]))
+    );
+
+    let range = Validator::FloatRange {
+        min: None,
+        max: Some(1.0),
+    };
+    assert_eq!(None, range.check(&Val::Float(-7.0)));
+    assert_eq!(
+        Some("1.5 is greater than the maximum of 1.0".to_owned()),
+        range.check(&Val::Float(1.5))
+    );
+
+    let matches = Validator::matches("^[a-z]+$").unwrap();
+    assert_eq!(None, matches.check(&Val::String("abc".into())));
+    assert_eq!(
+        Some("\"ABC\" does not match the pattern `^[a-z]+$`".to_owned()),
+        matches.check(&Val::String("ABC".into()))
+    );
+    assert!(Validator::matches("(").is_err());
+
+    assert_eq!(
+        Some("the value must not be empty".to_owned()),
+        Validator::NonEmpty.check(&Val::List(vec![]))
+    );
+    assert_eq!(None, Validator::NonEmpty.check(&Val::Int(0)));
+
+    // A validator registered for an option of the wrong type fails loudly.
+    assert_eq!(
+        Some("FloatRange { min: None, max: Some(1.0) } cannot validate the value 1".to_owned()),
+        range.check(&Val::Int(1))
+    );
+}
+
+fn parser(args: &[&str], env: &[(&str, &str)]) -> OptionParser {
+    let mut parser = tests::parser(args, env, "");
+    parser.register_validator(
+        option_id!("process", "concurrency"),
+        Validator::IntRange {
+            min: Some(1),
+            max: None,
+        },
+    );
+    parser.register_validator(option_id!("backends"), Validator::NonEmpty);
+    parser
+}
+
+#[test]
+fn test_validated_getters() {
+    let concurrency = option_id!("process", "concurrency");
+    assert_eq!(
+        4,
+        parser(&["--process-concurrency=4"], &[])
+            .parse_int(&concurrency, 1)
+            .unwrap()
+            .value
+    );
+    assert_eq!(
+        "Invalid value for --process-concurrency (from flags): -1 is less than the minimum of 1",
+        parser(&["--process-concurrency=-1"], &[])
+            .parse_int(&concurrency, 1)
+            .unwrap_err()
+    );
+    assert_eq!(
+        "Invalid value for PANTS_PROCESS_CONCURRENCY (from env): 0 is less than the minimum of 1",
+        parser(&[], &[("PANTS_PROCESS_CONCURRENCY", "0")])
+            .parse_int(&concurrency, 1)
+            .unwrap_err()
+    );
+    // Defaults are validated too.
+    assert_eq!(
+        "Invalid value for [GLOBAL] process_concurrency (from default): \
+         0 is less than the minimum of 1",
+        parser(&[], &[]).parse_int(&concurrency, 0).unwrap_err()
+    );
+
+    let backends = option_id!("backends");
+    assert_eq!(
+        "Invalid value for --backends (from flags): the value must not be empty",
+        parser(&["--backends=-['a']"], &[])
+            .parse_string_list(&backends, vec!["a".to_owned()])
+            .unwrap_err()
+    );
+    // Options without validators are unaffected.
+    assert_eq!(
+        -1,
+        parser(&["--other=-1"], &[])
+            .parse_int(&option_id!("other"), 0)
+            .unwrap()
+            .value
+    );
+}
@@ -0,0 +1,168 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::explain::describe_source;
+use crate::registry::OptionRegistry;
+use crate::{OptionId, OptionParser, Source};
+
+lazy_static! {
+    static ref VERSION_RE: Regex =
+        Regex::new(r"^(\d+(?:\.\d+)*)(?:\.?(dev|a|b|rc)(\d+))?$").unwrap();
+}
+
+// The stage of a release, in increasing order: e.g. 2.20.0.dev1 < 2.20.0a0 < 2.20.0rc1 < 2.20.0.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Stage {
+    Dev(u64),
+    Alpha(u64),
+    Beta(u64),
+    ReleaseCandidate(u64),
+    Final,
+}
+
+///
+/// A Pants version, such as `2.20.0`, `2.21.0rc1` or `2.22.0.dev3`. Versions compare by their
+/// release numbers (so `2.20` equals `2.20.0`), and then by their stage.
+///
+#[derive(Clone, Debug)]
+pub struct Version {
+    release: Vec<u64>,
+    stage: Stage,
+    text: String,
+}
+
+impl Version {
+    pub fn parse(text: &str) -> Result<Version, String> {
+        let invalid = || format!("Invalid Pants version `{text}`: expected e.g. `2.20.0`.");
+        let captures = VERSION_RE.captures(text.trim()).ok_or_else(invalid)?;
+        let release = captures[1]
+            .split('.')
+            .map(|n| n.parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let stage = match (captures.get(2), captures.get(3)) {
+            (Some(stage), Some(n)) => {
+                let n = n.as_str().parse::<u64>().map_err(|_| invalid())?;
+                match stage.as_str() {
+                    "dev" => Stage::Dev(n),
+                    "a" => Stage::Alpha(n),
+                    "b" => Stage::Beta(n),
+                    _ => Stage::ReleaseCandidate(n),
+                }
+            }
+            _ => Stage::Final,
+        };
+        Ok(Version {
+            release,
+            stage,
+            text: text.trim().to_owned(),
+        })
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.release.len().max(other.release.len());
+        let padded = |release: &[u64]| {
+            (0..len)
+                .map(|i| release.get(i).copied().unwrap_or(0))
+                .collect::<Vec<_>>()
+        };
+        padded(&self.release)
+            .cmp(&padded(&other.release))
+            .then(self.stage.cmp(&other.stage))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Why an option is not available in the running version of Pants.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Unavailability {
+    // The option is only introduced in a later version.
+    NotYetIntroduced(Version),
+    // The option was removed in this or an earlier version.
+    Removed(Version),
+}
+
+/// An option which is set, but which does not exist in the running version of Pants.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnavailableOption {
+    pub id: OptionId,
+    pub source: Source,
+    pub running: Version,
+    pub reason: Unavailability,
+}
+
+impl fmt::Display for UnavailableOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is set by {}, but ",
+            self.id,
+            describe_source(&self.source)
+        )?;
+        match &self.reason {
+            Unavailability::NotYetIntroduced(version) => write!(
+                f,
+                "it is only introduced in Pants {version}, and Pants {} is running.",
+                self.running
+            ),
+            Unavailability::Removed(version) => write!(
+                f,
+                "it was removed in Pants {version}, and Pants {} is running.",
+                self.running
+            ),
+        }
+    }
+}
+
+pub(crate) fn check(
+    parser: &OptionParser,
+    registry: &OptionRegistry,
+    running: &Version,
+) -> Vec<UnavailableOption> {
+    registry
+        .options()
+        .filter_map(|option| {
+            let reason = match (&option.introduced_in, &option.removed_in) {
+                (Some(introduced_in), _) if running < introduced_in => {
+                    Unavailability::NotYetIntroduced(introduced_in.clone())
+                }
+                (_, Some(removed_in)) if running >= removed_in => {
+                    Unavailability::Removed(removed_in.clone())
+                }
+                _ => return None,
+            };
+            Some(UnavailableOption {
+                id: option.id.clone(),
+                source: parser.explicit_source(&option.id)?,
+                running: running.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
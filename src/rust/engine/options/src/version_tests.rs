@@ -0,0 +1,106 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::tests::parser;
+use crate::{
+    option_id, OptionInfo, OptionRegistry, OptionType, Source, Unavailability, UnavailableOption,
+    Version,
+};
+
+fn version(text: &str) -> Version {
+    Version::parse(text).unwrap()
+}
+
+#[test]
+fn test_parse_and_compare() {
+    assert_eq!(version("2.20"), version("2.20.0"));
+    assert_eq!("2.20", version("2.20").to_string());
+    let ascending = [
+        "2.19.1",
+        "2.20.0.dev0",
+        "2.20.0dev3",
+        "2.20.0a0",
+        "2.20.0b2",
+        "2.20.0rc1",
+        "2.20.0",
+        "2.20.1",
+        "2.21.0.dev0",
+        "3.0",
+    ];
+    for pair in ascending.windows(2) {
+        assert!(version(pair[0]) < version(pair[1]), "{pair:?}");
+    }
+
+    for invalid in ["", "two", "2.x", "2.20.0-final", "2..0"] {
+        assert_eq!(
+            format!("Invalid Pants version `{invalid}`: expected e.g. `2.20.0`."),
+            Version::parse(invalid).unwrap_err()
+        );
+    }
+}
+
+#[test]
+fn test_check_versions() {
+    let parser = parser(
+        &["--test-report", "--loop", "--level=debug"],
+        &[("PANTS_TEST_OUTPUT", "all")],
+        "",
+    );
+
+    let mut registry = OptionRegistry::new();
+    for option in [
+        OptionInfo::new(option_id!(["test"], "report"), OptionType::Bool(false))
+            .introduced_in(version("2.22.0")),
+        OptionInfo::new(
+            option_id!(["test"], "output"),
+            OptionType::String("".to_owned()),
+        )
+        .removed_in(version("2.21.0")),
+        OptionInfo::new(option_id!("loop"), OptionType::Bool(false))
+            .introduced_in(version("2.18.0"))
+            .removed_in(version("2.30.0")),
+        // Unavailable, but not set.
+        OptionInfo::new(option_id!("watch"), OptionType::Bool(false))
+            .introduced_in(version("2.22.0")),
+        OptionInfo::new(option_id!("level"), OptionType::String("info".to_owned())),
+    ] {
+        registry.register(option).unwrap();
+    }
+
+    let running = version("2.21.1");
+    let unavailable = parser.check_versions(&registry, &running);
+    assert_eq!(
+        vec![
+            UnavailableOption {
+                id: option_id!(["test"], "report"),
+                source: Source::Flag,
+                running: running.clone(),
+                reason: Unavailability::NotYetIntroduced(version("2.22.0")),
+            },
+            UnavailableOption {
+                id: option_id!(["test"], "output"),
+                source: Source::Env,
+                running: running.clone(),
+                reason: Unavailability::Removed(version("2.21.0")),
+            },
+        ],
+        unavailable
+    );
+    assert_eq!(
+        vec![
+            "[test] report is set by flags, but it is only introduced in Pants 2.22.0, and Pants \
+            2.21.1 is running.",
+            "[test] output is set by env, but it was removed in Pants 2.21.0, and Pants 2.21.1 \
+            is running.",
+        ],
+        unavailable
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    );
+
+    assert!(parser
+        .check_versions(&registry, &version("2.20.0"))
+        .iter()
+        .all(|option| option.id == option_id!(["test"], "report")));
+}
@@ -296,12 +296,52 @@ pub fn fingerprint_compute(
                 let val = options_parser.parse_string(&option.id, &default)?;
                 Digest::update(&mut hasher, val.value.as_bytes());
             }
+            OptionType::BoolList(default) => {
+                let val = options_parser.parse_bool_list(&option.id, default)?.value;
+                for item in val {
+                    Digest::update(&mut hasher, [item as u8]);
+                }
+            }
+            OptionType::IntList(default) => {
+                let val = options_parser.parse_int_list(&option.id, default)?.value;
+                for item in val {
+                    Digest::update(&mut hasher, item.to_be_bytes());
+                }
+            }
+            OptionType::FloatList(default) => {
+                let val = options_parser.parse_float_list(&option.id, default)?.value;
+                for item in val {
+                    Digest::update(&mut hasher, item.to_be_bytes());
+                }
+            }
             OptionType::StringList(default) => {
                 let val = options_parser.parse_string_list(&option.id, default)?.value;
                 for item in val {
                     Digest::update(&mut hasher, item.as_bytes());
                 }
             }
+            OptionType::DictList(default) => {
+                let val = options_parser.parse_dict_list(&option.id, default)?.value;
+                for dict in val {
+                    for (key, value) in dict {
+                        Digest::update(&mut hasher, format!("{key}={value}").as_bytes());
+                        Digest::update(&mut hasher, [0]);
+                    }
+                    Digest::update(&mut hasher, [0]);
+                }
+            }
+            OptionType::Dict(default) => {
+                // Dicts preserve the order in which their keys were written, which is significant.
+                let val = options_parser.parse_dict(&option.id, default)?.value;
+                // Values are hashed in their rendered form, which (unlike `Debug`) is stable, and
+                // each entry and the dict itself are terminated so that their boundaries are
+                // unambiguous.
+                for (key, value) in val {
+                    Digest::update(&mut hasher, format!("{key}={value}").as_bytes());
+                    Digest::update(&mut hasher, [0]);
+                }
+                Digest::update(&mut hasher, [0]);
+            }
         }
     }
     let hash = hasher.finalize();
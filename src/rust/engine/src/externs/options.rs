@@ -3,11 +3,13 @@
 
 use pyo3::exceptions::{PyException, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use pyo3::types::{PyBool, PyBytes, PyDate, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
 
+use indexmap::IndexMap;
 use options::{
-    Args, ConfigSource, Env, ListOptionValue, OptionId, OptionParser, OptionalOptionValue, Scope,
-    Val,
+    describe_source, Args, Concurrency, ConfigSource, Datetime, Env, Explanation, LintFinding,
+    ListEditAction, ListOptionValue, OptionId, OptionInfo, OptionParser, OptionRegistry,
+    OptionType, OptionalOptionValue, Scope, SourceKind, Val,
 };
 
 use std::collections::HashMap;
@@ -15,6 +17,12 @@ use std::collections::HashMap;
 pub(crate) fn register(m: &PyModule) -> PyResult<()> {
     m.add_class::<PyOptionId>()?;
     m.add_class::<PyConfigSource>()?;
+    m.add_class::<PyOptionRegistry>()?;
+    m.add_class::<PyRawValue>()?;
+    m.add_class::<PyEdit>()?;
+    m.add_class::<PyContribution>()?;
+    m.add_class::<PyExplanation>()?;
+    m.add_class::<PyLintFinding>()?;
     m.add_class::<PyOptionParser>()?;
     Ok(())
 }
@@ -24,7 +32,7 @@ fn val_to_py_object(py: Python, val: &Val) -> PyResult<PyObject> {
         Val::Bool(b) => b.into_py(py),
         Val::Int(i) => i.into_py(py),
         Val::Float(f) => f.into_py(py),
-        Val::String(s) => s.into_py(py),
+        Val::String(s) => s.as_str().into_py(py),
         Val::List(list) => {
             let pylist = PyList::empty(py);
             for m in list {
@@ -39,6 +47,10 @@ fn val_to_py_object(py: Python, val: &Val) -> PyResult<PyObject> {
             }
             pydict.into_py(py)
         }
+        Val::Bytes(b) => PyBytes::new(py, b).into_py(py),
+        // NB: Dates are passed to Python as RFC 3339 strings.
+        Val::Date(d) => d.to_string().into_py(py),
+        Val::Null => py.None(),
     };
     Ok(res)
 }
@@ -57,7 +69,7 @@ pub(crate) fn py_object_to_val(obj: &PyAny) -> Result<Val, PyErr> {
     // NB: We check these in rough order of likelihood of the type appearing in a dict value,
     // but it is vital that we check bool before int, because bool is a subclass of int.
     if obj.is_instance_of::<PyString>() {
-        Ok(Val::String(obj.extract()?))
+        Ok(Val::String(obj.extract::<&str>()?.into()))
     } else if obj.is_instance_of::<PyBool>() {
         Ok(Val::Bool(obj.extract()?))
     } else if obj.is_instance_of::<PyInt>() {
@@ -71,7 +83,7 @@ pub(crate) fn py_object_to_val(obj: &PyAny) -> Result<Val, PyErr> {
                 .map(|(k, v)| {
                     Ok::<(String, Val), PyErr>((k.extract::<String>()?, py_object_to_val(v)?))
                 })
-                .collect::<Result<HashMap<_, _>, _>>()?,
+                .collect::<Result<IndexMap<_, _>, _>>()?,
         ))
     } else if obj.is_instance_of::<PyList>() {
         Ok(Val::List(
@@ -87,6 +99,16 @@ pub(crate) fn py_object_to_val(obj: &PyAny) -> Result<Val, PyErr> {
                 .map(py_object_to_val)
                 .collect::<Result<Vec<_>, _>>()?,
         ))
+    } else if obj.is_instance_of::<PyBytes>() {
+        Ok(Val::Bytes(obj.downcast::<PyBytes>()?.as_bytes().to_vec()))
+    } else if obj.is_instance_of::<PyDate>() {
+        // NB: This includes datetimes, since `datetime` is a subclass of `date`.
+        let isoformat = obj.call_method0("isoformat")?.extract::<String>()?;
+        Ok(Val::Date(isoformat.parse().map_err(|e| {
+            PyValueError::new_err(format!("Invalid date in option default: {e}"))
+        })?))
+    } else if obj.is_none() {
+        Ok(Val::Null)
     } else {
         Err(PyValueError::new_err(format!(
             "Unsupported Python type in option default: {}",
@@ -95,6 +117,12 @@ pub(crate) fn py_object_to_val(obj: &PyAny) -> Result<Val, PyErr> {
     }
 }
 
+fn py_dict_to_dict(dict: &PyDict) -> PyResult<IndexMap<String, Val>> {
+    dict.iter()
+        .map(|(k, v)| Ok((k.extract::<String>()?, py_object_to_val(v)?)))
+        .collect()
+}
+
 #[pyclass]
 struct PyOptionId(OptionId);
 
@@ -131,10 +159,182 @@ struct PyConfigSource(ConfigSource);
 impl PyConfigSource {
     #[new]
     fn __new__(path: &str, content: &[u8]) -> PyResult<Self> {
-        Ok(Self(ConfigSource {
-            path: path.into(),
-            content: std::str::from_utf8(content).map(str::to_string)?,
-        }))
+        Ok(Self(
+            ConfigSource::from_bytes(path, content.to_vec()).map_err(PyValueError::new_err)?,
+        ))
+    }
+}
+
+#[pyclass]
+struct PyOptionRegistry(OptionRegistry);
+
+#[pymethods]
+impl PyOptionRegistry {
+    #[new]
+    fn __new__() -> Self {
+        Self(OptionRegistry::new())
+    }
+
+    // The option type is named as in help, e.g. `int` or `list[str]`.
+    #[pyo3(signature = (option_id, option_type, default, help = "", deprecated = None))]
+    fn register(
+        &mut self,
+        option_id: &PyOptionId,
+        option_type: &str,
+        default: &PyAny,
+        help: &str,
+        deprecated: Option<&str>,
+    ) -> PyResult<()> {
+        let option_type = OptionType::from_type_name(option_type, py_object_to_val(default)?)
+            .map_err(PyValueError::new_err)?;
+        let mut option = OptionInfo::new(option_id.0.clone(), option_type).help(help);
+        if let Some(message) = deprecated {
+            option = option.deprecated(message);
+        }
+        self.0.register(option).map_err(PyValueError::new_err)
+    }
+}
+
+#[pyclass(get_all)]
+struct PyRawValue {
+    location: String,
+    value: Option<String>,
+}
+
+#[pyclass(get_all)]
+struct PyEdit {
+    // One of `replace`, `add`, `remove`, `insert`, `insert_before` or `insert_after`.
+    action: &'static str,
+    // For `insert`, the index at which the items are inserted.
+    index: Option<usize>,
+    value: PyObject,
+}
+
+#[pyclass(get_all)]
+struct PyContribution {
+    source: String,
+    rank: isize,
+    raw_values: Vec<Py<PyRawValue>>,
+    edits: Vec<Py<PyEdit>>,
+}
+
+// The derivation of the value of an option: its default, the contribution of each source in order
+// of increasing rank, and the human-readable trace of the same.
+#[pyclass(get_all)]
+struct PyExplanation {
+    option: String,
+    default: Option<PyObject>,
+    contributions: Vec<Py<PyContribution>>,
+    value: PyObject,
+    rank: isize,
+    trace: String,
+}
+
+#[pymethods]
+impl PyExplanation {
+    fn __str__(&self) -> String {
+        self.trace.clone()
+    }
+}
+
+impl PyExplanation {
+    fn new(py: Python, explanation: Explanation) -> PyResult<Self> {
+        let trace = explanation.to_string();
+        let contributions = explanation
+            .contributions
+            .into_iter()
+            .map(|contribution| {
+                let raw_values = contribution
+                    .raw_values
+                    .into_iter()
+                    .map(|raw| {
+                        Py::new(
+                            py,
+                            PyRawValue {
+                                location: raw.location,
+                                value: raw.value,
+                            },
+                        )
+                    })
+                    .collect::<PyResult<Vec<_>>>()?;
+                let edits = contribution
+                    .edits
+                    .into_iter()
+                    .map(|edit| {
+                        let (action, index) = match edit.action {
+                            ListEditAction::Replace => ("replace", None),
+                            ListEditAction::Add => ("add", None),
+                            ListEditAction::Remove => ("remove", None),
+                            ListEditAction::Insert(index) => ("insert", Some(index)),
+                            ListEditAction::InsertBefore => ("insert_before", None),
+                            ListEditAction::InsertAfter => ("insert_after", None),
+                        };
+                        Py::new(
+                            py,
+                            PyEdit {
+                                action,
+                                index,
+                                value: val_to_py_object(py, &edit.value)?,
+                            },
+                        )
+                    })
+                    .collect::<PyResult<Vec<_>>>()?;
+                Py::new(
+                    py,
+                    PyContribution {
+                        source: describe_source(&contribution.source),
+                        rank: contribution.source.rank() as isize,
+                        raw_values,
+                        edits,
+                    },
+                )
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Self {
+            option: explanation.option,
+            default: explanation
+                .default
+                .map(|default| val_to_py_object(py, &default))
+                .transpose()?,
+            contributions,
+            value: val_to_py_object(py, &explanation.value)?,
+            rank: explanation.source.rank() as isize,
+            trace,
+        })
+    }
+}
+
+#[pyclass(get_all)]
+struct PyLintFinding {
+    // E.g. `redundant-default`.
+    rule: String,
+    path: String,
+    // The 1-based line of the finding, if it could be located.
+    line: Option<usize>,
+    message: String,
+    // Whether the finding may be fixed by removing the line which sets the offending value.
+    fixable: bool,
+}
+
+#[pymethods]
+impl PyLintFinding {
+    fn __str__(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{line}: [{}] {}", self.path, self.rule, self.message),
+            None => format!("{}: [{}] {}", self.path, self.rule, self.message),
+        }
+    }
+}
+
+impl From<LintFinding> for PyLintFinding {
+    fn from(finding: LintFinding) -> Self {
+        Self {
+            rule: finding.rule.to_string(),
+            path: finding.path.display().to_string(),
+            line: finding.line,
+            message: finding.message,
+            fixable: finding.fix.is_some(),
+        }
     }
 }
 
@@ -225,6 +425,45 @@ impl PyOptionParser {
         to_py(self.0.parse_string_optional(&option_id.0, default))
     }
 
+    fn get_date(
+        &self,
+        option_id: &PyOptionId,
+        default: Option<&str>,
+    ) -> PyResult<RankedVal<Option<String>>> {
+        let default = default
+            .map(|default| {
+                default
+                    .parse::<Datetime>()
+                    .map_err(|e| PyValueError::new_err(format!("Invalid default date: {e}")))
+            })
+            .transpose()?;
+        let opt_val = self
+            .0
+            .parse_date_optional(&option_id.0, default.as_ref())
+            .map_err(PyException::new_err)?;
+        Ok((
+            opt_val.value.map(|date| date.to_string()),
+            opt_val.source.rank() as isize,
+        ))
+    }
+
+    fn get_percentage(
+        &self,
+        option_id: &PyOptionId,
+        default: Option<f64>,
+    ) -> PyResult<RankedVal<Option<f64>>> {
+        to_py(self.0.parse_percentage_optional(&option_id.0, default))
+    }
+
+    fn get_concurrency(&self, option_id: &PyOptionId, default: &str) -> PyResult<RankedVal<usize>> {
+        let default = Concurrency::parse(default).map_err(PyValueError::new_err)?;
+        let opt_val = self
+            .0
+            .parse_concurrency(&option_id.0, default)
+            .map_err(PyException::new_err)?;
+        Ok((opt_val.value, opt_val.source.rank() as isize))
+    }
+
     fn get_bool_list(
         &self,
         option_id: &PyOptionId,
@@ -270,31 +509,102 @@ impl PyOptionParser {
         py: Python,
         option_id: &PyOptionId,
         default: &PyDict,
-    ) -> PyResult<RankedVal<HashMap<String, PyObject>>> {
-        let default = default
-            .items()
-            .into_iter()
-            .map(|kv_pair| {
-                let (k, v) = kv_pair.extract::<(String, &PyAny)>()?;
-                Ok::<(String, Val), PyErr>((k, py_object_to_val(v)?))
-            })
-            .collect::<Result<HashMap<_, _>, _>>()?;
+    ) -> PyResult<RankedVal<PyObject>> {
+        let default = py_dict_to_dict(default)?;
         let opt_val = self
             .0
             .parse_dict(&option_id.0, default)
             .map_err(PyException::new_err)?;
-        let opt_val_py = opt_val
-            .value
+        // NB: We convert via a `Val` rather than a `HashMap`, so that key order is preserved.
+        let opt_val_py = val_to_py_object(py, &Val::Dict(opt_val.value))?;
+        Ok((opt_val_py, opt_val.source.rank() as isize))
+    }
+
+    fn get_dict_list(
+        &self,
+        py: Python,
+        option_id: &PyOptionId,
+        default: Vec<&PyDict>,
+    ) -> PyResult<RankedVal<PyObject>> {
+        let default = default
             .into_iter()
-            .map(|(k, v)| match val_to_py_object(py, &v) {
-                Ok(pyobj) => Ok((k, pyobj)),
-                Err(err) => Err(err),
-            })
-            .collect::<PyResult<HashMap<String, PyObject>>>()?;
+            .map(py_dict_to_dict)
+            .collect::<Result<Vec<_>, _>>()?;
+        let opt_val = self
+            .0
+            .parse_dict_list(&option_id.0, default)
+            .map_err(PyException::new_err)?;
+        let opt_val_py = val_to_py_object(
+            py,
+            &Val::List(opt_val.value.into_iter().map(Val::Dict).collect()),
+        )?;
         Ok((opt_val_py, opt_val.source.rank() as isize))
     }
 
+    fn get_args(&self) -> Vec<String> {
+        self.0.get_args().to_vec()
+    }
+
     fn get_passthrough_args(&self) -> PyResult<Option<Vec<String>>> {
-        Ok(self.0.get_passthrough_args().cloned())
+        Ok(self
+            .0
+            .get_passthrough_args()
+            .map(|passthrough_args| passthrough_args.args.clone()))
+    }
+
+    // Explains how the value of the option is derived, given its type, named as in help, and its
+    // default.
+    fn explain(
+        &self,
+        py: Python,
+        option_id: &PyOptionId,
+        option_type: &str,
+        default: &PyAny,
+    ) -> PyResult<PyExplanation> {
+        let option_type = OptionType::from_type_name(option_type, py_object_to_val(default)?)
+            .map_err(PyValueError::new_err)?;
+        let explanation = self
+            .0
+            .explain(&option_id.0, option_type)
+            .map_err(PyException::new_err)?;
+        PyExplanation::new(py, explanation)
+    }
+
+    fn lint_config(&self, registry: &PyOptionRegistry) -> Vec<PyLintFinding> {
+        self.0
+            .lint_config(&registry.0)
+            .into_iter()
+            .map(PyLintFinding::from)
+            .collect()
+    }
+
+    // Renders the effective config as TOML, with comments noting the origin of each value if
+    // `annotate`.
+    fn dump_config(&self, registry: &PyOptionRegistry, annotate: bool) -> PyResult<String> {
+        self.0
+            .dump_config(&registry.0, annotate)
+            .map_err(PyException::new_err)
+    }
+
+    // The config files and fromfiles that the parser has consumed so far, with the sha256 digests
+    // of their content, or None for optional fromfiles that did not exist.
+    fn consumed_files(&self) -> HashMap<String, Option<String>> {
+        self.0
+            .consumed_files()
+            .into_iter()
+            .map(|(path, digest)| (path.display().to_string(), digest))
+            .collect()
+    }
+
+    // The kind of the highest-priority source that explicitly sets the option, if any: one of
+    // `config`, `keyring`, `env` or `flag`.
+    fn explicit_source_kind(&self, option_id: &PyOptionId) -> Option<&'static str> {
+        let source = self.0.explicit_source(&option_id.0)?;
+        Some(match source.kind()? {
+            SourceKind::Config => "config",
+            SourceKind::Keyring => "keyring",
+            SourceKind::Env => "env",
+            SourceKind::Flag => "flag",
+        })
     }
 }